@@ -0,0 +1,30 @@
+//! Writes the label allowlist of the `allowlist` feature, read from
+//! `FUTURES_POLL_LOG_LABELS`, to `$OUT_DIR/allowlist.rs`, and sets the
+//! `silenced` cfg the logging is compiled out under: with the `silence`
+//! feature, or with `debug-only` in builds without debug assertions.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(silenced)");
+    let debug_only = env::var_os("CARGO_FEATURE_DEBUG_ONLY").is_some() &&
+                     env::var_os("CARGO_CFG_DEBUG_ASSERTIONS").is_none();
+    if env::var_os("CARGO_FEATURE_SILENCE").is_some() || debug_only {
+        println!("cargo:rustc-cfg=silenced");
+    }
+    println!("cargo:rerun-if-env-changed=FUTURES_POLL_LOG_LABELS");
+    let prefixes: Option<Vec<String>> = if env::var_os("CARGO_FEATURE_ALLOWLIST").is_some() {
+        let labels = env::var("FUTURES_POLL_LOG_LABELS").unwrap_or_default();
+        Some(labels.split(',').map(str::trim).filter(|prefix| !prefix.is_empty()).map(String::from).collect())
+    } else {
+        None
+    };
+    let source = match prefixes {
+        Some(prefixes) => format!("const PREFIXES: Option<&[&str]> = Some(&{:?});\n", prefixes),
+        None => "const PREFIXES: Option<&[&str]> = None;\n".to_string(),
+    };
+    let out = Path::new(&env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo")).join("allowlist.rs");
+    fs::write(out, source).expect("cannot write the label allowlist");
+}
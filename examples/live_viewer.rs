@@ -0,0 +1,29 @@
+extern crate futures;
+extern crate futures_poll_log;
+
+use std::thread;
+use std::time::Duration;
+
+use futures::{Async, Future};
+use futures_poll_log::LoggingExt;
+
+fn main() {
+    for worker in 0..4 {
+        thread::spawn(move || {
+            loop {
+                let mut polls = 0;
+                let _ = futures::future::poll_fn(move || {
+                    polls += 1;
+                    thread::sleep(Duration::from_millis(300 * (worker + 1)));
+                    if polls < 10 {
+                        futures::task::park().unpark();
+                        Ok::<_, ()>(Async::NotReady)
+                    } else {
+                        Ok(Async::Ready(polls))
+                    }
+                }).inspect(&format!("worker.{}", worker)).wait();
+            }
+        });
+    }
+    futures_poll_log::tui::run().unwrap();
+}
@@ -0,0 +1,16 @@
+extern crate futures;
+extern crate futures_poll_log;
+
+use futures_poll_log::StreamLoggingExt;
+use futures::{Future, Stream};
+
+fn main() {
+    futures_poll_log::stdout_logger::init();
+
+    let _: Result<Vec<i32>, ()> = futures::stream::iter(vec![Ok(1), Ok(2), Ok(3)])
+        .inspect_stream("numbers")
+        .map(|i| i * 2)
+        .inspect_stream("doubled numbers")
+        .collect()
+        .wait();
+}
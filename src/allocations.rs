@@ -0,0 +1,129 @@
+//! Counting the memory allocated by each poll.
+//!
+//! `CountingAllocator` wraps the global allocator and counts the
+//! allocations of every thread. Installed in the binary, it lets
+//! inspections set up with `Inspect::track_allocations()` log how much
+//! every poll allocated:
+//!
+//! ```rust,ignore
+//! use futures_poll_log::allocations::CountingAllocator;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator::system();
+//! ```
+//!
+//! ```plain
+//! DEBUG - Polling future `handler'
+//! DEBUG - `handler' allocated 4096 bytes in 3 allocations
+//! DEBUG - Future `handler' polled: Ok(NotReady)
+//! ```
+//!
+//! An allocator of its own, such as jemalloc, is wrapped through
+//! `CountingAllocator::new`. Allocations are counted per thread, so a
+//! poll is charged with what its own thread allocated while it ran,
+//! including the allocations of the logging itself. Without a
+//! `CountingAllocator` installed, nothing is logged.
+//!
+//! This module is available with the `allocations` feature.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::alloc::System;
+
+thread_local! {
+    // A `const` Cell needs no destructor, so counting never
+    // allocates itself.
+    static ALLOCATED: Cell<Allocated> = const { Cell::new(Allocated { bytes: 0, count: 0 }) };
+}
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// The memory allocated by a thread, or while a poll ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Allocated {
+    /// The bytes requested, with reallocations counting their new
+    /// size.
+    pub bytes: u64,
+    /// The number of allocations and reallocations.
+    pub count: u64,
+}
+
+impl Allocated {
+    /// The memory allocated since `earlier`.
+    pub fn since(self, earlier: Allocated) -> Allocated {
+        Allocated {
+            bytes: self.bytes.wrapping_sub(earlier.bytes),
+            count: self.count.wrapping_sub(earlier.count),
+        }
+    }
+}
+
+/// A GlobalAlloc counting the allocations of every thread before
+/// handing them to the allocator it wraps, `System` by default.
+#[derive(Debug, Default)]
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+
+impl CountingAllocator<System> {
+    /// Counts the allocations of the system allocator.
+    pub const fn system() -> CountingAllocator<System> {
+        CountingAllocator { inner: System }
+    }
+}
+
+impl<A> CountingAllocator<A> {
+    /// Counts the allocations of `inner`.
+    pub const fn new(inner: A) -> CountingAllocator<A> {
+        CountingAllocator { inner }
+    }
+
+    fn count(size: usize) {
+        if !INSTALLED.load(Ordering::Relaxed) {
+            INSTALLED.store(true, Ordering::Relaxed);
+        }
+        // Threads being torn down are not counted any more.
+        let _ = ALLOCATED.try_with(|allocated| {
+            let mut current = allocated.get();
+            current.bytes += size as u64;
+            current.count += 1;
+            allocated.set(current);
+        });
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        CountingAllocator::<A>::count(layout.size());
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        CountingAllocator::<A>::count(layout.size());
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        CountingAllocator::<A>::count(new_size);
+        self.inner.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Whether a `CountingAllocator` is installed and counted anything.
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// The memory allocated by the current thread so far, if a
+/// `CountingAllocator` is installed.
+pub fn current() -> Option<Allocated> {
+    if !is_installed() {
+        return None;
+    }
+    ALLOCATED.try_with(Cell::get).ok()
+}
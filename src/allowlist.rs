@@ -0,0 +1,73 @@
+//! The label allowlist consulted by `inspect!` at compile time.
+
+#[cfg(not(silenced))]
+use core::fmt::Debug;
+#[cfg(not(silenced))]
+use futures::Future;
+
+#[cfg(not(silenced))]
+use future::{LoggedFuture, LoggingExt};
+
+include!(concat!(env!("OUT_DIR"), "/allowlist.rs"));
+
+/// Whether `label` starts with one of the prefixes the crate was
+/// built with, or the `allowlist` feature is disabled.
+pub const fn allowed(label: &str) -> bool {
+    let prefixes = match PREFIXES {
+        Some(prefixes) => prefixes,
+        None => return true,
+    };
+    let mut i = 0;
+    while i < prefixes.len() {
+        if starts_with(label.as_bytes(), prefixes[i].as_bytes()) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn starts_with(label: &[u8], prefix: &[u8]) -> bool {
+    if label.len() < prefix.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < prefix.len() {
+        if label[i] != prefix[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Picks the wrapper of a call site of `inspect!`: a
+/// `LoggedFuture` for `Gate<true>`, the future itself for
+/// `Gate<false>`.
+#[derive(Debug)]
+pub struct Gate<const ALLOWED: bool>;
+
+impl Gate<true> {
+    /// Wraps `future` through `LoggingExt::inspect_static`.
+    #[cfg(not(silenced))]
+    #[track_caller]
+    pub fn inspect<T, E, F>(future: F, label: &'static str) -> LoggedFuture<T, E, F>
+        where T: Debug,
+              E: Debug,
+              F: Future<Item = T, Error = E>
+    {
+        future.inspect_static(label)
+    }
+    /// Returns `future`, as the `silence` feature is activated.
+    #[cfg(silenced)]
+    pub fn inspect<F>(future: F, _: &'static str) -> F {
+        future
+    }
+}
+
+impl Gate<false> {
+    /// Returns `future`, as its label is not allowed.
+    pub fn inspect<F>(future: F, _: &'static str) -> F {
+        future
+    }
+}
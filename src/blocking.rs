@@ -0,0 +1,185 @@
+//! Logging threads blocked on a future.
+
+use alloc::sync::Arc;
+use core::fmt::Debug;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(silenced))]
+use core::time::Duration;
+use futures::Future;
+#[cfg(not(silenced))]
+use futures::Async;
+#[cfg(not(silenced))]
+use futures::executor::{self, Unpark};
+use std::thread::{self, Thread};
+
+#[cfg(not(silenced))]
+use context;
+#[cfg(not(silenced))]
+use inspector::Inspector;
+#[cfg(not(silenced))]
+use logger::EventKind;
+#[cfg(not(silenced))]
+use options::Inspect;
+
+/// Wakes a thread parked on a future.
+#[derive(Debug)]
+#[cfg_attr(all(silenced, not(feature="futures03")), allow(dead_code))]
+pub(crate) struct ThreadUnpark {
+    thread: Thread,
+    unparked: AtomicBool,
+}
+
+#[cfg_attr(all(silenced, not(feature="futures03")), allow(dead_code))]
+impl ThreadUnpark {
+    /// Wakes the current thread.
+    pub(crate) fn current() -> Arc<ThreadUnpark> {
+        Arc::new(ThreadUnpark {
+            thread: thread::current(),
+            unparked: AtomicBool::new(false),
+        })
+    }
+
+    pub(crate) fn unpark(&self) {
+        self.unparked.store(true, Ordering::Release);
+        self.thread.unpark();
+    }
+
+    /// Parks the current thread until `unpark()` is called, unless
+    /// it was called since the last park.
+    pub(crate) fn park(&self) {
+        while !self.unparked.swap(false, Ordering::Acquire) {
+            thread::park();
+        }
+    }
+}
+
+#[cfg(not(silenced))]
+impl Unpark for ThreadUnpark {
+    fn unpark(&self) {
+        ThreadUnpark::unpark(self)
+    }
+}
+
+#[cfg(feature="futures03")]
+impl std::task::Wake for ThreadUnpark {
+    fn wake(self: Arc<Self>) {
+        ThreadUnpark::unpark(&self)
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        ThreadUnpark::unpark(self)
+    }
+}
+
+/// The logging of a thread blocked on a future, shared by
+/// `logged_wait()` and `futures03::logged_block_on()`.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub(crate) struct Blocked {
+    pub(crate) inspector: Inspector,
+    parked: Duration,
+    wakeups: u64,
+}
+
+#[cfg(not(silenced))]
+impl Blocked {
+    /// Logs that the current thread starts blocking, warning if it
+    /// does so inside the poll of another inspection.
+    #[track_caller]
+    pub(crate) fn start(label: &str) -> Blocked {
+        let inspector = Inspector::new(Inspect::new(label));
+        if let Some((outer, _)) = context::current() {
+            inspector.emit(EventKind::BlockingInPoll(&outer), None);
+        }
+        let current = thread::current();
+        inspector.emit(EventKind::Blocking(current.name().unwrap_or("<unnamed>")), None);
+        Blocked {
+            inspector,
+            parked: Duration::ZERO,
+            wakeups: 0,
+        }
+    }
+
+    /// Parks the thread until `unpark` wakes it, logging the wakeup.
+    pub(crate) fn park(&mut self, unpark: &ThreadUnpark) {
+        let stopwatch = self.inspector.stopwatch();
+        unpark.park();
+        self.parked += stopwatch.elapsed().unwrap_or_default();
+        self.wakeups += 1;
+        self.inspector.emit(EventKind::Unparked(self.wakeups), None);
+    }
+
+    /// Logs how long the thread was blocked in total.
+    pub(crate) fn finish(&self) {
+        self.inspector.emit(EventKind::Unblocked(self.parked, self.wakeups), None);
+    }
+}
+
+/// logged_wait() blocks the current thread until `future` resolves,
+/// like `Future::wait`, and logs it as `label`: when the thread
+/// starts blocking, every poll, every time the thread is unparked
+/// to poll again, and for how long it was parked in total:
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_poll_log;
+/// # use futures::future;
+/// # fn main() {
+/// let answer = futures_poll_log::logged_wait("answer", future::ok::<u32, ()>(42));
+/// assert_eq!(answer, Ok(42));
+/// // `answer' blocks thread `main' until it resolves
+/// // Future `answer' polled: Ok(Ready(42))
+/// // `answer' released its thread after parking it for 0ns over 0 wakeups
+/// # }
+/// ```
+///
+/// Blocking inside the poll of another inspection stalls everything
+/// else its executor runs, and is logged as a warning naming that
+/// inspection.
+///
+/// This function is `Future::wait` when the `silence` feature is
+/// activated, and is only available with the `std` feature.
+#[cfg(not(silenced))]
+#[track_caller]
+pub fn logged_wait<F>(label: &str, future: F) -> Result<F::Item, F::Error>
+    where F: Future,
+          F::Item: Debug,
+          F::Error: Debug
+{
+    let unpark = ThreadUnpark::current();
+    let mut blocked = Blocked::start(label);
+    let mut spawned = executor::spawn(future);
+    loop {
+        let poll = {
+            let inspector = &mut blocked.inspector;
+            let _entered = inspector.enter();
+            let stopwatch = inspector.begin();
+            inspector.emit(EventKind::Polling, None);
+            let poll = spawned.poll_future(unpark.clone());
+            let outcome = inspector.outcome(&poll);
+            inspector.end(&outcome);
+            inspector.emit(EventKind::Polled(outcome), stopwatch);
+            poll
+        };
+        match poll {
+            Ok(Async::NotReady) => blocked.park(&unpark),
+            Ok(Async::Ready(item)) => {
+                blocked.finish();
+                return Ok(item);
+            }
+            Err(error) => {
+                blocked.finish();
+                return Err(error);
+            }
+        }
+    }
+}
+/// logged_wait() is `Future::wait` when the `silence` feature is activated.
+#[cfg(silenced)]
+pub fn logged_wait<F>(_: &str, future: F) -> Result<F::Item, F::Error>
+    where F: Future,
+          F::Item: Debug,
+          F::Error: Debug
+{
+    future.wait()
+}
@@ -0,0 +1,185 @@
+//! Logging which branch of a choice between futures was taken.
+
+use core::fmt::Debug;
+#[cfg(not(silenced))]
+use core::fmt;
+use futures::Future;
+use futures::future::Either;
+#[cfg(silenced)]
+use futures::future::Select2;
+#[cfg(not(silenced))]
+use futures::{Async, Poll};
+
+#[cfg(not(silenced))]
+use future::LoggedFuture;
+#[cfg(not(silenced))]
+use inspector::Inspector;
+#[cfg(not(silenced))]
+use logger::{EventKind, Outcome};
+#[cfg(not(silenced))]
+use options::Inspect;
+
+/// The name of the branch `either` holds.
+#[cfg_attr(silenced, allow(dead_code))]
+fn side<A, B>(either: &Either<A, B>) -> &'static str {
+    match *either {
+        Either::A(_) => "left",
+        Either::B(_) => "right",
+    }
+}
+
+/// LoggedSelect2 races two futures like `Future::select2`, and
+/// logs which of them won, see `SelectLoggingExt::inspect_select2`.
+#[cfg(not(silenced))]
+pub struct LoggedSelect2<A, B> {
+    // Taken once the race is decided.
+    inner: Option<(A, B)>,
+    inspector: Inspector,
+}
+
+#[cfg(not(silenced))]
+impl<A, B> Debug for LoggedSelect2<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoggedSelect2")
+         .field("inspector", &self.inspector)
+         .finish()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<A, B> Future for LoggedSelect2<A, B>
+    where A: Future,
+          B: Future,
+          A::Item: Debug,
+          A::Error: Debug,
+          B::Item: Debug,
+          B::Error: Debug
+{
+    type Item = Either<(A::Item, B), (B::Item, A)>;
+    type Error = Either<(A::Error, B), (B::Error, A)>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::Polling, None);
+        let (ref mut a, ref mut b) = *self.inner.as_mut().expect("LoggedSelect2 polled after it resolved");
+        // The first future wins ties, like in `select2`.
+        let left = a.poll();
+        let right = match left {
+            Ok(Async::NotReady) => b.poll(),
+            _ => Ok(Async::NotReady),
+        };
+        let branch = match (&left, &right) {
+            (&Ok(Async::NotReady), &Ok(Async::NotReady)) => None,
+            (&Ok(Async::NotReady), _) => Some(Either::B(self.inspector.outcome(&right))),
+            _ => Some(Either::A(self.inspector.outcome(&left))),
+        };
+        let outcome = match branch {
+            None => Outcome::NotReady,
+            Some(ref branch) => {
+                self.inspector.emit(EventKind::Branch(side(branch)), None);
+                match *branch {
+                    Either::A(outcome) | Either::B(outcome) => outcome,
+                }
+            }
+        };
+        self.inspector.end(&outcome);
+        self.inspector.emit(EventKind::Polled(outcome), stopwatch);
+        if branch.is_none() {
+            return Ok(Async::NotReady);
+        }
+        let (a, b) = self.inner.take().expect("checked above");
+        match (left, right) {
+            (Ok(Async::Ready(item)), _) => Ok(Async::Ready(Either::A((item, b)))),
+            (Err(error), _) => Err(Either::A((error, b))),
+            (_, Ok(Async::Ready(item))) => Ok(Async::Ready(Either::B((item, a)))),
+            (_, Err(error)) => Err(Either::B((error, a))),
+            (Ok(Async::NotReady), Ok(Async::NotReady)) => unreachable!("the race is decided"),
+        }
+    }
+}
+
+/// SelectLoggingExt races futures while logging which one won.
+pub trait SelectLoggingExt: Future + Sized {
+    /// inspect_select2() races the Future against `other` like
+    /// `select2()`, logging the polls of the race under `label`
+    /// and, once it is decided, which branch won, `left` for the
+    /// Future and `right` for `other`:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::SelectLoggingExt;
+    /// # fn main() {
+    /// let _ = future::empty::<u32, ()>()
+    ///     .inspect_select2(future::ok::<&str, ()>("timeout"), "request or timeout")
+    ///     .wait();
+    /// // `request or timeout' took its right branch
+    /// // Future `request or timeout' polled: Ok(Ready("timeout"))
+    /// # }
+    /// ```
+    ///
+    /// Only the item or error of the winner is printed.
+    ///
+    /// This method returns a plain `Select2` when the `silence`
+    /// feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect_select2<B: Future>(self, other: B, label: &str) -> LoggedSelect2<Self, B>;
+    /// inspect_select2() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_select2<B: Future>(self, other: B, label: &str) -> Select2<Self, B>;
+}
+
+impl<F: Future> SelectLoggingExt for F {
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_select2<B: Future>(self, other: B, label: &str) -> LoggedSelect2<F, B> {
+        LoggedSelect2 {
+            inner: Some((self, other)),
+            inspector: Inspector::new(Inspect::new(label)),
+        }
+    }
+    #[cfg(silenced)]
+    fn inspect_select2<B: Future>(self, other: B, _: &str) -> Select2<F, B> {
+        self.select2(other)
+    }
+}
+
+/// EitherLoggingExt logs which branch of an `Either` future is
+/// taken.
+pub trait EitherLoggingExt<T, E>: Future<Item = T, Error = E> + Sized
+    where T: Debug,
+          E: Debug
+{
+    /// inspect_either() sets up the logging like `inspect()`, and
+    /// logs on the first poll whether the `Either` holds its `left`
+    /// (`A`) or `right` (`B`) branch, e.g. to tell which way an
+    /// `and_then` closure went.
+    ///
+    /// This method returns `Self` instead of a `LoggedFuture`
+    /// when the `silence` feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect_either(self, label: &str) -> LoggedFuture<T, E, Self>;
+    /// inspect_either() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_either(self, label: &str) -> Self;
+}
+
+impl<T, E, A, B> EitherLoggingExt<T, E> for Either<A, B>
+    where T: Debug,
+          E: Debug,
+          A: Future<Item = T, Error = E>,
+          B: Future<Item = T, Error = E>
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_either(self, label: &str) -> LoggedFuture<T, E, Self> {
+        let branch = side(&self);
+        LoggedFuture::new(self, Inspect::new(label)).branch(branch)
+    }
+    #[cfg(silenced)]
+    fn inspect_either(self, _: &str) -> Self {
+        self
+    }
+}
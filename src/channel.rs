@@ -0,0 +1,38 @@
+//! A backend sending poll events into a channel.
+
+use futures::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use logger::{PollEvent, PollLogger};
+use record::EventRecord;
+
+/// A PollLogger that sends every event as an `EventRecord`
+/// into an unbounded channel, so that a task can consume them
+/// as a `Stream`.
+///
+/// Events are dropped once the receiving end is gone.
+///
+/// This backend is only available with the `std` feature.
+#[derive(Debug, Clone)]
+pub struct ChannelLogger {
+    sender: UnboundedSender<EventRecord>,
+}
+
+impl ChannelLogger {
+    /// Creates a backend along with the receiving end of
+    /// its channel.
+    pub fn new() -> (ChannelLogger, UnboundedReceiver<EventRecord>) {
+        let (sender, receiver) = mpsc::unbounded();
+        (ChannelLogger::from_sender(sender), receiver)
+    }
+
+    /// Creates a backend sending into an existing channel.
+    pub fn from_sender(sender: UnboundedSender<EventRecord>) -> ChannelLogger {
+        ChannelLogger { sender }
+    }
+}
+
+impl PollLogger for ChannelLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let _ = self.sender.send(event.to_record());
+    }
+}
@@ -0,0 +1,224 @@
+//! Injecting faults into inspected futures and streams.
+//!
+//! Faults are injected into the inspections whose label matches a
+//! pattern, for testing how retry and timeout logic copes with slow
+//! or failing dependencies:
+//!
+//! ```rust
+//! extern crate futures;
+//! extern crate futures_poll_log;
+//!
+//! use std::time::Duration;
+//! use futures::{Future, future};
+//! use futures_poll_log::LoggingExt;
+//! use futures_poll_log::chaos::{self, Faults};
+//!
+//! # fn main() {
+//! chaos::seed(7);
+//! chaos::inject("db.*", Faults::new().not_ready(0.2)
+//!                                    .delayed_wake(0.1, Duration::from_millis(50))
+//!                                    .error(0.05));
+//! let _ = future::ok::<u32, &str>(1).inspect_with_faults("db.query", || "injected").wait();
+//! chaos::clear();
+//! # }
+//! ```
+//!
+//! Each poll of a matching inspection draws at most one fault, which
+//! replaces the poll of the inner value and is logged as a warning:
+//!
+//! ```plain
+//! WARN - Injected NotReady into `db.query', waking it up right away
+//! WARN - Injected NotReady into `db.query', waking it up in 50ms
+//! WARN - Injected an error into `db.query': "injected"
+//! ```
+//!
+//! Errors are only injected into futures inspected through
+//! `LoggingExt::inspect_with_faults`, which builds them. Other futures
+//! and streams only get `NotReady` results.
+//!
+//! This module is only available with the `chaos` feature.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::time::Duration;
+use std::sync::RwLock;
+
+use config::matches_pattern;
+
+static FAULTS: RwLock<Vec<(String, Faults)>> = RwLock::new(Vec::new());
+/// Spares the polls the lock while no faults are injected.
+static HAS_FAULTS: AtomicBool = AtomicBool::new(false);
+/// The state of the generator the faults are drawn from.
+static RANDOM: AtomicU64 = AtomicU64::new(DEFAULT_SEED);
+/// The state the generator starts from, and restarts from for a seed of
+/// zero, which xorshift would never leave.
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// The faults to inject into the inspections matching a pattern,
+/// each with the probability of a poll getting it. The probabilities
+/// should add up to at most one.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Faults {
+    not_ready: f64,
+    delayed_wake: f64,
+    delay: Duration,
+    error: f64,
+}
+
+impl Faults {
+    /// Injects no faults.
+    pub fn new() -> Faults {
+        Faults::default()
+    }
+
+    /// Returns `NotReady` instead of polling with the probability
+    /// `probability`, waking the task up right away.
+    pub fn not_ready(mut self, probability: f64) -> Faults {
+        self.not_ready = probability;
+        self
+    }
+
+    /// Returns `NotReady` instead of polling with the probability
+    /// `probability`, waking the task up only `delay` later, from a
+    /// timer thread shared by all inspections.
+    pub fn delayed_wake(mut self, probability: f64, delay: Duration) -> Faults {
+        self.delayed_wake = probability;
+        self.delay = delay;
+        self
+    }
+
+    /// Returns an error instead of polling with the probability
+    /// `probability`, for futures inspected through
+    /// `LoggingExt::inspect_with_faults`.
+    pub fn error(mut self, probability: f64) -> Faults {
+        self.error = probability;
+        self
+    }
+
+    /// Draws the fault for a poll, with errors only if they can be
+    /// built. A single number is drawn, falling into the range of an
+    /// error, a delayed wake-up or a `NotReady` in that order, so each
+    /// fault comes with its own probability as long as they add up to
+    /// at most one.
+    #[cfg_attr(silenced, allow(dead_code))]
+    pub(crate) fn draw(&self, errors: bool) -> Option<Fault> {
+        if self.error <= 0.0 && self.delayed_wake <= 0.0 && self.not_ready <= 0.0 {
+            return None;
+        }
+        let random = random();
+        let error = self.error.max(0.0);
+        let delayed_wake = error + self.delayed_wake.max(0.0);
+        let not_ready = delayed_wake + self.not_ready.max(0.0);
+        if random < error {
+            if errors { Some(Fault::Error) } else { None }
+        } else if random < delayed_wake {
+            Some(Fault::DelayedWake(self.delay))
+        } else if random < not_ready {
+            Some(Fault::NotReady)
+        } else {
+            None
+        }
+    }
+}
+
+/// A fault injected into a poll.
+#[cfg_attr(silenced, allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Fault {
+    NotReady,
+    DelayedWake(Duration),
+    Error,
+}
+
+/// inject() injects `faults` into all inspections whose label matches
+/// `pattern`, including inspections that are already running. In
+/// `pattern`, `*` matches any sequence of characters.
+///
+/// When several patterns match a label, the one injected last
+/// applies. Injecting a pattern again replaces its faults.
+pub fn inject(pattern: &str, faults: Faults) {
+    let mut all = FAULTS.write().unwrap_or_else(|e| e.into_inner());
+    all.retain(|(existing, _)| existing != pattern);
+    all.push((pattern.to_owned(), faults));
+    HAS_FAULTS.store(true, Ordering::Release);
+}
+
+/// clear() stops injecting the faults set up through `inject()`.
+pub fn clear() {
+    let mut all = FAULTS.write().unwrap_or_else(|e| e.into_inner());
+    all.clear();
+    HAS_FAULTS.store(false, Ordering::Release);
+}
+
+/// seed() restarts the sequence the faults are drawn from at `seed`,
+/// so a test run injects the same faults again when its polls happen
+/// in the same order.
+pub fn seed(seed: u64) {
+    RANDOM.store(if seed == 0 { DEFAULT_SEED } else { seed }, Ordering::Relaxed);
+}
+
+/// The faults injected into `label`, if any.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn faults(label: &str) -> Option<Faults> {
+    if !HAS_FAULTS.load(Ordering::Acquire) {
+        return None;
+    }
+    let all = FAULTS.read().unwrap_or_else(|e| e.into_inner());
+    all.iter()
+       .rev()
+       .find(|(pattern, _)| matches_pattern(pattern, label))
+       .map(|&(_, faults)| faults)
+}
+
+/// Draws a number in `[0, 1)` through xorshift64*.
+fn random() -> f64 {
+    let step = |mut x: u64| {
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        x
+    };
+    let previous = RANDOM.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(step(x)))
+                         .unwrap_or_else(|x| x);
+    let random = step(previous).wrapping_mul(0x2545_f491_4f6c_dd1d);
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::time::Duration;
+
+    use super::{Fault, Faults, random, seed};
+
+    // One test, as the generator is shared by all threads.
+    #[test]
+    fn draws() {
+        seed(2);
+        let two: Vec<f64> = (0..4).map(|_| random()).collect();
+        seed(3);
+        let three: Vec<f64> = (0..4).map(|_| random()).collect();
+        seed(2);
+        let again: Vec<f64> = (0..4).map(|_| random()).collect();
+        assert_ne!(two, three);
+        assert_eq!(two, again);
+        seed(0);
+        assert!((0..4).map(|_| random()).any(|random| random != 0.0));
+
+        let delay = Duration::from_millis(5);
+        let faults = Faults::new().error(0.2).delayed_wake(0.3, delay).not_ready(0.1);
+        let share = |errors: bool, fault: Option<Fault>| {
+            (0..100_000).filter(|_| faults.draw(errors) == fault).count() as f64 / 100_000.0
+        };
+        assert!((share(true, Some(Fault::Error)) - 0.2).abs() < 0.01);
+        assert!((share(true, Some(Fault::DelayedWake(delay))) - 0.3).abs() < 0.01);
+        assert!((share(true, Some(Fault::NotReady)) - 0.1).abs() < 0.01);
+        assert!((share(true, None) - 0.4).abs() < 0.01);
+        assert_eq!(share(false, Some(Fault::Error)), 0.0);
+        assert!((share(false, Some(Fault::NotReady)) - 0.1).abs() < 0.01);
+        assert!((share(false, None) - 0.6).abs() < 0.01);
+        assert_eq!(Faults::new().draw(true), None);
+    }
+}
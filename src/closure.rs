@@ -0,0 +1,190 @@
+//! Logging support for futures written as closures.
+
+use futures::{IntoFuture, Poll};
+#[cfg(not(silenced))]
+use futures::Future;
+#[cfg(silenced)]
+use futures::future::{self, Lazy, PollFn};
+use core::fmt::Debug;
+#[cfg(not(silenced))]
+use core::fmt;
+
+#[cfg(not(silenced))]
+use inspector::Inspector;
+#[cfg(not(silenced))]
+use logger::EventKind;
+#[cfg(not(silenced))]
+use options::Inspect;
+
+/// LoggedPollFn works like the future returned by
+/// `future::poll_fn`, logging every call of its closure, see
+/// `logged_poll_fn()`.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct LoggedPollFn<F> {
+    poll: F,
+    inspector: Inspector,
+}
+
+#[cfg(not(silenced))]
+impl<T, E, F> Future for LoggedPollFn<F>
+    where T: Debug,
+          E: Debug,
+          F: FnMut() -> Poll<T, E>
+{
+    type Item = T;
+    type Error = E;
+
+    fn poll(&mut self) -> Poll<T, E> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::Polling, None);
+        if self.inspector.polls() == 1 {
+            self.inspector.emit(EventKind::ClosureInvoked, None);
+        }
+        let poll = (self.poll)();
+        let outcome = self.inspector.outcome(&poll);
+        self.inspector.end(&outcome);
+        self.inspector.emit(EventKind::Polled(outcome), stopwatch);
+        poll
+    }
+}
+
+/// logged_poll_fn() creates a future like `future::poll_fn`, calling
+/// `poll` on every poll, and logs it as `label` right away. The
+/// first call is logged apart from the polls, so a hand-written poll
+/// function is watched from its very start:
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_poll_log;
+/// # use futures::{Async, Future};
+/// # fn main() {
+/// let mut countdown = 2;
+/// let _ = futures_poll_log::logged_poll_fn("countdown", move || {
+///     countdown -= 1;
+///     match countdown {
+///         0 => Ok::<_, ()>(Async::Ready("done")),
+///         _ => {
+///             futures::task::park().unpark();
+///             Ok(Async::NotReady)
+///         }
+///     }
+/// }).wait();
+/// // Polling future `countdown'
+/// // Closure of `countdown' invoked for the first time
+/// // Future `countdown' polled: Ok(NotReady)
+/// # }
+/// ```
+///
+/// This function returns a plain `PollFn` when the `silence` feature
+/// is activated.
+#[cfg(not(silenced))]
+#[track_caller]
+pub fn logged_poll_fn<T, E, F>(label: &str, poll: F) -> LoggedPollFn<F>
+    where T: Debug,
+          E: Debug,
+          F: FnMut() -> Poll<T, E>
+{
+    LoggedPollFn {
+        poll,
+        inspector: Inspector::new(Inspect::new(label)),
+    }
+}
+/// logged_poll_fn() is `future::poll_fn` when the `silence` feature is activated.
+#[cfg(silenced)]
+pub fn logged_poll_fn<T, E, F>(_: &str, poll: F) -> PollFn<F>
+    where T: Debug,
+          E: Debug,
+          F: FnMut() -> Poll<T, E>
+{
+    future::poll_fn(poll)
+}
+
+/// The closure of a `LoggedLazy`, or the future it returned.
+#[cfg(not(silenced))]
+enum Lazily<F, R: IntoFuture> {
+    Closure(Option<F>),
+    Future(R::Future),
+}
+
+/// LoggedLazy works like the future returned by `future::lazy`,
+/// logging when its closure is called and every poll of the future
+/// it returned, see `logged_lazy()`.
+#[cfg(not(silenced))]
+pub struct LoggedLazy<F, R: IntoFuture> {
+    state: Lazily<F, R>,
+    inspector: Inspector,
+}
+
+#[cfg(not(silenced))]
+impl<F, R: IntoFuture> fmt::Debug for LoggedLazy<F, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoggedLazy")
+         .field("invoked", &matches!(self.state, Lazily::Future(_)))
+         .field("inspector", &self.inspector)
+         .finish()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<F, R> Future for LoggedLazy<F, R>
+    where F: FnOnce() -> R,
+          R: IntoFuture,
+          R::Item: Debug,
+          R::Error: Debug
+{
+    type Item = R::Item;
+    type Error = R::Error;
+
+    fn poll(&mut self) -> Poll<R::Item, R::Error> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::Polling, None);
+        if let Lazily::Closure(ref mut closure) = self.state {
+            self.inspector.emit(EventKind::ClosureInvoked, None);
+            let closure = closure.take().expect("polled after completion");
+            self.state = Lazily::Future(closure().into_future());
+        }
+        let poll = match self.state {
+            Lazily::Future(ref mut future) => future.poll(),
+            Lazily::Closure(_) => unreachable!("called above"),
+        };
+        let outcome = self.inspector.outcome(&poll);
+        self.inspector.end(&outcome);
+        self.inspector.emit(EventKind::Polled(outcome), stopwatch);
+        poll
+    }
+}
+
+/// logged_lazy() creates a future like `future::lazy`, calling
+/// `closure` on the first poll and polling the future it returns
+/// from then on, and logs it as `label`. The call of the closure is
+/// logged apart from the polls, which tells the work done to set the
+/// future up from the work of the future itself.
+///
+/// This function returns a plain `Lazy` when the `silence` feature is
+/// activated.
+#[cfg(not(silenced))]
+#[track_caller]
+pub fn logged_lazy<F, R>(label: &str, closure: F) -> LoggedLazy<F, R>
+    where F: FnOnce() -> R,
+          R: IntoFuture,
+          R::Item: Debug,
+          R::Error: Debug
+{
+    LoggedLazy {
+        state: Lazily::Closure(Some(closure)),
+        inspector: Inspector::new(Inspect::new(label)),
+    }
+}
+/// logged_lazy() is `future::lazy` when the `silence` feature is activated.
+#[cfg(silenced)]
+pub fn logged_lazy<F, R>(_: &str, closure: F) -> Lazy<F, R>
+    where F: FnOnce() -> R,
+          R: IntoFuture,
+          R::Item: Debug,
+          R::Error: Debug
+{
+    future::lazy(closure)
+}
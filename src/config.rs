@@ -0,0 +1,409 @@
+//! Process-wide defaults for all inspections.
+
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+#[cfg(feature="std")]
+use alloc::borrow::ToOwned;
+#[cfg(feature="std")]
+use alloc::string::String;
+#[cfg(feature="std")]
+use alloc::vec::Vec;
+#[cfg(feature="std")]
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature="std")]
+use std::sync::RwLock;
+
+use label::Label;
+use options::{Level, OutcomeLevels};
+use timing;
+
+/// How events are timestamped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Timestamps {
+    /// Events carry no timestamp, the default.
+    None,
+    /// Events are stamped with the time passed since `init()`
+    /// was called.
+    Elapsed,
+    /// Events are stamped with the time passed since the Unix epoch.
+    Unix,
+    /// Events are stamped with the wall-clock time, written in
+    /// RFC 3339 format in UTC, like `2024-05-01T12:30:00.000123Z`.
+    /// This keeps traces time-ordered even through backends that
+    /// do not timestamp their records.
+    Rfc3339,
+}
+
+impl Timestamps {
+    #[cfg_attr(silenced, allow(dead_code))]
+    pub(crate) fn now(self) -> Option<core::time::Duration> {
+        match self {
+            Timestamps::None => None,
+            Timestamps::Elapsed => timing::since_start(),
+            Timestamps::Unix | Timestamps::Rfc3339 => timing::since_unix_epoch(),
+        }
+    }
+}
+
+/// The amount of events written, as set through the
+/// `FUTURES_POLL_LOG_LEVEL` environment variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(any(silenced, not(feature="std")), allow(dead_code))]
+pub(crate) enum Verbosity {
+    /// `off`: nothing.
+    Off,
+    /// `error`: errors and warnings.
+    Error,
+    /// `summary`: errors, warnings and the summaries of all futures.
+    Summary,
+    /// `full`, the default: everything.
+    Full,
+}
+
+/// The verbosity set through `FUTURES_POLL_LOG_LEVEL`, read on first
+/// use. Unset or unknown values mean `Full`.
+#[cfg(feature="std")]
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn verbosity() -> Verbosity {
+    use std::sync::OnceLock;
+
+    static VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+    *VERBOSITY.get_or_init(|| {
+        let value = std::env::var("FUTURES_POLL_LOG_LEVEL").unwrap_or_default();
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" => Verbosity::Off,
+            "error" => Verbosity::Error,
+            "summary" => Verbosity::Summary,
+            _ => Verbosity::Full,
+        }
+    })
+}
+
+#[cfg(not(feature="std"))]
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn verbosity() -> Verbosity {
+    Verbosity::Full
+}
+
+/// Config holds the defaults inherited by every `Inspect`
+/// created after it was installed through `init()`.
+///
+/// All of them can still be overridden for a single inspection.
+///
+/// # Examples
+///
+/// ```rust
+/// use futures_poll_log::{Config, Level, Timestamps};
+///
+/// futures_poll_log::init(Config::new()
+///                            .level(Level::Trace)
+///                            .timestamps(Timestamps::Elapsed)
+///                            .truncate(80));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub(crate) level: Level,
+    pub(crate) outcome_levels: OutcomeLevels,
+    pub(crate) target: Cow<'static, str>,
+    pub(crate) timestamps: Timestamps,
+    pub(crate) relative_timestamps: bool,
+    pub(crate) truncate: Option<usize>,
+    pub(crate) single_line: bool,
+    pub(crate) format: Option<Arc<str>>,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) caller_locations: bool,
+}
+
+impl Config {
+    /// The configuration used before `init()` is called: events at
+    /// `Debug`, to the target `futures_log`, without timestamps or
+    /// truncation or a depth limit.
+    pub const fn new() -> Config {
+        Config {
+            level: Level::Debug,
+            outcome_levels: OutcomeLevels::new(),
+            target: Cow::Borrowed("futures_log"),
+            timestamps: Timestamps::None,
+            relative_timestamps: false,
+            truncate: None,
+            single_line: false,
+            format: None,
+            max_depth: None,
+            caller_locations: false,
+        }
+    }
+
+    /// Writes events at `level` by default.
+    pub fn level(mut self, level: Level) -> Config {
+        self.level = level;
+        self
+    }
+
+    /// Writes events reporting a `NotReady` poll at `level` by
+    /// default.
+    pub fn not_ready_level(mut self, level: Level) -> Config {
+        self.outcome_levels.not_ready = Some(level);
+        self
+    }
+
+    /// Writes events reporting a `Ready` poll at `level` by default.
+    pub fn ready_level(mut self, level: Level) -> Config {
+        self.outcome_levels.ready = Some(level);
+        self
+    }
+
+    /// Writes events reporting an error at `level` by default.
+    pub fn error_level(mut self, level: Level) -> Config {
+        self.outcome_levels.err = Some(level);
+        self
+    }
+
+    /// Writes events to the log target `target` by default.
+    pub fn target(mut self, target: &str) -> Config {
+        self.target = Cow::Owned(target.into());
+        self
+    }
+
+    /// Stamps events as given by `timestamps` by default.
+    ///
+    /// Timestamps are only available with the `std` feature.
+    pub fn timestamps(mut self, timestamps: Timestamps) -> Config {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Stamps events with the time since their inspection was set
+    /// up by default, see `Inspect::relative_timestamps`.
+    pub fn relative_timestamps(mut self) -> Config {
+        self.relative_timestamps = true;
+        self
+    }
+
+    /// Cuts items and errors printed in messages to at most `len`
+    /// characters by default.
+    pub fn truncate(mut self, len: usize) -> Config {
+        self.truncate = Some(len);
+        self
+    }
+
+    /// Keeps every message on a single line by default, see
+    /// `Inspect::single_line`.
+    pub fn single_line(mut self) -> Config {
+        self.single_line = true;
+        self
+    }
+
+    /// Formats messages by `template` by default, see
+    /// `Inspect::format`.
+    pub fn format(mut self, template: &str) -> Config {
+        self.format = Some(Arc::from(template));
+        self
+    }
+
+    /// Only writes events of inspections polled from within fewer
+    /// than `depth` other inspections by default.
+    pub fn max_depth(mut self, depth: usize) -> Config {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Writes where in the source every inspection was set up into
+    /// its events by default, see `Inspect::caller_location`.
+    pub fn caller_locations(mut self) -> Config {
+        self.caller_locations = true;
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::new()
+    }
+}
+
+/// init() installs `config` as the defaults of all following
+/// inspections. Inspections started before keep their options.
+///
+/// `Timestamps::Elapsed` counts from the first call of init().
+pub fn init(config: Config) {
+    if config.timestamps == Timestamps::Elapsed {
+        timing::since_start();
+    }
+    global::replace(config);
+}
+
+/// The defaults currently installed.
+pub(crate) fn current() -> Config {
+    global::get()
+}
+
+#[cfg(feature="std")]
+static LABEL_LEVELS: RwLock<Vec<(String, Level)>> = RwLock::new(Vec::new());
+/// Spares events the lock while no overrides are set.
+#[cfg(feature="std")]
+static HAS_LABEL_LEVELS: AtomicBool = AtomicBool::new(false);
+
+/// set_label_level() writes the events of all inspections whose
+/// label matches `pattern` at `level`, including inspections that
+/// are already running. In `pattern`, `*` matches any sequence of
+/// characters, e.g. `db*` matches `db` and `db.query`.
+///
+/// When several patterns match a label, the one set last applies.
+/// Setting a pattern again replaces its level.
+///
+/// This function is only available with the `std` feature.
+#[cfg(feature="std")]
+pub fn set_label_level(pattern: &str, level: Level) {
+    let mut levels = LABEL_LEVELS.write().unwrap_or_else(|e| e.into_inner());
+    levels.retain(|(existing, _)| existing != pattern);
+    levels.push((pattern.to_owned(), level));
+    HAS_LABEL_LEVELS.store(true, Ordering::Release);
+}
+
+/// clear_label_levels() removes all levels set through
+/// `set_label_level()`.
+///
+/// This function is only available with the `std` feature.
+#[cfg(feature="std")]
+pub fn clear_label_levels() {
+    let mut levels = LABEL_LEVELS.write().unwrap_or_else(|e| e.into_inner());
+    levels.clear();
+    HAS_LABEL_LEVELS.store(false, Ordering::Release);
+}
+
+/// The level set for `label` through `set_label_level()`, if any.
+#[cfg(feature="std")]
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn label_level(label: &Label) -> Option<Level> {
+    // Checked first, so a lazy label is not written for nothing.
+    if !HAS_LABEL_LEVELS.load(Ordering::Acquire) {
+        return None;
+    }
+    let levels = LABEL_LEVELS.read().unwrap_or_else(|e| e.into_inner());
+    levels
+        .iter()
+        .rev()
+        .find(|(pattern, _)| matches_pattern(pattern, label))
+        .map(|&(_, level)| level)
+}
+
+#[cfg(not(feature="std"))]
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn label_level(_: &Label) -> Option<Level> {
+    None
+}
+
+/// Matches `label` against `pattern`, where `*` stands for any
+/// sequence of characters.
+#[cfg(feature="std")]
+pub(crate) fn matches_pattern(pattern: &str, label: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    if !label.starts_with(first) {
+        return false;
+    }
+    let mut rest = &label[first.len()..];
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        // No `*` at all.
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(feature="std")]
+mod global {
+    use std::sync::RwLock;
+
+    use super::Config;
+
+    static CONFIG: RwLock<Config> = RwLock::new(Config::new());
+
+    pub(crate) fn replace(config: Config) {
+        *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    pub(crate) fn get() -> Config {
+        CONFIG.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Without `std`, installed configurations are leaked, like
+/// global backends.
+#[cfg(not(feature="std"))]
+mod global {
+    use alloc::boxed::Box;
+    use core::ptr;
+    use core::sync::atomic::{AtomicPtr, Ordering};
+
+    use super::Config;
+
+    static CONFIG: AtomicPtr<Config> = AtomicPtr::new(ptr::null_mut());
+
+    pub(crate) fn replace(config: Config) {
+        CONFIG.store(Box::into_raw(Box::new(config)), Ordering::Release);
+    }
+
+    pub(crate) fn get() -> Config {
+        // Safety: pointers stored in CONFIG come from `Box::into_raw`
+        // and are never freed.
+        match unsafe { CONFIG.load(Ordering::Acquire).as_ref() } {
+            Some(config) => config.clone(),
+            None => Config::new(),
+        }
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use super::matches_pattern;
+
+    #[test]
+    fn patterns_without_wildcards_match_exactly() {
+        assert!(matches_pattern("db", "db"));
+        assert!(matches_pattern("", ""));
+        assert!(!matches_pattern("db", "db.query"));
+        assert!(!matches_pattern("db", "d"));
+        assert!(!matches_pattern("", "db"));
+    }
+
+    #[test]
+    fn wildcards_match_any_sequence() {
+        assert!(matches_pattern("*", ""));
+        assert!(matches_pattern("*", "db.query"));
+        assert!(matches_pattern("db*", "db"));
+        assert!(matches_pattern("db*", "db.query"));
+        assert!(!matches_pattern("db*", "a.db"));
+        assert!(matches_pattern("*query", "db.query"));
+        assert!(!matches_pattern("*query", "db.query.1"));
+        assert!(matches_pattern("*.query.*", "db.query.1"));
+        assert!(!matches_pattern("*.query.*", "query.1"));
+        assert!(matches_pattern("db.*.*", "db.a.b"));
+        assert!(matches_pattern("db**", "db"));
+        assert!(matches_pattern("a*b*c", "abc"));
+        assert!(matches_pattern("a*b*c", "a.b.b.c"));
+        assert!(!matches_pattern("a*b*c", "a.c.b"));
+    }
+
+    #[test]
+    fn prefix_and_suffix_do_not_overlap() {
+        assert!(!matches_pattern("ab*ba", "aba"));
+        assert!(matches_pattern("ab*ba", "abba"));
+        assert!(!matches_pattern("a*a", "a"));
+        assert!(matches_pattern("a*a", "aa"));
+    }
+
+    #[test]
+    fn wildcards_work_on_characters() {
+        assert!(matches_pattern("é*ü", "éaü"));
+        assert!(matches_pattern("*ü", "ü"));
+        assert!(!matches_pattern("é*", "e"));
+    }
+}
@@ -0,0 +1,290 @@
+//! The stack of inspections being polled on the current thread.
+//!
+//! Without `std`, there are no thread locals and the stack
+//! always appears empty.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature="std")]
+use core::cell::{Cell, RefCell};
+use core::fmt;
+#[cfg(feature="std")]
+use core::marker::PhantomData;
+
+use label::Label;
+
+/// A key/value pair of diagnostic context.
+pub(crate) type Field = (Arc<str>, Arc<str>);
+
+/// An inspection on the poll stack.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature="std"), allow(dead_code))]
+pub(crate) struct Frame {
+    pub(crate) label: Label,
+    pub(crate) instance: u64,
+    pub(crate) correlation: Option<Arc<str>>,
+    pub(crate) context: Option<Arc<[Field]>>,
+    /// Whether the inspection logs nothing of its own and hands
+    /// its label to the one it wraps, see `Nesting::Merge`.
+    pub(crate) merged: bool,
+}
+
+#[cfg(feature="std")]
+thread_local! {
+    static STACK: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+    /// The inspected futures that resolved in a row since the
+    /// outermost one was entered, and whether the streak was
+    /// reported.
+    static STREAK: Cell<(u32, bool)> = const { Cell::new((0, false)) };
+    /// The context pushed through `push_context()`.
+    static PUSHED: RefCell<Vec<Field>> = const { RefCell::new(Vec::new()) };
+}
+
+/// ContextGuard keeps a key/value pair in the diagnostic context
+/// of the current thread until it is dropped, see `push_context()`.
+#[cfg(feature="std")]
+#[derive(Debug)]
+#[must_use = "the context is only kept while the guard is alive"]
+pub struct ContextGuard {
+    /// The number of pairs pushed before this one.
+    outer: usize,
+    // The context belongs to the thread it was pushed on.
+    _thread: PhantomData<*const ()>,
+}
+
+/// push_context() adds `key` and `value` to the diagnostic context
+/// of the current thread until the returned guard is dropped. All
+/// events emitted meanwhile carry it, as `PollEvent::context`:
+///
+/// ```rust
+/// let _tenant = futures_poll_log::push_context("tenant", "acme");
+/// // Polls on this thread are logged like
+/// // `[tenant=acme] Polling future `request''.
+/// ```
+///
+/// To keep the context with a future across its polls, set it on the
+/// inspection through `Inspect::context` instead. Every event emitted
+/// while that inspection is polled carries it, including the ones of
+/// the inspections it polls in turn.
+///
+/// This function is only available with the `std` feature.
+#[cfg(feature="std")]
+pub fn push_context(key: &str, value: &str) -> ContextGuard {
+    let outer = PUSHED.with(|pushed| {
+        let mut pushed = pushed.borrow_mut();
+        pushed.push((Arc::from(key), Arc::from(value)));
+        pushed.len() - 1
+    });
+    ContextGuard {
+        outer,
+        _thread: PhantomData,
+    }
+}
+
+#[cfg(feature="std")]
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let _ = PUSHED.try_with(|pushed| pushed.borrow_mut().truncate(self.outer));
+    }
+}
+
+/// ContextFields is the diagnostic context of an event: the pairs
+/// set through `Inspect::context` on the inspections being polled
+/// and through `push_context()`.
+#[derive(Clone, Copy, Default)]
+pub struct ContextFields<'a>(&'a [Field]);
+
+impl<'a> ContextFields<'a> {
+    pub(crate) fn new(fields: &'a [Field]) -> ContextFields<'a> {
+        ContextFields(fields)
+    }
+
+    /// Whether there is no context.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The key/value pairs, outermost first.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.0.iter().map(|(key, value)| (&**key, &**value))
+    }
+}
+
+impl<'a> fmt::Debug for ContextFields<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Writes the pairs as `key=value`, separated by spaces.
+impl<'a> fmt::Display for ContextFields<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds `new` to `fields`, replacing the value of a pair with
+/// the same key.
+fn merge(fields: &mut Vec<Field>, new: &[Field]) {
+    for field in new {
+        match fields.iter_mut().find(|existing| existing.0 == field.0) {
+            Some(existing) => existing.1 = field.1.clone(),
+            None => fields.push(field.clone()),
+        }
+    }
+}
+
+/// The diagnostic context of an event of the inspection numbered
+/// `instance`, whose own context is `own`. Inner inspections and
+/// pushed pairs take precedence over outer ones with the same key.
+#[cfg(feature="std")]
+pub(crate) fn fields(own: Option<&Arc<[Field]>>, instance: u64) -> Vec<Field> {
+    let mut fields = Vec::new();
+    let polling = STACK.try_with(|stack| {
+                           let stack = stack.borrow();
+                           for context in stack.iter().filter_map(|frame| frame.context.as_ref()) {
+                               merge(&mut fields, context);
+                           }
+                           stack.iter().any(|frame| frame.instance == instance)
+                       })
+                       .unwrap_or(false);
+    if let (false, Some(own)) = (polling, own) {
+        merge(&mut fields, own);
+    }
+    let _ = PUSHED.try_with(|pushed| merge(&mut fields, &pushed.borrow()));
+    fields
+}
+
+#[cfg(not(feature="std"))]
+pub(crate) fn fields(own: Option<&Arc<[Field]>>, _: u64) -> Vec<Field> {
+    let mut fields = Vec::new();
+    if let Some(own) = own {
+        merge(&mut fields, own);
+    }
+    fields
+}
+
+/// Keeps an inspection on the poll stack until dropped.
+#[derive(Debug)]
+#[must_use]
+pub(crate) struct Entered {
+    _private: (),
+}
+
+/// Pushes `frame` onto the poll stack of the current thread,
+/// returning the frame of the inspection polling it, if any.
+#[cfg(feature="std")]
+pub(crate) fn enter(frame: Frame) -> (Entered, Option<Frame>) {
+    let parent = STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let parent = stack.last().cloned();
+        if parent.is_none() {
+            // A new poll of a task by its executor.
+            STREAK.with(|streak| streak.set((0, false)));
+        }
+        stack.push(frame);
+        parent
+    });
+    (Entered { _private: () }, parent)
+}
+
+/// The number of inspections on the poll stack of the current
+/// thread.
+#[cfg(feature="std")]
+pub(crate) fn depth() -> usize {
+    STACK.try_with(|stack| stack.borrow().len()).unwrap_or(0)
+}
+
+#[cfg(not(feature="std"))]
+pub(crate) fn depth() -> usize {
+    0
+}
+
+/// The label and instance of the inspection being polled on the
+/// current thread, if any.
+#[cfg(feature="std")]
+pub(crate) fn current() -> Option<(Label, u64)> {
+    STACK.try_with(|stack| stack.borrow().last().map(|frame| (frame.label.clone(), frame.instance)))
+         .unwrap_or(None)
+}
+
+#[cfg(not(feature="std"))]
+pub(crate) fn current() -> Option<(Label, u64)> {
+    None
+}
+
+/// The label of the inspection being polled on the current thread,
+/// if it merges its label into the one it wraps.
+#[cfg(feature="std")]
+pub(crate) fn merged_label() -> Option<Label> {
+    STACK.try_with(|stack| {
+             stack.borrow().last().and_then(|frame| if frame.merged { Some(frame.label.clone()) } else { None })
+         })
+         .unwrap_or(None)
+}
+
+#[cfg(not(feature="std"))]
+pub(crate) fn merged_label() -> Option<Label> {
+    None
+}
+
+/// The correlation id of the inspection being polled on the
+/// current thread, if any.
+#[cfg(feature="std")]
+pub(crate) fn correlation() -> Option<Arc<str>> {
+    STACK.try_with(|stack| stack.borrow().last().and_then(|frame| frame.correlation.clone()))
+         .unwrap_or(None)
+}
+
+#[cfg(not(feature="std"))]
+pub(crate) fn correlation() -> Option<Arc<str>> {
+    None
+}
+
+/// Whether the inspection numbered `instance` is on the poll
+/// stack of the current thread.
+#[cfg(feature="std")]
+pub(crate) fn contains(instance: u64) -> bool {
+    STACK.try_with(|stack| stack.borrow().iter().any(|frame| frame.instance == instance))
+         .unwrap_or(false)
+}
+
+/// Counts a poll resulting in `Ready` if `ready` is set, and
+/// ends the current streak of them otherwise. Returns the length
+/// of the streak the first time it exceeds `budget` within the
+/// current poll of the task.
+#[cfg(feature="std")]
+pub(crate) fn ready_streak(ready: bool, budget: Option<u32>) -> Option<u32> {
+    STREAK.try_with(|streak| {
+              let (count, reported) = streak.get();
+              let count = if ready { count + 1 } else { 0 };
+              let exceeded = budget.is_some_and(|budget| count > budget) && !reported;
+              streak.set((count, reported || exceeded));
+              if exceeded { Some(count) } else { None }
+          })
+          .unwrap_or(None)
+}
+
+#[cfg(not(feature="std"))]
+pub(crate) fn contains(_: u64) -> bool {
+    false
+}
+
+#[cfg(not(feature="std"))]
+pub(crate) fn enter(_: Frame) -> (Entered, Option<Frame>) {
+    (Entered { _private: () }, None)
+}
+
+#[cfg(feature="std")]
+impl Drop for Entered {
+    fn drop(&mut self) {
+        // The thread local is gone while the thread is torn down.
+        let _ = STACK.try_with(|stack| stack.borrow_mut().pop());
+    }
+}
@@ -0,0 +1,28 @@
+//! A backend emitting poll events through `defmt`.
+
+use defmt::Display2Format;
+
+use logger::{PollEvent, PollLogger};
+use options::Level;
+
+/// A PollLogger that emits all events through `defmt`, for
+/// debugging executors on microcontrollers.
+///
+/// The label is sent as a `str`, the rest of the message is
+/// formatted on the device through `core::fmt`.
+///
+/// This backend is available with the `defmt` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefmtLogger;
+
+impl PollLogger for DefmtLogger {
+    fn on_event(&self, event: &PollEvent) {
+        match event.level {
+            Level::Error => defmt::error!("{=str}: {}", event.label, Display2Format(event)),
+            Level::Warn => defmt::warn!("{=str}: {}", event.label, Display2Format(event)),
+            Level::Info => defmt::info!("{=str}: {}", event.label, Display2Format(event)),
+            Level::Debug => defmt::debug!("{=str}: {}", event.label, Display2Format(event)),
+            Level::Trace => defmt::trace!("{=str}: {}", event.label, Display2Format(event)),
+        }
+    }
+}
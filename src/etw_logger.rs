@@ -0,0 +1,197 @@
+//! A backend emitting poll events through Event Tracing for Windows.
+
+use std::ffi::c_void;
+use std::ptr;
+
+use etw_metadata::{event_metadata, guid_fields, nul_terminated, provider_traits};
+use logger::{EventKind, PollEvent, PollLogger};
+use options::Level;
+
+/// The layout of a Windows `GUID`.
+#[repr(C)]
+struct Guid {
+    data1: u32,
+    data2: u16,
+    data3: u16,
+    data4: [u8; 8],
+}
+
+/// The layout of `EVENT_DESCRIPTOR`.
+#[repr(C)]
+struct EventDescriptor {
+    id: u16,
+    version: u8,
+    channel: u8,
+    level: u8,
+    opcode: u8,
+    task: u16,
+    keyword: u64,
+}
+
+/// The layout of `EVENT_DATA_DESCRIPTOR`.
+#[repr(C)]
+struct EventDataDescriptor {
+    ptr: u64,
+    size: u32,
+    kind: u8,
+    reserved1: u8,
+    reserved2: u16,
+}
+
+impl EventDataDescriptor {
+    fn new(data: &[u8], kind: u8) -> EventDataDescriptor {
+        EventDataDescriptor {
+            ptr: data.as_ptr() as u64,
+            size: data.len() as u32,
+            kind,
+            reserved1: 0,
+            reserved2: 0,
+        }
+    }
+}
+
+type EnableCallback = Option<unsafe extern "system" fn()>;
+
+#[link(name = "advapi32")]
+extern "system" {
+    fn EventRegister(provider: *const Guid, callback: EnableCallback, context: *const c_void, handle: *mut u64) -> u32;
+    fn EventUnregister(handle: u64) -> u32;
+    fn EventSetInformation(handle: u64, class: i32, information: *const c_void, length: u32) -> u32;
+    fn EventProviderEnabled(handle: u64, level: u8, keyword: u64) -> u8;
+    fn EventWriteTransfer(handle: u64,
+                          descriptor: *const EventDescriptor,
+                          activity: *const Guid,
+                          related: *const Guid,
+                          count: u32,
+                          data: *const EventDataDescriptor)
+                          -> u32;
+}
+
+/// `EventProviderSetTraits`.
+const SET_TRAITS: i32 = 2;
+/// The channel of TraceLogging events.
+const CHANNEL_TRACELOGGING: u8 = 11;
+/// The data descriptor types of the TraceLogging metadata.
+const EVENT_METADATA: u8 = 1;
+const PROVIDER_METADATA: u8 = 2;
+/// The opcodes marking the start and the end of an activity.
+const OPCODE_START: u8 = 1;
+const OPCODE_STOP: u8 = 2;
+
+/// A PollLogger that emits every event as a TraceLogging event of
+/// an ETW provider, so polls show up in Windows Performance Analyzer
+/// next to the scheduling of the threads running them:
+///
+/// ```rust,ignore
+/// use std::sync::Arc;
+/// use futures_poll_log::EtwLogger;
+///
+/// let logger = EtwLogger::new("MyService.Futures", 0x5c2d_6a3e_8f4b_4c1d_9e27_1b3a_4f6d_8c90)?;
+/// futures_poll_log::set_poll_logger(Arc::new(logger));
+/// ```
+///
+/// and record with `wpr`, or `tracelog -guid #5c2d6a3e-8f4b-4c1d-9e27-1b3a4f6d8c90`.
+///
+/// The start of a poll is the event `PollBegin` with the opcode
+/// `Start`, and its result `PollEnd` with the opcode `Stop`. All
+/// other events are named `Event`. Each carries the fields `Label`,
+/// `Instance`, `Poll`, `Kind`, `Message` and `DurationUs`, 0 unless
+/// the poll was timed. Events are only formatted while a session
+/// records the provider at their level.
+///
+/// This backend is available with the `etw` feature, on Windows.
+#[derive(Debug)]
+pub struct EtwLogger {
+    handle: u64,
+    /// The provider traits passed with every event.
+    traits: Vec<u8>,
+}
+
+impl EtwLogger {
+    /// Registers the provider `name` with the GUID `guid`, written
+    /// as a number, e.g. `0x5c2d6a3e_8f4b_4c1d_9e27_1b3a4f6d8c90`
+    /// for `{5c2d6a3e-8f4b-4c1d-9e27-1b3a4f6d8c90}`.
+    pub fn new(name: &str, guid: u128) -> std::io::Result<EtwLogger> {
+        let (data1, data2, data3, data4) = guid_fields(guid);
+        let guid = Guid { data1, data2, data3, data4 };
+        let mut handle = 0;
+        // Safety: `guid` and `handle` outlive the call, and no
+        // callback is registered.
+        let status = unsafe { EventRegister(&guid, None, ptr::null(), &mut handle) };
+        if status != 0 {
+            return Err(std::io::Error::from_raw_os_error(status as i32));
+        }
+        let traits = provider_traits(name);
+        // Safety: `traits` outlives the call. Without the traits the
+        // provider still works, its events just lack its name.
+        unsafe {
+            EventSetInformation(handle, SET_TRAITS, traits.as_ptr() as *const c_void, traits.len() as u32);
+        }
+        Ok(EtwLogger { handle, traits })
+    }
+}
+
+impl Drop for EtwLogger {
+    fn drop(&mut self) {
+        // Safety: the handle was registered by `new()`.
+        unsafe {
+            EventUnregister(self.handle);
+        }
+    }
+}
+
+impl PollLogger for EtwLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let level = match event.level {
+            Level::Error => 2,
+            Level::Warn => 3,
+            Level::Info => 4,
+            Level::Debug | Level::Trace => 5,
+        };
+        // Safety: a plain query on a registered handle.
+        if unsafe { EventProviderEnabled(self.handle, level, 0) } == 0 {
+            return;
+        }
+        let (name, opcode) = match event.kind {
+            EventKind::Polling | EventKind::StreamPolling => ("PollBegin", OPCODE_START),
+            _ if event.outcome().is_some() => ("PollEnd", OPCODE_STOP),
+            _ => ("Event", 0),
+        };
+        let descriptor = EventDescriptor {
+            id: 0,
+            version: 0,
+            channel: CHANNEL_TRACELOGGING,
+            level,
+            opcode,
+            task: 0,
+            keyword: 0,
+        };
+        let metadata = event_metadata(name);
+        let label = nul_terminated(event.label);
+        let instance = event.instance.to_le_bytes();
+        let poll = event.poll.to_le_bytes();
+        let kind = nul_terminated(event.kind.name());
+        let message = nul_terminated(&event.to_string());
+        let duration = event.duration.map_or(0, |duration| duration.as_micros() as u64).to_le_bytes();
+        let data = [
+            EventDataDescriptor::new(&self.traits, PROVIDER_METADATA),
+            EventDataDescriptor::new(&metadata, EVENT_METADATA),
+            EventDataDescriptor::new(&label, 0),
+            EventDataDescriptor::new(&instance, 0),
+            EventDataDescriptor::new(&poll, 0),
+            EventDataDescriptor::new(&kind, 0),
+            EventDataDescriptor::new(&message, 0),
+            EventDataDescriptor::new(&duration, 0),
+        ];
+        // Safety: all buffers described by `data` live until the
+        // call returns.
+        unsafe {
+            EventWriteTransfer(self.handle,
+                               &descriptor,
+                               ptr::null(),
+                               ptr::null(),
+                               data.len() as u32,
+                               data.as_ptr());
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! The TraceLogging metadata written by `EtwLogger`, kept apart from
+//! the calls into Windows so it is tested on every platform.
+
+/// The TraceLogging types of the fields: nul-terminated UTF-8
+/// strings and unsigned 64 bit integers.
+const IN_ANSISTRING_WITH_OUT_TYPE: u8 = 2 | 0x80;
+const OUT_UTF8: u8 = 35;
+const IN_UINT64: u8 = 10;
+
+/// The fields of every event, in the order `EtwLogger` writes them.
+const FIELDS: [(&str, u8); 6] = [("Label", IN_ANSISTRING_WITH_OUT_TYPE),
+                                 ("Instance", IN_UINT64),
+                                 ("Poll", IN_UINT64),
+                                 ("Kind", IN_ANSISTRING_WITH_OUT_TYPE),
+                                 ("Message", IN_ANSISTRING_WITH_OUT_TYPE),
+                                 ("DurationUs", IN_UINT64)];
+
+/// The provider traits naming the provider `name`.
+pub(crate) fn provider_traits(name: &str) -> Vec<u8> {
+    let mut traits = vec![0, 0];
+    traits.extend_from_slice(&nul_terminated(name));
+    sized(traits)
+}
+
+/// The TraceLogging metadata of the event `name`, describing the
+/// fields written by `EtwLogger`.
+pub(crate) fn event_metadata(name: &str) -> Vec<u8> {
+    let mut metadata = vec![0, 0, 0];
+    metadata.extend_from_slice(&nul_terminated(name));
+    for &(field, in_type) in &FIELDS {
+        metadata.extend_from_slice(&nul_terminated(field));
+        metadata.push(in_type);
+        if in_type == IN_ANSISTRING_WITH_OUT_TYPE {
+            metadata.push(OUT_UTF8);
+        }
+    }
+    sized(metadata)
+}
+
+/// `text` as a nul-terminated string, cut at the first nul in it.
+pub(crate) fn nul_terminated(text: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = text.bytes().take_while(|&byte| byte != 0).collect();
+    bytes.push(0);
+    bytes
+}
+
+/// The fields of a `GUID` written as a number, e.g.
+/// `0x5c2d6a3e_8f4b_4c1d_9e27_1b3a4f6d8c90` for
+/// `{5c2d6a3e-8f4b-4c1d-9e27-1b3a4f6d8c90}`.
+pub(crate) fn guid_fields(guid: u128) -> (u32, u16, u16, [u8; 8]) {
+    ((guid >> 96) as u32, (guid >> 80) as u16, (guid >> 64) as u16, (guid as u64).to_be_bytes())
+}
+
+/// `bytes` with its size, including the size itself, written over
+/// its first two bytes.
+fn sized(mut bytes: Vec<u8>) -> Vec<u8> {
+    let size = (bytes.len() as u16).to_le_bytes();
+    bytes[..2].copy_from_slice(&size);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{event_metadata, guid_fields, nul_terminated, provider_traits};
+
+    #[test]
+    fn provider_traits_carry_their_size_and_name() {
+        assert_eq!(provider_traits("App"), b"\x06\x00App\x00");
+    }
+
+    #[test]
+    fn event_metadata_describes_every_field() {
+        let mut expected = b"\x00\x00\x00End\x00".to_vec();
+        expected.extend_from_slice(b"Label\x00\x82\x23");
+        expected.extend_from_slice(b"Instance\x00\x0a");
+        expected.extend_from_slice(b"Poll\x00\x0a");
+        expected.extend_from_slice(b"Kind\x00\x82\x23");
+        expected.extend_from_slice(b"Message\x00\x82\x23");
+        expected.extend_from_slice(b"DurationUs\x00\x0a");
+        expected[0] = expected.len() as u8;
+        assert_eq!(event_metadata("End"), expected);
+    }
+
+    #[test]
+    fn strings_are_cut_at_their_first_nul() {
+        assert_eq!(nul_terminated("a\0b"), b"a\0");
+        assert_eq!(nul_terminated(""), b"\0");
+    }
+
+    #[test]
+    fn guids_are_split_into_their_fields() {
+        assert_eq!(guid_fields(0x5c2d6a3e_8f4b_4c1d_9e27_1b3a4f6d8c90),
+                   (0x5c2d6a3e, 0x8f4b, 0x4c1d, [0x9e, 0x27, 0x1b, 0x3a, 0x4f, 0x6d, 0x8c, 0x90]));
+    }
+}
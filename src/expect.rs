@@ -0,0 +1,272 @@
+//! Checking the order of poll events in tests.
+//!
+//! `assert_events()` runs a future to completion on the current thread,
+//! captures the events of all inspections polled on the way and compares
+//! them to the expected ones, panicking with a diff if they differ:
+//!
+//! ```rust
+//! extern crate futures;
+//! extern crate futures_poll_log;
+//!
+//! use futures::{Future, future};
+//! use futures_poll_log::LoggingExt;
+//! use futures_poll_log::expect::{self, poll, ready};
+//!
+//! # #[cfg(not(silenced))]
+//! # fn main() {
+//! let a = future::ok::<u32, ()>(1).inspect("a");
+//! let b = future::ok::<u32, ()>(2).inspect("b");
+//! let both = a.join(b);
+//!
+//! expect::assert_events(both, &[poll("a"), ready("a"), poll("b"), ready("b")]);
+//! # }
+//! # #[cfg(silenced)]
+//! # fn main() {}
+//! ```
+//!
+//! Only the events of the labels and kinds named by the expectations are
+//! compared, so `poll("a"), ready("a")` holds for a future that returned
+//! `NotReady` a few times before, while adding `not_ready("a")` pins each of
+//! those polls down.
+//!
+//! Events are captured per thread, so tests running in parallel do not see
+//! each other's events, whatever backend is installed. With the `silence`
+//! feature, no events are captured, and `assert_events()` panics saying
+//! so instead of showing a diff.
+//!
+//! This module is only available with the `std` feature.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+use futures::Future;
+use std::error::Error;
+
+use logger::PollEvent;
+use record::{EventRecord, RecordedOutcome};
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<EventRecord>>> = const { RefCell::new(None) };
+}
+
+/// An event expected to be logged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expected {
+    label: String,
+    kind: &'static str,
+}
+
+impl Expected {
+    /// The label the event is expected for.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The kind of the event, `poll`, `not_ready`, `ready`, `err` or
+    /// the name of any other `EventKind`.
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} `{}'", self.kind, self.label)
+    }
+}
+
+/// A poll of the inspection labeled `label` starts.
+pub fn poll(label: &str) -> Expected {
+    event(label, "poll")
+}
+
+/// A poll of `label` returns `NotReady`.
+pub fn not_ready(label: &str) -> Expected {
+    event(label, "not_ready")
+}
+
+/// A poll of `label` returns `Ready`.
+pub fn ready(label: &str) -> Expected {
+    event(label, "ready")
+}
+
+/// A poll of `label` returns an error.
+pub fn err(label: &str) -> Expected {
+    event(label, "err")
+}
+
+/// The inspection labeled `label` is dropped.
+pub fn dropped(label: &str) -> Expected {
+    event(label, "dropped")
+}
+
+/// An event of `label` with the kind named `kind`, as returned by
+/// `EventKind::name()`, e.g. `"stream_item"`.
+pub fn event(label: &str, kind: &'static str) -> Expected {
+    Expected {
+        label: label.into(),
+        kind,
+    }
+}
+
+/// The kind a recorded event is compared by. Polls of futures,
+/// streams and sinks are told apart by their outcome.
+fn kind_of(record: &EventRecord) -> &'static str {
+    match (record.kind, &record.outcome) {
+        ("polling", _) => "poll",
+        ("polled", &Some(RecordedOutcome::NotReady)) => "not_ready",
+        ("polled", &Some(RecordedOutcome::Ready(_))) => "ready",
+        ("polled", &Some(RecordedOutcome::Err(_))) => "err",
+        (kind, _) => kind,
+    }
+}
+
+/// capture() runs `f` and returns its result along with the events of
+/// all inspections sent on the current thread meanwhile.
+pub fn capture<R, F: FnOnce() -> R>(f: F) -> (R, Vec<EventRecord>) {
+    let outer = CAPTURED.with(|captured| captured.replace(Some(Vec::new())));
+    let result = f();
+    let events = CAPTURED.with(|captured| captured.replace(outer)).unwrap_or_default();
+    (result, events)
+}
+
+/// check() compares `events` to `expected`, considering only the events
+/// of the labels and kinds named by `expected`.
+pub fn check(expected: &[Expected], events: &[EventRecord]) -> Result<(), Mismatch> {
+    let logged: Vec<Expected> = events.iter()
+        .map(|record| event(&record.label, kind_of(record)))
+        .filter(|logged| {
+            expected.iter().any(|expected| expected.label == logged.label) &&
+            expected.iter().any(|expected| expected.kind == logged.kind)
+        })
+        .collect();
+    if logged == expected {
+        return Ok(());
+    }
+    Err(Mismatch { lines: diff(expected, &logged) })
+}
+
+/// assert_events() runs `future` to completion on the current thread and
+/// returns its result, after checking that it logged the `expected`
+/// events.
+///
+/// # Panics
+///
+/// Panics with a diff of the events if they do not match, and when
+/// any events are expected with the logging compiled out through the
+/// `silence` feature.
+pub fn assert_events<F: Future>(future: F, expected: &[Expected]) -> Result<F::Item, F::Error> {
+    if cfg!(silenced) && !expected.is_empty() {
+        panic!("no events are captured with the `silence` feature activated, so none can be asserted on");
+    }
+    let (result, events) = capture(|| future.wait());
+    if let Err(mismatch) = check(expected, &events) {
+        panic!("{}", mismatch);
+    }
+    result
+}
+
+/// Keeps `event`, if the current thread is capturing.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn record(event: &PollEvent) {
+    let _ = CAPTURED.try_with(|captured| {
+        if let Some(ref mut events) = *captured.borrow_mut() {
+            events.push(event.to_record());
+        }
+    });
+}
+
+/// Whether the current thread is capturing.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn is_capturing() -> bool {
+    CAPTURED.try_with(|captured| captured.borrow().is_some()).unwrap_or(false)
+}
+
+/// A line of the diff between the expected and the logged events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    Both(Expected),
+    Missing(Expected),
+    Unexpected(Expected),
+}
+
+/// The difference between the expected events and the logged ones,
+/// displayed as a diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    lines: Vec<Line>,
+}
+
+impl Mismatch {
+    /// The expected events that were not logged.
+    pub fn missing(&self) -> Vec<&Expected> {
+        self.lines
+            .iter()
+            .filter_map(|line| match *line {
+                Line::Missing(ref expected) => Some(expected),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The logged events that were not expected.
+    pub fn unexpected(&self) -> Vec<&Expected> {
+        self.lines
+            .iter()
+            .filter_map(|line| match *line {
+                Line::Unexpected(ref logged) => Some(logged),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the logged events differ from the expected ones (- expected, + logged):")?;
+        for line in &self.lines {
+            match *line {
+                Line::Both(ref event) => write!(f, "\n  {}", event)?,
+                Line::Missing(ref event) => write!(f, "\n- {}", event)?,
+                Line::Unexpected(ref event) => write!(f, "\n+ {}", event)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for Mismatch {}
+
+/// The lines of a diff from `expected` to `logged`, keeping their
+/// longest common subsequence.
+fn diff(expected: &[Expected], logged: &[Expected]) -> Vec<Line> {
+    // common[i][j] is the length of the longest common subsequence of
+    // expected[i..] and logged[j..].
+    let mut common = vec![vec![0usize; logged.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..logged.len()).rev() {
+            common[i][j] = if expected[i] == logged[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < expected.len() || j < logged.len() {
+        if i < expected.len() && j < logged.len() && expected[i] == logged[j] {
+            lines.push(Line::Both(expected[i].clone()));
+            i += 1;
+            j += 1;
+        } else if j == logged.len() || (i < expected.len() && common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(Line::Missing(expected[i].clone()));
+            i += 1;
+        } else {
+            lines.push(Line::Unexpected(logged[j].clone()));
+            j += 1;
+        }
+    }
+    lines
+}
@@ -0,0 +1,315 @@
+//! Logging support for `Stream::forward` and `Sink::send_all`.
+
+use futures::{Sink, Stream};
+#[cfg(silenced)]
+use futures::stream::Forward;
+#[cfg(silenced)]
+use futures::sink::SendAll;
+#[cfg(not(silenced))]
+use futures::{Async, AsyncSink, Future, Poll};
+use core::fmt::Debug;
+#[cfg(not(silenced))]
+use core::fmt;
+
+#[cfg(not(silenced))]
+use inspector::Inspector;
+#[cfg(not(silenced))]
+use logger::{EventKind, Outcome};
+#[cfg(not(silenced))]
+use options::Inspect;
+
+/// The half of a pipeline that failed.
+#[cfg(not(silenced))]
+enum Failed<S, T> {
+    Stream(S),
+    Sink(T),
+}
+
+/// The state shared by `LoggedForward` and `LoggedSendAll`: a
+/// stream whose items are sent into a sink until it ends, after
+/// which the sink is closed.
+#[cfg(not(silenced))]
+struct Pipeline<St: Stream, Si> {
+    stream: Option<St>,
+    sink: Option<Si>,
+    /// An item the sink handed back, sent again before the stream
+    /// is polled.
+    buffered: Option<St::Item>,
+    inspector: Inspector,
+    forwarded: u64,
+    ended: bool,
+}
+
+#[cfg(not(silenced))]
+impl<St: Stream + Debug, Si: Debug> Debug for Pipeline<St, Si> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Pipeline")
+         .field("stream", &self.stream)
+         .field("sink", &self.sink)
+         .field("inspector", &self.inspector)
+         .field("forwarded", &self.forwarded)
+         .finish()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<St, Si> Pipeline<St, Si>
+    where St: Stream,
+          St::Item: Debug,
+          St::Error: Debug,
+          Si: Sink<SinkItem = St::Item>,
+          Si::SinkError: Debug
+{
+    #[track_caller]
+    fn new(stream: St, sink: Si, label: &str) -> Pipeline<St, Si> {
+        Pipeline {
+            stream: Some(stream),
+            sink: Some(sink),
+            buffered: None,
+            inspector: Inspector::new(Inspect::new(label)),
+            forwarded: 0,
+            ended: false,
+        }
+    }
+
+    fn poll(&mut self) -> Poll<(St, Si), Failed<St::Error, Si::SinkError>> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::Polling, None);
+        let poll = self.advance();
+        self.inspector.end(&match poll {
+            Ok(Async::NotReady) => Outcome::NotReady,
+            Ok(Async::Ready(())) => Outcome::Ready(&self.forwarded),
+            Err(Failed::Stream(ref error)) => Outcome::Err(self.inspector.value(error)),
+            Err(Failed::Sink(ref error)) => Outcome::Err(self.inspector.value(error)),
+        });
+        let kind = match poll {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(())) => EventKind::PipelineDone(self.forwarded),
+            Err(Failed::Stream(ref error)) => EventKind::Polled(Outcome::Err(self.inspector.value(error))),
+            Err(Failed::Sink(ref error)) => EventKind::Polled(Outcome::Err(self.inspector.value(error))),
+        };
+        self.inspector.emit(kind, stopwatch);
+        poll.map(|_| {
+            let stream = self.stream.take().expect("polled after completion");
+            let sink = self.sink.take().expect("polled after completion");
+            Async::Ready((stream, sink))
+        })
+    }
+
+    /// Moves items from the stream into the sink until either side
+    /// is not ready, logging which one it was.
+    fn advance(&mut self) -> Poll<(), Failed<St::Error, Si::SinkError>> {
+        let stream = self.stream.as_mut().expect("polled after completion");
+        let sink = self.sink.as_mut().expect("polled after completion");
+        loop {
+            if let Some(item) = self.buffered.take() {
+                if let AsyncSink::NotReady(item) = sink.start_send(item).map_err(Failed::Sink)? {
+                    self.buffered = Some(item);
+                    self.inspector.emit(EventKind::PipelineStalled("sink"), None);
+                    return Ok(Async::NotReady);
+                }
+            }
+            if self.ended {
+                if sink.close().map_err(Failed::Sink)?.is_not_ready() {
+                    self.inspector.emit(EventKind::PipelineStalled("sink"), None);
+                    return Ok(Async::NotReady);
+                }
+                return Ok(Async::Ready(()));
+            }
+            match stream.poll().map_err(Failed::Stream)? {
+                Async::Ready(Some(item)) => {
+                    self.forwarded += 1;
+                    self.inspector.emit(EventKind::PipelineItem(self.forwarded, self.inspector.value(&item)), None);
+                    self.buffered = Some(item);
+                }
+                Async::Ready(None) => self.ended = true,
+                Async::NotReady => {
+                    let flushed = sink.poll_complete().map_err(Failed::Sink)?.is_ready();
+                    self.inspector.emit(EventKind::PipelineFlushed(flushed), None);
+                    self.inspector.emit(EventKind::PipelineStalled("stream"), None);
+                    return Ok(Async::NotReady);
+                }
+            }
+        }
+    }
+}
+
+/// LoggedForward works like the future returned by
+/// `Stream::forward`, logging every item passed from the stream to
+/// the sink, every flush of the sink and which of the two a poll
+/// returning `NotReady` waits on, see `ForwardLoggingExt`.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct LoggedForward<St: Stream, Si> {
+    pipeline: Pipeline<St, Si>,
+}
+
+#[cfg(not(silenced))]
+impl<St, Si> Future for LoggedForward<St, Si>
+    where St: Stream,
+          St::Item: Debug,
+          St::Error: Debug + From<Si::SinkError>,
+          Si: Sink<SinkItem = St::Item>,
+          Si::SinkError: Debug
+{
+    type Item = (St, Si);
+    type Error = St::Error;
+
+    fn poll(&mut self) -> Poll<(St, Si), St::Error> {
+        self.pipeline.poll().map_err(|failed| match failed {
+            Failed::Stream(error) => error,
+            Failed::Sink(error) => error.into(),
+        })
+    }
+}
+
+/// ForwardLoggingExt introduces the logging of a Stream forwarded
+/// into a Sink, as long as its Item and both errors can be printed.
+pub trait ForwardLoggingExt: Stream + Sized
+    where Self::Item: Debug,
+          Self::Error: Debug
+{
+    /// inspect_forward() forwards the Stream into `sink` like
+    /// `Stream::forward`, logging the pipeline as `label`:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, stream};
+    /// # use futures_poll_log::ForwardLoggingExt;
+    /// # fn main() {
+    /// let rows = stream::iter((0..3).map(Ok::<u32, ()>));
+    /// let (_, sent) = rows.inspect_forward(Vec::new(), "rows").wait().unwrap();
+    /// assert_eq!(sent, [0, 1, 2]);
+    /// // Pipeline `rows' passes item #1: 0
+    /// // Pipeline `rows' forwarded 3 items and closed its sink
+    /// # }
+    /// ```
+    ///
+    /// A poll returning `NotReady` names the half it waits on, the
+    /// stream for the next item or the sink to accept one, right
+    /// where the combined future of `forward` stays opaque:
+    ///
+    /// ```plain
+    /// DEBUG - Pipeline `rows' flushed its sink
+    /// DEBUG - Pipeline `rows' not ready, waiting on its stream
+    /// ```
+    ///
+    /// This method returns a plain `Forward` instead of a
+    /// `LoggedForward` when the `silence` feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect_forward<Si>(self, sink: Si, label: &str) -> LoggedForward<Self, Si>
+        where Si: Sink<SinkItem = Self::Item>,
+              Si::SinkError: Debug,
+              Self::Error: From<Si::SinkError>;
+    /// inspect_forward() is `Stream::forward` when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_forward<Si>(self, sink: Si, label: &str) -> Forward<Self, Si>
+        where Si: Sink<SinkItem = Self::Item>,
+              Si::SinkError: Debug,
+              Self::Error: From<Si::SinkError>;
+}
+
+impl<St> ForwardLoggingExt for St
+    where St: Stream,
+          St::Item: Debug,
+          St::Error: Debug
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_forward<Si>(self, sink: Si, label: &str) -> LoggedForward<Self, Si>
+        where Si: Sink<SinkItem = Self::Item>,
+              Si::SinkError: Debug,
+              Self::Error: From<Si::SinkError>
+    {
+        LoggedForward { pipeline: Pipeline::new(self, sink, label) }
+    }
+    #[cfg(silenced)]
+    fn inspect_forward<Si>(self, sink: Si, _: &str) -> Forward<Self, Si>
+        where Si: Sink<SinkItem = Self::Item>,
+              Si::SinkError: Debug,
+              Self::Error: From<Si::SinkError>
+    {
+        self.forward(sink)
+    }
+}
+
+/// LoggedSendAll works like the future returned by
+/// `Sink::send_all`, logging it like `LoggedForward`, see
+/// `SendAllLoggingExt`.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct LoggedSendAll<Si, St: Stream> {
+    pipeline: Pipeline<St, Si>,
+}
+
+#[cfg(not(silenced))]
+impl<Si, St> Future for LoggedSendAll<Si, St>
+    where St: Stream,
+          St::Item: Debug,
+          St::Error: Debug,
+          Si: Sink<SinkItem = St::Item>,
+          Si::SinkError: Debug + From<St::Error>
+{
+    type Item = (Si, St);
+    type Error = Si::SinkError;
+
+    fn poll(&mut self) -> Poll<(Si, St), Si::SinkError> {
+        match self.pipeline.poll() {
+            Ok(Async::Ready((stream, sink))) => Ok(Async::Ready((sink, stream))),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(Failed::Stream(error)) => Err(error.into()),
+            Err(Failed::Sink(error)) => Err(error),
+        }
+    }
+}
+
+/// SendAllLoggingExt introduces the logging of a Stream sent into
+/// a Sink, as long as the items and both errors can be printed.
+pub trait SendAllLoggingExt: Sink + Sized
+    where Self::SinkItem: Debug,
+          Self::SinkError: Debug
+{
+    /// inspect_send_all() sends all items of `stream` into the Sink
+    /// like `Sink::send_all`, logging the pipeline as `label` like
+    /// `ForwardLoggingExt::inspect_forward()`.
+    ///
+    /// This method returns a plain `SendAll` instead of a
+    /// `LoggedSendAll` when the `silence` feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect_send_all<St>(self, stream: St, label: &str) -> LoggedSendAll<Self, St>
+        where St: Stream<Item = Self::SinkItem>,
+              St::Error: Debug,
+              Self::SinkError: From<St::Error>;
+    /// inspect_send_all() is `Sink::send_all` when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_send_all<St>(self, stream: St, label: &str) -> SendAll<Self, St>
+        where St: Stream<Item = Self::SinkItem>,
+              St::Error: Debug,
+              Self::SinkError: From<St::Error>;
+}
+
+impl<Si> SendAllLoggingExt for Si
+    where Si: Sink,
+          Si::SinkItem: Debug,
+          Si::SinkError: Debug
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_send_all<St>(self, stream: St, label: &str) -> LoggedSendAll<Self, St>
+        where St: Stream<Item = Self::SinkItem>,
+              St::Error: Debug,
+              Self::SinkError: From<St::Error>
+    {
+        LoggedSendAll { pipeline: Pipeline::new(stream, self, label) }
+    }
+    #[cfg(silenced)]
+    fn inspect_send_all<St>(self, stream: St, _: &str) -> SendAll<Self, St>
+        where St: Stream<Item = Self::SinkItem>,
+              St::Error: Debug,
+              Self::SinkError: From<St::Error>
+    {
+        self.send_all(stream)
+    }
+}
@@ -0,0 +1,1574 @@
+//! Logging support for `Future`s.
+
+use futures::{Future, Poll};
+#[cfg(not(silenced))]
+use futures::future::Either;
+use futures::Async;
+#[cfg(all(feature="std", silenced))]
+use futures::future::Shared;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+#[cfg(not(silenced))]
+use core::mem;
+#[cfg(feature="std")]
+use core::time::Duration;
+use alloc::string::String;
+#[cfg(not(silenced))]
+use alloc::string::ToString;
+use core::error::Error;
+use core::ops::Deref;
+#[cfg(all(feature="std", not(silenced)))]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(all(feature="std", not(silenced)))]
+use std::thread;
+#[cfg(feature="std")]
+use std::thread::ThreadId;
+
+#[cfg(all(feature="chaos", not(silenced)))]
+use chaos::Fault;
+#[cfg(not(silenced))]
+use config::{self, Verbosity};
+use inspector::Inspector;
+use label::{Label, TypedLabel};
+#[cfg(not(silenced))]
+use logger::EventKind;
+use logger::{Completion, Outcome, PollLogger, PollStats, Summary};
+use options::{Inspect, Stage};
+#[cfg(not(silenced))]
+use options::Nesting;
+#[cfg(all(feature="std", not(silenced)))]
+use shared::LoggedShared;
+use timing::Stopwatch;
+
+/// The LoggedFuture struct wraps another Future and
+/// will log all poll calls.
+///
+/// It is labeled by an `L`, kept as it is and written only when
+/// needed, if set up through `LoggingExt::inspect_typed`. Other
+/// inspections are labeled by text, with `L` left a `String`.
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct LoggedFuture<T, E, F: Future<Item = T, Error = E>, L = String> {
+    // Only taken by `into_inner`.
+    future: Option<F>,
+    inspector: Inspector,
+    #[cfg(feature="std")]
+    deadline: Option<Deadline>,
+    #[cfg(feature="std")]
+    executor: Option<ExecutorTracking>,
+    #[cfg(feature="std")]
+    catch_panics: bool,
+    #[cfg(feature="std")]
+    threads: Option<ThreadTracking>,
+    summary: Option<SummaryTracking>,
+    classify: Option<fn(&E) -> &'static str>,
+    /// Lists the causes of an error, for logging them one by one.
+    sources: Option<fn(&E) -> Vec<String>>,
+    /// Builds the errors injected through `chaos::inject`.
+    #[cfg(feature="chaos")]
+    fault_error: Option<fn() -> E>,
+    /// The branch of an `Either` logged on the first poll.
+    branch: Option<&'static str>,
+    #[cfg(feature="console")]
+    span: tracing::Span,
+    /// The label given through `inspect_typed`, shared with the
+    /// inspector.
+    typed: Option<Arc<TypedLabel<L>>>,
+}
+
+#[cfg(feature="std")]
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+struct Deadline {
+    started: Stopwatch,
+    duration: Duration,
+    reported: bool,
+}
+
+/// Remembers the thread of the last poll, as a fingerprint
+/// of the executor driving the future.
+#[cfg(feature="std")]
+#[derive(Debug, Default)]
+#[cfg_attr(silenced, allow(dead_code))]
+struct ExecutorTracking {
+    last: Option<ThreadId>,
+}
+
+/// Remembers the threads of the first and the last poll.
+#[cfg(feature="std")]
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+struct ThreadTracking {
+    first: ThreadId,
+    last: ThreadId,
+}
+
+/// Collects the summary reported on drop.
+#[derive(Debug, Default)]
+#[cfg_attr(silenced, allow(dead_code))]
+struct SummaryTracking {
+    started: Option<Stopwatch>,
+    pending: Option<core::time::Duration>,
+    completion: Option<Completion>,
+}
+
+const TAKEN: &str = "LoggedFuture polled after into_inner()";
+
+impl<T, E, F, L> LoggedFuture<T, E, F, L>
+    where F: Future<Item = T, Error = E>
+{
+    /// Borrows the wrapped Future.
+    pub fn get_ref(&self) -> &F {
+        self.future.as_ref().expect(TAKEN)
+    }
+
+    /// Borrows the wrapped Future mutably, e.g. to call its
+    /// inherent methods between polls. Polls made through it are
+    /// not logged.
+    pub fn get_mut(&mut self) -> &mut F {
+        self.future.as_mut().expect(TAKEN)
+    }
+
+    /// The label of the inspection, including its prefixes.
+    pub fn label(&self) -> &str {
+        self.inspector.label()
+    }
+
+    /// The label the future was set up with through
+    /// `LoggingExt::inspect_typed` or relabeled with through
+    /// `set_typed_label()`. `None` for labels given as text, including
+    /// a typed one replaced through `set_label()`.
+    pub fn typed_label(&self) -> Option<&L> {
+        self.typed.as_deref().map(TypedLabel::value)
+    }
+
+    /// Replaces the label of the inspection for the events from now
+    /// on, e.g. to add a request id once it is known:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::future;
+    /// # use futures_poll_log::LoggingExt;
+    /// # #[cfg(not(silenced))]
+    /// # fn main() {
+    /// let mut request = future::ok::<u32, ()>(7).inspect("request");
+    /// let label = format!("{} #{}", request.label(), 42);
+    /// request.set_label(&label);
+    /// assert_eq!(request.label(), "request #42");
+    /// # }
+    /// # // `inspect()` returns the future itself when silenced.
+    /// # #[cfg(silenced)]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// Prefixes are not applied again to the new label. A label given
+    /// through `inspect_typed` is dropped, see `set_typed_label()`.
+    pub fn set_label(&mut self, label: &str) {
+        self.typed = None;
+        self.inspector.set_label(label);
+    }
+
+    /// Replaces the label like `set_label()`, keeping `label` as it is
+    /// for `typed_label()` and the backends.
+    pub fn set_typed_label(&mut self, label: L)
+        where L: fmt::Display + Send + Sync + 'static
+    {
+        let typed = Arc::new(TypedLabel::new(label));
+        self.inspector.relabel(Label::typed(&typed));
+        self.typed = Some(typed);
+    }
+
+    /// Ends the inspection and returns the wrapped Future. No
+    /// summary is logged for it.
+    pub fn into_inner(mut self) -> F {
+        self.summary = None;
+        self.future.take().expect(TAKEN)
+    }
+}
+
+/// Whether `F` is a `LoggedFuture` itself. Type names come without
+/// guarantees, so if their form changes, nesting goes undetected
+/// rather than misdetected.
+#[cfg(not(silenced))]
+fn wraps_logged_future<F>() -> bool {
+    core::any::type_name::<F>().starts_with(concat!(module_path!(), "::LoggedFuture<"))
+}
+
+#[cfg(not(silenced))]
+impl<T, E, F, L> LoggedFuture<T, E, F, L>
+    where F: Future<Item = T, Error = E>
+{
+    pub(crate) fn new(future: F, mut options: Inspect) -> LoggedFuture<T, E, F, L> {
+        if options.nesting != Nesting::Log && wraps_logged_future::<F>() {
+            options.nested = true;
+            options.log = false;
+        }
+        #[cfg(feature="std")]
+        let (deadline, executor, catch_panics) = (options.deadline, options.executor, options.catch_panics);
+        let summary = options.summary;
+        #[cfg(feature="console")]
+        let span = tracing::trace_span!(target: "tokio::task",
+                                        "runtime.spawn",
+                                        kind = "futures_poll_log",
+                                        task.name = &*options.label);
+        let inspector = Inspector::new(options);
+        LoggedFuture {
+            future: Some(future),
+            #[cfg(feature="std")]
+            deadline: deadline.map(|duration| {
+                Deadline {
+                    started: inspector.stopwatch(),
+                    duration,
+                    reported: false,
+                }
+            }),
+            #[cfg(feature="std")]
+            executor: if executor { Some(ExecutorTracking::default()) } else { None },
+            #[cfg(feature="std")]
+            catch_panics,
+            #[cfg(feature="std")]
+            threads: None,
+            summary: if summary { Some(SummaryTracking::default()) } else { None },
+            classify: None,
+            sources: None,
+            #[cfg(feature="chaos")]
+            fault_error: None,
+            branch: None,
+            #[cfg(feature="console")]
+            span,
+            inspector,
+            typed: None,
+        }
+    }
+
+    /// Logs `branch` as the branch taken on the first poll.
+    pub(crate) fn branch(mut self, branch: &'static str) -> LoggedFuture<T, E, F, L> {
+        self.branch = Some(branch);
+        self
+    }
+
+    pub(crate) fn inspector(&self) -> &Inspector {
+        &self.inspector
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T, E, F, L> Future for LoggedFuture<T, E, F, L>
+    where T: Debug,
+          E: Debug,
+          F: Future<Item = T, Error = E>
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        #[cfg(feature="console")]
+        let _span = self.span.enter();
+        #[cfg(feature="std")]
+        if self.inspector.slow_motion() {
+            return Ok(Async::NotReady);
+        }
+        let reentered = self.inspector.is_polling();
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::Polling, None);
+        if reentered {
+            self.inspector.emit(EventKind::Reentered, None);
+        }
+        if self.inspector.polls() == 1 && self.inspector.logs_size() {
+            self.inspector.emit(EventKind::Size(mem::size_of::<F>()), None);
+        }
+        if let (1, Some(branch)) = (self.inspector.polls(), self.branch) {
+            self.inspector.emit(EventKind::Branch(branch), None);
+        }
+        #[cfg(feature="std")]
+        if let Some(ref mut executor) = self.executor {
+            let current = thread::current();
+            if executor.last != Some(current.id()) {
+                executor.last = Some(current.id());
+                // Whether an event is shown depends on its kind only.
+                if self.inspector.enabled(&EventKind::PolledBy("")) {
+                    let description = alloc::format!("thread `{}' ({:?})",
+                                                     current.name().unwrap_or("<unnamed>"),
+                                                     current.id());
+                    self.inspector.emit(EventKind::PolledBy(&description), None);
+                }
+            }
+        }
+        #[cfg(feature="std")]
+        {
+            let current = thread::current().id();
+            match self.threads {
+                None => self.threads = Some(ThreadTracking { first: current, last: current }),
+                Some(ref mut threads) if threads.last != current => {
+                    threads.last = current;
+                    // Moving back to the first thread is not worth a notice.
+                    if current != threads.first && self.inspector.enabled(&EventKind::ThreadChanged("", "")) {
+                        let first = alloc::format!("{:?}", threads.first);
+                        let current = alloc::format!("{:?}", current);
+                        self.inspector.emit(EventKind::ThreadChanged(&first, &current), None);
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        if self.summary.is_none() && self.inspector.polls() == 1 &&
+           config::verbosity() == Verbosity::Summary {
+            self.summary = Some(SummaryTracking::default());
+        }
+        if let Some(ref mut summary) = self.summary {
+            let inspector = &self.inspector;
+            summary.started.get_or_insert_with(|| inspector.stopwatch());
+        }
+        #[cfg(feature="chaos")]
+        let poll = match (self.inspector.inject_fault(self.fault_error.is_some()), self.fault_error) {
+            (Some(Fault::Error), Some(fault_error)) => {
+                let error = fault_error();
+                self.inspector.emit(EventKind::InjectedError(self.inspector.value(&error)), None);
+                Err(error)
+            }
+            (Some(_), _) => Ok(Async::NotReady),
+            (None, _) => poll_inner(self.future.as_mut().expect(TAKEN), &self.inspector, self.catch_panics),
+        };
+        #[cfg(all(feature="std", not(feature="chaos")))]
+        let poll = poll_inner(self.future.as_mut().expect(TAKEN), &self.inspector, self.catch_panics);
+        #[cfg(not(feature="std"))]
+        let poll = self.future.as_mut().expect(TAKEN).poll();
+        let outcome = self.inspector.outcome(&poll);
+        self.inspector.end(&outcome);
+        let category = match (&poll, self.classify) {
+            (Err(error), Some(classify)) => Some(classify(error)),
+            _ => None,
+        };
+        self.inspector.emit_classified(EventKind::Polled(outcome), stopwatch, category);
+        if let (Err(error), Some(sources)) = (&poll, self.sources) {
+            for (depth, source) in sources(error).iter().enumerate() {
+                let source = if self.inspector.redacts() { "<redacted>" } else { source };
+                self.inspector.emit(EventKind::ErrorSource(depth + 1, source), None);
+            }
+        }
+        #[cfg(feature="std")]
+        self.inspector.count_ready(!matches!(poll, Ok(Async::NotReady)));
+        if let Some(ref mut summary) = self.summary {
+            let completion = match poll {
+                Ok(Async::Ready(_)) => Some(Completion::Completed),
+                Ok(Async::NotReady) => None,
+                Err(_) => Some(Completion::Errored),
+            };
+            if completion.is_some() && summary.completion.is_none() {
+                summary.completion = completion;
+                summary.pending = summary.started.and_then(|started| started.elapsed());
+            }
+        }
+        #[cfg(feature="std")]
+        if let (Ok(Async::NotReady), Some(ref mut deadline)) = (&poll, self.deadline.as_mut()) {
+            if !deadline.reported && deadline.started.elapsed() >= Some(deadline.duration) {
+                deadline.reported = true;
+                self.inspector.emit(EventKind::DeadlineExceeded(deadline.duration), None);
+            }
+        }
+        poll
+    }
+}
+
+/// The causes of `error`, outermost first.
+#[cfg(not(silenced))]
+fn sources(error: &dyn Error) -> Vec<String> {
+    let mut sources = Vec::new();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        sources.push(cause.to_string());
+        source = cause.source();
+    }
+    sources
+}
+
+/// Polls `future`, reporting a panic to `inspector` if `catch_panics`
+/// is set.
+#[cfg(all(feature="std", not(silenced)))]
+fn poll_inner<F: Future>(future: &mut F, inspector: &Inspector, catch_panics: bool) -> Poll<F::Item, F::Error> {
+    if !catch_panics {
+        return future.poll();
+    }
+    match panic::catch_unwind(AssertUnwindSafe(|| future.poll())) {
+        Ok(poll) => poll,
+        Err(payload) => {
+            // Panic messages often quote the values involved.
+            #[cfg(feature="redact")]
+            let message = "<redacted>";
+            #[cfg(not(feature="redact"))]
+            let message = payload
+                .downcast_ref::<&str>()
+                .cloned()
+                .or_else(|| payload.downcast_ref::<String>().map(|message| &**message))
+                .unwrap_or("Box<dyn Any>");
+            inspector.emit(EventKind::Panicked(message), None);
+            panic::resume_unwind(payload)
+        }
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T, E, F, L> Drop for LoggedFuture<T, E, F, L>
+    where F: Future<Item = T, Error = E>
+{
+    fn drop(&mut self) {
+        if let Some(ref summary) = self.summary {
+            let summary = Summary {
+                polls: self.inspector.polls(),
+                pending: summary.pending.or_else(|| {
+                    summary.started.and_then(|started| started.elapsed())
+                }),
+                completion: summary.completion.unwrap_or(Completion::Cancelled),
+                #[cfg(feature="std")]
+                overhead: self.inspector.overhead(),
+                #[cfg(not(feature="std"))]
+                overhead: None,
+            };
+            self.inspector.emit(EventKind::Dropped(summary), None);
+        }
+    }
+}
+
+#[cfg(silenced)]
+impl<T, E, F, L> Future for LoggedFuture<T, E, F, L>
+    where T: Debug,
+          E: Debug,
+          F: Future<Item = T, Error = E>
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.future.as_mut().expect(TAKEN).poll()
+    }
+}
+
+/// FilteredFuture wraps a Future like `LoggedFuture`, but
+/// logs only the polls whose result passes a predicate, see
+/// `LoggingExt::inspect_when`.
+#[cfg(not(silenced))]
+pub struct FilteredFuture<F, P> {
+    future: F,
+    predicate: P,
+    inspector: Inspector,
+}
+
+#[cfg(not(silenced))]
+impl<F: Debug, P> Debug for FilteredFuture<F, P> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("FilteredFuture")
+         .field("future", &self.future)
+         .field("inspector", &self.inspector)
+         .finish()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T, E, F, P> Future for FilteredFuture<F, P>
+    where T: Debug,
+          E: Debug,
+          F: Future<Item = T, Error = E>,
+          P: FnMut(&Poll<T, E>) -> bool
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        let poll = self.future.poll();
+        let outcome = self.inspector.outcome(&poll);
+        self.inspector.end(&outcome);
+        if (self.predicate)(&poll) {
+            self.inspector.emit(EventKind::Polled(outcome), stopwatch);
+        }
+        poll
+    }
+}
+
+/// CompletionFuture wraps a Future like `LoggedFuture`, and
+/// hands its final outcome to a closure, see
+/// `LoggingExt::inspect_on_complete`.
+#[cfg(not(silenced))]
+pub struct CompletionFuture<F: Future, C: FnOnce(Outcome, &Summary)> {
+    future: LoggedFuture<F::Item, F::Error, F>,
+    // Taken once the Future resolved.
+    on_complete: Option<C>,
+    started: Option<Stopwatch>,
+}
+
+#[cfg(not(silenced))]
+impl<F, C> Debug for CompletionFuture<F, C>
+    where F: Future + Debug,
+          F::Item: Debug,
+          F::Error: Debug,
+          C: FnOnce(Outcome, &Summary)
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("CompletionFuture").field("future", &self.future).finish()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<F: Future, C> CompletionFuture<F, C>
+    where C: FnOnce(Outcome, &Summary)
+{
+    fn complete(&mut self, outcome: Outcome, completion: Completion) {
+        if let Some(on_complete) = self.on_complete.take() {
+            let inspector = self.future.inspector();
+            let summary = Summary {
+                polls: inspector.polls(),
+                pending: self.started.and_then(|started| started.elapsed()),
+                completion,
+                #[cfg(feature="std")]
+                overhead: inspector.overhead(),
+                #[cfg(not(feature="std"))]
+                overhead: None,
+            };
+            on_complete(outcome, &summary);
+        }
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T, E, F, C> Future for CompletionFuture<F, C>
+    where T: Debug,
+          E: Debug,
+          F: Future<Item = T, Error = E>,
+          C: FnOnce(Outcome, &Summary)
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let future = &self.future;
+        self.started.get_or_insert_with(|| future.inspector().stopwatch());
+        let poll = self.future.poll();
+        let completion = match poll {
+            Ok(Async::NotReady) => return poll,
+            Ok(Async::Ready(_)) => Completion::Completed,
+            Err(_) => Completion::Errored,
+        };
+        let outcome = self.future.inspector().outcome(&poll);
+        self.complete(outcome, completion);
+        poll
+    }
+}
+
+#[cfg(not(silenced))]
+impl<F: Future, C> Drop for CompletionFuture<F, C>
+    where C: FnOnce(Outcome, &Summary)
+{
+    fn drop(&mut self) {
+        self.complete(Outcome::NotReady, Completion::Cancelled);
+    }
+}
+
+/// ProjectedFuture wraps a Future like `LoggedFuture`, but
+/// logs only a projection of its item, see
+/// `LoggingExt::inspect_project`.
+#[cfg(not(silenced))]
+pub struct ProjectedFuture<F: Future, P> {
+    inner: ProjectedInner<F, P>,
+}
+
+#[cfg(not(silenced))]
+type ProjectedInner<F, P> = LoggedFuture<Projected<<F as Future>::Item, P>, <F as Future>::Error, Project<F, P>>;
+
+#[cfg(not(silenced))]
+impl<F, P> Debug for ProjectedFuture<F, P>
+    where F: Future + Debug,
+          F::Item: Debug
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_struct("ProjectedFuture").field("future", &self.inner.get_ref().future).finish()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<F, P, U> Future for ProjectedFuture<F, P>
+    where F: Future,
+          F::Error: Debug,
+          P: Fn(&F::Item) -> &U,
+          U: Debug + ?Sized
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.inner.poll() {
+            Ok(Async::Ready(projected)) => Ok(Async::Ready(projected.item)),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Yields the item of the wrapped future together with the
+/// projection to log it by.
+#[cfg(not(silenced))]
+struct Project<F, P> {
+    future: F,
+    projection: Arc<P>,
+}
+
+#[cfg(not(silenced))]
+impl<F: Future, P> Future for Project<F, P> {
+    type Item = Projected<F::Item, P>;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let projection = &self.projection;
+        self.future.poll().map(|poll| {
+            poll.map(|item| {
+                Projected {
+                    item,
+                    projection: projection.clone(),
+                }
+            })
+        })
+    }
+}
+
+/// An item printed through its projection.
+#[cfg(not(silenced))]
+struct Projected<T, P> {
+    item: T,
+    projection: Arc<P>,
+}
+
+#[cfg(not(silenced))]
+impl<T, P, U> Debug for Projected<T, P>
+    where P: Fn(&T) -> &U,
+          U: Debug + ?Sized
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        (self.projection)(&self.item).fmt(f)
+    }
+}
+
+/// TimedFuture wraps a Future like `LoggedFuture`, and resolves to
+/// its item together with the `PollStats` of its polls, see
+/// `LoggingExt::inspect_timed`.
+#[derive(Debug)]
+pub struct TimedFuture<F: Future> {
+    #[cfg(not(silenced))]
+    future: LoggedFuture<F::Item, F::Error, F>,
+    #[cfg(silenced)]
+    future: F,
+    created: Stopwatch,
+    stats: PollStats,
+}
+
+impl<F: Future> TimedFuture<F>
+    where F::Item: Debug,
+          F::Error: Debug
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn new(future: F, label: &str) -> TimedFuture<F> {
+        let future = LoggedFuture::new(future, Inspect::new(label).timed());
+        TimedFuture {
+            created: future.inspector().stopwatch(),
+            future,
+            stats: PollStats::default(),
+        }
+    }
+
+    #[cfg(silenced)]
+    fn new(future: F, _: &str) -> TimedFuture<F> {
+        TimedFuture {
+            future,
+            created: Stopwatch::start(),
+            stats: PollStats::default(),
+        }
+    }
+
+    #[cfg(not(silenced))]
+    fn stopwatch(&self) -> Stopwatch {
+        self.future.inspector().stopwatch()
+    }
+
+    #[cfg(silenced)]
+    fn stopwatch(&self) -> Stopwatch {
+        Stopwatch::start()
+    }
+}
+
+impl<F: Future> Future for TimedFuture<F>
+    where F::Item: Debug,
+          F::Error: Debug
+{
+    type Item = (F::Item, PollStats);
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if self.stats.polls == 0 {
+            self.stats.first_poll = self.created.elapsed();
+        }
+        self.stats.polls += 1;
+        let stopwatch = self.stopwatch();
+        let poll = self.future.poll();
+        if let Some(elapsed) = stopwatch.elapsed() {
+            self.stats.poll_time = Some(self.stats.poll_time.unwrap_or_default() + elapsed);
+        }
+        match poll {
+            Ok(Async::Ready(item)) => {
+                self.stats.total = self.created.elapsed();
+                Ok(Async::Ready((item, self.stats)))
+            }
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Gate picks at compile time whether `inspect_gated` wraps a
+/// Future, through `ON`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gate<const ON: bool>;
+
+/// Gated is how a `Gate` wraps the Future `F`: a closed gate hands
+/// it back unchanged, an open one sets up the logging like
+/// `inspect()`.
+pub trait Gated<F> {
+    /// The Future `inspect_gated` returns.
+    type Output: Future;
+
+    /// wrap() wraps `future`, labeled `label` if it is logged.
+    fn wrap(future: F, label: &str) -> Self::Output;
+}
+
+impl<F: Future> Gated<F> for Gate<false> {
+    type Output = F;
+
+    #[inline]
+    fn wrap(future: F, _: &str) -> F {
+        future
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T, E, F> Gated<F> for Gate<true>
+    where T: Debug,
+          E: Debug,
+          F: Future<Item = T, Error = E>
+{
+    type Output = LoggedFuture<T, E, F>;
+
+    #[track_caller]
+    fn wrap(future: F, label: &str) -> LoggedFuture<T, E, F> {
+        LoggedFuture::new(future, Inspect::new(label))
+    }
+}
+
+#[cfg(silenced)]
+impl<F: Future> Gated<F> for Gate<true> {
+    type Output = F;
+
+    #[inline]
+    fn wrap(future: F, _: &str) -> F {
+        future
+    }
+}
+
+/// LoggingExt introduces the logging capabilities
+/// to any Future, as long as all its Item and Error
+/// can be printed.
+pub trait LoggingExt<T, E>
+    where T: Debug,
+          E: Debug,
+          Self: Future<Item = T, Error = E> + Sized
+{
+    /// inspect() sets up the logging. The `label` will
+    /// be used to identify the Future in the log messages
+    /// used.
+    ///
+    /// This method returns `Self` instead of a `LoggedFuture`
+    /// when the `silence` feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect(self, label: &str) -> LoggedFuture<T, E, Self>;
+    /// inspect() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect(self, label: &str) -> Self;
+
+    /// inspect_static() sets up the logging like `inspect()`,
+    /// keeping a reference to `label` instead of a copy. Setting up
+    /// the inspection then allocates nothing for the label unless
+    /// prefixes apply, for futures inspected once per request.
+    #[cfg(not(silenced))]
+    fn inspect_static(self, label: &'static str) -> LoggedFuture<T, E, Self>;
+    /// inspect_static() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_static(self, label: &'static str) -> Self;
+
+    /// inspect_typed() sets up the logging like `inspect()`, with a
+    /// label of any type written through `Display` once it is needed.
+    /// The label is kept as it is, for `LoggedFuture::typed_label`
+    /// and for backends matching on it through `PollEvent::label_as`:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use std::fmt;
+    /// # use futures::future;
+    /// # use futures_poll_log::LoggingExt;
+    /// #[derive(Debug, Clone, Copy, PartialEq)]
+    /// enum Query {
+    ///     Users,
+    ///     Orders,
+    /// }
+    ///
+    /// impl fmt::Display for Query {
+    ///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    ///         fmt::Debug::fmt(self, f)
+    ///     }
+    /// }
+    ///
+    /// # #[cfg(not(silenced))]
+    /// # fn main() {
+    /// let users = future::ok::<u32, ()>(3).inspect_typed(Query::Users);
+    /// assert_eq!(users.label(), "Users");
+    /// assert_eq!(users.typed_label(), Some(&Query::Users));
+    /// # }
+    /// # #[cfg(silenced)]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// See `Inspect::new_typed`.
+    #[cfg(not(silenced))]
+    fn inspect_typed<L>(self, label: L) -> LoggedFuture<T, E, Self, L>
+        where L: fmt::Display + Send + Sync + 'static;
+    /// inspect_typed() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_typed<L>(self, label: L) -> Self
+        where L: fmt::Display + Send + Sync + 'static;
+
+    /// inspect_fmt() sets up the logging like `inspect()`, with a
+    /// label written by `label` only once it is needed, e.g. for an
+    /// event that is logged:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// # fn main() {
+    /// let (id, host) = (42, "example.com");
+    /// let _ = future::ok::<i32, ()>(3)
+    ///     .inspect_fmt(move |f| write!(f, "request {} to {}", id, host))
+    ///     .wait();
+    /// # }
+    /// ```
+    ///
+    /// See `Inspect::new_fmt`.
+    #[cfg(not(silenced))]
+    fn inspect_fmt<L>(self, label: L) -> LoggedFuture<T, E, Self>
+        where L: Fn(&mut fmt::Formatter) -> fmt::Result + Send + Sync + 'static;
+    /// inspect_fmt() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_fmt<L>(self, label: L) -> Self
+        where L: Fn(&mut fmt::Formatter) -> fmt::Result + Send + Sync + 'static;
+
+    /// inspect_with_opts() sets up the logging according to
+    /// `options`, see `Inspect`.
+    #[cfg(not(silenced))]
+    fn inspect_with_opts(self, options: Inspect) -> LoggedFuture<T, E, Self>;
+    /// inspect_with_opts() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_with_opts(self, options: Inspect) -> Self;
+
+    /// inspect_with_logger() sets up the logging like `inspect()`,
+    /// but sends the events of this Future to `logger` instead of
+    /// the globally installed backend.
+    #[cfg(not(silenced))]
+    fn inspect_with_logger(self, label: &str, logger: Arc<dyn PollLogger>) -> LoggedFuture<T, E, Self>;
+    /// inspect_with_logger() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_with_logger(self, label: &str, logger: Arc<dyn PollLogger>) -> Self;
+
+    /// inspect_pipeline() sets up a single inspection handling
+    /// each of `stages` on the same poll, instead of one wrapper
+    /// per concern polling and formatting on its own:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// use futures_poll_log::{LoggingExt, Stage};
+    ///
+    /// # fn main() {
+    /// let _ = future::ok::<i32, ()>(3)
+    ///     .inspect_pipeline("request", &[Stage::Timing, Stage::Logging, Stage::Metrics])
+    ///     .wait();
+    /// # }
+    /// ```
+    ///
+    /// See `Inspect::pipeline`.
+    #[cfg(not(silenced))]
+    fn inspect_pipeline(self, label: &str, stages: &[Stage]) -> LoggedFuture<T, E, Self>;
+    /// inspect_pipeline() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_pipeline(self, label: &str, stages: &[Stage]) -> Self;
+
+    /// inspect_executor() sets up the logging like `inspect()`,
+    /// and additionally logs which thread polls the Future, on
+    /// the first poll and whenever it changes. Thread names tell
+    /// runtime workers (e.g. `tokio-runtime-worker`) apart from a
+    /// manual `wait()` on the main thread.
+    ///
+    /// This method is only available with the `std` feature.
+    #[cfg(all(feature="std", not(silenced)))]
+    fn inspect_executor(self, label: &str) -> LoggedFuture<T, E, Self>;
+    /// inspect_executor() is a no-op when the `silence` feature is activated.
+    #[cfg(all(feature="std", silenced))]
+    fn inspect_executor(self, label: &str) -> Self;
+
+    /// inspect_in_group() sets up the logging like `inspect()`, with
+    /// the Future in `group`, see `Inspect::group`:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::{Level, LoggingExt};
+    /// # fn main() {
+    /// futures_poll_log::set_group_level("db", Level::Trace);
+    /// let _ = future::ok::<i32, ()>(3).inspect_in_group("db", "select users").wait();
+    /// # }
+    /// ```
+    ///
+    /// This method is only available with the `std` feature.
+    #[cfg(all(feature="std", not(silenced)))]
+    fn inspect_in_group(self, group: &str, label: &str) -> LoggedFuture<T, E, Self>;
+    /// inspect_in_group() is a no-op when the `silence` feature is activated.
+    #[cfg(all(feature="std", silenced))]
+    fn inspect_in_group(self, group: &str, label: &str) -> Self;
+
+    /// inspect_deadline() sets up the logging like `inspect()`, and
+    /// additionally logs a warning the first time the Future is still
+    /// `NotReady` after `duration` has passed since this call.
+    ///
+    /// The Future is not cancelled when the deadline passes.
+    ///
+    /// This method is only available with the `std` feature.
+    #[cfg(all(feature="std", not(silenced)))]
+    fn inspect_deadline(self, label: &str, duration: Duration) -> LoggedFuture<T, E, Self>;
+    /// inspect_deadline() is a no-op when the `silence` feature is activated.
+    #[cfg(all(feature="std", silenced))]
+    fn inspect_deadline(self, label: &str, duration: Duration) -> Self;
+
+    /// inspect_shared() turns the Future into a `Shared` one like
+    /// `shared()`, and logs the polls of every clone under its
+    /// waiter number, like `fetch (waiter 2)`. The underlying Future
+    /// is logged under the plain `label`, so its completion can be
+    /// told apart from each waiter observing the result:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// # fn main() {
+    /// let first = future::ok::<u32, ()>(6).inspect_shared("config");
+    /// let second = first.clone();
+    /// assert_eq!(6, *first.wait().unwrap());
+    /// assert_eq!(6, *second.wait().unwrap());
+    /// # }
+    /// ```
+    ///
+    /// This method is only available with the `std` feature.
+    #[cfg(all(feature="std", not(silenced)))]
+    fn inspect_shared(self, label: &str) -> LoggedShared<Self>;
+    /// inspect_shared() is a no-op when the `silence` feature is
+    /// activated, apart from turning the Future into a `Shared` one.
+    #[cfg(all(feature="std", silenced))]
+    fn inspect_shared(self, label: &str) -> Shared<Self>;
+
+    /// inspect_if() sets up the logging like `inspect()` if
+    /// `condition` holds, and leaves the Future alone otherwise, so
+    /// the choice can be made at runtime for every instance:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// # fn main() {
+    /// let debug_header = true;
+    /// let _ = future::ok::<i32, ()>(3).inspect_if(debug_header, "request").wait();
+    /// # }
+    /// ```
+    #[cfg(not(silenced))]
+    fn inspect_if(self, condition: bool, label: &str) -> Either<LoggedFuture<T, E, Self>, Self>;
+    /// inspect_if() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_if(self, condition: bool, label: &str) -> Self;
+
+    /// inspect_when() sets up the logging like `inspect()`, but
+    /// logs the result of a poll only if `predicate` returns true
+    /// for it, e.g. only for a certain kind of error:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Async, Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// # fn main() {
+    /// let _ = future::err::<(), u16>(503)
+    ///     .inspect_when("request", |poll| match *poll {
+    ///         Err(status) => status >= 500,
+    ///         Ok(Async::Ready(_)) | Ok(Async::NotReady) => false,
+    ///     })
+    ///     .wait();
+    /// # }
+    /// ```
+    ///
+    /// The polls skipped by the predicate are still counted, and
+    /// the "polling" message before each poll is left out.
+    #[cfg(not(silenced))]
+    fn inspect_when<P>(self, label: &str, predicate: P) -> FilteredFuture<Self, P>
+        where P: FnMut(&Poll<T, E>) -> bool;
+    /// inspect_when() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_when<P>(self, label: &str, predicate: P) -> Self
+        where P: FnMut(&Poll<T, E>) -> bool;
+
+    /// inspect_project() sets up the logging like `inspect()`, but
+    /// logs only the part of the item returned by `projection`,
+    /// e.g. an identifying field of a large struct:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// #[derive(Debug)]
+    /// struct Response {
+    ///     id: u32,
+    ///     body: Vec<u8>,
+    /// }
+    ///
+    /// # fn main() {
+    /// let _ = future::ok::<_, ()>(Response { id: 7, body: vec![0; 4096] })
+    ///     .inspect_project("request", |response| &response.id)
+    ///     .wait();
+    /// # }
+    /// ```
+    #[cfg(not(silenced))]
+    fn inspect_project<P, U>(self, label: &str, projection: P) -> ProjectedFuture<Self, P>
+        where P: Fn(&T) -> &U,
+              U: Debug + ?Sized;
+    /// inspect_project() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_project<P, U>(self, label: &str, projection: P) -> Self
+        where P: Fn(&T) -> &U,
+              U: Debug + ?Sized;
+
+    /// inspect_on_complete() sets up the logging like `inspect()`,
+    /// and calls `on_complete` once the Future resolves, with its
+    /// result and a `Summary` of the polls. If the Future is dropped
+    /// before, `on_complete` is called with `Outcome::NotReady` and
+    /// the completion `Cancelled`:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// # fn main() {
+    /// let _ = future::ok::<i32, ()>(3)
+    ///     .inspect_on_complete("request", |outcome, summary| {
+    ///         println!("{:?} after {} polls", outcome, summary.polls);
+    ///     })
+    ///     .wait();
+    /// # }
+    /// ```
+    ///
+    /// The item and error are hidden from `on_complete` if redaction
+    /// is enabled.
+    ///
+    /// This method returns `Self` when the `silence` feature is
+    /// activated, and `on_complete` is never called.
+    #[cfg(not(silenced))]
+    fn inspect_on_complete<C>(self, label: &str, on_complete: C) -> CompletionFuture<Self, C>
+        where C: FnOnce(Outcome, &Summary);
+    /// inspect_on_complete() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_on_complete<C>(self, label: &str, on_complete: C) -> Self
+        where C: FnOnce(Outcome, &Summary);
+
+    /// inspect_classified() sets up the logging like `inspect()`,
+    /// and sorts errors into the categories returned by `classify`.
+    /// The category is added to the message reporting the error and
+    /// counted in the statistics of the label:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// # use std::io;
+    /// # fn main() {
+    /// let _ = future::err::<(), _>(io::Error::from(io::ErrorKind::TimedOut))
+    ///     .inspect_classified("request", |error| match error.kind() {
+    ///         io::ErrorKind::TimedOut => "timeout",
+    ///         _ => "io",
+    ///     })
+    ///     .wait();
+    /// // Future `request' polled: Err(Kind(TimedOut)) (category `timeout')
+    /// # }
+    /// ```
+    #[cfg(not(silenced))]
+    fn inspect_classified(self, label: &str, classify: fn(&E) -> &'static str) -> LoggedFuture<T, E, Self>;
+    /// inspect_classified() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_classified(self, label: &str, classify: fn(&E) -> &'static str) -> Self;
+
+    /// inspect_error_chain() sets up the logging like `inspect()`,
+    /// and follows an error through its `source()` chain, logging
+    /// every cause on a line of its own right after the error:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// # use std::{error, fmt, io};
+    /// #[derive(Debug)]
+    /// struct QueryFailed(io::Error);
+    ///
+    /// impl fmt::Display for QueryFailed {
+    ///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    ///         f.write_str("query failed")
+    ///     }
+    /// }
+    ///
+    /// impl error::Error for QueryFailed {
+    ///     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+    ///         Some(&self.0)
+    ///     }
+    /// }
+    ///
+    /// # fn main() {
+    /// let refused = io::Error::from(io::ErrorKind::ConnectionRefused);
+    /// let _ = future::err::<(), _>(QueryFailed(refused)).inspect_error_chain("query").wait();
+    /// // Future `query' polled: Err(QueryFailed(Kind(ConnectionRefused)))
+    /// // Error of `query' caused by (1): connection refused
+    /// # }
+    /// ```
+    ///
+    /// The causes are written at the level of the error, through
+    /// their `Display` implementations.
+    #[cfg(not(silenced))]
+    fn inspect_error_chain(self, label: &str) -> LoggedFuture<T, E, Self>
+        where E: Error;
+    /// inspect_error_chain() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_error_chain(self, label: &str) -> Self
+        where E: Error;
+
+    /// inspect_dyn_error_chain() works like `inspect_error_chain()`
+    /// for errors that only dereference to an error trait object,
+    /// such as `Box<dyn Error + Send + Sync>` and `anyhow::Error`.
+    #[cfg(not(silenced))]
+    fn inspect_dyn_error_chain(self, label: &str) -> LoggedFuture<T, E, Self>
+        where E: Deref<Target = dyn Error + Send + Sync>;
+    /// inspect_dyn_error_chain() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_dyn_error_chain(self, label: &str) -> Self
+        where E: Deref<Target = dyn Error + Send + Sync>;
+
+    /// inspect_with_faults() sets up the logging like `inspect()`,
+    /// and builds the errors `chaos::inject()` injects through
+    /// `error`. Without it, only `NotReady` results are injected.
+    ///
+    /// This method is only available with the `chaos` feature.
+    #[cfg(all(feature="chaos", not(silenced)))]
+    fn inspect_with_faults(self, label: &str, error: fn() -> E) -> LoggedFuture<T, E, Self>;
+    /// inspect_with_faults() is a no-op when the `silence` feature is activated.
+    #[cfg(all(feature="chaos", silenced))]
+    fn inspect_with_faults(self, label: &str, error: fn() -> E) -> Self;
+
+    /// inspect_timed() sets up the logging like `inspect()` with
+    /// timing enabled, and resolves to the item together with the
+    /// `PollStats` of the Future, for acting on the measurements
+    /// directly:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// # fn main() {
+    /// let (item, stats) = future::ok::<i32, ()>(3).inspect_timed("request").wait().unwrap();
+    /// assert_eq!((item, stats.polls), (3, 1));
+    /// # }
+    /// ```
+    ///
+    /// Errors are passed on without the measurements.
+    ///
+    /// The Future is still measured, without logging, when the
+    /// `silence` feature is activated.
+    fn inspect_timed(self, label: &str) -> TimedFuture<Self>;
+
+    /// inspect_gated() sets up the logging like `inspect()` if `ON`
+    /// is true, and returns the Future unchanged otherwise. With
+    /// `cfg!` as `ON`, a single call site is compiled in or out by a
+    /// feature of the calling crate:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, future};
+    /// # use futures_poll_log::LoggingExt;
+    /// # fn main() {
+    /// let query = future::ok::<i32, ()>(3)
+    ///     .inspect_gated::<{ cfg!(feature = "trace-db") }>("db query");
+    /// assert_eq!(query.wait(), Ok(3));
+    /// # }
+    /// ```
+    ///
+    /// Without the feature, `query` is the bare future, which costs
+    /// nothing at runtime, as with the `silence` feature. The Future
+    /// is always returned unchanged when the `silence` feature is
+    /// activated.
+    fn inspect_gated<const ON: bool>(self, label: &str) -> <Gate<ON> as Gated<Self>>::Output
+        where Gate<ON>: Gated<Self>;
+}
+
+impl<T, E, F> LoggingExt<T, E> for F
+    where T: Debug,
+          E: Debug,
+          Self: Future<Item = T, Error = E>
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect(self, label: &str) -> LoggedFuture<T, E, Self> {
+        LoggedFuture::new(self, Inspect::new(label))
+    }
+    #[cfg(silenced)]
+    fn inspect(self, _: &str) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_static(self, label: &'static str) -> LoggedFuture<T, E, Self> {
+        LoggedFuture::new(self, Inspect::new_static(label))
+    }
+    #[cfg(silenced)]
+    fn inspect_static(self, _: &'static str) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_typed<L>(self, label: L) -> LoggedFuture<T, E, Self, L>
+        where L: fmt::Display + Send + Sync + 'static
+    {
+        let typed = Arc::new(TypedLabel::new(label));
+        let mut future = LoggedFuture::new(self, Inspect::new_typed_label(&typed));
+        future.typed = Some(typed);
+        future
+    }
+    #[cfg(silenced)]
+    fn inspect_typed<L>(self, _: L) -> Self
+        where L: fmt::Display + Send + Sync + 'static
+    {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_fmt<L>(self, label: L) -> LoggedFuture<T, E, Self>
+        where L: Fn(&mut fmt::Formatter) -> fmt::Result + Send + Sync + 'static
+    {
+        LoggedFuture::new(self, Inspect::new_fmt(label))
+    }
+    #[cfg(silenced)]
+    fn inspect_fmt<L>(self, _: L) -> Self
+        where L: Fn(&mut fmt::Formatter) -> fmt::Result + Send + Sync + 'static
+    {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    fn inspect_with_opts(self, options: Inspect) -> LoggedFuture<T, E, Self> {
+        LoggedFuture::new(self, options)
+    }
+    #[cfg(silenced)]
+    fn inspect_with_opts(self, _: Inspect) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_with_logger(self, label: &str, logger: Arc<dyn PollLogger>) -> LoggedFuture<T, E, Self> {
+        LoggedFuture::new(self, Inspect::new(label).logger(logger))
+    }
+    #[cfg(silenced)]
+    fn inspect_with_logger(self, _: &str, _: Arc<dyn PollLogger>) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_pipeline(self, label: &str, stages: &[Stage]) -> LoggedFuture<T, E, Self> {
+        LoggedFuture::new(self, Inspect::new(label).pipeline(stages))
+    }
+    #[cfg(silenced)]
+    fn inspect_pipeline(self, _: &str, _: &[Stage]) -> Self {
+        self
+    }
+
+    #[cfg(all(feature="std", not(silenced)))]
+    #[track_caller]
+    fn inspect_executor(self, label: &str) -> LoggedFuture<T, E, Self> {
+        LoggedFuture::new(self, Inspect::new(label).executor())
+    }
+    #[cfg(all(feature="std", silenced))]
+    fn inspect_executor(self, _: &str) -> Self {
+        self
+    }
+
+    #[cfg(all(feature="std", not(silenced)))]
+    #[track_caller]
+    fn inspect_in_group(self, group: &str, label: &str) -> LoggedFuture<T, E, Self> {
+        LoggedFuture::new(self, Inspect::new(label).group(group))
+    }
+    #[cfg(all(feature="std", silenced))]
+    fn inspect_in_group(self, _: &str, _: &str) -> Self {
+        self
+    }
+
+    #[cfg(all(feature="std", not(silenced)))]
+    #[track_caller]
+    fn inspect_deadline(self, label: &str, duration: Duration) -> LoggedFuture<T, E, Self> {
+        LoggedFuture::new(self, Inspect::new(label).deadline(duration))
+    }
+    #[cfg(all(feature="std", silenced))]
+    fn inspect_deadline(self, _: &str, _: Duration) -> Self {
+        self
+    }
+
+    #[cfg(all(feature="std", not(silenced)))]
+    #[track_caller]
+    fn inspect_shared(self, label: &str) -> LoggedShared<Self> {
+        LoggedShared::new(self, label)
+    }
+    #[cfg(all(feature="std", silenced))]
+    fn inspect_shared(self, _: &str) -> Shared<Self> {
+        self.shared()
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_if(self, condition: bool, label: &str) -> Either<LoggedFuture<T, E, Self>, Self> {
+        if condition {
+            Either::A(LoggingExt::inspect(self, label))
+        } else {
+            Either::B(self)
+        }
+    }
+    #[cfg(silenced)]
+    fn inspect_if(self, _: bool, _: &str) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_when<P>(self, label: &str, predicate: P) -> FilteredFuture<Self, P>
+        where P: FnMut(&Poll<T, E>) -> bool
+    {
+        FilteredFuture {
+            future: self,
+            predicate,
+            inspector: Inspector::new(Inspect::new(label)),
+        }
+    }
+    #[cfg(silenced)]
+    fn inspect_when<P>(self, _: &str, _: P) -> Self
+        where P: FnMut(&Poll<T, E>) -> bool
+    {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_project<P, U>(self, label: &str, projection: P) -> ProjectedFuture<Self, P>
+        where P: Fn(&T) -> &U,
+              U: Debug + ?Sized
+    {
+        let future = Project {
+            future: self,
+            projection: Arc::new(projection),
+        };
+        ProjectedFuture { inner: LoggedFuture::new(future, Inspect::new(label)) }
+    }
+    #[cfg(silenced)]
+    fn inspect_project<P, U>(self, _: &str, _: P) -> Self
+        where P: Fn(&T) -> &U,
+              U: Debug + ?Sized
+    {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_on_complete<C>(self, label: &str, on_complete: C) -> CompletionFuture<Self, C>
+        where C: FnOnce(Outcome, &Summary)
+    {
+        CompletionFuture {
+            future: LoggingExt::inspect(self, label),
+            on_complete: Some(on_complete),
+            started: None,
+        }
+    }
+    #[cfg(silenced)]
+    fn inspect_on_complete<C>(self, _: &str, _: C) -> Self
+        where C: FnOnce(Outcome, &Summary)
+    {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_classified(self, label: &str, classify: fn(&E) -> &'static str) -> LoggedFuture<T, E, Self> {
+        let mut future = LoggingExt::inspect(self, label);
+        future.classify = Some(classify);
+        future
+    }
+    #[cfg(silenced)]
+    fn inspect_classified(self, _: &str, _: fn(&E) -> &'static str) -> Self {
+        self
+    }
+
+    #[cfg(all(feature="chaos", not(silenced)))]
+    #[track_caller]
+    fn inspect_with_faults(self, label: &str, error: fn() -> E) -> LoggedFuture<T, E, Self> {
+        let mut future = LoggingExt::inspect(self, label);
+        future.fault_error = Some(error);
+        future
+    }
+    #[cfg(all(feature="chaos", silenced))]
+    fn inspect_with_faults(self, _: &str, _: fn() -> E) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_error_chain(self, label: &str) -> LoggedFuture<T, E, Self>
+        where E: Error
+    {
+        let mut future = LoggingExt::inspect(self, label);
+        future.sources = Some(|error| sources(error));
+        future
+    }
+    #[cfg(silenced)]
+    fn inspect_error_chain(self, _: &str) -> Self
+        where E: Error
+    {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_dyn_error_chain(self, label: &str) -> LoggedFuture<T, E, Self>
+        where E: Deref<Target = dyn Error + Send + Sync>
+    {
+        let mut future = LoggingExt::inspect(self, label);
+        future.sources = Some(|error| sources(&**error));
+        future
+    }
+    #[cfg(silenced)]
+    fn inspect_dyn_error_chain(self, _: &str) -> Self
+        where E: Deref<Target = dyn Error + Send + Sync>
+    {
+        self
+    }
+
+    #[track_caller]
+    fn inspect_timed(self, label: &str) -> TimedFuture<Self> {
+        TimedFuture::new(self, label)
+    }
+
+    #[inline]
+    #[track_caller]
+    fn inspect_gated<const ON: bool>(self, label: &str) -> <Gate<ON> as Gated<Self>>::Output
+        where Gate<ON>: Gated<Self>
+    {
+        Gate::<ON>::wrap(self, label)
+    }
+}
+
+/// inspect_all() wraps every future yielded by `futures`, labeling
+/// them `label[0]`, `label[1]` and so on. The result can be handed
+/// directly to `join_all` or `select_all`.
+///
+/// This function returns the futures unchanged when the `silence`
+/// feature is activated.
+#[cfg(not(silenced))]
+#[track_caller]
+pub fn inspect_all<I, T, E, F>(futures: I, label: &str) -> Vec<LoggedFuture<T, E, F>>
+    where I: IntoIterator<Item = F>,
+          T: Debug,
+          E: Debug,
+          F: Future<Item = T, Error = E>
+{
+    // A loop rather than `map`, as closures do not pass on the
+    // location of the caller.
+    let mut inspected = Vec::new();
+    for (i, future) in futures.into_iter().enumerate() {
+        // Qualified, as `Future::inspect` exists from futures 0.1.14 on.
+        inspected.push(LoggingExt::inspect(future, &alloc::format!("{}[{}]", label, i)));
+    }
+    inspected
+}
+
+/// inspect_all() is a no-op when the `silence` feature is activated.
+#[cfg(silenced)]
+pub fn inspect_all<I, T, E, F>(futures: I, _: &str) -> Vec<F>
+    where I: IntoIterator<Item = F>,
+          T: Debug,
+          E: Debug,
+          F: Future<Item = T, Error = E>
+{
+    futures.into_iter().collect()
+}
+
+#[cfg(all(test, feature="std", not(silenced)))]
+mod tests {
+    use LoggingExt;
+    use inspector::tests::Countdown;
+    use options::Inspect;
+
+    #[test]
+    fn typed_labels_are_kept_until_relabeled() {
+        let mut future = LoggingExt::inspect_typed(Countdown(0), 7u32);
+        assert_eq!(future.typed_label(), Some(&7));
+        assert_eq!(future.label(), "7");
+        future.set_label("eight");
+        assert_eq!(future.typed_label(), None);
+        assert_eq!(future.label(), "eight");
+        future.set_typed_label(9);
+        assert_eq!(future.typed_label(), Some(&9));
+        assert_eq!(future.label(), "9");
+    }
+
+    #[test]
+    fn text_labels_have_no_typed_label() {
+        assert_eq!(LoggingExt::inspect(Countdown(0), "7").typed_label(), None);
+        let future = Countdown(0).inspect_with_opts(Inspect::new_typed(7u32));
+        assert_eq!(future.typed_label(), None);
+        assert_eq!(future.label(), "7");
+    }
+}
@@ -0,0 +1,1116 @@
+//! Logging support for `std::future::Future`s, as used by futures 0.3
+//! and `async` blocks.
+//!
+//! The wrappers mirror the ones for futures 0.1: `LoggingExt::inspect`
+//! labels a Future, and every poll is logged with its result:
+//!
+//! ```rust,edition2018
+//! use futures_poll_log::futures03::LoggingExt;
+//!
+//! let future = async { 3 }.inspect("async block");
+//! # drop(future);
+//! ```
+//!
+//! Futures that implement `FusedFuture` stay fused when wrapped, so they
+//! keep working inside `select!`.
+//!
+//! The wrappers project their pinning onto the wrapped Future instead of
+//! requiring `Unpin`, so `async fn`s and other self-referential futures
+//! can be inspected as well. A wrapper is `Unpin` exactly when its Future
+//! is, and the Future is dropped in place:
+//!
+//! ```rust,edition2018
+//! use std::future::Future;
+//! use std::marker::PhantomPinned;
+//! use std::pin::Pin;
+//! use std::task::{Context, Poll, Waker};
+//! use futures_poll_log::futures03::LoggingExt;
+//!
+//! struct Pinned(PhantomPinned);
+//!
+//! impl Future for Pinned {
+//!     type Output = u32;
+//!
+//!     fn poll(self: Pin<&mut Self>, _: &mut Context) -> Poll<u32> {
+//!         Poll::Ready(4)
+//!     }
+//! }
+//!
+//! let mut future = Box::pin(Pinned(PhantomPinned).inspect("pinned"));
+//! let poll = future.as_mut().poll(&mut Context::from_waker(Waker::noop()));
+//! assert_eq!(poll, Poll::Ready(4));
+//! ```
+//!
+//! `LoggedFuture::get_pin_mut` reaches the pinned Future inside.
+//!
+//! `StreamLoggingExt::inspect_stream` labels a Stream the same way, logging
+//! every item it yields. Unlike in futures 0.1, streams have a `size_hint`,
+//! which `Inspect::log_size_hint` logs when the Stream is wrapped and
+//! whenever it changes, so an adaptor advertising the wrong size shows up
+//! next to the items it actually yields:
+//!
+//! ```plain
+//! DEBUG - Stream `rows' hints at 3 to 3 more items after 0 items
+//! DEBUG - Stream `rows' yielded item #1: 7
+//! DEBUG - Stream `rows' hints at 2 to 2 more items after 1 items
+//! ```
+//!
+//! `Inspect::track_wakers` counts the wakers handed to the Future, and
+//! warns about clones that outlive it. `Inspect::log_wakers` logs a
+//! fingerprint of the waker of every poll and wakeup, telling apart the
+//! tasks driving a Future. With `std`, either of them also notes which
+//! inspection a Future was woken from, if the wakeup came from within
+//! the poll of one, and adds it to the wakeups recorded by `graph`.
+//!
+//! With `std`, `CompatLoggingExt::inspect_compat` turns a futures 0.1
+//! Future into a `std::future::Future` and logs both sides of the boundary:
+//! the polls it receives and whether they follow a wakeup from the futures
+//! 0.1 side. A wakeup that is never followed by a poll is reported when the
+//! wrapper is dropped.
+//!
+//! Also with `std`, `wrap_for_spawn` prepares a Future to be spawned on any
+//! executor as a labeled task, and `LoggedExecutor` does so for every
+//! Future spawned through a `Spawn` implementation.
+//!
+//! This module is only available with the `futures03` feature.
+
+#[cfg(any(feature="std", not(silenced)))]
+use alloc::boxed::Box;
+#[cfg(feature="std")]
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt::Debug;
+#[cfg(feature="std")]
+use core::fmt;
+use core::future::Future;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+use core::ptr;
+use core::sync::atomic::{AtomicU64, AtomicUsize};
+#[cfg(feature="std")]
+use core::sync::atomic::AtomicBool;
+#[cfg(any(feature="std", not(silenced)))]
+use core::sync::atomic::Ordering;
+#[cfg(not(silenced))]
+use core::task::{RawWaker, RawWakerVTable};
+#[cfg(any(feature="std", not(silenced)))]
+use core::task::Waker;
+use core::task::{Context, Poll};
+#[cfg(feature="std")]
+use futures::Async;
+#[cfg(feature="std")]
+use futures::executor::{self, Spawn, Unpark};
+use futures_core::future::FusedFuture;
+use futures_core::stream::Stream;
+#[cfg(not(silenced))]
+use futures_core::stream::FusedStream;
+#[cfg(feature="std")]
+use futures_task::{self, FutureObj, LocalFutureObj, LocalSpawn, SpawnError};
+#[cfg(feature="std")]
+use std::sync::Mutex;
+
+#[cfg(all(feature="std", not(silenced)))]
+use blocking::Blocked;
+#[cfg(feature="std")]
+use blocking::ThreadUnpark;
+#[cfg(all(feature="std", not(silenced)))]
+use context;
+#[cfg(all(feature="std", not(silenced)))]
+use graph;
+use inspector::Inspector;
+#[cfg(feature="std")]
+use label::Label;
+#[cfg(not(silenced))]
+use logger::{EventKind, Outcome};
+use options::Inspect;
+#[cfg(feature="std")]
+use timing::Stopwatch;
+
+/// The LoggedFuture struct wraps a `std::future::Future` and
+/// will log all poll calls.
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct LoggedFuture<F> {
+    // Dropped before the wakers are checked for leaks.
+    future: ManuallyDrop<F>,
+    inspector: Inspector,
+    /// The wakers handed to `future`, while they are tracked.
+    wakers: Option<Arc<WakerCounts>>,
+    /// The fingerprint of the waker of the last poll, or 0.
+    last_waker: u64,
+}
+
+#[cfg(not(silenced))]
+impl<F: Future> LoggedFuture<F> {
+    fn new(future: F, options: Inspect) -> LoggedFuture<F> {
+        let inspector = Inspector::new(options);
+        LoggedFuture {
+            future: ManuallyDrop::new(future),
+            wakers: if inspector.tracks_wakers() || inspector.logs_wakers() {
+                Some(Arc::default())
+            } else {
+                None
+            },
+            last_waker: 0,
+            inspector,
+        }
+    }
+}
+
+impl<F> LoggedFuture<F> {
+    /// Borrows the wrapped Future.
+    pub fn get_ref(&self) -> &F {
+        &self.future
+    }
+
+    /// Borrows the wrapped Future mutably, which is only possible
+    /// as long as the wrapper is not pinned. Polls made through it
+    /// are not logged.
+    pub fn get_mut(&mut self) -> &mut F {
+        &mut self.future
+    }
+
+    /// Borrows the wrapped Future of a pinned wrapper, pinned in
+    /// turn.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut F> {
+        // Safety: `future` is pinned along with the wrapper.
+        unsafe { self.map_unchecked_mut(|this| &mut *this.future) }
+    }
+
+    /// Ends the inspection and returns the wrapped Future, without
+    /// checking its wakers for leaks.
+    pub fn into_inner(self) -> F {
+        let mut this = ManuallyDrop::new(self);
+        // Safety: `this` is never dropped, so `future` is moved out
+        // only once, and all other fields holding resources are
+        // dropped here instead.
+        unsafe {
+            ptr::drop_in_place(&mut this.inspector);
+            ptr::drop_in_place(&mut this.wakers);
+            ManuallyDrop::take(&mut this.future)
+        }
+    }
+}
+
+#[cfg(not(silenced))]
+impl<F> LoggedFuture<F> {
+    /// Stops tracking the wakers, warning about the clones still
+    /// alive.
+    fn check_wakers(&mut self) {
+        if let Some(wakers) = self.wakers.take() {
+            let live = wakers.live.load(Ordering::SeqCst);
+            if live > 0 && self.inspector.tracks_wakers() {
+                let clones = wakers.clones.load(Ordering::SeqCst);
+                self.inspector.emit(EventKind::WakersLeaked(live, clones), None);
+            }
+        }
+    }
+
+    /// Logs the waker of the current poll, whether it changed since
+    /// the last one, and the waker the Future was woken through.
+    fn log_waker(&mut self, waker: &Waker) {
+        if let Some(ref wakers) = self.wakers {
+            let woken = wakers.woken.swap(0, Ordering::SeqCst);
+            if woken != 0 {
+                self.inspector.emit(EventKind::WokenBy(woken), None);
+            }
+        }
+        let current = fingerprint(waker);
+        self.inspector.emit(EventKind::Waker(current), None);
+        if self.last_waker != 0 && self.last_waker != current {
+            self.inspector.emit(EventKind::WakerChanged(self.last_waker, current), None);
+        }
+        self.last_waker = current;
+    }
+
+    /// Logs the inspection the last wakeup came from, if it came
+    /// from within the poll of one.
+    #[cfg(feature="std")]
+    fn log_woken_from(&mut self) {
+        let woken_from = match self.wakers {
+            Some(ref wakers) => wakers.woken_from.lock().unwrap_or_else(|e| e.into_inner()).take(),
+            None => None,
+        };
+        if let Some((waker, instance)) = woken_from {
+            graph::record_wake(&waker, self.inspector.label());
+            self.inspector.emit(EventKind::WokenFrom(&waker, instance), None);
+        }
+    }
+}
+
+/// A fingerprint of `waker`, the same for all clones of a waker
+/// and never 0.
+#[cfg(not(silenced))]
+fn fingerprint(waker: &Waker) -> u64 {
+    let data = waker.data() as usize as u64;
+    let vtable = waker.vtable() as *const RawWakerVTable as usize as u64;
+    // Mixes the pointers so that fingerprints of nearby wakers
+    // differ in more than the last digits.
+    let mixed = (data ^ vtable.rotate_left(32)).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    (mixed ^ (mixed >> 29)) | 1
+}
+
+#[cfg(not(silenced))]
+impl<F> Future for LoggedFuture<F>
+    where F: Future,
+          F::Output: Debug
+{
+    type Output = F::Output;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // Safety: `future` is never moved out of the pinned wrapper.
+        let this = unsafe { self.get_unchecked_mut() };
+        let _entered = this.inspector.enter();
+        let stopwatch = this.inspector.begin();
+        this.inspector.emit(EventKind::Polling, None);
+        #[cfg(feature="std")]
+        this.log_woken_from();
+        if this.inspector.logs_wakers() {
+            this.log_waker(cx.waker());
+        }
+        let future = unsafe { Pin::new_unchecked(&mut *this.future) };
+        let poll = match this.wakers {
+            Some(ref wakers) => {
+                let waker = CountedWaker::waker(cx.waker().clone(), wakers.clone());
+                future.poll(&mut Context::from_waker(&waker))
+            }
+            None => future.poll(cx),
+        };
+        let outcome = match poll {
+            Poll::Ready(ref output) => Outcome::Ready(this.inspector.value(output)),
+            Poll::Pending => Outcome::NotReady,
+        };
+        this.inspector.end(&outcome);
+        this.inspector.emit(EventKind::Polled(outcome), stopwatch);
+        if poll.is_ready() {
+            this.check_wakers();
+        }
+        poll
+    }
+}
+
+#[cfg(not(silenced))]
+impl<F> Drop for LoggedFuture<F> {
+    fn drop(&mut self) {
+        // Safety: `future` is not used after this, and dropping it in
+        // place keeps the pinning guarantees.
+        unsafe { ManuallyDrop::drop(&mut self.future) };
+        self.check_wakers();
+    }
+}
+
+/// Counts the wakers handed to a Future through `CountedWaker`.
+#[derive(Debug, Default)]
+#[cfg_attr(silenced, allow(dead_code))]
+struct WakerCounts {
+    /// The clones made by the Future.
+    clones: AtomicUsize,
+    /// The wakers not dropped or woken yet.
+    live: AtomicUsize,
+    /// The fingerprint of the waker of the last wakeup, or 0.
+    woken: AtomicU64,
+    /// The inspection whose poll the last wakeup came from, if any
+    /// and not logged yet.
+    #[cfg(feature="std")]
+    woken_from: Mutex<Option<(Label, u64)>>,
+}
+
+#[cfg(not(silenced))]
+impl WakerCounts {
+    /// Notes a wakeup through the waker fingerprinted `waker`.
+    fn woken(&self, waker: u64) {
+        self.woken.store(waker, Ordering::SeqCst);
+        #[cfg(feature="std")]
+        {
+            *self.woken_from.lock().unwrap_or_else(|e| e.into_inner()) = context::current();
+        }
+    }
+}
+
+/// Forwards to the waker of a poll, counted in `counts`.
+#[cfg(not(silenced))]
+struct CountedWaker {
+    waker: Waker,
+    counts: Arc<WakerCounts>,
+}
+
+#[cfg(not(silenced))]
+static COUNTED_WAKER: RawWakerVTable = RawWakerVTable::new(CountedWaker::clone,
+                                                           CountedWaker::wake,
+                                                           CountedWaker::wake_by_ref,
+                                                           CountedWaker::drop);
+
+#[cfg(not(silenced))]
+impl CountedWaker {
+    fn waker(waker: Waker, counts: Arc<WakerCounts>) -> Waker {
+        // Safety: the vtable functions treat the data as the
+        // `CountedWaker` boxed here.
+        unsafe { Waker::from_raw(CountedWaker::raw(waker, counts)) }
+    }
+
+    fn raw(waker: Waker, counts: Arc<WakerCounts>) -> RawWaker {
+        counts.live.fetch_add(1, Ordering::SeqCst);
+        let counted = Box::into_raw(Box::new(CountedWaker { waker, counts }));
+        RawWaker::new(counted as *const (), &COUNTED_WAKER)
+    }
+
+    unsafe fn clone(data: *const ()) -> RawWaker {
+        let counted = &*(data as *const CountedWaker);
+        counted.counts.clones.fetch_add(1, Ordering::SeqCst);
+        CountedWaker::raw(counted.waker.clone(), counted.counts.clone())
+    }
+
+    unsafe fn wake(data: *const ()) {
+        let counted = Box::from_raw(data as *mut CountedWaker);
+        counted.counts.live.fetch_sub(1, Ordering::SeqCst);
+        counted.counts.woken(fingerprint(&counted.waker));
+        counted.waker.wake();
+    }
+
+    unsafe fn wake_by_ref(data: *const ()) {
+        let counted = &*(data as *const CountedWaker);
+        counted.counts.woken(fingerprint(&counted.waker));
+        counted.waker.wake_by_ref();
+    }
+
+    unsafe fn drop(data: *const ()) {
+        let counted = Box::from_raw(data as *mut CountedWaker);
+        counted.counts.live.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(not(silenced))]
+impl<F> FusedFuture for LoggedFuture<F>
+    where F: FusedFuture,
+          F::Output: Debug
+{
+    fn is_terminated(&self) -> bool {
+        let terminated = self.future.is_terminated();
+        if self.inspector.logs_is_terminated() {
+            self.inspector.emit(EventKind::IsTerminated(terminated), None);
+        }
+        terminated
+    }
+}
+
+#[cfg(silenced)]
+impl<F: Future> Future for LoggedFuture<F> {
+    type Output = F::Output;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // Safety: `future` is never moved out of the pinned wrapper.
+        unsafe { self.map_unchecked_mut(|this| &mut *this.future) }.poll(cx)
+    }
+}
+
+#[cfg(silenced)]
+impl<F> Drop for LoggedFuture<F> {
+    fn drop(&mut self) {
+        // Safety: `future` is not used after this.
+        unsafe { ManuallyDrop::drop(&mut self.future) };
+    }
+}
+
+#[cfg(silenced)]
+impl<F: FusedFuture> FusedFuture for LoggedFuture<F> {
+    fn is_terminated(&self) -> bool {
+        self.future.is_terminated()
+    }
+}
+
+/// LoggingExt introduces the logging capabilities to any
+/// `std::future::Future`, as long as its Output can be printed.
+pub trait LoggingExt: Future + Sized
+    where Self::Output: Debug
+{
+    /// inspect() sets up the logging. The `label` will be used to
+    /// identify the Future in the log messages used.
+    ///
+    /// This method returns `Self` instead of a `LoggedFuture`
+    /// when the `silence` feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect(self, label: &str) -> LoggedFuture<Self>;
+    /// inspect() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect(self, label: &str) -> Self;
+
+    /// inspect_with_opts() sets up the logging according to
+    /// `options`, see `Inspect`. Options that only apply to futures
+    /// 0.1 are ignored.
+    #[cfg(not(silenced))]
+    fn inspect_with_opts(self, options: Inspect) -> LoggedFuture<Self>;
+    /// inspect_with_opts() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_with_opts(self, options: Inspect) -> Self;
+}
+
+impl<F> LoggingExt for F
+    where F: Future,
+          F::Output: Debug
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect(self, label: &str) -> LoggedFuture<Self> {
+        LoggedFuture::new(self, Inspect::new(label))
+    }
+    #[cfg(silenced)]
+    fn inspect(self, _: &str) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    fn inspect_with_opts(self, options: Inspect) -> LoggedFuture<Self> {
+        LoggedFuture::new(self, options)
+    }
+    #[cfg(silenced)]
+    fn inspect_with_opts(self, _: Inspect) -> Self {
+        self
+    }
+}
+
+/// The LoggedStream struct wraps a futures 0.3 Stream and will log
+/// all poll calls, numbering every item it yields, see
+/// `StreamLoggingExt`. Dropping it before the stream ended is logged
+/// too.
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct LoggedStream<S> {
+    stream: S,
+    inspector: Inspector,
+    items: u64,
+    ended: bool,
+    /// The size hint logged last, if size hints are logged.
+    size_hint: Option<(usize, Option<usize>)>,
+}
+
+#[cfg(not(silenced))]
+impl<S: Stream> LoggedStream<S> {
+    fn new(stream: S, options: Inspect) -> LoggedStream<S> {
+        let mut logged = LoggedStream {
+            stream,
+            inspector: Inspector::new(options),
+            items: 0,
+            ended: false,
+            size_hint: None,
+        };
+        logged.log_size_hint();
+        logged
+    }
+
+    /// Logs the size hint of the Stream, if it changed.
+    fn log_size_hint(&mut self) {
+        if !self.inspector.logs_size_hint() {
+            return;
+        }
+        let size_hint = self.stream.size_hint();
+        if self.size_hint != Some(size_hint) {
+            self.size_hint = Some(size_hint);
+            self.inspector.emit(EventKind::SizeHint(self.items, size_hint.0, size_hint.1), None);
+        }
+    }
+}
+
+impl<S> LoggedStream<S> {
+    /// Borrows the wrapped Stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Borrows the wrapped Stream of a pinned wrapper, pinned in
+    /// turn.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut S> {
+        // Safety: `stream` is pinned along with the wrapper.
+        unsafe { self.map_unchecked_mut(|this| &mut this.stream) }
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S> Stream for LoggedStream<S>
+    where S: Stream,
+          S::Item: Debug
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<S::Item>> {
+        // Safety: `stream` is never moved out of the pinned wrapper.
+        let this = unsafe { self.get_unchecked_mut() };
+        let _entered = this.inspector.enter();
+        let stopwatch = this.inspector.begin();
+        this.inspector.emit(EventKind::StreamPolling, None);
+        let poll = unsafe { Pin::new_unchecked(&mut this.stream) }.poll_next(cx);
+        let kind = match poll {
+            Poll::Ready(Some(ref item)) => {
+                this.items += 1;
+                this.inspector.end(&Outcome::Ready(this.inspector.value(item)));
+                EventKind::StreamItem(this.items, this.inspector.value(item))
+            }
+            Poll::Ready(None) => {
+                this.ended = true;
+                this.inspector.end(&Outcome::Ready(&()));
+                EventKind::StreamEnded(this.items, this.inspector.polls())
+            }
+            Poll::Pending => {
+                this.inspector.end(&Outcome::NotReady);
+                EventKind::StreamPolled(Outcome::NotReady)
+            }
+        };
+        this.inspector.emit(kind, stopwatch);
+        if !this.ended {
+            this.log_size_hint();
+        }
+        poll
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S> FusedStream for LoggedStream<S>
+    where S: FusedStream,
+          S::Item: Debug
+{
+    fn is_terminated(&self) -> bool {
+        self.stream.is_terminated()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S> Drop for LoggedStream<S> {
+    fn drop(&mut self) {
+        if !self.ended {
+            self.inspector.emit(EventKind::StreamDropped(self.items, self.inspector.polls()), None);
+        }
+    }
+}
+
+#[cfg(silenced)]
+impl<S: Stream> Stream for LoggedStream<S> {
+    type Item = S::Item;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<S::Item>> {
+        self.get_pin_mut().poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+/// StreamLoggingExt introduces the logging capabilities to any
+/// futures 0.3 Stream, as long as its Item can be printed.
+pub trait StreamLoggingExt: Stream + Sized
+    where Self::Item: Debug
+{
+    /// inspect_stream() sets up the logging. The `label` will be
+    /// used to identify the Stream in the log messages used.
+    ///
+    /// This method returns `Self` instead of a `LoggedStream`
+    /// when the `silence` feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect_stream(self, label: &str) -> LoggedStream<Self>;
+    /// inspect_stream() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_stream(self, label: &str) -> Self;
+
+    /// inspect_stream_with_opts() sets up the logging according to
+    /// `options`, see `Inspect`. With `Inspect::log_size_hint`, the
+    /// size hint is logged along with the items:
+    ///
+    /// ```rust,edition2018
+    /// use futures_poll_log::Inspect;
+    /// use futures_poll_log::futures03::StreamLoggingExt;
+    /// # use futures_core::Stream;
+    ///
+    /// # fn rows() -> impl Stream<Item = u32> { Empty }
+    /// # struct Empty;
+    /// # impl Stream for Empty {
+    /// #     type Item = u32;
+    /// #     fn poll_next(self: std::pin::Pin<&mut Self>, _: &mut std::task::Context) -> std::task::Poll<Option<u32>> {
+    /// #         std::task::Poll::Ready(None)
+    /// #     }
+    /// # }
+    /// let rows = rows().inspect_stream_with_opts(Inspect::new("rows").log_size_hint());
+    /// // Stream `rows' hints at 0 to unbounded more items after 0 items
+    /// # drop(rows);
+    /// ```
+    #[cfg(not(silenced))]
+    fn inspect_stream_with_opts(self, options: Inspect) -> LoggedStream<Self>;
+    /// inspect_stream_with_opts() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_stream_with_opts(self, options: Inspect) -> Self;
+}
+
+impl<S> StreamLoggingExt for S
+    where S: Stream,
+          S::Item: Debug
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_stream(self, label: &str) -> LoggedStream<Self> {
+        LoggedStream::new(self, Inspect::new(label))
+    }
+    #[cfg(silenced)]
+    fn inspect_stream(self, _: &str) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    fn inspect_stream_with_opts(self, options: Inspect) -> LoggedStream<Self> {
+        LoggedStream::new(self, options)
+    }
+    #[cfg(silenced)]
+    fn inspect_stream_with_opts(self, _: Inspect) -> Self {
+        self
+    }
+}
+
+/// LoggedCompat runs a futures 0.1 Future as a
+/// `std::future::Future`, logging its polls and the wakeups
+/// crossing the boundary, see `CompatLoggingExt`.
+///
+/// This type is only available with the `std` feature.
+#[cfg(feature="std")]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct LoggedCompat<F> {
+    spawn: Spawn<F>,
+    unpark: Arc<CompatUnpark>,
+    inspector: Inspector,
+    done: bool,
+}
+
+// Futures 0.1 are polled through `&mut` and may always be moved.
+#[cfg(feature="std")]
+impl<F> Unpin for LoggedCompat<F> {}
+
+#[cfg(feature="std")]
+impl<F> fmt::Debug for LoggedCompat<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoggedCompat")
+         .field("inspector", &self.inspector)
+         .field("done", &self.done)
+         .finish()
+    }
+}
+
+/// Forwards wakeups from the futures 0.1 side to the waker of
+/// the last poll, remembering that one happened.
+#[cfg(feature="std")]
+struct CompatUnpark {
+    waker: Mutex<Option<Waker>>,
+    woken: AtomicBool,
+}
+
+#[cfg(feature="std")]
+impl Unpark for CompatUnpark {
+    fn unpark(&self) {
+        self.woken.store(true, Ordering::SeqCst);
+        let waker = self.waker.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(ref waker) = *waker {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl<F: ::futures::Future> LoggedCompat<F> {
+    fn new(future: F, options: Inspect) -> LoggedCompat<F> {
+        LoggedCompat {
+            spawn: executor::spawn(future),
+            unpark: Arc::new(CompatUnpark {
+                waker: Mutex::new(None),
+                woken: AtomicBool::new(false),
+            }),
+            inspector: Inspector::new(options),
+            done: false,
+        }
+    }
+
+    /// Polls the futures 0.1 side on behalf of the task of `cx`.
+    fn poll_01(&mut self, cx: &mut Context) -> futures::Poll<F::Item, F::Error> {
+        {
+            let mut waker = self.unpark.waker.lock().unwrap_or_else(|e| e.into_inner());
+            match *waker {
+                Some(ref waker) if waker.will_wake(cx.waker()) => {}
+                _ => *waker = Some(cx.waker().clone()),
+            }
+        }
+        let poll = self.spawn.poll_future(self.unpark.clone());
+        self.done = !matches!(poll, Ok(Async::NotReady));
+        poll
+    }
+}
+
+#[cfg(all(feature="std", not(silenced)))]
+impl<F> Future for LoggedCompat<F>
+    where F: ::futures::Future,
+          F::Item: Debug,
+          F::Error: Debug
+{
+    type Output = Result<F::Item, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let woken = this.unpark.woken.swap(false, Ordering::SeqCst);
+        let _entered = this.inspector.enter();
+        let stopwatch = this.inspector.begin();
+        this.inspector.emit(EventKind::Polling, None);
+        if woken {
+            this.inspector.emit(EventKind::CompatWoken, None);
+        }
+        let poll = this.poll_01(cx);
+        let outcome = this.inspector.outcome(&poll);
+        this.inspector.end(&outcome);
+        this.inspector.emit(EventKind::Polled(outcome), stopwatch);
+        match poll {
+            Ok(Async::Ready(item)) => Poll::Ready(Ok(item)),
+            Ok(Async::NotReady) => Poll::Pending,
+            Err(error) => Poll::Ready(Err(error)),
+        }
+    }
+}
+
+#[cfg(all(feature="std", not(silenced)))]
+impl<F> Drop for LoggedCompat<F> {
+    fn drop(&mut self) {
+        if !self.done && self.unpark.woken.load(Ordering::SeqCst) {
+            self.inspector.emit(EventKind::LostWakeup, None);
+        }
+    }
+}
+
+#[cfg(all(feature="std", silenced))]
+impl<F: ::futures::Future> Future for LoggedCompat<F> {
+    type Output = Result<F::Item, F::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.get_mut().poll_01(cx) {
+            Ok(Async::Ready(item)) => Poll::Ready(Ok(item)),
+            Ok(Async::NotReady) => Poll::Pending,
+            Err(error) => Poll::Ready(Err(error)),
+        }
+    }
+}
+
+/// CompatLoggingExt turns any futures 0.1 Future into a logged
+/// `std::future::Future`, as long as all its Item and Error can
+/// be printed.
+///
+/// This trait is only available with the `std` feature.
+#[cfg(feature="std")]
+pub trait CompatLoggingExt: ::futures::Future + Sized
+    where Self::Item: Debug,
+          Self::Error: Debug
+{
+    /// inspect_compat() converts the Future into a
+    /// `std::future::Future` resolving to its result, and logs its
+    /// polls like `inspect()`. Polls following a wakeup from the
+    /// futures 0.1 side are noted, and a wakeup that never led to a
+    /// poll is reported as a warning when the Future is dropped.
+    ///
+    /// The conversion still happens, without logging, when the
+    /// `silence` feature is activated.
+    fn inspect_compat(self, label: &str) -> LoggedCompat<Self>;
+}
+
+#[cfg(feature="std")]
+impl<F> CompatLoggingExt for F
+    where F: ::futures::Future,
+          F::Item: Debug,
+          F::Error: Debug
+{
+    #[track_caller]
+    fn inspect_compat(self, label: &str) -> LoggedCompat<Self> {
+        LoggedCompat::new(self, Inspect::new(label))
+    }
+}
+
+/// LoggedTask wraps a Future about to be spawned as a task,
+/// logging when it was spawned, first polled and completed, see
+/// `wrap_for_spawn`.
+///
+/// This type is only available with the `std` feature.
+#[cfg(feature="std")]
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct LoggedTask<F> {
+    future: F,
+    inspector: Inspector,
+    spawned: Stopwatch,
+}
+
+#[cfg(feature="std")]
+impl<F: Future> LoggedTask<F> {
+    fn new(future: F, options: Inspect) -> LoggedTask<F> {
+        let inspector = Inspector::new(options);
+        let task = LoggedTask {
+            future,
+            spawned: inspector.stopwatch(),
+            inspector,
+        };
+        #[cfg(not(silenced))]
+        task.inspector.emit(EventKind::Spawned, None);
+        task
+    }
+}
+
+#[cfg(all(feature="std", not(silenced)))]
+impl<F: Future> Future for LoggedTask<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // Safety: `future` is never moved out of the pinned wrapper.
+        let this = unsafe { self.get_unchecked_mut() };
+        let _entered = this.inspector.enter();
+        this.inspector.begin();
+        if this.inspector.polls() == 1 {
+            if let Some(elapsed) = this.spawned.elapsed() {
+                this.inspector.emit(EventKind::TaskStarted(elapsed), None);
+            }
+        }
+        let poll = unsafe { Pin::new_unchecked(&mut this.future) }.poll(cx);
+        this.inspector.end(&if poll.is_ready() { Outcome::Ready(Inspector::opaque()) } else { Outcome::NotReady });
+        if poll.is_ready() {
+            if let Some(elapsed) = this.spawned.elapsed() {
+                this.inspector.emit(EventKind::TaskCompleted(elapsed), None);
+            }
+        }
+        poll
+    }
+}
+
+#[cfg(all(feature="std", silenced))]
+impl<F: Future> Future for LoggedTask<F> {
+    type Output = F::Output;
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // Safety: `future` is never moved out of the pinned wrapper.
+        unsafe { self.map_unchecked_mut(|this| &mut this.future) }.poll(cx)
+    }
+}
+
+/// logged_block_on() blocks the current thread until `future`
+/// resolves and returns its output, logging it as `label` like
+/// `futures_poll_log::logged_wait()` does for futures 0.1: when the
+/// thread starts blocking, every poll, every wakeup and how long it
+/// was parked in total. Blocking inside the poll of another
+/// inspection is logged as a warning.
+///
+/// ```rust,edition2018
+/// let answer = futures_poll_log::futures03::logged_block_on("answer", async { 42 });
+/// assert_eq!(answer, 42);
+/// ```
+///
+/// The future is still run, without logging, when the `silence`
+/// feature is activated. This function is only available with the
+/// `std` feature.
+#[cfg(feature="std")]
+#[track_caller]
+pub fn logged_block_on<F>(label: &str, future: F) -> F::Output
+    where F: Future,
+          F::Output: Debug
+{
+    let unpark = ThreadUnpark::current();
+    let waker = Waker::from(unpark.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut future = core::pin::pin!(future);
+    #[cfg(not(silenced))]
+    let mut blocked = Blocked::start(label);
+    #[cfg(silenced)]
+    let _ = label;
+    loop {
+        #[cfg(not(silenced))]
+        let poll = {
+            let inspector = &mut blocked.inspector;
+            let _entered = inspector.enter();
+            let stopwatch = inspector.begin();
+            inspector.emit(EventKind::Polling, None);
+            let poll = future.as_mut().poll(&mut cx);
+            let outcome = match poll {
+                Poll::Ready(ref output) => Outcome::Ready(inspector.value(output)),
+                Poll::Pending => Outcome::NotReady,
+            };
+            inspector.end(&outcome);
+            inspector.emit(EventKind::Polled(outcome), stopwatch);
+            poll
+        };
+        #[cfg(silenced)]
+        let poll = future.as_mut().poll(&mut cx);
+        match poll {
+            Poll::Ready(output) => {
+                #[cfg(not(silenced))]
+                blocked.finish();
+                return output;
+            }
+            #[cfg(not(silenced))]
+            Poll::Pending => blocked.park(&unpark),
+            #[cfg(silenced)]
+            Poll::Pending => unpark.park(),
+        }
+    }
+}
+
+/// wrap_for_spawn() prepares `future` to be spawned as a task
+/// labeled `label` on any executor, e.g. through
+/// `async_std::task::spawn(wrap_for_spawn("request", future))`.
+/// The task is logged when it is spawned, first polled and
+/// completed, along with the time since it was spawned:
+///
+/// ```plain
+/// DEBUG - Task `request' spawned
+/// DEBUG - Task `request' first polled 21.4µs after spawn
+/// DEBUG - Task `request' completed 1.2ms after spawn
+/// ```
+///
+/// The spawn is logged right away, so the result should be spawned
+/// immediately. Nothing is logged when the `silence` feature is
+/// activated.
+///
+/// This function is only available with the `std` feature.
+#[cfg(feature="std")]
+#[track_caller]
+pub fn wrap_for_spawn<F: Future>(label: &str, future: F) -> LoggedTask<F> {
+    LoggedTask::new(future, Inspect::new(label))
+}
+
+/// LoggedExecutor wraps a futures 0.3 executor and spawns every
+/// Future as a logged task, see `wrap_for_spawn`.
+///
+/// Futures spawned through the `Spawn` and `LocalSpawn` traits, e.g.
+/// by `SpawnExt::spawn`, are labeled by the executor, `task-1`,
+/// `task-2` and so on. `spawn_labeled` picks the label instead:
+///
+/// ```rust,edition2018
+/// # extern crate futures_task;
+/// use std::sync::Mutex;
+/// use futures_task::{FutureObj, Spawn, SpawnError};
+/// use futures_poll_log::futures03::LoggedExecutor;
+///
+/// #[derive(Default)]
+/// struct Queue(Mutex<Vec<FutureObj<'static, ()>>>);
+///
+/// impl Spawn for Queue {
+///     fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), SpawnError> {
+///         self.0.lock().unwrap().push(future);
+///         Ok(())
+///     }
+/// }
+///
+/// let executor = LoggedExecutor::new(Queue::default()).prefix("worker");
+/// executor.spawn_obj(FutureObj::new(Box::new(async {})))?; // worker-1
+/// executor.spawn_labeled("flush", async {})?;
+/// # assert_eq!(executor.get_ref().0.lock().unwrap().len(), 2);
+/// # Ok::<(), SpawnError>(())
+/// ```
+///
+/// Futures 0.1 executors only ever receive futures already wrapped
+/// in a `Run`, so they cannot be instrumented this way.
+///
+/// This type is only available with the `std` feature.
+#[cfg(feature="std")]
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct LoggedExecutor<S> {
+    inner: S,
+    prefix: String,
+    spawned: AtomicU64,
+}
+
+#[cfg(feature="std")]
+impl<S> LoggedExecutor<S> {
+    /// Wraps `inner`, labeling the tasks spawned through it as
+    /// `task-1`, `task-2` and so on.
+    pub fn new(inner: S) -> LoggedExecutor<S> {
+        LoggedExecutor {
+            inner,
+            prefix: "task".into(),
+            spawned: AtomicU64::new(0),
+        }
+    }
+
+    /// Labels the tasks `{prefix}-1`, `{prefix}-2` and so on.
+    pub fn prefix(mut self, prefix: &str) -> LoggedExecutor<S> {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Returns a reference to the wrapped executor.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the wrapped executor.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The label of the next task spawned without one.
+    #[cfg(not(silenced))]
+    fn next_label(&self) -> String {
+        let number = self.spawned.fetch_add(1, Ordering::Relaxed) + 1;
+        format!("{}-{}", self.prefix, number)
+    }
+}
+
+#[cfg(feature="std")]
+impl<S: futures_task::Spawn> LoggedExecutor<S> {
+    /// spawn_labeled() spawns `future` as a task labeled `label`.
+    #[track_caller]
+    pub fn spawn_labeled<F>(&self, label: &str, future: F) -> Result<(), SpawnError>
+        where F: Future<Output = ()> + Send + 'static
+    {
+        self.inner.spawn_obj(FutureObj::new(Box::pin(wrap_for_spawn(label, future))))
+    }
+}
+
+#[cfg(all(feature="std", not(silenced)))]
+impl<S: futures_task::Spawn> futures_task::Spawn for LoggedExecutor<S> {
+    fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), SpawnError> {
+        self.spawn_labeled(&self.next_label(), future)
+    }
+
+    fn status(&self) -> Result<(), SpawnError> {
+        self.inner.status()
+    }
+}
+
+#[cfg(all(feature="std", silenced))]
+impl<S: futures_task::Spawn> futures_task::Spawn for LoggedExecutor<S> {
+    #[inline]
+    fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), SpawnError> {
+        self.inner.spawn_obj(future)
+    }
+
+    fn status(&self) -> Result<(), SpawnError> {
+        self.inner.status()
+    }
+}
+
+#[cfg(all(feature="std", not(silenced)))]
+impl<S: LocalSpawn> LocalSpawn for LoggedExecutor<S> {
+    fn spawn_local_obj(&self, future: LocalFutureObj<'static, ()>) -> Result<(), SpawnError> {
+        let task = wrap_for_spawn(&self.next_label(), future);
+        self.inner.spawn_local_obj(LocalFutureObj::new(Box::pin(task)))
+    }
+
+    fn status_local(&self) -> Result<(), SpawnError> {
+        self.inner.status_local()
+    }
+}
+
+#[cfg(all(feature="std", silenced))]
+impl<S: LocalSpawn> LocalSpawn for LoggedExecutor<S> {
+    #[inline]
+    fn spawn_local_obj(&self, future: LocalFutureObj<'static, ()>) -> Result<(), SpawnError> {
+        self.inner.spawn_local_obj(future)
+    }
+
+    fn status_local(&self) -> Result<(), SpawnError> {
+        self.inner.status_local()
+    }
+}
@@ -0,0 +1,144 @@
+//! The hierarchy of nested inspections, as a Graphviz graph.
+//!
+//! After `enable()`, every time an inspection is polled from within
+//! the poll of another one, an edge from the outer label to the inner
+//! one is recorded. `to_dot()` renders all edges seen so far:
+//!
+//! ```rust
+//! extern crate futures;
+//! extern crate futures_poll_log;
+//!
+//! use futures::{Future, future};
+//! use futures_poll_log::{LoggingExt, graph};
+//!
+//! # fn main() {
+//! graph::enable();
+//! let _ = future::ok::<i32, ()>(3)
+//!     .inspect("inner")
+//!     .map(|x| x * 2)
+//!     .inspect("outer")
+//!     .wait();
+//! print!("{}", graph::to_dot());
+//! # }
+//! ```
+//!
+//! Instances sharing a label are merged into a single node.
+//!
+//! Wakeups are recorded as well, for futures 0.3 inspections tracking
+//! or logging their wakers: a future woken from within the poll of
+//! another inspection adds a dashed edge from the waking label to the
+//! woken one. `wakes()` returns them as a list.
+//!
+//! This module is only available with the `std` feature.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::io;
+use std::sync::Mutex;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static GRAPH: Mutex<Graph> = Mutex::new(Graph {
+    nodes: BTreeSet::new(),
+    edges: BTreeMap::new(),
+    wakes: BTreeSet::new(),
+});
+
+struct Graph {
+    nodes: BTreeSet<String>,
+    /// The labels polled from within each label.
+    edges: BTreeMap<String, BTreeSet<String>>,
+    /// The labels that woke another one, paired with it.
+    wakes: BTreeSet<(String, String)>,
+}
+
+/// enable() starts recording the hierarchy of inspections.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// disable() stops recording the hierarchy. Recorded edges
+/// are kept.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Release);
+}
+
+/// Whether the hierarchy is recorded.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// reset() discards the recorded hierarchy.
+pub fn reset() {
+    let mut graph = GRAPH.lock().unwrap_or_else(|e| e.into_inner());
+    graph.nodes.clear();
+    graph.edges.clear();
+    graph.wakes.clear();
+}
+
+/// wakes() lists the pairs of labels recorded so far where the
+/// first one woke the second one.
+pub fn wakes() -> Vec<(String, String)> {
+    let graph = GRAPH.lock().unwrap_or_else(|e| e.into_inner());
+    graph.wakes.iter().cloned().collect()
+}
+
+/// to_dot() renders the hierarchy recorded so far as a
+/// Graphviz `digraph`.
+pub fn to_dot() -> String {
+    let graph = GRAPH.lock().unwrap_or_else(|e| e.into_inner());
+    let mut dot = String::from("digraph futures {\n");
+    for node in &graph.nodes {
+        let _ = writeln!(dot, "    {};", quoted(node));
+    }
+    for (parent, children) in &graph.edges {
+        for child in children {
+            let _ = writeln!(dot, "    {} -> {};", quoted(parent), quoted(child));
+        }
+    }
+    for (waker, woken) in &graph.wakes {
+        let _ = writeln!(dot, "    {} -> {} [style=dashed, label=\"wakes\"];", quoted(waker), quoted(woken));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// write_dot() writes the output of `to_dot()` to `writer`.
+pub fn write_dot<W: io::Write>(mut writer: W) -> io::Result<()> {
+    writer.write_all(to_dot().as_bytes())
+}
+
+fn quoted(id: &str) -> String {
+    alloc::format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Records that `label` is polled from within `parent`.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn record(parent: Option<&str>, label: &str) {
+    let mut graph = GRAPH.lock().unwrap_or_else(|e| e.into_inner());
+    if !graph.nodes.contains(label) {
+        graph.nodes.insert(label.into());
+    }
+    if let Some(parent) = parent {
+        if !graph.edges.get(parent).is_some_and(|children| children.contains(label)) {
+            graph.edges.entry(parent.into()).or_default().insert(label.into());
+        }
+    }
+}
+
+/// Records that `woken` was woken from within the poll of `waker`.
+#[cfg_attr(any(silenced, not(feature="futures03")), allow(dead_code))]
+pub(crate) fn record_wake(waker: &str, woken: &str) {
+    if !ENABLED.load(Ordering::Acquire) {
+        return;
+    }
+    let mut graph = GRAPH.lock().unwrap_or_else(|e| e.into_inner());
+    for label in [waker, woken] {
+        if !graph.nodes.contains(label) {
+            graph.nodes.insert(label.into());
+        }
+    }
+    graph.wakes.insert((waker.into(), woken.into()));
+}
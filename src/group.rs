@@ -0,0 +1,128 @@
+//! Settings shared by the inspections of a group.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use options::Level;
+
+static GROUP_LEVELS: RwLock<Vec<(String, Level)>> = RwLock::new(Vec::new());
+static GROUP_LIMITS: RwLock<Vec<(String, Arc<RateLimit>)>> = RwLock::new(Vec::new());
+/// Spares events the locks while nothing is set for any group.
+static HAS_GROUP_SETTINGS: AtomicBool = AtomicBool::new(false);
+
+/// A limit on the events written for a group in every second.
+#[derive(Debug)]
+struct RateLimit {
+    per_second: u64,
+    window: Mutex<Window>,
+}
+
+#[derive(Debug)]
+struct Window {
+    start: Instant,
+    written: u64,
+}
+
+/// Whether an event of a rate limited group is written.
+#[cfg_attr(silenced, allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Admission {
+    Written,
+    /// The first event over the given limit in this second.
+    Exceeded(u64),
+    Dropped,
+}
+
+/// set_group_level() writes the events of all inspections in
+/// `group`, set through `Inspect::group` or
+/// `LoggingExt::inspect_in_group`, at `level`, including inspections
+/// that are already running. A level set for the label through
+/// `set_label_level()` takes precedence.
+///
+/// This function is only available with the `std` feature.
+pub fn set_group_level(group: &str, level: Level) {
+    let mut levels = GROUP_LEVELS.write().unwrap_or_else(|e| e.into_inner());
+    levels.retain(|(existing, _)| existing != group);
+    levels.push((group.to_owned(), level));
+    HAS_GROUP_SETTINGS.store(true, Ordering::Release);
+}
+
+/// clear_group_levels() removes all levels set through
+/// `set_group_level()`.
+///
+/// This function is only available with the `std` feature.
+pub fn clear_group_levels() {
+    GROUP_LEVELS.write().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// set_group_rate_limit() writes at most `per_second` events of the
+/// inspections in `group` every second, together. Further events
+/// that second are dropped, after one stating that the limit was
+/// reached. Final results and warnings are always written.
+///
+/// This function is only available with the `std` feature.
+pub fn set_group_rate_limit(group: &str, per_second: u64) {
+    let limit = Arc::new(RateLimit {
+        per_second,
+        window: Mutex::new(Window {
+            start: Instant::now(),
+            written: 0,
+        }),
+    });
+    let mut limits = GROUP_LIMITS.write().unwrap_or_else(|e| e.into_inner());
+    limits.retain(|(existing, _)| existing != group);
+    limits.push((group.to_owned(), limit));
+    HAS_GROUP_SETTINGS.store(true, Ordering::Release);
+}
+
+/// clear_group_rate_limits() removes all limits set through
+/// `set_group_rate_limit()`.
+///
+/// This function is only available with the `std` feature.
+pub fn clear_group_rate_limits() {
+    GROUP_LIMITS.write().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// The level set for `group` through `set_group_level()`, if any.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn level(group: &str) -> Option<Level> {
+    if !HAS_GROUP_SETTINGS.load(Ordering::Acquire) {
+        return None;
+    }
+    let levels = GROUP_LEVELS.read().unwrap_or_else(|e| e.into_inner());
+    levels.iter().find(|(existing, _)| existing == group).map(|&(_, level)| level)
+}
+
+/// Counts an event of `group` against its rate limit, if it has one.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn admit(group: &str) -> Admission {
+    if !HAS_GROUP_SETTINGS.load(Ordering::Acquire) {
+        return Admission::Written;
+    }
+    let limit = {
+        let limits = GROUP_LIMITS.read().unwrap_or_else(|e| e.into_inner());
+        match limits.iter().find(|(existing, _)| existing == group) {
+            Some((_, limit)) => limit.clone(),
+            None => return Admission::Written,
+        }
+    };
+    let mut window = limit.window.lock().unwrap_or_else(|e| e.into_inner());
+    let now = Instant::now();
+    if now.duration_since(window.start) >= Duration::from_secs(1) {
+        window.start = now;
+        window.written = 0;
+    }
+    window.written += 1;
+    if window.written <= limit.per_second {
+        Admission::Written
+    } else if window.written == limit.per_second + 1 {
+        Admission::Exceeded(limit.per_second)
+    } else {
+        Admission::Dropped
+    }
+}
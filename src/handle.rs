@@ -0,0 +1,129 @@
+//! Tuning a single inspection while it runs.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use options::Level;
+
+/// InspectHandle changes the label, the level or whether anything
+/// is logged for the inspection it was given to through
+/// `Inspect::handle`, from anywhere in the program while the
+/// inspected value is running, e.g. to turn on verbose logging for
+/// one long-lived connection at a time:
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_poll_log;
+/// # use futures::future;
+/// use futures_poll_log::{Inspect, InspectHandle, Level, LoggingExt};
+///
+/// # fn main() {
+/// let handle = InspectHandle::new();
+/// let connection = future::empty::<(), ()>()
+///     .inspect_with_opts(Inspect::new("connection").handle(&handle));
+/// handle.disable();
+/// // Later, when this connection is the interesting one:
+/// handle.set_level(Level::Info);
+/// handle.set_label("connection 10.0.0.7");
+/// handle.enable();
+/// # drop(connection);
+/// # }
+/// ```
+///
+/// A new label applies from the next poll on, the level and
+/// enablement from the next event. Clones of a handle control the
+/// same inspection. A handle is meant for a single inspection: given
+/// to several, they all follow its level and enablement, but only
+/// one of them picks up a new label.
+///
+/// This type is only available with the `std` feature.
+#[derive(Debug, Clone, Default)]
+pub struct InspectHandle {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    /// Whether logging is turned off.
+    disabled: AtomicBool,
+    /// The level overriding the one of the inspection, encoded by
+    /// `encode`, or 0 if none.
+    level: AtomicUsize,
+    /// The label waiting to be picked up by the next poll.
+    label: Mutex<Option<Arc<str>>>,
+}
+
+impl InspectHandle {
+    /// Creates a handle that changes nothing until told to.
+    pub fn new() -> InspectHandle {
+        InspectHandle::default()
+    }
+
+    /// Replaces the label of the inspection from its next poll on.
+    /// Prefixes are not applied to the new label.
+    pub fn set_label(&self, label: &str) {
+        *self.shared.label.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::from(label));
+    }
+
+    /// Writes the events of the inspection at `level`, no matter
+    /// the levels it was set up with. Warnings are still written at
+    /// `Warn` or more severe.
+    pub fn set_level(&self, level: Level) {
+        self.shared.level.store(encode(level), Ordering::Relaxed);
+    }
+
+    /// Goes back to the levels the inspection was set up with.
+    pub fn reset_level(&self) {
+        self.shared.level.store(0, Ordering::Relaxed);
+    }
+
+    /// Turns logging back on for the inspection.
+    pub fn enable(&self) {
+        self.shared.disabled.store(false, Ordering::Relaxed);
+    }
+
+    /// Turns logging off for the inspection. It is still counted in
+    /// the statistics and the registry.
+    pub fn disable(&self) {
+        self.shared.disabled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the inspection logs, as far as the handle is
+    /// concerned.
+    pub fn is_enabled(&self) -> bool {
+        !self.shared.disabled.load(Ordering::Relaxed)
+    }
+
+    /// The level set through `set_level()`, if any.
+    pub fn level(&self) -> Option<Level> {
+        decode(self.shared.level.load(Ordering::Relaxed))
+    }
+
+    /// Takes the label set since the last call, if any.
+    #[cfg_attr(silenced, allow(dead_code))]
+    pub(crate) fn take_label(&self) -> Option<Arc<str>> {
+        self.shared.label.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+fn encode(level: Level) -> usize {
+    match level {
+        Level::Error => 1,
+        Level::Warn => 2,
+        Level::Info => 3,
+        Level::Debug => 4,
+        Level::Trace => 5,
+    }
+}
+
+fn decode(level: usize) -> Option<Level> {
+    match level {
+        1 => Some(Level::Error),
+        2 => Some(Level::Warn),
+        3 => Some(Level::Info),
+        4 => Some(Level::Debug),
+        5 => Some(Level::Trace),
+        _ => None,
+    }
+}
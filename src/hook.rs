@@ -0,0 +1,121 @@
+//! Hooks running around every poll of an inspection.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+#[cfg(feature="std")]
+use std::sync::RwLock;
+
+use logger::Outcome;
+
+/// The inspection a `PollHook` is called for.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct PollContext<'a> {
+    /// The label given at inspection.
+    pub label: &'a str,
+    /// The number identifying the inspection, see
+    /// `PollEvent::instance`.
+    pub instance: u64,
+    /// The number of the poll, starting at 1.
+    pub poll: u64,
+    /// The correlation id of the inspection, if any.
+    pub correlation: Option<&'a str>,
+}
+
+/// A PollHook runs code of its own around every poll of an
+/// inspection, e.g. to count polls in the metrics of an
+/// application or to mark them for a profiler. Unlike a
+/// `PollLogger`, it is called for every poll, regardless of
+/// levels, sampling or verbosity.
+///
+/// Hooks are registered for a single inspection through
+/// `Inspect::hook`, or for all of them through `add_hook()`:
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use futures_poll_log::{PollContext, PollHook};
+///
+/// #[derive(Default)]
+/// struct CountPolls(AtomicU64);
+///
+/// impl PollHook for CountPolls {
+///     fn on_poll_start(&self, _: &PollContext) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// futures_poll_log::add_hook(Arc::new(CountPolls::default()));
+/// ```
+///
+/// For sinks, every call to `start_send`, `poll_complete` and
+/// `close` counts as a poll.
+pub trait PollHook: Send + Sync {
+    /// Called before the inner poll.
+    fn on_poll_start(&self, _cx: &PollContext) {}
+
+    /// Called after the inner poll with its result. Items and
+    /// errors are hidden if redaction is enabled, and so are the
+    /// results of tasks from `futures03::wrap_for_spawn` and of
+    /// locks, which need not implement `Debug`. The result of
+    /// `start_send` is `Ready(())` once the item is accepted.
+    fn on_poll_end(&self, _cx: &PollContext, _outcome: &Outcome) {}
+}
+
+/// The hooks registered with a single inspection.
+#[derive(Clone, Default)]
+pub(crate) struct Hooks(Vec<Arc<dyn PollHook>>);
+
+#[cfg_attr(silenced, allow(dead_code))]
+impl Hooks {
+    pub(crate) fn push(&mut self, hook: Arc<dyn PollHook>) {
+        self.0.push(hook);
+    }
+
+    /// Calls `f` with the hooks of the inspection, then with the
+    /// global ones.
+    pub(crate) fn each<F: FnMut(&dyn PollHook)>(&self, mut f: F) {
+        for hook in &self.0 {
+            f(&**hook);
+        }
+        with_global(f);
+    }
+}
+
+impl Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Hooks({})", self.0.len())
+    }
+}
+
+#[cfg(feature="std")]
+static HOOKS: RwLock<Vec<Arc<dyn PollHook>>> = RwLock::new(Vec::new());
+
+/// add_hook() registers `hook` with all inspections, including
+/// running ones. Hooks must not register further hooks while being
+/// called.
+///
+/// This function is only available with the `std` feature.
+#[cfg(feature="std")]
+pub fn add_hook(hook: Arc<dyn PollHook>) {
+    HOOKS.write().unwrap_or_else(|e| e.into_inner()).push(hook);
+}
+
+/// clear_hooks() removes all hooks registered through `add_hook()`.
+///
+/// This function is only available with the `std` feature.
+#[cfg(feature="std")]
+pub fn clear_hooks() {
+    HOOKS.write().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+#[cfg(feature="std")]
+fn with_global<F: FnMut(&dyn PollHook)>(mut f: F) {
+    for hook in HOOKS.read().unwrap_or_else(|e| e.into_inner()).iter() {
+        f(&**hook);
+    }
+}
+
+#[cfg(not(feature="std"))]
+fn with_global<F: FnMut(&dyn PollHook)>(_: F) {}
@@ -0,0 +1,189 @@
+//! A debug endpoint rendering the live inspections and the statistics.
+//!
+//! `handle()` answers a request for a path with the inspections listed
+//! in the `registry` and the per-label `stats`, as HTML or, for paths
+//! ending in `.json`, as JSON. It can be mounted in the server of the
+//! application, or run on a port of its own through `serve()`:
+//!
+//! ```rust,no_run
+//! use futures_poll_log::{http, registry, stats};
+//!
+//! registry::enable();
+//! stats::enable();
+//! http::serve("127.0.0.1:9898").unwrap();
+//! // curl http://127.0.0.1:9898/debug/futures.json
+//! ```
+//!
+//! This module is available with the `http` feature.
+
+use alloc::string::String;
+use core::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread::{self, JoinHandle};
+
+use json::json_string;
+use registry::{self, Inspection, Status};
+use stats::{self, LabelStats};
+
+/// The answer to a request, for the server of the application to
+/// send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Response {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The value of the `Content-Type` header.
+    pub content_type: &'static str,
+    /// The body.
+    pub body: String,
+}
+
+/// handle() renders the inspections alive right now and the statistics
+/// collected so far, as JSON if `path` ends in `.json`, and as an HTML
+/// page otherwise. A query string is ignored.
+pub fn handle(path: &str) -> Response {
+    let path = path.split('?').next().unwrap_or("");
+    let live = registry::snapshot();
+    let stats = stats::snapshot();
+    if path.ends_with(".json") {
+        Response {
+            status: 200,
+            content_type: "application/json",
+            body: to_json(&live, &stats),
+        }
+    } else {
+        Response {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: to_html(&live, &stats),
+        }
+    }
+}
+
+/// serve() answers every request on `addr` through `handle()`, from a
+/// thread of its own, one connection at a time.
+pub fn serve<A: ToSocketAddrs>(addr: A) -> io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)?;
+    thread::Builder::new().name("futures-poll-log-http".into()).spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // A client going away is no reason to stop serving.
+            let _ = answer(stream);
+        }
+    })
+}
+
+fn answer(mut stream: TcpStream) -> io::Result<()> {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let response = handle(path);
+    write!(stream,
+           "HTTP/1.0 {} OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+           response.status,
+           response.content_type,
+           response.body.len())?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+fn status(status: Status) -> &'static str {
+    match status {
+        Status::Unpolled => "unpolled",
+        Status::NotReady => "not_ready",
+        Status::Ready => "ready",
+        Status::Failed => "failed",
+    }
+}
+
+fn to_json(live: &[Inspection], stats: &[LabelStats]) -> String {
+    let mut json = String::from("{\"live\":[");
+    for (index, inspection) in live.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str("{\"label\":");
+        json_string(&mut json, &inspection.label);
+        let _ = write!(json,
+                       ",\"instance\":{},\"status\":\"{}\",\"polls\":{},\"age_ns\":{}",
+                       inspection.instance,
+                       status(inspection.status),
+                       inspection.polls,
+                       inspection.age.as_nanos());
+        if let Some(ref correlation) = inspection.correlation {
+            json.push_str(",\"correlation\":");
+            json_string(&mut json, correlation);
+        }
+        if let Some((ref label, instance)) = inspection.spawned_by {
+            json.push_str(",\"spawned_by\":{\"label\":");
+            json_string(&mut json, label);
+            let _ = write!(json, ",\"instance\":{}}}", instance);
+        }
+        json.push('}');
+    }
+    json.push_str("],\"stats\":[");
+    for (index, stats) in stats.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str("{\"label\":");
+        json_string(&mut json, &stats.label);
+        let _ = write!(json,
+                       ",\"count\":{},\"completions\":{},\"errors\":{},\"poll_time_ns\":{}",
+                       stats.count,
+                       stats.completions,
+                       stats.errors,
+                       stats.poll_time.as_nanos());
+        for (name, percentile) in [("p50_ns", stats.latency.p50()), ("p99_ns", stats.latency.p99())] {
+            if let Some(percentile) = percentile {
+                let _ = write!(json, ",\"{}\":{}", name, percentile.as_nanos());
+            }
+        }
+        json.push('}');
+    }
+    json.push_str("]}\n");
+    json
+}
+
+fn to_html(live: &[Inspection], stats: &[LabelStats]) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>futures</title></head>\n<body>\n");
+    let _ = writeln!(html, "<h1>Live inspections ({})</h1>", live.len());
+    html.push_str("<table>\n<tr><th>label</th><th>instance</th><th>status</th><th>polls</th><th>age</th><th>correlation</th><th>spawned by</th></tr>\n");
+    for inspection in live {
+        let _ = writeln!(html,
+                         "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td></tr>",
+                         escaped(&inspection.label),
+                         inspection.instance,
+                         status(inspection.status),
+                         inspection.polls,
+                         inspection.age,
+                         escaped(inspection.correlation.as_deref().unwrap_or("")),
+                         inspection.spawned_by
+                             .as_ref()
+                             .map_or_else(String::new, |&(ref label, instance)| {
+                                 alloc::format!("{} (#{})", escaped(label), instance)
+                             }));
+    }
+    html.push_str("</table>\n<h1>Statistics</h1>\n<table>\n");
+    html.push_str("<tr><th>label</th><th>count</th><th>completed</th><th>errors</th><th>poll time</th><th>p50</th><th>p99</th></tr>\n");
+    let optional = |duration: Option<core::time::Duration>| {
+        duration.map_or_else(|| "-".into(), |duration| alloc::format!("{:?}", duration))
+    };
+    for stats in stats {
+        let _ = writeln!(html,
+                         "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:?}</td><td>{}</td><td>{}</td></tr>",
+                         escaped(&stats.label),
+                         stats.count,
+                         stats.completions,
+                         stats.errors,
+                         stats.poll_time,
+                         optional(stats.latency.p50()),
+                         optional(stats.latency.p99()));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+fn escaped(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,859 @@
+//! The state shared by all logging wrappers.
+
+#[cfg(feature="std")]
+use alloc::sync::Arc;
+use core::fmt::{self, Debug};
+#[cfg(feature="std")]
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use core::time::Duration;
+use futures::Poll;
+#[cfg(feature="chaos")]
+use futures::task;
+#[cfg(feature="std")]
+use std::thread;
+
+#[cfg(feature="allocations")]
+use allocations::{self, Allocated};
+#[cfg(feature="chaos")]
+use chaos::{self, Fault};
+use config::{self, Verbosity};
+use context::{self, ContextFields, Entered, Frame};
+#[cfg(feature="std")]
+use expect;
+use hook::PollContext;
+use label::Label;
+#[cfg(feature="std")]
+use graph;
+#[cfg(feature="std")]
+use group::{self, Admission};
+use logger::{EventKind, Outcome, PollEvent};
+use options::{Inspect, Level, Nesting};
+#[cfg(feature="std")]
+use options::SlowMotion;
+#[cfg(feature="std")]
+use recorder;
+#[cfg(feature="std")]
+use registry::{self, Status};
+#[cfg(feature="std")]
+use scope;
+#[cfg(feature="std")]
+use settings;
+#[cfg(feature="std")]
+use stats;
+#[cfg(feature="std")]
+use subscriber;
+#[cfg(feature="std")]
+use timer;
+use timing::Stopwatch;
+#[cfg(feature="std")]
+use timing::{self, Clock};
+
+/// Stands in for values when redaction is enabled.
+struct Redacted;
+
+impl Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+static REDACTED: Redacted = Redacted;
+
+/// The instance number of the next inspection.
+static NEXT_INSTANCE: AtomicUsize = AtomicUsize::new(1);
+
+/// The sequence number of the next event.
+static NEXT_SEQUENCE: AtomicUsize = AtomicUsize::new(1);
+
+/// An Inspector applies the options of an inspection and
+/// counts its polls.
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) struct Inspector {
+    options: Inspect,
+    instance: u64,
+    polls: u64,
+    parent: Option<Label>,
+    /// The inspection being polled when this one was set up.
+    spawned_by: Option<(Label, u64)>,
+    depth: usize,
+    created: Stopwatch,
+    /// When the last poll ended, if gaps are tracked.
+    idle_since: Option<Stopwatch>,
+    /// The time between the last two polls, if tracked.
+    gap: Option<Duration>,
+    /// The polls since the last one that was ready, if storms are
+    /// watched for.
+    unready_polls: u64,
+    /// The events written so far, if limited.
+    emitted: AtomicUsize,
+    /// Whether the last poll returned `NotReady`, if coalescing.
+    not_ready: AtomicBool,
+    /// The `NotReady` polls left out since the last one written.
+    coalesced: AtomicUsize,
+    /// The nanoseconds spent logging, if measured.
+    #[cfg(feature="std")]
+    overhead: AtomicU64,
+    #[cfg(feature="std")]
+    overhead_at_begin: u64,
+    /// Whether this inspection is listed in the registry.
+    #[cfg(feature="std")]
+    registered: bool,
+    /// What the thread had allocated when the poll began, if
+    /// allocations are tracked.
+    #[cfg(feature="allocations")]
+    allocated_at_begin: Option<Allocated>,
+    /// The CPU time of the thread and a stopwatch for the wall time
+    /// when the poll began, if CPU time is measured.
+    #[cfg(feature="std")]
+    cpu_at_begin: Option<(Duration, Stopwatch)>,
+    /// Set once a poll held back in slow motion may go through.
+    #[cfg(feature="std")]
+    held_back: Option<Arc<AtomicBool>>,
+}
+
+#[cfg_attr(silenced, allow(dead_code))]
+impl Inspector {
+    pub(crate) fn new(options: Inspect) -> Inspector {
+        #[cfg(feature="std")]
+        let options = settings::apply(options);
+        let inspector = Inspector {
+            created: Inspector::stopwatch_for(&options),
+            options,
+            instance: NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed) as u64,
+            polls: 0,
+            parent: None,
+            spawned_by: context::current(),
+            depth: 0,
+            idle_since: None,
+            gap: None,
+            unready_polls: 0,
+            emitted: AtomicUsize::new(0),
+            not_ready: AtomicBool::new(false),
+            coalesced: AtomicUsize::new(0),
+            #[cfg(feature="std")]
+            overhead: AtomicU64::new(0),
+            #[cfg(feature="std")]
+            overhead_at_begin: 0,
+            #[cfg(feature="std")]
+            registered: registry::is_enabled(),
+            #[cfg(feature="allocations")]
+            allocated_at_begin: None,
+            #[cfg(feature="std")]
+            cpu_at_begin: None,
+            #[cfg(feature="std")]
+            held_back: None,
+        };
+        #[cfg(feature="std")]
+        if inspector.registered {
+            registry::register(inspector.instance,
+                               inspector.options.label.clone(),
+                               inspector.options.correlation.clone(),
+                               inspector.spawned_by.clone(),
+                               inspector.created);
+        }
+        inspector
+    }
+
+    /// Starts measuring on the clock of this inspection.
+    pub(crate) fn stopwatch(&self) -> Stopwatch {
+        Inspector::stopwatch_for(&self.options)
+    }
+
+    /// The clock of this inspection.
+    #[cfg(feature="std")]
+    fn clock(&self) -> &'static dyn Clock {
+        self.options.clock.unwrap_or_else(timing::clock)
+    }
+
+    #[cfg(feature="std")]
+    fn stopwatch_for(options: &Inspect) -> Stopwatch {
+        Stopwatch::start_on(options.clock.unwrap_or_else(timing::clock))
+    }
+    #[cfg(not(feature="std"))]
+    fn stopwatch_for(_options: &Inspect) -> Stopwatch {
+        Stopwatch::start()
+    }
+
+    pub(crate) fn label(&self) -> &str {
+        &self.options.label
+    }
+
+    pub(crate) fn set_label(&mut self, label: &str) {
+        self.relabel(Label::copied(label));
+    }
+
+    pub(crate) fn relabel(&mut self, label: Label) {
+        self.options.label = label;
+        #[cfg(feature="std")]
+        if self.registered {
+            registry::relabeled(self.instance, self.options.label.clone());
+        }
+    }
+
+    pub(crate) fn polls(&self) -> u64 {
+        self.polls
+    }
+
+    pub(crate) fn logs_size(&self) -> bool {
+        self.options.log_size
+    }
+
+    /// Whether values are hidden from the log.
+    pub(crate) fn redacts(&self) -> bool {
+        cfg!(feature="redact") || self.options.redact
+    }
+
+    #[cfg_attr(not(feature="futures03"), allow(dead_code))]
+    pub(crate) fn logs_size_hint(&self) -> bool {
+        self.options.log_size_hint
+    }
+
+    #[cfg_attr(not(feature="futures03"), allow(dead_code))]
+    pub(crate) fn logs_is_terminated(&self) -> bool {
+        self.options.log_is_terminated
+    }
+
+    #[cfg_attr(not(feature="futures03"), allow(dead_code))]
+    pub(crate) fn tracks_wakers(&self) -> bool {
+        self.options.track_wakers
+    }
+
+    #[cfg_attr(not(feature="futures03"), allow(dead_code))]
+    pub(crate) fn logs_wakers(&self) -> bool {
+        self.options.log_wakers
+    }
+
+    /// Whether this inspection is being polled on the current
+    /// thread already.
+    pub(crate) fn is_polling(&self) -> bool {
+        context::contains(self.instance)
+    }
+
+    /// Counts the result of a poll towards the streak of
+    /// resolved futures within the current poll of the task, and
+    /// warns if it exceeds the configured budget.
+    #[cfg(feature="std")]
+    pub(crate) fn count_ready(&self, ready: bool) {
+        if let Some(streak) = context::ready_streak(ready, self.options.ready_budget) {
+            self.emit(EventKind::ReadyStreak(streak), None);
+        }
+    }
+
+    /// Puts this inspection on the poll stack for the duration
+    /// of a poll.
+    pub(crate) fn enter(&mut self) -> Entered {
+        if self.polls == 0 {
+            if let Some(outer) = context::merged_label() {
+                let label = alloc::format!("{}/{}", outer, self.options.label);
+                self.set_label(&label);
+            }
+        }
+        let frame = Frame {
+            label: self.options.label.clone(),
+            instance: self.instance,
+            correlation: self.options.correlation.clone(),
+            context: self.options.context.clone(),
+            merged: self.options.nested && self.options.nesting == Nesting::Merge,
+        };
+        self.depth = context::depth();
+        let (entered, parent) = context::enter(frame);
+        self.parent = parent.map(|parent| parent.label);
+        #[cfg(feature="std")]
+        if graph::is_enabled() {
+            graph::record(self.parent.as_deref(), &self.options.label);
+        }
+        entered
+    }
+
+    /// Counts a new poll and starts timing it, if enabled.
+    pub(crate) fn begin(&mut self) -> Option<Stopwatch> {
+        self.polls += 1;
+        #[cfg(feature="std")]
+        if let Some(label) = self.options.handle.as_ref().and_then(|handle| handle.take_label()) {
+            self.set_label(&label);
+        }
+        if self.polls == 1 {
+            let waited = self.created.elapsed();
+            #[cfg(feature="std")]
+            if self.collects_stats() {
+                stats::started(&self.options.label, self.group(), waited);
+            }
+            if let (true, Some(waited)) = (self.options.log_first_poll, waited) {
+                self.emit(EventKind::FirstPoll(waited), None);
+            }
+        }
+        if self.tracks_gaps() {
+            self.gap = self.idle_since.take().and_then(|idle_since| idle_since.elapsed());
+            if let (Some(gap), Some(max)) = (self.gap, self.options.max_poll_gap) {
+                if gap > max {
+                    self.emit(EventKind::PollGap(gap, max), None);
+                }
+            }
+        }
+        #[cfg(feature="std")]
+        {
+            self.overhead_at_begin = *self.overhead.get_mut();
+        }
+        self.options.hooks.each(|hook| hook.on_poll_start(&self.poll_context()));
+        #[cfg(feature="allocations")]
+        if self.options.track_allocations {
+            self.allocated_at_begin = allocations::current();
+        }
+        #[cfg(feature="std")]
+        if self.options.cpu_time {
+            self.cpu_at_begin = timing::thread_cpu_time().map(|cpu| (cpu, self.stopwatch()));
+        }
+        if self.options.timed || self.collects_stats() {
+            Some(self.stopwatch())
+        } else {
+            None
+        }
+    }
+
+    /// Runs the hooks at the end of a poll with its result.
+    pub(crate) fn end(&mut self, outcome: &Outcome) {
+        #[cfg(feature="std")]
+        if let (Some((begin, stopwatch)), Some(now)) = (self.cpu_at_begin.take(), timing::thread_cpu_time()) {
+            let wall = stopwatch.elapsed().unwrap_or_default();
+            self.emit(EventKind::CpuTime(now.saturating_sub(begin), wall), None);
+        }
+        #[cfg(feature="allocations")]
+        if let (Some(begin), Some(now)) = (self.allocated_at_begin.take(), allocations::current()) {
+            let allocated = now.since(begin);
+            if allocated.count > 0 {
+                self.emit(EventKind::Allocated(allocated.bytes, allocated.count), None);
+            }
+        }
+        self.options.hooks.each(|hook| hook.on_poll_end(&self.poll_context(), outcome));
+        if self.tracks_gaps() {
+            self.idle_since = Some(self.stopwatch());
+        }
+        if let Some(max) = self.options.max_polls {
+            if let Outcome::NotReady = *outcome {
+                self.unready_polls += 1;
+                if self.unready_polls == max + 1 {
+                    self.emit(EventKind::PollStorm(self.unready_polls, self.created.elapsed()), None);
+                }
+            } else {
+                self.unready_polls = 0;
+            }
+        }
+        #[cfg(feature="std")]
+        if self.registered {
+            let status = match *outcome {
+                Outcome::NotReady => Status::NotReady,
+                Outcome::Ready(_) => Status::Ready,
+                Outcome::Err(_) => Status::Failed,
+            };
+            registry::polled(self.instance, self.polls, status, self.stopwatch());
+        }
+    }
+
+    fn tracks_gaps(&self) -> bool {
+        self.options.poll_gaps || self.options.max_poll_gap.is_some()
+    }
+
+    /// Stands in for results that cannot be printed.
+    #[cfg(feature="std")]
+    pub(crate) fn opaque() -> &'static dyn Debug {
+        &REDACTED
+    }
+
+    fn poll_context(&self) -> PollContext<'_> {
+        PollContext {
+            label: &self.options.label,
+            instance: self.instance,
+            poll: self.polls,
+            correlation: self.options.correlation.as_deref(),
+        }
+    }
+
+    /// Hides `value` if redaction is enabled.
+    #[cfg(not(feature="redact"))]
+    pub(crate) fn value<'a>(&self, value: &'a dyn Debug) -> &'a dyn Debug {
+        if self.options.redact { &REDACTED } else { value }
+    }
+    /// With the `redact` feature, values are always hidden.
+    #[cfg(feature="redact")]
+    pub(crate) fn value<'a>(&self, _: &'a dyn Debug) -> &'a dyn Debug {
+        &REDACTED
+    }
+
+    pub(crate) fn outcome<'a, T: Debug, E: Debug>(&self, poll: &'a Poll<T, E>) -> Outcome<'a> {
+        match Outcome::from_poll(poll) {
+            Outcome::NotReady => Outcome::NotReady,
+            Outcome::Ready(item) => Outcome::Ready(self.value(item)),
+            Outcome::Err(error) => Outcome::Err(self.value(error)),
+        }
+    }
+
+    /// Whether `kind` gets through the verbosity and the scopes
+    /// of logging.
+    fn shows(&self, kind: &EventKind) -> bool {
+        #[cfg(feature="std")]
+        if !scope::is_enabled() {
+            return false;
+        }
+        match config::verbosity() {
+            Verbosity::Off => false,
+            Verbosity::Error => kind.is_error() || kind.is_warning() || matches!(*kind, EventKind::ErrorSource(..)),
+            Verbosity::Summary => {
+                kind.is_error() || kind.is_warning() || matches!(*kind, EventKind::ErrorSource(..)) ||
+                matches!(*kind, EventKind::Dropped(_) | EventKind::StreamDropped(..))
+            }
+            Verbosity::Full => true,
+        }
+    }
+
+    /// The level `kind` is written at unless the inspection is
+    /// configured for a more severe one.
+    fn min_level(&self, kind: &EventKind) -> Option<Level> {
+        #[cfg(feature="std")]
+        if self.options.warn_on_thread_change && matches!(*kind, EventKind::ThreadChanged(..)) {
+            return Some(Level::Warn);
+        }
+        kind.min_level()
+    }
+
+    #[cfg(feature="std")]
+    fn handle_level(&self) -> Option<Level> {
+        self.options.handle.as_ref().and_then(|handle| handle.level())
+    }
+
+    #[cfg(not(feature="std"))]
+    fn handle_level(&self) -> Option<Level> {
+        None
+    }
+
+    #[cfg(feature="std")]
+    fn handle_enabled(&self) -> bool {
+        self.options.handle.as_ref().is_none_or(|handle| handle.is_enabled())
+    }
+
+    #[cfg(not(feature="std"))]
+    fn handle_enabled(&self) -> bool {
+        true
+    }
+
+    #[cfg(feature="std")]
+    fn records(&self) -> bool {
+        recorder::is_enabled() || expect::is_capturing() || subscriber::is_active()
+    }
+
+    #[cfg(not(feature="std"))]
+    fn records(&self) -> bool {
+        false
+    }
+
+    /// The group of this inspection, if any.
+    #[cfg(feature="std")]
+    fn group(&self) -> Option<&str> {
+        self.options.group.as_deref()
+    }
+
+    #[cfg(feature="std")]
+    fn group_level(&self) -> Option<Level> {
+        self.group().and_then(group::level)
+    }
+
+    #[cfg(not(feature="std"))]
+    fn group_level(&self) -> Option<Level> {
+        None
+    }
+
+    #[cfg(feature="std")]
+    fn collects_stats(&self) -> bool {
+        self.options.metrics || stats::is_enabled()
+    }
+
+    #[cfg(not(feature="std"))]
+    fn collects_stats(&self) -> bool {
+        false
+    }
+
+    /// The time spent logging this inspection so far, if
+    /// measured.
+    #[cfg(feature="std")]
+    pub(crate) fn overhead(&self) -> Option<Duration> {
+        if self.options.measure_overhead {
+            Some(Duration::from_nanos(self.overhead.load(Ordering::Relaxed)))
+        } else {
+            None
+        }
+    }
+
+    /// Slows down the poll about to begin as set up through
+    /// `Inspect::slow_motion`, and tells whether to return an
+    /// injected `NotReady` instead of polling. The current task is
+    /// woken up again after the delay then.
+    #[cfg(feature="std")]
+    pub(crate) fn slow_motion(&mut self) -> bool {
+        match self.options.slow_motion {
+            None => false,
+            Some(SlowMotion::Sleep(delay)) => {
+                self.emit(EventKind::SlowedDown(delay), None);
+                thread::sleep(delay);
+                false
+            }
+            Some(SlowMotion::NotReady(delay)) => {
+                if let Some(released) = self.held_back.take() {
+                    if released.load(Ordering::Acquire) {
+                        return false;
+                    }
+                    // Woken up early by something else.
+                    self.held_back = Some(released);
+                    return true;
+                }
+                self.emit(EventKind::HeldBack(delay), None);
+                let released = Arc::new(AtomicBool::new(false));
+                self.held_back = Some(released.clone());
+                if !timer::wake_up_in(self.clock(), delay, Some(released)) {
+                    self.held_back = None;
+                    return false;
+                }
+                true
+            }
+        }
+    }
+
+    /// Draws a fault to inject into the poll about to happen, for
+    /// inspections matching a pattern given to `chaos::inject()`.
+    /// Errors are only drawn if `errors` can be built. The current
+    /// task is woken up again when returning `NotReady`.
+    #[cfg(feature="chaos")]
+    pub(crate) fn inject_fault(&self, errors: bool) -> Option<Fault> {
+        let fault = chaos::faults(&self.options.label)?.draw(errors)?;
+        match fault {
+            Fault::NotReady => {
+                self.emit(EventKind::InjectedNotReady, None);
+                task::park().unpark();
+            }
+            Fault::DelayedWake(delay) => {
+                self.emit(EventKind::InjectedDelay(delay), None);
+                if !timer::wake_up_in(self.clock(), delay, None) {
+                    task::park().unpark();
+                }
+            }
+            // Logged by the wrapper, which builds the error.
+            Fault::Error => {}
+        }
+        Some(fault)
+    }
+
+    /// Takes the time spent logging since the poll began out of
+    /// `elapsed`, if measured.
+    #[cfg(feature="std")]
+    fn without_overhead(&self, elapsed: Duration) -> Duration {
+        if !self.options.measure_overhead {
+            return elapsed;
+        }
+        let overhead = self.overhead.load(Ordering::Relaxed) - self.overhead_at_begin;
+        elapsed.saturating_sub(Duration::from_nanos(overhead))
+    }
+
+    #[cfg(not(feature="std"))]
+    fn without_overhead(&self, elapsed: Duration) -> Duration {
+        elapsed
+    }
+
+    /// Sends an event for the current poll, unless sampling, the
+    /// verbosity or the scopes of logging skip it.
+    pub(crate) fn emit(&self, kind: EventKind, stopwatch: Option<Stopwatch>) {
+        self.emit_classified(kind, stopwatch, None);
+    }
+
+    /// Sends an event like `emit`, with the category of the
+    /// error it reports.
+    pub(crate) fn emit_classified(&self, kind: EventKind, stopwatch: Option<Stopwatch>, category: Option<&str>) {
+        #[cfg(feature="std")]
+        if self.registered && kind.is_terminal() {
+            registry::finished(self.instance);
+        }
+        let elapsed = stopwatch.and_then(|stopwatch| stopwatch.elapsed())
+                               .map(|elapsed| self.without_overhead(elapsed));
+        #[cfg(feature="std")]
+        if self.options.measure_overhead {
+            let stopwatch = self.stopwatch();
+            self.send(kind, elapsed, category);
+            if let Some(overhead) = stopwatch.elapsed() {
+                self.overhead.fetch_add(overhead.as_nanos() as u64, Ordering::Relaxed);
+                if self.collects_stats() {
+                    stats::record_overhead(&self.options.label, self.group(), overhead);
+                }
+            }
+            return;
+        }
+        self.send(kind, elapsed, category);
+    }
+
+    /// Whether `kind` is written when coalescing `NotReady` polls,
+    /// writing how many were left out after every `every` of them
+    /// and before the streak ends.
+    fn coalesce(&self, kind: &EventKind, every: u64) -> bool {
+        match *kind {
+            EventKind::Polling | EventKind::StreamPolling => !self.not_ready.load(Ordering::Relaxed),
+            EventKind::Polled(Outcome::NotReady) | EventKind::StreamPolled(Outcome::NotReady) => {
+                if !self.not_ready.swap(true, Ordering::Relaxed) {
+                    return true;
+                }
+                let coalesced = self.coalesced.fetch_add(1, Ordering::Relaxed) as u64 + 1;
+                if coalesced == every {
+                    self.coalesced.store(0, Ordering::Relaxed);
+                    self.send(EventKind::StillNotReady(coalesced), None, None);
+                }
+                false
+            }
+            _ if kind.is_terminal() || kind.outcome().is_some_and(|outcome| !matches!(outcome, Outcome::NotReady)) => {
+                if self.not_ready.swap(false, Ordering::Relaxed) {
+                    let coalesced = self.coalesced.swap(0, Ordering::Relaxed) as u64;
+                    if coalesced > 0 {
+                        self.send(EventKind::StillNotReady(coalesced), None, None);
+                    }
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether an event of `kind` reaches a backend, the recorder, a
+    /// subscriber or the statistics, for events worth building only
+    /// then. Sampling and the limits on events are left to `emit`.
+    #[cfg(feature="std")]
+    pub(crate) fn enabled(&self, kind: &EventKind) -> bool {
+        self.records() || self.collects_stats() ||
+        self.options.log && self.handle_enabled() && self.shows(kind) &&
+        self.options.backend.enabled(self.level(kind), &self.options.target)
+    }
+
+    /// The level `kind` is written at.
+    fn level(&self, kind: &EventKind) -> Level {
+        let level = self.handle_level()
+            .or_else(|| config::label_level(&self.options.label))
+            .or_else(|| self.group_level())
+            .or_else(|| kind.outcome().and_then(|outcome| self.options.outcome_levels.get(&outcome)))
+            .or(match *kind {
+                EventKind::ErrorSource(..) => self.options.outcome_levels.err,
+                _ => None,
+            })
+            .unwrap_or(self.options.level);
+        self.min_level(kind).map_or(level, |min_level| min_level.min(level))
+    }
+
+    fn send(&self, kind: EventKind, elapsed: Option<Duration>, category: Option<&str>) {
+        #[cfg(feature="std")]
+        if self.collects_stats() {
+            stats::record(&self.options.label, self.group(), self.polls, &kind, elapsed, category);
+        }
+        let sampled = self.polls.saturating_sub(1).is_multiple_of(self.options.sample);
+        let sampled = sampled || kind.is_terminal() || kind.is_warning() || matches!(kind, EventKind::ErrorSource(..));
+        let summarized = !self.options.summary_only ||
+                         matches!(kind, EventKind::Dropped(_) | EventKind::StreamDropped(..)) ||
+                         kind.is_warning();
+        // Events hidden from the backend are still kept by the recorder,
+        // captured for `expect` and handed to subscribers.
+        let shallow = self.options.max_depth.is_none_or(|max_depth| self.depth < max_depth) ||
+                      kind.is_warning();
+        let level = self.level(&kind);
+        let mut shown = self.options.log && self.handle_enabled() && sampled && summarized && shallow && self.shows(&kind) &&
+                        self.options.backend.enabled(level, &self.options.target);
+        if let (true, Some(every)) = (shown, self.options.coalesce) {
+            shown = self.coalesce(&kind, every);
+        }
+        if let (true, Some(max)) = (shown, self.options.max_events) {
+            let limited = !kind.is_terminal() && !kind.is_warning() && !matches!(kind, EventKind::Suppressed(_));
+            if limited {
+                let emitted = self.emitted.fetch_add(1, Ordering::Relaxed) as u64;
+                if emitted == max {
+                    self.send(EventKind::Suppressed(max), None, None);
+                }
+                shown = emitted < max;
+            }
+        }
+        #[cfg(feature="std")]
+        if let (true, Some(group)) = (shown, self.group()) {
+            let limited = !kind.is_terminal() && !kind.is_warning() &&
+                          !matches!(kind, EventKind::Suppressed(_) | EventKind::GroupLimited(..));
+            if limited {
+                match group::admit(group) {
+                    Admission::Written => {}
+                    Admission::Exceeded(limit) => {
+                        self.send(EventKind::GroupLimited(group, limit), None, None);
+                        shown = false;
+                    }
+                    Admission::Dropped => shown = false,
+                }
+            }
+        }
+        if !shown && !self.records() {
+            return;
+        }
+        let context = context::fields(self.options.context.as_ref(), self.instance);
+        let event = PollEvent {
+            label: &self.options.label,
+            label_value: self.options.label.value(),
+            instance: self.instance,
+            sequence: NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed) as u64,
+            parent: self.parent.as_deref(),
+            spawned_by: self.spawned_by.as_ref().map(|&(ref label, instance)| (&**label, instance)),
+            location: if self.options.show_location { Some(self.options.location) } else { None },
+            correlation: self.options.correlation.as_deref(),
+            context: ContextFields::new(&context),
+            category,
+            poll: self.polls,
+            level,
+            target: &self.options.target,
+            duration: if self.options.timed { elapsed } else { None },
+            timestamp: self.options.timestamps.now(),
+            timestamps: self.options.timestamps,
+            age: if self.options.relative_timestamps { self.created.elapsed() } else { None },
+            gap: if self.options.poll_gaps { self.gap } else { None },
+            truncate: self.options.truncate,
+            pretty: self.options.pretty,
+            single_line: self.options.single_line,
+            format: self.options.format.as_deref(),
+            kind,
+        };
+        #[cfg(feature="std")]
+        recorder::record(&event);
+        #[cfg(feature="std")]
+        expect::record(&event);
+        #[cfg(feature="std")]
+        subscriber::notify(&event);
+        if shown {
+            self.options.backend.emit(&event);
+        }
+    }
+}
+
+#[cfg(feature="std")]
+impl Drop for Inspector {
+    fn drop(&mut self) {
+        if self.registered {
+            registry::unregister(self.instance);
+        }
+        #[cfg(feature="std")]
+        subscriber::retire(self.instance);
+    }
+}
+
+#[cfg(all(test, feature="std", not(silenced)))]
+pub(crate) mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use futures::{Async, Future, Poll, task};
+    use std::sync::Mutex;
+
+    use LoggingExt;
+    use logger::{PollEvent, PollLogger};
+    use options::Inspect;
+    use timing::TestClock;
+
+    /// Keeps the messages of the events written to it.
+    #[derive(Default)]
+    pub(crate) struct Written(Mutex<Vec<String>>);
+
+    impl Written {
+        pub(crate) fn take(&self) -> Vec<String> {
+            self.0.lock().unwrap().drain(..).collect()
+        }
+    }
+
+    impl PollLogger for Written {
+        fn on_event(&self, event: &PollEvent) {
+            self.0.lock().unwrap().push(event.to_string());
+        }
+    }
+
+    /// Returns `NotReady` the given number of times, waking itself up
+    /// right away, before it is ready.
+    pub(crate) struct Countdown(pub(crate) u32);
+
+    impl Future for Countdown {
+        type Item = ();
+        type Error = ();
+
+        fn poll(&mut self) -> Poll<(), ()> {
+            if self.0 == 0 {
+                return Ok(Async::Ready(()));
+            }
+            self.0 -= 1;
+            task::park().unpark();
+            Ok(Async::NotReady)
+        }
+    }
+
+    static CLOCK: TestClock = TestClock::new();
+
+    fn written(not_ready: u32, options: Inspect) -> Vec<String> {
+        let written = Arc::new(Written::default());
+        Countdown(not_ready).inspect_with_opts(options.clock(&CLOCK).logger(written.clone())).wait().unwrap();
+        written.take()
+    }
+
+    // The polls of a streak start unseen, so the one ending it only
+    // shows its result.
+    #[test]
+    fn coalesces_not_ready_polls() {
+        assert_eq!(written(8, Inspect::new("coalesced").coalesce_not_ready(3)), [
+            "Polling future `coalesced'",
+            "Future `coalesced' polled: Ok(NotReady)",
+            "`coalesced' still NotReady (polled 3 more times)",
+            "`coalesced' still NotReady (polled 3 more times)",
+            "`coalesced' still NotReady (polled 1 more time)",
+            "Future `coalesced' polled: Ok(Ready(()))",
+        ]);
+    }
+
+    #[test]
+    fn coalesces_nothing_for_a_single_not_ready_poll() {
+        assert_eq!(written(1, Inspect::new("coalesced").coalesce_not_ready(3)), [
+            "Polling future `coalesced'",
+            "Future `coalesced' polled: Ok(NotReady)",
+            "Future `coalesced' polled: Ok(Ready(()))",
+        ]);
+    }
+
+    #[test]
+    fn suppresses_events_beyond_max_events() {
+        assert_eq!(written(3, Inspect::new("capped").max_events(4)), [
+            "Polling future `capped'",
+            "Future `capped' polled: Ok(NotReady)",
+            "Polling future `capped'",
+            "Future `capped' polled: Ok(NotReady)",
+            "`capped' emitted 4 events, suppressing further ones until the end",
+            "Future `capped' polled: Ok(Ready(()))",
+        ]);
+    }
+
+    #[test]
+    fn max_events_does_not_suppress_below_the_limit() {
+        assert_eq!(written(1, Inspect::new("capped").max_events(4)), [
+            "Polling future `capped'",
+            "Future `capped' polled: Ok(NotReady)",
+            "Polling future `capped'",
+            "Future `capped' polled: Ok(Ready(()))",
+        ]);
+    }
+
+    /// Keeps the typed labels of the events written to it.
+    #[derive(Default)]
+    struct Labels(Mutex<Vec<Option<u32>>>);
+
+    impl PollLogger for Labels {
+        fn on_event(&self, event: &PollEvent) {
+            self.0.lock().unwrap().push(event.label_as::<u32>().cloned());
+        }
+    }
+
+    #[test]
+    fn events_carry_typed_labels() {
+        let labels = Arc::new(Labels::default());
+        Countdown(0).inspect_with_opts(Inspect::new_typed(7u32).logger(labels.clone())).wait().unwrap();
+        Countdown(0).inspect_with_opts(Inspect::new("7").logger(labels.clone())).wait().unwrap();
+        assert_eq!(*labels.0.lock().unwrap(), [Some(7), Some(7), None, None]);
+    }
+}
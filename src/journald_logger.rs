@@ -0,0 +1,125 @@
+//! A backend writing poll events to the systemd journal.
+
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+
+use logger::{Outcome, PollEvent, PollLogger};
+use options::Level;
+
+/// A PollLogger that writes every event to the systemd journal
+/// through its native protocol, with the details of the event as
+/// fields of their own:
+///
+/// ```rust,no_run
+/// # extern crate futures_poll_log;
+/// use std::sync::Arc;
+/// use futures_poll_log::JournaldLogger;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let logger = JournaldLogger::new()?.identifier("gateway");
+/// futures_poll_log::set_poll_logger(Arc::new(logger));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Besides `MESSAGE` and `PRIORITY`, the entries carry `LABEL`,
+/// `INSTANCE`, `POLL` and `EVENT`, and if they apply `OUTCOME`
+/// (`not_ready`, `ready` or `err`), `DURATION_US`, `CORRELATION`
+/// and `CATEGORY`, so `journalctl` can filter on them:
+///
+/// ```plain
+/// journalctl LABEL=request OUTCOME=err
+/// ```
+///
+/// Entries are sent as they are emitted. An entry that cannot be
+/// sent, e.g. because it does not fit into a datagram, is lost.
+///
+/// This backend is available with the `journald` feature, on Unix.
+#[derive(Debug)]
+pub struct JournaldLogger {
+    socket: UnixDatagram,
+    identifier: Option<String>,
+}
+
+impl JournaldLogger {
+    /// Creates a backend writing to the journal of the system.
+    pub fn new() -> io::Result<JournaldLogger> {
+        JournaldLogger::with_socket("/run/systemd/journal/socket")
+    }
+
+    /// Creates a backend writing to the journal listening at
+    /// `path`.
+    pub fn with_socket<P: AsRef<Path>>(path: P) -> io::Result<JournaldLogger> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(JournaldLogger {
+            socket,
+            identifier: None,
+        })
+    }
+
+    /// Sets the `SYSLOG_IDENTIFIER` of the entries, which
+    /// `journalctl -t` selects by. The journal falls back to the
+    /// name of the process.
+    pub fn identifier(mut self, identifier: &str) -> JournaldLogger {
+        self.identifier = Some(identifier.into());
+        self
+    }
+
+    /// Encodes `event` as an entry of the native protocol.
+    fn entry(&self, event: &PollEvent) -> Vec<u8> {
+        let priority = match event.level {
+            Level::Error => "3",
+            Level::Warn => "4",
+            Level::Info => "6",
+            Level::Debug | Level::Trace => "7",
+        };
+        let mut entry = Vec::new();
+        field(&mut entry, "MESSAGE", &event.to_string());
+        field(&mut entry, "PRIORITY", priority);
+        if let Some(ref identifier) = self.identifier {
+            field(&mut entry, "SYSLOG_IDENTIFIER", identifier);
+        }
+        field(&mut entry, "LABEL", event.label);
+        field(&mut entry, "INSTANCE", &event.instance.to_string());
+        field(&mut entry, "POLL", &event.poll.to_string());
+        field(&mut entry, "EVENT", event.kind.name());
+        match event.outcome() {
+            Some(Outcome::NotReady) => field(&mut entry, "OUTCOME", "not_ready"),
+            Some(Outcome::Ready(_)) => field(&mut entry, "OUTCOME", "ready"),
+            Some(Outcome::Err(_)) => field(&mut entry, "OUTCOME", "err"),
+            None => {}
+        }
+        if let Some(duration) = event.duration {
+            field(&mut entry, "DURATION_US", &duration.as_micros().to_string());
+        }
+        if let Some(correlation) = event.correlation {
+            field(&mut entry, "CORRELATION", correlation);
+        }
+        if let Some(category) = event.category {
+            field(&mut entry, "CATEGORY", category);
+        }
+        entry
+    }
+}
+
+impl PollLogger for JournaldLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let _ = self.socket.send(&self.entry(event));
+    }
+}
+
+/// Appends the field `name` to `entry`, in the binary form if
+/// `value` spans several lines.
+fn field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
@@ -0,0 +1,93 @@
+//! Writing JSON by hand, for the backends and endpoints that
+//! produce it.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+#[cfg(any(feature="net", feature="jsonl"))]
+use record::{EventRecord, RecordedOutcome};
+
+/// Appends `value` to `out` as a JSON string.
+pub(crate) fn json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Appends `record` to `out` as a line of JSON.
+#[cfg(any(feature="net", feature="jsonl"))]
+pub(crate) fn write_record(out: &mut String, record: &EventRecord) {
+    out.push_str("{\"seq\":");
+    let _ = write!(out, "{}", record.sequence);
+    out.push_str(",\"label\":");
+    json_string(out, &record.label);
+    let _ = write!(out, ",\"instance\":{},\"poll\":{}", record.instance, record.poll);
+    out.push_str(",\"kind\":");
+    json_string(out, record.kind);
+    if let Some(ref location) = record.location {
+        out.push_str(",\"location\":");
+        json_string(out, location);
+    }
+    if let Some(ref parent) = record.parent {
+        out.push_str(",\"parent\":");
+        json_string(out, parent);
+    }
+    if let Some((ref label, instance)) = record.spawned_by {
+        out.push_str(",\"spawned_by\":{\"label\":");
+        json_string(out, label);
+        let _ = write!(out, ",\"instance\":{}}}", instance);
+    }
+    if let Some(ref correlation) = record.correlation {
+        out.push_str(",\"correlation\":");
+        json_string(out, correlation);
+    }
+    if !record.context.is_empty() {
+        out.push_str(",\"context\":{");
+        for (index, (key, value)) in record.context.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            json_string(out, key);
+            out.push(':');
+            json_string(out, value);
+        }
+        out.push('}');
+    }
+    if let Some(ref category) = record.category {
+        out.push_str(",\"category\":");
+        json_string(out, category);
+    }
+    match record.outcome {
+        Some(RecordedOutcome::NotReady) => out.push_str(",\"outcome\":\"not_ready\""),
+        Some(RecordedOutcome::Ready(ref item)) => {
+            out.push_str(",\"outcome\":\"ready\",\"value\":");
+            json_string(out, item);
+        }
+        Some(RecordedOutcome::Err(ref error)) => {
+            out.push_str(",\"outcome\":\"err\",\"value\":");
+            json_string(out, error);
+        }
+        None => {}
+    }
+    if let Some(duration) = record.duration {
+        let _ = write!(out, ",\"duration_ns\":{}", duration.as_nanos());
+    }
+    if let Some(timestamp) = record.timestamp {
+        let _ = write!(out, ",\"timestamp_ns\":{}", timestamp.as_nanos());
+    }
+    out.push_str(",\"message\":");
+    json_string(out, &record.message);
+    out.push_str("}\n");
+}
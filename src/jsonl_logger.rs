@@ -0,0 +1,148 @@
+//! A backend appending poll events to a file of JSON lines.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use json::write_record;
+use logger::{PollEvent, PollLogger};
+
+/// A PollLogger that appends every event as a line of JSON to a
+/// file, for traces to post-process with `jq` or load into a data
+/// frame, whatever the `log` facade is set up to do:
+///
+/// ```rust,no_run
+/// # extern crate futures_poll_log;
+/// use std::sync::Arc;
+/// use futures_poll_log::JsonLinesLogger;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let logger = JsonLinesLogger::create("polls.jsonl")?.max_size(64 << 20).keep(3);
+/// futures_poll_log::set_poll_logger(Arc::new(logger));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The lines have the same fields as those of `NetworkLogger`. Each
+/// one is written as it happens, so nothing is lost when the process
+/// exits without dropping the backend. Events that cannot be written
+/// are counted, see `lost()`.
+///
+/// This backend is available with the `jsonl` feature.
+#[derive(Debug)]
+pub struct JsonLinesLogger {
+    path: PathBuf,
+    max_size: Option<u64>,
+    keep: usize,
+    output: Mutex<Output>,
+    lost: AtomicU64,
+}
+
+/// The file written to, reopened after a rotation or a failure.
+#[derive(Debug)]
+struct Output {
+    file: Option<File>,
+    size: u64,
+}
+
+impl JsonLinesLogger {
+    /// Creates a backend appending to the file at `path`, created if
+    /// it does not exist yet.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<JsonLinesLogger> {
+        let path = path.as_ref().to_path_buf();
+        let file = open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(JsonLinesLogger {
+            path,
+            max_size: None,
+            keep: 1,
+            output: Mutex::new(Output {
+                file: Some(file),
+                size,
+            }),
+            lost: AtomicU64::new(0),
+        })
+    }
+
+    /// Rotates the file before a line would grow it past `bytes`.
+    /// The full file is renamed by appending `.1` to its name, and a
+    /// new one is started. Files are not rotated by default.
+    pub fn max_size(mut self, bytes: u64) -> JsonLinesLogger {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Keeps `files` rotated files, 1 by default, named with `.1` for
+    /// the newest up to `.<files>` for the oldest. With 0, a full file
+    /// is emptied instead.
+    pub fn keep(mut self, files: usize) -> JsonLinesLogger {
+        self.keep = files;
+        self
+    }
+
+    /// The number of events that could not be written.
+    pub fn lost(&self) -> u64 {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    fn write(&self, output: &mut Output, line: &[u8]) -> io::Result<()> {
+        let full = self.max_size.is_some_and(|max| output.size > 0 && output.size + line.len() as u64 > max);
+        if full {
+            output.file = None;
+            self.rotate()?;
+            output.size = 0;
+        }
+        if output.file.is_none() {
+            let file = open(&self.path)?;
+            output.size = file.metadata()?.len();
+            output.file = Some(file);
+        }
+        let written = output.file.as_mut().expect("opened above").write_all(line);
+        if written.is_err() {
+            output.file = None;
+        }
+        written?;
+        output.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Moves the current file out of the way, shifting the rotated
+    /// ones and dropping the oldest.
+    fn rotate(&self) -> io::Result<()> {
+        if self.keep == 0 {
+            return File::create(&self.path).map(|_| ());
+        }
+        for index in (1..self.keep).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(from, self.rotated(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+    }
+
+    /// The path of the `index`th rotated file.
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{}", index));
+        path.into()
+    }
+}
+
+impl PollLogger for JsonLinesLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let mut line = String::new();
+        write_record(&mut line, &event.to_record());
+        let mut output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        if self.write(&mut output, line.as_bytes()).is_err() {
+            self.lost.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
@@ -0,0 +1,230 @@
+//! The label of an inspection.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::any::Any;
+use core::fmt::{self, Debug, Display};
+use core::ops::Deref;
+#[cfg(feature="std")]
+use std::sync::OnceLock;
+
+/// Writes a label, see `Label::lazy`.
+pub(crate) type Format = dyn Fn(&mut fmt::Formatter) -> fmt::Result + Send + Sync;
+
+/// The value a label is written from, see `Label::typed`.
+pub(crate) type Value = dyn Any + Send + Sync;
+
+/// A label, borrowed for the lifetime of the program or shared
+/// between the inspection, the poll stack and the registry.
+///
+/// Labels written out in the source need no allocation this way,
+/// which matters when inspecting a future per request.
+#[derive(Clone)]
+pub(crate) enum Label {
+    Static(&'static str),
+    Shared(Arc<str>),
+    #[cfg(feature="std")]
+    Lazy(Arc<Lazy>),
+    #[cfg(feature="std")]
+    Typed(Arc<dyn Typed>),
+}
+
+/// A label written the first time it is read.
+#[cfg(feature="std")]
+pub(crate) struct Lazy {
+    format: Box<Format>,
+    text: OnceLock<Box<str>>,
+    /// The typed label it is written from behind a prefix, if any.
+    typed: Option<Arc<dyn Typed>>,
+}
+
+/// A label kept as the value it is written from, shared between
+/// the wrapper handing the value out again and the label of its
+/// inspection, which writes it the first time it is read.
+#[derive(Debug)]
+pub(crate) struct TypedLabel<L> {
+    value: L,
+    #[cfg(feature="std")]
+    text: OnceLock<Box<str>>,
+}
+
+impl<L> TypedLabel<L> {
+    pub(crate) fn new(value: L) -> TypedLabel<L> {
+        TypedLabel {
+            value,
+            #[cfg(feature="std")]
+            text: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn value(&self) -> &L {
+        &self.value
+    }
+}
+
+/// A `TypedLabel` of any type.
+#[cfg(feature="std")]
+pub(crate) trait Typed: Send + Sync {
+    fn text(&self) -> &str;
+    fn value(&self) -> &Value;
+}
+
+#[cfg(feature="std")]
+impl<L: Display + Send + Sync + 'static> Typed for TypedLabel<L> {
+    fn text(&self) -> &str {
+        self.text.get_or_init(|| alloc::format!("{}", self.value).into_boxed_str())
+    }
+
+    fn value(&self) -> &Value {
+        &self.value
+    }
+}
+
+/// Displays through the `Format` it holds.
+struct Formatted<'a>(&'a Format);
+
+impl<'a> Display for Formatted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.0)(f)
+    }
+}
+
+impl Label {
+    /// Copies `label` into a shared string.
+    pub(crate) fn copied(label: &str) -> Label {
+        Label::Shared(Arc::from(label))
+    }
+
+    /// A label written by `format` once it is first read. Without
+    /// `std`, it is written right away.
+    #[cfg(feature="std")]
+    #[cfg_attr(silenced, allow(dead_code))]
+    pub(crate) fn lazy(format: Box<Format>) -> Label {
+        Label::lazy_from(format, None)
+    }
+    #[cfg(not(feature="std"))]
+    #[cfg_attr(silenced, allow(dead_code))]
+    pub(crate) fn lazy(format: Box<Format>) -> Label {
+        Label::from(alloc::format!("{}", Formatted(&*format)))
+    }
+
+    #[cfg(feature="std")]
+    fn lazy_from(format: Box<Format>, typed: Option<Arc<dyn Typed>>) -> Label {
+        Label::Lazy(Arc::new(Lazy {
+            format,
+            text: OnceLock::new(),
+            typed,
+        }))
+    }
+
+    /// The label of `typed`, sharing it rather than converting it.
+    /// Without `std`, it is written right away.
+    #[cfg(feature="std")]
+    #[cfg_attr(silenced, allow(dead_code))]
+    pub(crate) fn typed<L: Display + Send + Sync + 'static>(typed: &Arc<TypedLabel<L>>) -> Label {
+        Label::Typed(typed.clone())
+    }
+    #[cfg(not(feature="std"))]
+    #[cfg_attr(silenced, allow(dead_code))]
+    pub(crate) fn typed<L: Display + Send + Sync + 'static>(typed: &Arc<TypedLabel<L>>) -> Label {
+        Label::from(alloc::format!("{}", typed.value))
+    }
+
+    /// The value the label is written from, if it was set up
+    /// through `typed()`. Always `None` without `std`.
+    #[cfg(feature="std")]
+    pub(crate) fn value(&self) -> Option<&Value> {
+        match *self {
+            Label::Lazy(ref lazy) => lazy.typed.as_ref().map(|typed| typed.value()),
+            Label::Typed(ref typed) => Some(typed.value()),
+            _ => None,
+        }
+    }
+    #[cfg(not(feature="std"))]
+    pub(crate) fn value(&self) -> Option<&Value> {
+        None
+    }
+
+    /// `label` behind `prefix`, still unwritten and keeping its
+    /// value if `label` is.
+    pub(crate) fn prefixed(prefix: &str, label: Label) -> Label {
+        #[cfg(feature="std")]
+        {
+            let typed = match label {
+                Label::Lazy(ref lazy) => Some(lazy.typed.clone()),
+                Label::Typed(ref typed) => Some(Some(typed.clone())),
+                _ => None,
+            };
+            if let Some(typed) = typed {
+                let prefix: Box<str> = prefix.into();
+                return Label::lazy_from(Box::new(move |f| write!(f, "{}{}", prefix, label)), typed);
+            }
+        }
+        Label::from(alloc::format!("{}{}", prefix, label))
+    }
+}
+
+impl Deref for Label {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match *self {
+            Label::Static(label) => label,
+            Label::Shared(ref label) => label,
+            #[cfg(feature="std")]
+            Label::Lazy(ref lazy) => {
+                lazy.text.get_or_init(|| alloc::format!("{}", Formatted(&*lazy.format)).into_boxed_str())
+            }
+            #[cfg(feature="std")]
+            Label::Typed(ref typed) => typed.text(),
+        }
+    }
+}
+
+impl Debug for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&**self, f)
+    }
+}
+
+impl From<alloc::string::String> for Label {
+    fn from(label: alloc::string::String) -> Label {
+        Label::Shared(Arc::from(label))
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use alloc::sync::Arc;
+
+    use super::{Label, TypedLabel};
+
+    #[test]
+    fn typed_labels_keep_their_value() {
+        let typed = Arc::new(TypedLabel::new(7u32));
+        let label = Label::typed(&typed);
+        assert_eq!(&*label, "7");
+        assert_eq!(label.value().and_then(|value| value.downcast_ref::<u32>()), Some(&7));
+        assert!(label.value().and_then(|value| value.downcast_ref::<u64>()).is_none());
+        // Shared, not copied.
+        assert_eq!(Arc::strong_count(&typed), 2);
+
+        let prefixed = Label::prefixed("db.", label);
+        assert_eq!(&*prefixed, "db.7");
+        assert_eq!(prefixed.value().and_then(|value| value.downcast_ref::<u32>()), Some(&7));
+    }
+
+    #[test]
+    fn other_labels_have_no_value() {
+        assert!(Label::Static("db").value().is_none());
+        assert!(Label::copied("db").value().is_none());
+        assert!(Label::lazy(Box::new(|f| f.write_str("db"))).value().is_none());
+        assert!(Label::prefixed("db.", Label::lazy(Box::new(|f| f.write_str("query")))).value().is_none());
+    }
+}
@@ -44,102 +44,702 @@
 //!
 //! Note that it logs the Async state.
 //!
+//! `inspect_if` decides at runtime whether a future is logged at all, and
+//! `inspect_when` logs only the poll results a predicate picks.
+//!
+//! For large items, `inspect_project` logs only the part of the item picked
+//! by a closure, such as an identifying field.
+//!
+//! `inspect_deadline` additionally takes a `Duration` and logs a warning the
+//! first time the future is still `NotReady` once it has passed, without
+//! cancelling it.
+//!
+//! `inspect_executor` logs the name and id of the thread polling the future,
+//! on the first poll and whenever it changes. This tells runtime workers
+//! apart from a manual `wait()` on the main thread.
+//!
+//! # Streams
+//!
+//! Streams can be tagged the same way through `inspect_stream`, provided by
+//! the `StreamLoggingExt` trait. Every item the stream yields is numbered,
+//! and the totals are logged once the stream ends:
+//!
+//! ```plain
+//! DEBUG - Polling stream `numbers'
+//! DEBUG - Stream `numbers' yielded item #1: 1
+//! DEBUG - Polling stream `numbers'
+//! DEBUG - Stream `numbers' ended after 1 items and 2 polls
+//! ```
+//!
+//! A stream dropped before it ended is reported as "dropped before ending"
+//! instead, telling an abandoned stream apart from a finished one.
+//!
+//! For streams whose items or errors cannot be printed, `inspect_stream_simple`
+//! from the `SimpleStreamLoggingExt` trait logs the same events, printing
+//! items and errors as `_`.
+//!
+//! Streams repeating the same value, like heartbeats or status updates, are
+//! better tagged through `inspect_stream_changes` from the
+//! `ChangesStreamLoggingExt` trait: it logs an item only if it differs from
+//! the previous one, and counts the repetitions in between.
+//!
+//! For intervals and other streams of timer ticks, `inspect_ticks` from the
+//! `TickStreamLoggingExt` trait logs how late every tick came compared to a
+//! schedule counted from the first one, and warns when a tick drifts further
+//! than a given maximum. `inspect_throughput` from the
+//! `ThroughputStreamLoggingExt` trait logs the items per second a stream
+//! yields over a sliding window, and `inspect_throughput_bytes` the bytes
+//! per second as measured by a closure.
+//!
+//! # Sinks
+//!
+//! Sinks are tagged through `inspect_sink`, provided by the `SinkLoggingExt`
+//! trait. The phases of a sink (`start_send`, `poll_complete` and `close`)
+//! are logged separately, and a `start_send` that hands the item back
+//! because the sink is not ready is called out explicitly:
+//!
+//! ```plain
+//! DEBUG - Sink `channel' start_send: 2
+//! DEBUG - Sink `channel' not ready in start_send, item handed back
+//! ```
+//!
+//! `inspect_forward` from `ForwardLoggingExt` and `inspect_send_all` from
+//! `SendAllLoggingExt` stand in for `Stream::forward` and `Sink::send_all`.
+//! Besides the items passing through and the flushes of the sink, they log
+//! whether a poll returning `NotReady` waits on the stream or on the sink.
+//!
+//! # Joins and selects
+//!
+//! The `logged_join!` and `logged_select!` macros behave like `join` and
+//! `select`, but wrap every branch in a `LoggedFuture` labeled with the text
+//! of its expression, so a hanging branch can be spotted right away.
+//! `inspect_chain!` does the same for every step of a chain of
+//! combinators, labeling each with the method names leading up to it,
+//! like `request.map.and_then`.
+//!
+//! To wrap a whole collection of futures, `inspect_all` labels each one
+//! with its index (`label[0]`, `label[1]`, ...) before they are handed to
+//! `join_all`.
+//!
+//! # Channels
+//!
+//! The `sync` module creates `futures::sync` channels whose ends are logged
+//! under one label: `sync::oneshot(label)`, `sync::channel(label, buffer)`
+//! and `sync::unbounded(label)`. Besides every send and the backpressure of
+//! a full channel, each end reports being dropped, which answers who went
+//! away when the other end hangs.
+//!
+//! `sync::lock(bilock, label)` acquires a `BiLock` and logs how long the
+//! acquisition was pending and how long the lock was held, which makes lock
+//! contention visible beyond a run of `NotReady` polls.
+//!
+//! # Shared futures
+//!
+//! `inspect_shared` turns a future into a `Shared` one and numbers its
+//! clones. Each clone is logged as its own waiter (`config (waiter 2)`),
+//! while the underlying future keeps the plain label, so the one poll that
+//! completes it stands out from the waiters picking up the result.
+//!
+//! # Retries
+//!
+//! `inspect_retries` wraps the factory of a retry loop. Each future created
+//! through `Retries::attempt()` is labeled with its attempt number
+//! (`fetch (attempt 2)`), and the backoff since the previous attempt resolved
+//! is logged when the next one is first polled.
+//!
+//! # Closures
+//!
+//! `logged_poll_fn` and `logged_lazy` create futures like `future::poll_fn`
+//! and `future::lazy`, logged from the start, and log the first call of the
+//! closure apart from the polls.
+//!
+//! # Blocking waits
+//!
+//! With `std`, `logged_wait` blocks the current thread on a future like
+//! `Future::wait`, and logs when it starts blocking, every wakeup and the
+//! time it was parked. Blocking inside the poll of another inspection is
+//! logged as a warning. `futures03::logged_block_on` does the same for
+//! `std::future::Future`s.
+//!
+//! # futures 0.3
+//!
+//! With the `futures03` feature, the `futures03` module provides the same
+//! wrappers for `std::future::Future`s, such as `async` blocks. Wrapped
+//! futures implementing `FusedFuture` stay fused. With `std`,
+//! `inspect_compat` converts a futures 0.1 future and logs the wakeups
+//! crossing the boundary, pointing out lost ones.
+//!
+//! With the `tokio` feature, `tokio::spawn_logged` spawns a labeled task,
+//! logging when it was spawned, first polled and completed. For other
+//! executors, `futures03::wrap_for_spawn` prepares a future to be spawned
+//! with the same logging, and `futures03::LoggedExecutor` wraps a `Spawn`
+//! implementation to log every future spawned through it.
+//!
+//! With `std`, the `pool` module wraps work handed to thread pools, futures
+//! for a `CpuPool` as well as closures for blocking threads, and logs how
+//! long it waited in the queue before it ran. `tokio::spawn_blocking_logged`
+//! does so for tokio's `spawn_blocking`.
+//!
+//! # Options
+//!
+//! `inspect_with_opts` and its stream and sink counterparts take an
+//! `Inspect`, which collects the options of an inspection: the level and
+//! target of the events, whether polls are timed, sampling of every `n`th
+//! poll and redaction of items and errors. Deadlines and executor tracking
+//! can be set through it as well.
+//!
+//! With `Inspect::catch_panics`, a panic inside the poll of the future is
+//! logged as an error naming its label before the panic continues:
+//!
+//! ```plain
+//! ERROR - Future `request' panicked during poll: index out of bounds
+//! ```
+//!
+//! With `std`, a `LoggedFuture` notes when it is polled from another thread
+//! than on its first poll, naming both threads. `Inspect::warn_on_thread_change`
+//! turns the note into a warning.
+//!
+//! `Inspect::ready_budget` warns when a chain of inspected futures keeps
+//! resolving within one poll of the task without ever yielding.
+//!
+//! Also with `std`, a `LoggedFuture` polled again from within its own poll on the
+//! same thread is always reported as an error, as this points at a broken
+//! combinator.
+//!
+//! A `LoggedFuture` directly wrapping another one, e.g. a future returned
+//! by a helper that inspects everything and then inspected once more, logs
+//! nothing of its own, so the events are not doubled. `Inspect::nesting`
+//! keeps both or merges their labels instead.
+//!
+//! `Inspect::correlation` tags every message with an id such as that of a
+//! request. With `std`, inspections created while a tagged one is polled
+//! inherit its id, so one request can be followed through all futures
+//! created on its behalf.
+//!
+//! Further key/value pairs, like a user or tenant id, are added to the
+//! diagnostic context of events through `Inspect::context`, for all events
+//! emitted while that inspection is being polled, or with `std` through the
+//! guard returned by `push_context()`. They show up as `[user=7] ` in front
+//! of the message and as `PollEvent::context`.
+//!
+//! `Inspect::summary` reports a single line when the future is dropped,
+//! with its instance number, number of polls, the time it was pending and
+//! whether it completed, errored or was cancelled. `Inspect::summary_only`
+//! leaves out everything else:
+//!
+//! ```plain
+//! DEBUG - Future `request' (#7) completed after 3 polls, pending for 1.2ms
+//! ```
+//!
+//! `inspect_on_complete` hands the same `Summary` to a closure instead,
+//! together with the result of the future, and `inspect_timed` resolves to
+//! the item paired with `PollStats`: the number of polls, the time spent in
+//! them and the wait for the first one. `inspect_classified` sorts the
+//! errors of a future into categories, which are logged with the error and
+//! counted in the statistics. `inspect_error_chain` logs the causes of an
+//! error down its `source()` chain, one line each, and
+//! `inspect_dyn_error_chain` does so for errors like `anyhow::Error`.
+//!
+//! Process-wide defaults for the level, target, timestamps and truncation
+//! of values are installed once through `init()`, taking a `Config`. Every
+//! `Inspect` created afterwards inherits them. `Timestamps::Rfc3339` stamps
+//! messages with the wall-clock time, so they line up with the logs of other
+//! systems even if the backend adds no time of its own. For a single future,
+//! `relative_timestamps()` is often easier to follow: it stamps messages with
+//! the time since the inspection was set up, like `[t+14.2ms]`, and
+//! `Inspect::poll_gaps` with the time the future sat idle since its last
+//! poll. `Inspect::warn_poll_gap` warns when that is too long, and
+//! `Inspect::warn_poll_storm` when a future is polled over and over without
+//! being ready, as in a wake loop.
+//!
+//! With `std`, all durations are measured on a `Clock`. `set_clock()`
+//! replaces the system clock process-wide and `Inspect::clock` for a single
+//! inspection, e.g. with a `TestClock` that only moves when a test advances
+//! it, so assertions on slow polls and deadlines do not depend on timing.
+//! On `wasm32-unknown-unknown`, where the system clock stands still, a
+//! `Clock` reading `performance.now()` brings the timings back.
+//!
+//! `Inspect::log_size` logs the size of the wrapped future in memory on its
+//! first poll, and `Inspect::log_first_poll` how long it waited for it.
+//!
+//! With the `allocations` feature and an `allocations::CountingAllocator`
+//! installed as the global allocator, `Inspect::track_allocations` logs the
+//! bytes and allocations of every poll that allocated anything.
+//!
+//! With the `cpu-time` feature, `Inspect::cpu_time` logs the CPU time of
+//! every poll next to its wall time on Linux, Android, macOS and FreeBSD,
+//! telling polls that waited from polls that computed.
+//!
+//! `Inspect::slow_motion` holds every poll back by a delay, by sleeping or
+//! by returning an injected `NotReady` and waking up later, so the sequence
+//! of polls can be watched step by step.
+//!
+//! Events reporting `NotReady`, `Ready` or an error can each be written at
+//! a level of their own through `not_ready_level`, `ready_level` and
+//! `error_level`, on `Config` or `Inspect`.
+//!
+//! `format` replaces the built-in sentences with a template such as
+//! `"{label} [{instance}] poll {n}: {outcome}"`, for log pipelines that
+//! expect a format of their own.
+//!
+//! `max_depth` limits the output of nested inspections to their outermost
+//! layers, as inner ones stay silent.
+//!
+//! `Inspect::max_events` caps the events written for one inspection. Past
+//! the cap, a single notice says that further events are suppressed, and
+//! only the final result and warnings get through.
+//!
+//! With `std`, `with_label_prefix("db::")` returns a guard prepending a
+//! prefix to the labels of all inspections set up on the current thread
+//! while it is alive, so a subsystem's labels share a namespace without
+//! repeating it. `Inspect::label_prefix` does the same for one inspection.
+//!
+//! The `inspect*` methods are `#[track_caller]`. `Inspect::caller_location`
+//! and `Config::caller_locations` add the file and line they were called at
+//! to the events, like `(at src/db.rs:42)`.
+//!
+//! With `std`, `set_label_level` changes the level of all inspections whose
+//! label matches a pattern like `db*` at runtime, including running ones.
+//!
+//! With `std`, `load_settings("poll-log.toml")` reads options like levels,
+//! sampling, redaction and warning thresholds for label patterns from a
+//! file, so they can be tuned without touching the code.
+//! `reload_settings()` reads it again for the inspections set up from then
+//! on.
+//!
+//! With `std`, inspections can be put into groups through `Inspect::group`
+//! or `LoggingExt::inspect_in_group`. `set_group_level` and
+//! `set_group_rate_limit` apply to all inspections of a group, and
+//! `stats::group_snapshot()` and `stats::report_groups()` aggregate their
+//! statistics per group.
+//!
+//! # Statistics
+//!
+//! With `std`, `stats::enable()` starts aggregating per-label statistics
+//! across all inspections: how many were polled, completed and failed, the
+//! fewest, average and most polls they took and the total time spent
+//! polling them. Single poll durations are kept in a `stats::Histogram`
+//! per label, which answers percentile queries such as `p99()`. `report()`
+//! renders all of it as a table, and `stats::write_csv` writes it as CSV
+//! for comparing runs in a spreadsheet. `report_top(n)` answers where to
+//! look first, listing the `n` labels with the highest poll time, the worst
+//! single poll and the most polls.
+//!
+//! The polls of each label are also counted per second in a
+//! `stats::PollRate`. `stats::report_rates()` renders the rolling rates,
+//! and `stats::log_rates_every(interval)` logs them periodically, for
+//! spotting labels whose poll rate spikes or drops to zero.
+//!
+//! Inspections created with `Inspect::measure_overhead` measure the time
+//! spent logging them apart from their polls. It is left out of their poll
+//! durations, added to their summary and summed up per label.
+//!
+//! # Hierarchy
+//!
+//! With `std`, `graph::enable()` records which labeled future, stream or
+//! sink is polled from within which other one, and `graph::to_dot()`
+//! renders that hierarchy as a Graphviz graph.
+//!
+//! Recorded events, e.g. the `EventRecord`s received from a
+//! `ChannelLogger`, are turned into a Mermaid sequence diagram of all poll
+//! calls and their results by `render::mermaid`, or into a timeline for
+//! the terminal by `render::timeline`.
+//!
+//! With `std`, `recorder::enable(n)` keeps the last `n` events of all
+//! inspections in a ring buffer, whether they are logged or not.
+//! `recorder::install_panic_hook()` prints them when the process panics,
+//! showing what led up to a crash.
+//!
+//! In tests, `expect::assert_events()` runs a future to completion and
+//! checks that the events of its inspections came in the expected order,
+//! like `[expect::poll("a"), expect::ready("a"), expect::poll("b")]`,
+//! showing a diff of the events if they did not.
+//!
+//! With the `chaos` feature, `chaos::inject(pattern, faults)` turns the
+//! inspections matching a label pattern into a fault-injection harness for
+//! retry and timeout logic: their polls randomly return `NotReady`, wake up
+//! late, or fail with errors built by the closure handed to
+//! `inspect_with_faults`, and every injected fault is logged as a warning.
+//!
+//! `registry::enable()` lists every inspection set up afterwards in a
+//! registry until it is dropped, with its poll count, age and the result of
+//! its last poll. `registry::snapshot()` shows what is still pending, and
+//! with the `http` feature, `http::handle()` renders it together with the
+//! statistics as an HTML page or JSON, to mount in a server or to run on a
+//! port of its own through `http::serve()`.
+//!
+//! `report_pending()` renders the registered inspections that did not
+//! finish yet, with their age, polls and the time since their last poll.
+//! `registry::report_pending_at_exit()` writes it to stderr when the
+//! process exits, naming the tasks that never finished.
+//!
+//! On Unix, with the `signal` feature, `dump_on_signal(DumpSignal::Usr1)`
+//! writes the registry and the flight recorder to the log through
+//! `dump_state()` whenever the process receives `SIGUSR1`.
+//!
+//! With the `tui` feature, `tui::run()` shows a live table of all
+//! inspections in the terminal, with their state, polls and the time since
+//! their last poll, filtered by label and with the recent events of a single
+//! inspection on request.
+//!
+//! # Custom backends
+//!
+//! All output goes through the `PollLogger` trait, which receives a
+//! `PollEvent` for everything that happens. The default backend,
+//! `LogLogger`, writes them through the `log` crate. Another backend can be
+//! installed for all inspections with `set_poll_logger`, or for a single one
+//! with `inspect_with_logger`:
+//!
+//! ```rust
+//! extern crate futures;
+//! extern crate futures_poll_log;
+//!
+//! use std::sync::Arc;
+//! use futures::{Future, future};
+//! use futures_poll_log::{LoggingExt, PollEvent, PollLogger};
+//!
+//! struct PrintLogger;
+//!
+//! impl PollLogger for PrintLogger {
+//!     fn on_event(&self, event: &PollEvent) {
+//!         println!("{}: {:?}", event.label, event.kind);
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let _ = future::ok::<i32, ()>(3)
+//!     .inspect_with_logger("printed future", Arc::new(PrintLogger))
+//!     .wait();
+//! # }
+//! ```
+//!
+//! To consume events programmatically, `ChannelLogger` sends them as owned
+//! `EventRecord`s into a channel, whose receiving end is a `Stream`.
+//!
+//! Tooling that should see every event, next to whichever backend is
+//! installed, registers an `EventSubscriber` through `subscribe()`. It also
+//! receives the events the backend filters out, and works on the fields of
+//! `PollEvent` rather than on messages. `EVENT_MODEL_VERSION` changes
+//! whenever the meaning of those fields or of the names of `EventKind`s
+//! does.
+//!
+//! With the `log-kv` feature, `LogLogger` writes through `log` 0.4 instead
+//! of 0.3, passing the label, instance, poll number, outcome, error category
+//! and duration as key-values of the record.
+//!
+//! With the `slog` feature, `SlogLogger` emits all events through a
+//! `slog::Logger`, with the label, poll number, outcome and duration as
+//! key-value pairs.
+//!
+//! With the `tracing` feature, `TracingLogger` emits all events through
+//! `tracing`. The `console` feature additionally wraps every poll of a
+//! `LoggedFuture` in a `runtime.spawn` span carrying the label as
+//! `task.name`, which makes labeled futures show up as tasks in
+//! tokio-console.
+//!
+//! With the `pretty` feature, `PrettyLogger` prints all events to standard
+//! error, colored by their result. `PrettyLogger::init()` installs it.
+//!
+//! With the `net` feature, `NetworkLogger` ships all events as lines of JSON
+//! to a collector over UDP or TCP, batched and through a bounded queue, for
+//! devices whose logs cannot be read locally.
+//!
+//! With the `jsonl` feature, `JsonLinesLogger` appends all events as lines
+//! of JSON to a file, rotated by size, as a trace for `jq` or a data frame.
+//!
+//! With the `syslog` feature, `SyslogLogger` sends all events to syslog as
+//! RFC 5424 messages carrying the label in structured data.
+//!
+//! With the `journald` feature, `JournaldLogger` writes all events to the
+//! systemd journal, with the label, poll number, outcome and duration as
+//! journal fields for `journalctl` to filter on.
+//!
+//! With the `etw` feature, `EtwLogger` emits all events as TraceLogging
+//! events of an ETW provider on Windows, for Windows Performance Analyzer.
+//!
+//! With the `android` feature, `LogcatLogger` writes all events to logcat on
+//! Android, tagged with their label.
+//!
+//! With the `defmt` feature, `DefmtLogger` emits all events through `defmt`
+//! for use on microcontrollers.
+//!
+//! # Poll hooks
+//!
+//! Code that needs every poll rather than log output, like counters of an
+//! application's metrics or profiler markers, implements `PollHook`. Its
+//! `on_poll_start` and `on_poll_end` run around each inner poll, whatever
+//! the level, sampling or verbosity, and receive a `PollContext` naming the
+//! inspection. Hooks are registered for one inspection through
+//! `Inspect::hook`, or with `std` for all of them through `add_hook()`.
+//!
+//! `inspect_pipeline` combines the concerns of an inspection into one
+//! wrapper, polling once for all of them: each `Stage`, like timing,
+//! logging, statistics or a hook, handles the same poll, and stages left
+//! out are off.
+//!
 //! # Notes on logging
 //!
-//! The log target is `futures_log`.
+//! With `std`, the `FUTURES_POLL_LOG_LEVEL` environment variable limits what
+//! this crate logs, independently of the configuration of the logger. It is
+//! read once, on the first poll, and takes one of:
+//!
+//! * `off`: nothing is logged.
+//! * `error`: only errors and warnings are logged.
+//! * `summary`: additionally, every polled future reports a summary when it
+//!   is dropped, as with `Inspect::summary`, but no other events.
+//! * `full`: everything is logged, the default.
+//!
+//! With `std`, `enabled_scope()` returns a guard enabling logging on the
+//! current thread while it is alive. After the first call, logging is off
+//! outside of such scopes.
+//!
+//! Without a logger installed, the `log` crate discards all messages. For
+//! examples and tests, `stdout_logger::init()` installs a minimal one that
+//! prints the messages of this crate.
+//!
+//! The log target is `futures_log`, unless set through `Config::target` or
+//! `Inspect::target`. While the logger filters out the target or level of an
+//! inspection, its events are neither built nor formatted. Custom backends
+//! can do the same through `PollLogger::enabled`.
 //!
 //! Building the crate with the feature "silence" makes the effect completely
 //! vanish, _including_ the intermediate futures. The library also stops binding
 //! to `log` lib.
 //!
 //! This allows you to keep the tagging around for future debugging sessions.
+//! `inspect_gated::<{ cfg!(feature = "trace-db") }>(label)` leaves the
+//! choice to a feature of the calling crate, call site by call site.
+//! The feature "debug-only" does the same in builds without debug
+//! assertions, such as `cargo build --release`, and keeps the logging in the
+//! others, so release builds are silenced without a separate set of
+//! features.
+//! For a finer choice, the feature "allowlist" keeps only the call sites of
+//! the `inspect!` macro whose label starts with one of the prefixes in the
+//! `FUTURES_POLL_LOG_LABELS` environment variable at build time, and turns the
+//! others into the bare future.
+//!
+//! Production builds that should keep the poll counts, timings and outcomes
+//! but never log a payload can use the feature "redact" instead: items,
+//! errors and panic messages are replaced by `<redacted>` for every
+//! inspection, and their `Debug` implementations are never called.
+//!
+//! # `no_std` support
+//!
+//! The crate only needs `core` and `alloc` when built without the default
+//! `std` feature. Without `std`, timed polls are not measured and
+//! `inspect_deadline` is unavailable. Backends installed through
+//! `set_poll_logger` are never freed in that configuration, as there is no
+//! lock to guard them.
 
 #![deny(missing_docs)]
+#![cfg_attr(not(feature="std"), no_std)]
 
+extern crate alloc;
+#[cfg(feature="std")]
+extern crate core;
 extern crate futures;
 
-#[cfg(not(feature="silence"))]
+#[cfg(not(silenced))]
 #[macro_use]
 extern crate log;
+#[cfg(all(feature="log-kv", not(silenced)))]
+extern crate log04;
+#[cfg(feature="slog")]
+extern crate slog;
+#[cfg(feature="defmt")]
+extern crate defmt;
+#[cfg(feature="tracing")]
+extern crate tracing;
+#[cfg(feature="futures03")]
+extern crate futures_core;
+#[cfg(feature="futures03")]
+extern crate futures_task;
+#[cfg(feature="tokio")]
+extern crate tokio as tokio_crate;
+#[cfg(any(all(feature="signal", unix, not(silenced)), all(feature="cpu-time", unix)))]
+extern crate libc;
 
-use futures::{Future, Poll};
-use std::fmt::Debug;
-
-/// The LoggedFuture struct wraps another Future and
-/// will log all poll calls.
-#[derive(Debug)]
-pub struct LoggedFuture<T, E, F: Future<Item = T, Error = E>> {
-    future: F,
-    label: String,
-}
-
-#[cfg(not(feature="silence"))]
-impl<T, E, F> Future for LoggedFuture<T, E, F>
-    where T: Debug,
-          E: Debug,
-          F: Future<Item = T, Error = E>
-{
-    type Item = F::Item;
-    type Error = F::Error;
-
-    #[inline]
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        debug!(target: "futures_log", "Polling future `{}'", self.label);
-        let poll = self.future.poll();
-        debug!(target: "futures_log", "Future `{}' polled: {:?}", self.label, poll);
-        poll
-    }
-}
-
-#[cfg(feature="silence")]
-impl<T, E, F> Future for LoggedFuture<T, E, F>
-    where T: Debug,
-          E: Debug,
-          F: Future<Item = T, Error = E>
-{
-    type Item = F::Item;
-    type Error = F::Error;
-
-    #[inline]
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        self.future.poll()
-    }
-}
+#[macro_use]
+mod macros;
+mod allowlist;
+#[cfg(feature="allocations")]
+pub mod allocations;
+#[cfg(feature="std")]
+mod blocking;
+mod branch;
+#[cfg(feature="chaos")]
+pub mod chaos;
+mod closure;
+#[cfg(feature="std")]
+mod channel;
+mod config;
+mod context;
+#[cfg(all(feature="etw", windows))]
+mod etw_logger;
+#[cfg(all(feature="etw", any(windows, test)))]
+mod etw_metadata;
+#[cfg(feature="std")]
+pub mod expect;
+#[cfg(feature="defmt")]
+mod defmt_logger;
+mod forward;
+mod future;
+#[cfg(feature="std")]
+mod handle;
+mod hook;
+#[cfg(feature="futures03")]
+pub mod futures03;
+#[cfg(feature="std")]
+pub mod graph;
+#[cfg(feature="std")]
+mod group;
+#[cfg(feature="http")]
+pub mod http;
+mod inspector;
+#[cfg(all(feature="journald", unix))]
+mod journald_logger;
+#[cfg(feature="jsonl")]
+mod jsonl_logger;
+mod label;
+#[cfg(any(feature="net", feature="http", feature="jsonl"))]
+mod json;
+#[cfg(all(feature="android", target_os="android"))]
+mod logcat_logger;
+mod logger;
+mod make;
+#[cfg(feature="net")]
+mod net_logger;
+mod options;
+#[cfg(feature="std")]
+pub mod pool;
+#[cfg(feature="std")]
+mod prefix;
+#[cfg(feature="pretty")]
+mod pretty_logger;
+mod record;
+mod retry;
+#[cfg(feature="std")]
+pub mod recorder;
+#[cfg(feature="std")]
+pub mod registry;
+pub mod render;
+#[cfg(feature="std")]
+mod scope;
+#[cfg(feature="std")]
+mod settings;
+#[cfg(all(feature="std", not(silenced)))]
+mod shared;
+#[cfg(all(feature="signal", unix))]
+mod signal;
+mod sink;
+#[cfg(feature="std")]
+pub mod sync;
+#[cfg(feature="slog")]
+mod slog_logger;
+#[cfg(feature="std")]
+pub mod stats;
+#[cfg(feature="std")]
+pub mod stdout_logger;
+#[cfg(feature="std")]
+mod subscriber;
+#[cfg(feature="syslog")]
+mod syslog_logger;
+mod stream;
+#[cfg(feature="std")]
+mod timer;
+mod timing;
+#[cfg(feature="tokio")]
+pub mod tokio;
+#[cfg(feature="tracing")]
+mod tracing_logger;
+#[cfg(feature="tui")]
+pub mod tui;
 
-/// LoggingExt introduces the logging capabilities
-/// to any Future, as long as all its Item and Error
-/// can be printed.
-pub trait LoggingExt<T, E>
-    where T: Debug,
-          E: Debug,
-          Self: Future<Item = T, Error = E> + Sized
-{
-    /// inspect() sets up the logging. The `label` will
-    /// be used to identify the Future in the log messages
-    /// used.
-    ///
-    /// This method returns `Self` instead of a `LoggedFuture`
-    /// when the `silence` feature is activated.
-    #[cfg(not(feature="silence"))]
-    fn inspect(self, label: &str) -> LoggedFuture<T, E, Self>;
-    #[cfg(feature="silence")]
-    fn inspect(self, label: &str) -> Self;
-}
+#[cfg(feature="std")]
+pub use blocking::logged_wait;
+pub use branch::{EitherLoggingExt, SelectLoggingExt};
+#[cfg(not(silenced))]
+pub use branch::LoggedSelect2;
+#[cfg(feature="std")]
+pub use channel::ChannelLogger;
+pub use closure::{logged_lazy, logged_poll_fn};
+#[cfg(not(silenced))]
+pub use closure::{LoggedLazy, LoggedPollFn};
+pub use config::{Config, Timestamps, init};
+pub use context::ContextFields;
+#[cfg(feature="std")]
+pub use context::{ContextGuard, push_context};
+#[cfg(feature="std")]
+pub use config::{clear_label_levels, set_label_level};
+#[cfg(feature="defmt")]
+pub use defmt_logger::DefmtLogger;
+#[cfg(all(feature="etw", windows))]
+pub use etw_logger::EtwLogger;
+pub use forward::{ForwardLoggingExt, SendAllLoggingExt};
+#[cfg(not(silenced))]
+pub use forward::{LoggedForward, LoggedSendAll};
+pub use future::{Gate, Gated, LoggedFuture, LoggingExt, TimedFuture, inspect_all};
+#[cfg(feature="std")]
+pub use group::{clear_group_levels, clear_group_rate_limits, set_group_level, set_group_rate_limit};
+#[cfg(feature="std")]
+pub use handle::InspectHandle;
+pub use hook::{PollContext, PollHook};
+#[cfg(all(feature="journald", unix))]
+pub use journald_logger::JournaldLogger;
+#[cfg(feature="jsonl")]
+pub use jsonl_logger::JsonLinesLogger;
+#[cfg(feature="std")]
+pub use hook::{add_hook, clear_hooks};
+#[cfg(not(silenced))]
+pub use future::{CompletionFuture, FilteredFuture, ProjectedFuture};
+#[cfg(all(feature="android", target_os="android"))]
+pub use logcat_logger::LogcatLogger;
+pub use logger::{Completion, EventKind, LogLogger, Outcome, PollEvent, PollLogger, PollStats, Summary,
+                 set_poll_logger};
+pub use make::MakeLogged;
+#[cfg(feature="net")]
+pub use net_logger::NetworkLogger;
+pub use options::{Inspect, Level, Nesting, Stage};
+#[cfg(feature="std")]
+pub use options::SlowMotion;
+#[cfg(feature="std")]
+pub use prefix::{PrefixGuard, with_label_prefix};
+#[cfg(feature="pretty")]
+pub use pretty_logger::PrettyLogger;
+pub use record::{EventRecord, RecordedOutcome};
+#[cfg(feature="std")]
+pub use registry::report_pending;
+pub use retry::{Retries, inspect_retries};
+#[cfg(not(silenced))]
+pub use retry::Attempt;
+#[cfg(feature="std")]
+pub use scope::{ScopeGuard, enabled_scope};
+#[cfg(feature="std")]
+pub use settings::{SettingsError, clear_settings, load_settings, reload_settings, set_settings};
+#[cfg(all(feature="std", not(silenced)))]
+pub use shared::LoggedShared;
+#[cfg(all(feature="signal", unix))]
+pub use signal::{DumpSignal, dump_on_signal, dump_state};
+pub use sink::{LoggedSink, SinkLoggingExt};
+#[cfg(feature="std")]
+pub use subscriber::{EVENT_MODEL_VERSION, EventSubscriber, Subscription, subscribe, unsubscribe};
+#[cfg(feature="slog")]
+pub use slog_logger::SlogLogger;
+#[cfg(feature="std")]
+pub use stats::{report, report_top};
+#[cfg(feature="syslog")]
+pub use syslog_logger::SyslogLogger;
+pub use stream::{BatchStreamLoggingExt, ChangesStreamLoggingExt, LoggedStream, SimpleStreamLoggingExt,
+                 StreamLoggingExt, ThroughputStreamLoggingExt, TickStreamLoggingExt};
+#[cfg(not(silenced))]
+pub use stream::{BatchLoggedStream, ChangesLoggedStream, SimpleLoggedStream, ThroughputLoggedStream, TickLoggedStream};
+#[cfg(feature="std")]
+pub use timing::{Clock, SystemClock, TestClock, set_clock};
+#[cfg(feature="tracing")]
+pub use tracing_logger::TracingLogger;
 
-impl<T, E, F> LoggingExt<T, E> for F
-    where T: Debug,
-          E: Debug,
-          Self: Future<Item = T, Error = E>
-{
-    #[cfg(not(feature="silence"))]
-    fn inspect(self, label: &str) -> LoggedFuture<T, E, Self> {
-        LoggedFuture {
-            future: self,
-            label: label.to_owned(),
-        }
-    }
-    #[cfg(feature="silence")]
-    fn inspect(self, _: &str) -> Self {
-        self
-    }
+#[doc(hidden)]
+pub mod __private {
+    pub use allowlist::{Gate, allowed};
+    pub use futures::Future;
 }
@@ -0,0 +1,99 @@
+//! A backend writing poll events to the Android log.
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+
+use logger::{PollEvent, PollLogger};
+use options::Level;
+
+#[link(name = "log")]
+extern "C" {
+    fn __android_log_write(priority: c_int, tag: *const c_char, text: *const c_char) -> c_int;
+}
+
+/// A PollLogger that writes every event to logcat, tagged with the
+/// label of the inspection, for async Rust inside an Android app
+/// where nothing is behind the `log` facade:
+///
+/// ```rust,ignore
+/// use std::sync::Arc;
+/// use futures_poll_log::LogcatLogger;
+///
+/// futures_poll_log::set_poll_logger(Arc::new(LogcatLogger::new()));
+/// ```
+///
+/// `adb logcat -s request` then shows the events of the inspections
+/// labeled `request`. Before Android 7.0, tags longer than 23 bytes
+/// are rejected, so labels are cut to that length unless
+/// `full_tags()` is set.
+///
+/// This backend is available with the `android` feature, on
+/// Android.
+#[derive(Debug, Clone, Default)]
+pub struct LogcatLogger {
+    prefix: Option<String>,
+    full_tags: bool,
+}
+
+impl LogcatLogger {
+    /// Creates a backend tagging the events with their label.
+    pub fn new() -> LogcatLogger {
+        LogcatLogger::default()
+    }
+
+    /// Puts `prefix` in front of the label in every tag, e.g. the
+    /// name of the app.
+    pub fn tag_prefix(mut self, prefix: &str) -> LogcatLogger {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Keeps tags longer than 23 bytes whole, for Android 7.0 and
+    /// later.
+    pub fn full_tags(mut self) -> LogcatLogger {
+        self.full_tags = true;
+        self
+    }
+
+    fn tag(&self, label: &str) -> CString {
+        let mut tag = String::new();
+        if let Some(ref prefix) = self.prefix {
+            tag.push_str(prefix);
+        }
+        tag.push_str(label);
+        if !self.full_tags && tag.len() > 23 {
+            let mut end = 23;
+            while !tag.is_char_boundary(end) {
+                end -= 1;
+            }
+            tag.truncate(end);
+        }
+        c_string(tag)
+    }
+}
+
+impl PollLogger for LogcatLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let priority = match event.level {
+            Level::Error => 6,
+            Level::Warn => 5,
+            Level::Info => 4,
+            Level::Debug => 3,
+            Level::Trace => 2,
+        };
+        let tag = self.tag(event.label);
+        let text = c_string(event.to_string());
+        // Safety: both strings are nul-terminated and outlive the call.
+        unsafe {
+            __android_log_write(priority, tag.as_ptr(), text.as_ptr());
+        }
+    }
+}
+
+/// `text` as a C string, cut at the first nul in it.
+fn c_string(mut text: String) -> CString {
+    if let Some(nul) = text.find('\0') {
+        text.truncate(nul);
+    }
+    CString::new(text).expect("cut at the first nul")
+}
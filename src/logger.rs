@@ -0,0 +1,1291 @@
+//! The pluggable backend receiving all poll events.
+
+use futures::{Async, Poll};
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::any::Any;
+use core::fmt::{self, Debug};
+use core::panic::Location;
+use core::time::Duration;
+
+use config::Timestamps;
+use context::ContextFields;
+use options::Level;
+
+/// A PollLogger receives every event produced by the logging
+/// wrappers of this crate.
+///
+/// By default, events are written through the `log` crate by
+/// `LogLogger`. A different backend can be installed globally
+/// through `set_poll_logger`, or for a single inspection through
+/// the `*_with_logger` methods of the extension traits.
+pub trait PollLogger: Send + Sync {
+    /// on_event() is called for every event. It is called
+    /// synchronously from within the poll call being logged.
+    fn on_event(&self, event: &PollEvent);
+
+    /// enabled() tells whether events at `level` for `target` are
+    /// written at all. When it returns `false`, no event is built
+    /// and nothing is formatted for them. Defaults to `true`.
+    fn enabled(&self, level: Level, target: &str) -> bool {
+        let _ = (level, target);
+        true
+    }
+}
+
+/// A PollEvent describes something that happened to a
+/// labeled future, stream or sink.
+///
+/// Its `Display` output is the message written by `LogLogger`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct PollEvent<'a> {
+    /// The label given at inspection.
+    pub label: &'a str,
+    /// The value the label was written from, if given as one through
+    /// `Inspect::new_typed`, see `label_as()`.
+    pub label_value: Option<&'a (dyn Any + Send + Sync)>,
+    /// A number identifying the inspection, unique within
+    /// the process.
+    pub instance: u64,
+    /// The number of this event within the process, counting
+    /// up across all inspections and threads. Sorting by it
+    /// restores the order the events happened in, even if the
+    /// backend buffers them.
+    pub sequence: u64,
+    /// The file and line the inspection was set up at, if enabled
+    /// through `Inspect::caller_location`.
+    pub location: Option<&'a Location<'a>>,
+    /// The label of the inspection whose poll last polled this
+    /// one, if any. Only tracked with the `std` feature.
+    pub parent: Option<&'a str>,
+    /// The label and instance of the inspection that was being
+    /// polled when this one was set up, such as the task that
+    /// spawned it, if any. Only tracked with the `std` feature.
+    pub spawned_by: Option<(&'a str, u64)>,
+    /// The correlation id of the inspection, set through
+    /// `Inspect::correlation` or inherited, if any.
+    pub correlation: Option<&'a str>,
+    /// The diagnostic context of the event, set through
+    /// `Inspect::context` and `push_context()`.
+    pub context: ContextFields<'a>,
+    /// The category of the error reported by the event, as
+    /// returned by the classifier given to
+    /// `LoggingExt::inspect_classified`, if any.
+    pub category: Option<&'a str>,
+    /// The number of the poll this event belongs to, starting at 1.
+    /// For sinks, every call to `start_send`, `poll_complete` and
+    /// `close` counts as a poll.
+    pub poll: u64,
+    /// The level configured for the inspection or the result
+    /// the event reports, or `Warn` for events warning about a
+    /// problem and `Error` for panics.
+    pub level: Level,
+    /// The log target configured for the inspection.
+    pub target: &'a str,
+    /// The time spent in the inner poll, for events reporting
+    /// its result of an inspection with timing enabled.
+    pub duration: Option<Duration>,
+    /// When the event happened, as configured through
+    /// `Timestamps`.
+    pub timestamp: Option<Duration>,
+    /// How `timestamp` is written in the message.
+    pub timestamps: Timestamps,
+    /// The time since the inspection was set up, if enabled
+    /// through `Inspect::relative_timestamps`.
+    pub age: Option<Duration>,
+    /// The time since the previous poll of the inspection ended,
+    /// if enabled through `Inspect::poll_gaps`.
+    pub gap: Option<Duration>,
+    /// The maximum number of characters of items and errors
+    /// printed in the message.
+    pub truncate: Option<usize>,
+    /// Whether items and errors are printed with `{:#?}`, over
+    /// several lines, see `Inspect::pretty_values`.
+    pub pretty: bool,
+    /// Whether line breaks in the message are collapsed, see
+    /// `Inspect::single_line`.
+    pub single_line: bool,
+    /// The template the message is formatted by, if configured
+    /// through `Inspect::format`.
+    pub format: Option<&'a str>,
+    /// What happened.
+    pub kind: EventKind<'a>,
+}
+
+impl<'a> PollEvent<'a> {
+    /// The value the label was written from, if it was set up through
+    /// `Inspect::new_typed` with an `L`, for backends matching on
+    /// enums of labels rather than their text.
+    pub fn label_as<L: Any>(&self) -> Option<&'a L> {
+        self.label_value.and_then(|value| value.downcast_ref())
+    }
+
+    /// The result reported by this event, if any.
+    pub fn outcome(&self) -> Option<Outcome<'a>> {
+        self.kind.outcome()
+    }
+
+    /// Whether this event points at a problem rather than
+    /// normal progress.
+    pub fn is_warning(&self) -> bool {
+        self.kind.is_warning()
+    }
+}
+
+impl<'a> EventKind<'a> {
+    pub(crate) fn outcome(&self) -> Option<Outcome<'a>> {
+        match *self {
+            EventKind::Polled(outcome) |
+            EventKind::StreamPolled(outcome) |
+            EventKind::PollCompleted(outcome) |
+            EventKind::Closed(outcome) => Some(outcome),
+            EventKind::StreamItem(_, item) => Some(Outcome::Ready(item)),
+            EventKind::StartSendFailed(error) => Some(Outcome::Err(error)),
+            EventKind::Sent(item) => Some(Outcome::Ready(item)),
+            EventKind::StillNotReady(_) => Some(Outcome::NotReady),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_warning(&self) -> bool {
+        matches!(*self,
+                 EventKind::DeadlineExceeded(_) |
+                 EventKind::Panicked(_) |
+                 EventKind::Reentered |
+                 EventKind::ReadyStreak(_) |
+                 EventKind::PollGap(..) |
+                 EventKind::PollStorm(..) |
+                 EventKind::TickDrift(..) |
+                 EventKind::BlockingInPoll(_) |
+                 EventKind::LostWakeup |
+                 EventKind::WakersLeaked(..) |
+                 EventKind::InjectedNotReady |
+                 EventKind::InjectedDelay(_) |
+                 EventKind::InjectedError(_))
+    }
+
+    /// The level this event is written at unless the inspection
+    /// is configured for a more severe one.
+    pub(crate) fn min_level(&self) -> Option<Level> {
+        match *self {
+            EventKind::Panicked(_) | EventKind::Reentered => Some(Level::Error),
+            EventKind::DeadlineExceeded(_) |
+            EventKind::ReadyStreak(_) |
+            EventKind::PollGap(..) |
+            EventKind::PollStorm(..) |
+            EventKind::TickDrift(..) |
+            EventKind::BlockingInPoll(_) |
+            EventKind::LostWakeup |
+            EventKind::WakersLeaked(..) |
+            EventKind::InjectedNotReady |
+            EventKind::InjectedDelay(_) |
+            EventKind::InjectedError(_) => Some(Level::Warn),
+            _ => None,
+        }
+    }
+
+    /// Whether this event reports an error.
+    pub(crate) fn is_error(&self) -> bool {
+        matches!(*self,
+                 EventKind::Polled(Outcome::Err(_)) |
+                 EventKind::StreamPolled(Outcome::Err(_)) |
+                 EventKind::StartSendFailed(_) |
+                 EventKind::PollCompleted(Outcome::Err(_)) |
+                 EventKind::Closed(Outcome::Err(_)))
+    }
+
+    /// Whether this event reports a result that ends the
+    /// inspected value, or an error.
+    pub(crate) fn is_terminal(&self) -> bool {
+        matches!(*self,
+                 EventKind::Polled(Outcome::Ready(_)) |
+                 EventKind::Polled(Outcome::Err(_)) |
+                 EventKind::StreamPolled(Outcome::Err(_)) |
+                 EventKind::StreamEnded(..) |
+                 EventKind::StreamDropped(..) |
+                 EventKind::StartSendFailed(_) |
+                 EventKind::PollCompleted(Outcome::Err(_)) |
+                 EventKind::Closed(Outcome::Ready(_)) |
+                 EventKind::Closed(Outcome::Err(_)) |
+                 EventKind::TaskCompleted(_) |
+                 EventKind::WorkFinished(_) |
+                 EventKind::PipelineDone(_) |
+                 EventKind::Dropped(_))
+    }
+}
+
+impl<'a> fmt::Display for PollEvent<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.single_line {
+            let mut line = SingleLine {
+                f,
+                blank: String::new(),
+                broken: false,
+            };
+            fmt::write(&mut line, format_args!("{}", Line(self)))?;
+            return line.finish();
+        }
+        self.fmt_line(f)
+    }
+}
+
+/// Writes an event through `Display`, without collapsing line
+/// breaks.
+struct Line<'a, 'b: 'a>(&'a PollEvent<'b>);
+
+impl<'a, 'b> fmt::Display for Line<'a, 'b> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_line(f)
+    }
+}
+
+/// A writer replacing every run of whitespace containing a line
+/// break by a single space.
+struct SingleLine<'a, 'b: 'a> {
+    f: &'a mut fmt::Formatter<'b>,
+    /// The whitespace seen since the last other character.
+    blank: String,
+    broken: bool,
+}
+
+impl<'a, 'b> SingleLine<'a, 'b> {
+    fn flush_blank(&mut self) -> fmt::Result {
+        if self.broken {
+            self.f.write_str(" ")?;
+        } else {
+            self.f.write_str(&self.blank)?;
+        }
+        self.blank.clear();
+        self.broken = false;
+        Ok(())
+    }
+
+    fn finish(mut self) -> fmt::Result {
+        self.flush_blank()
+    }
+}
+
+impl<'a, 'b> fmt::Write for SingleLine<'a, 'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if c.is_whitespace() {
+                self.blank.push(c);
+                self.broken |= matches!(c, '\n' | '\r' | '\x0b' | '\x0c' | '\u{85}' | '\u{2028}' | '\u{2029}');
+            } else {
+                if !self.blank.is_empty() {
+                    self.flush_blank()?;
+                }
+                self.f.write_char(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> PollEvent<'a> {
+    fn fmt_line(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(template) = self.format {
+            return self.fmt_template(template, f);
+        }
+        if let Some(timestamp) = self.timestamp {
+            match self.timestamps {
+                Timestamps::Rfc3339 => write!(f, "[{}] ", Stamp(timestamp, self.timestamps))?,
+                _ => write!(f, "[{}s] ", Stamp(timestamp, self.timestamps))?,
+            }
+        }
+        if let Some(age) = self.age {
+            write!(f, "[t+{:?}] ", age)?;
+        }
+        if let Some(gap) = self.gap {
+            write!(f, "[idle {:?}] ", gap)?;
+        }
+        if let Some(correlation) = self.correlation {
+            write!(f, "[{}] ", correlation)?;
+        }
+        if !self.context.is_empty() {
+            write!(f, "[{}] ", self.context)?;
+        }
+        self.fmt_message(f)?;
+        if let Some(category) = self.category {
+            write!(f, " (category `{}')", category)?;
+        }
+        if let Some(location) = self.location {
+            write!(f, " (at {}:{})", location.file(), location.line())?;
+        }
+        if let Some(duration) = self.duration {
+            write!(f, " (took {:?})", duration)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a timestamp as seconds, or as an RFC 3339 date and time
+/// in UTC for `Timestamps::Rfc3339`.
+pub(crate) struct Stamp(pub(crate) Duration, pub(crate) Timestamps);
+
+impl fmt::Display for Stamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Stamp(timestamp, timestamps) = *self;
+        if timestamps != Timestamps::Rfc3339 {
+            return write!(f, "{}.{:06}", timestamp.as_secs(), timestamp.subsec_micros());
+        }
+        let secs = timestamp.as_secs();
+        let (year, month, day) = civil_from_days(secs / 86_400);
+        let time = secs % 86_400;
+        write!(f,
+               "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+               year,
+               month,
+               day,
+               time / 3600,
+               time / 60 % 60,
+               time % 60,
+               timestamp.subsec_micros())
+    }
+}
+
+/// The date `days` after the Unix epoch, after Howard Hinnant's
+/// `civil_from_days`.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Writes `message` through `Display`, for the `{message}`
+/// placeholder.
+struct Message<'a, 'b: 'a>(&'a PollEvent<'b>);
+
+impl<'a, 'b> fmt::Display for Message<'a, 'b> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_message(f)
+    }
+}
+
+impl<'a> PollEvent<'a> {
+    /// Writes the message as given by `template`, replacing the
+    /// placeholders described at `Inspect::format`.
+    fn fmt_template(&self, template: &str, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rest = template;
+        while let Some(start) = rest.find(['{', '}']) {
+            f.write_str(&rest[..start])?;
+            let tail = &rest[start..];
+            if tail.starts_with("{{") || tail.starts_with("}}") {
+                f.write_str(&tail[..1])?;
+                rest = &tail[2..];
+                continue;
+            }
+            let end = match tail.find('}') {
+                Some(end) if tail.starts_with('{') => end,
+                _ => {
+                    f.write_str(&tail[..1])?;
+                    rest = &tail[1..];
+                    continue;
+                }
+            };
+            self.fmt_placeholder(&tail[1..end], f)?;
+            rest = &tail[end + 1..];
+        }
+        f.write_str(rest)
+    }
+
+    fn fmt_placeholder(&self, name: &str, f: &mut fmt::Formatter) -> fmt::Result {
+        match name {
+            "label" => f.write_str(self.label),
+            "instance" => write!(f, "{}", self.instance),
+            "seq" => write!(f, "{}", self.sequence),
+            "n" | "poll" => write!(f, "{}", self.poll),
+            "kind" => f.write_str(self.kind.name()),
+            "level" => write!(f, "{:?}", self.level),
+            "outcome" => {
+                match self.outcome() {
+                    Some(outcome) => write!(f, "{:?}", Shown::Outcome(outcome, self.style())),
+                    None => f.write_str(self.kind.name()),
+                }
+            }
+            "elapsed" => self.duration.map_or(Ok(()), |duration| write!(f, "{:?}", duration)),
+            "timestamp" => {
+                self.timestamp.map_or(Ok(()), |timestamp| write!(f, "{}", Stamp(timestamp, self.timestamps)))
+            }
+            "age" => self.age.map_or(Ok(()), |age| write!(f, "t+{:?}", age)),
+            "gap" => self.gap.map_or(Ok(()), |gap| write!(f, "{:?}", gap)),
+            "parent" => f.write_str(self.parent.unwrap_or("")),
+            "location" => {
+                self.location.map_or(Ok(()), |location| write!(f, "{}:{}", location.file(), location.line()))
+            }
+            "spawned_by" => {
+                self.spawned_by.map_or(Ok(()), |(label, instance)| write!(f, "{} (#{})", label, instance))
+            }
+            "correlation" => f.write_str(self.correlation.unwrap_or("")),
+            "context" => write!(f, "{}", self.context),
+            "category" => f.write_str(self.category.unwrap_or("")),
+            "message" => write!(f, "{}", Message(self)),
+            _ => write!(f, "{{{}}}", name),
+        }
+    }
+
+    fn style(&self) -> Style {
+        Style {
+            truncate: self.truncate,
+            pretty: self.pretty,
+        }
+    }
+
+    fn fmt_message(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = self.label;
+        let style = self.style();
+        match self.kind {
+            EventKind::Polling => write!(f, "Polling future `{}'", label),
+            EventKind::Polled(outcome) => write!(f, "Future `{}' polled: {:?}", label, Shown::Outcome(outcome, style)),
+            EventKind::PolledBy(thread) => write!(f, "Future `{}' is polled from {}", label, thread),
+            EventKind::DeadlineExceeded(duration) => {
+                write!(f,
+                       "Future `{}' exceeded its deadline of {:?} while still NotReady",
+                       label,
+                       duration)
+            }
+            EventKind::Panicked(message) => {
+                write!(f, "Future `{}' panicked during poll: {}", label, message)
+            }
+            EventKind::Retrying(backoff) => {
+                write!(f, "Future `{}' started {:?} after the previous attempt resolved", label, backoff)
+            }
+            EventKind::Spawned => {
+                write!(f, "Task `{}' spawned", label)?;
+                match self.spawned_by {
+                    Some((spawner, instance)) => write!(f, " by `{}' (#{})", spawner, instance),
+                    None => Ok(()),
+                }
+            }
+            EventKind::TaskStarted(elapsed) => {
+                write!(f, "Task `{}' first polled {:?} after spawn", label, elapsed)
+            }
+            EventKind::TaskCompleted(elapsed) => {
+                write!(f, "Task `{}' completed {:?} after spawn", label, elapsed)
+            }
+            EventKind::Submitted => write!(f, "Work `{}' submitted to the pool", label),
+            EventKind::WorkStarted(waited) => {
+                write!(f, "Work `{}' started after waiting {:?} in the queue", label, waited)
+            }
+            EventKind::WorkFinished(ran) => write!(f, "Work `{}' finished after running for {:?}", label, ran),
+            EventKind::CompatWoken => {
+                write!(f, "Future `{}' polled after a wakeup from futures 0.1", label)
+            }
+            EventKind::LostWakeup => {
+                write!(f,
+                       "Future `{}' was woken from futures 0.1, but dropped without being polled again",
+                       label)
+            }
+            EventKind::Waker(waker) => write!(f, "Future `{}' polled with waker {:016x}", label, waker),
+            EventKind::WakerChanged(previous, current) => {
+                write!(f,
+                       "Future `{}' polled with waker {:016x}, the last poll had {:016x}",
+                       label,
+                       current,
+                       previous)
+            }
+            EventKind::WokenBy(waker) => {
+                write!(f, "Future `{}' woken through waker {:016x}", label, waker)
+            }
+            EventKind::WokenFrom(waker, instance) => {
+                write!(f, "Future `{}' woken by `{}' (#{})", label, waker, instance)
+            }
+            EventKind::WakersLeaked(live, clones) => {
+                write!(f,
+                       "Future `{}' ended with {} wakers still alive, out of {} clones it made",
+                       label,
+                       live,
+                       clones)
+            }
+            EventKind::IsTerminated(terminated) => {
+                write!(f, "Future `{}' is_terminated: {}", label, terminated)
+            }
+            EventKind::Size(size) => write!(f, "Future `{}' takes up {} bytes", label, size),
+            EventKind::Blocking(thread) => write!(f, "`{}' blocks thread `{}' until it resolves", label, thread),
+            EventKind::BlockingInPoll(outer) => {
+                write!(f, "`{}' blocks its thread inside the poll of `{}', stalling its executor", label, outer)
+            }
+            EventKind::Unparked(wakeup) => write!(f, "`{}' unparked its thread to poll again (wakeup #{})", label, wakeup),
+            EventKind::ErrorSource(depth, source) => write!(f, "Error of `{}' caused by ({}): {}", label, depth, source),
+            EventKind::SlowedDown(delay) => write!(f, "`{}' slowed down, sleeping {:?} before the poll", label, delay),
+            EventKind::HeldBack(delay) => {
+                write!(f, "`{}' held back, returning NotReady and waking up in {:?}", label, delay)
+            }
+            EventKind::InjectedNotReady => write!(f, "Injected NotReady into `{}', waking it up right away", label),
+            EventKind::InjectedDelay(delay) => {
+                write!(f, "Injected NotReady into `{}', waking it up in {:?}", label, delay)
+            }
+            EventKind::InjectedError(error) => {
+                write!(f, "Injected an error into `{}': {:?}", label, Shown::Value(error, style))
+            }
+            EventKind::Unblocked(parked, wakeups) => {
+                write!(f,
+                       "`{}' released its thread after parking it for {:?} over {} wakeups",
+                       label,
+                       parked,
+                       wakeups)
+            }
+            EventKind::Allocated(bytes, count) => {
+                let allocations = if count == 1 { "allocation" } else { "allocations" };
+                write!(f, "`{}' allocated {} bytes in {} {}", label, bytes, count, allocations)
+            }
+            EventKind::CpuTime(cpu, wall) => {
+                write!(f, "`{}' used {:?} of CPU time in {:?} of wall time", label, cpu, wall)
+            }
+            EventKind::ClosureInvoked => write!(f, "Closure of `{}' invoked for the first time", label),
+            EventKind::SizeHint(items, lower, upper) => {
+                write!(f, "Stream `{}' hints at {} to ", label, lower)?;
+                match upper {
+                    Some(upper) => write!(f, "{}", upper)?,
+                    None => f.write_str("unbounded")?,
+                }
+                write!(f, " more items after {} items", items)
+            }
+            EventKind::FirstPoll(waited) => write!(f, "`{}' waited {:?} for its first poll", label, waited),
+            EventKind::ThreadChanged(first, current) => {
+                write!(f, "Future `{}' polled from {}, first polled from {}", label, current, first)
+            }
+            EventKind::ReadyStreak(streak) => {
+                write!(f,
+                       "Future `{}' resolved, making {} resolved futures in a row without the task yielding",
+                       label,
+                       streak)
+            }
+            EventKind::Reentered => {
+                write!(f, "Future `{}' (#{}) polled again from within its own poll", label, self.instance)
+            }
+            EventKind::PollGap(gap, max) => {
+                write!(f, "`{}' was left idle for {:?} between polls, longer than {:?}", label, gap, max)
+            }
+            EventKind::Branch(branch) => write!(f, "`{}' took its {} branch", label, branch),
+            EventKind::PollStorm(polls, age) => {
+                write!(f, "`{}' (#{}) polled {} times in a row without being ready", label, self.instance, polls)?;
+                match age {
+                    Some(age) => write!(f, ", {:?} after it was set up", age),
+                    None => Ok(()),
+                }
+            }
+            EventKind::StillNotReady(polls) => {
+                let times = if polls == 1 { "time" } else { "times" };
+                write!(f, "`{}' still NotReady (polled {} more {})", label, polls, times)
+            }
+            EventKind::Suppressed(max) => {
+                write!(f, "`{}' emitted {} events, suppressing further ones until the end", label, max)
+            }
+            EventKind::GroupLimited(group, limit) => {
+                write!(f, "`{}' reached the limit of {} events per second of group `{}', dropping further ones", label, limit, group)
+            }
+            EventKind::StreamPolling => write!(f, "Polling stream `{}'", label),
+            EventKind::StreamPolled(outcome) => {
+                write!(f, "Stream `{}' polled: {:?}", label, Shown::Outcome(outcome, style))
+            }
+            EventKind::StreamItem(index, item) => {
+                write!(f, "Stream `{}' yielded item #{}: {:?}", label, index, Shown::Value(item, style))
+            }
+            EventKind::StreamEnded(items, polls) => {
+                write!(f, "Stream `{}' ended after {} items and {} polls", label, items, polls)
+            }
+            EventKind::StreamDropped(items, polls) => {
+                write!(f,
+                       "Stream `{}' dropped before ending, after {} items and {} polls",
+                       label,
+                       items,
+                       polls)
+            }
+            EventKind::StreamBatch(index, size, filled) => {
+                write!(f, "Stream `{}' yielded batch #{} of {} items", label, index, size)?;
+                match filled {
+                    Some(filled) => write!(f, ", filled in {:?}", filled),
+                    None => Ok(()),
+                }
+            }
+            EventKind::StreamTick(index, late) if late == Duration::ZERO => {
+                write!(f, "Stream `{}' tick #{} came on time", label, index)
+            }
+            EventKind::StreamTick(index, late) => write!(f, "Stream `{}' tick #{} came {:?} late", label, index, late),
+            EventKind::TickDrift(index, late, max) => {
+                write!(f, "Stream `{}' tick #{} came {:?} late, more than {:?}", label, index, late, max)
+            }
+            EventKind::StreamThroughput(items, bytes, window) => {
+                let seconds = window.as_secs_f64();
+                write!(f, "Stream `{}' yielded {} items", label, items)?;
+                if let Some(bytes) = bytes {
+                    write!(f, ", {} bytes", bytes)?;
+                }
+                write!(f, " in the last {:?}: {:.1} items/s", window, items as f64 / seconds)?;
+                match bytes {
+                    Some(bytes) => write!(f, ", {:.1} bytes/s", bytes as f64 / seconds),
+                    None => Ok(()),
+                }
+            }
+            EventKind::StreamRepeated(times) => {
+                write!(f, "Stream `{}' repeated the previous item {} times", label, times)
+            }
+            EventKind::StartSend(item) => write!(f, "Sink `{}' start_send: {:?}", label, Shown::Value(item, style)),
+            EventKind::StartSendAccepted => write!(f, "Sink `{}' accepted item in start_send", label),
+            EventKind::StartSendNotReady => {
+                write!(f, "Sink `{}' not ready in start_send, item handed back", label)
+            }
+            EventKind::StartSendFailed(error) => {
+                write!(f, "Sink `{}' start_send returned: Err({:?})", label, Shown::Value(error, style))
+            }
+            EventKind::PollComplete => write!(f, "Sink `{}' poll_complete", label),
+            EventKind::PollCompleted(outcome) => {
+                write!(f, "Sink `{}' poll_complete returned: {:?}", label, Shown::Outcome(outcome, style))
+            }
+            EventKind::Close => write!(f, "Sink `{}' close", label),
+            EventKind::Closed(outcome) => {
+                write!(f, "Sink `{}' close returned: {:?}", label, Shown::Outcome(outcome, style))
+            }
+            EventKind::Sent(item) => write!(f, "Sender of `{}' sent: {:?}", label, Shown::Value(item, style)),
+            EventKind::ReceiverGone => write!(f, "Sender of `{}' found the receiver dropped", label),
+            EventKind::SenderDropped(sent) => {
+                write!(f, "Sender of `{}' dropped after sending {} items", label, sent)
+            }
+            EventKind::LockRequested => write!(f, "Lock `{}' requested", label),
+            EventKind::LockAcquired(pending) => {
+                write!(f, "Lock `{}' acquired after {} polls", label, self.poll)?;
+                if let Some(pending) = pending {
+                    write!(f, ", pending for {:?}", pending)?;
+                }
+                Ok(())
+            }
+            EventKind::LockReleased(held) => {
+                write!(f, "Lock `{}' released", label)?;
+                if let Some(held) = held {
+                    write!(f, ", held for {:?}", held)?;
+                }
+                Ok(())
+            }
+            EventKind::PipelineItem(index, item) => {
+                write!(f, "Pipeline `{}' passes item #{}: {:?}", label, index, Shown::Value(item, style))
+            }
+            EventKind::PipelineFlushed(true) => write!(f, "Pipeline `{}' flushed its sink", label),
+            EventKind::PipelineFlushed(false) => write!(f, "Pipeline `{}' is still flushing its sink", label),
+            EventKind::PipelineStalled(half) => write!(f, "Pipeline `{}' not ready, waiting on its {}", label, half),
+            EventKind::PipelineDone(items) => {
+                write!(f, "Pipeline `{}' forwarded {} items and closed its sink", label, items)
+            }
+            EventKind::Dropped(summary) => {
+                let completion = match summary.completion {
+                    Completion::Completed => "completed",
+                    Completion::Errored => "errored",
+                    Completion::Cancelled => "was cancelled",
+                };
+                write!(f,
+                       "Future `{}' (#{}) {} after {} polls",
+                       label,
+                       self.instance,
+                       completion,
+                       summary.polls)?;
+                if let Some(pending) = summary.pending {
+                    write!(f, ", pending for {:?}", pending)?;
+                }
+                if let Some(overhead) = summary.overhead {
+                    write!(f, ", logging took {:?}", overhead)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How items and errors are printed.
+#[derive(Clone, Copy)]
+struct Style {
+    truncate: Option<usize>,
+    pretty: bool,
+}
+
+/// Prints an item, error or outcome in a message, cut to
+/// the configured length.
+enum Shown<'a> {
+    Value(&'a dyn Debug, Style),
+    Outcome(Outcome<'a>, Style),
+}
+
+impl<'a> Debug for Shown<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Shown::Value(value, Style { truncate: None, pretty: false }) => value.fmt(f),
+            Shown::Value(value, Style { truncate: None, pretty: true }) => write!(f, "{:#?}", value),
+            Shown::Value(value, Style { truncate: Some(max), pretty }) => {
+                let mut limited = Limited {
+                    f,
+                    remaining: max,
+                    truncated: false,
+                };
+                let result = if pretty {
+                    fmt::write(&mut limited, format_args!("{:#?}", value))
+                } else {
+                    fmt::write(&mut limited, format_args!("{:?}", value))
+                };
+                if limited.truncated {
+                    limited.f.write_str("...")
+                } else {
+                    result
+                }
+            }
+            Shown::Outcome(Outcome::NotReady, _) => Outcome::NotReady.fmt(f),
+            Shown::Outcome(Outcome::Ready(item), style) => {
+                f.debug_tuple("Ok").field(&Async::Ready(Shown::Value(item, style))).finish()
+            }
+            Shown::Outcome(Outcome::Err(error), style) => {
+                f.debug_tuple("Err").field(&Shown::Value(error, style)).finish()
+            }
+        }
+    }
+}
+
+/// A writer passing on at most `remaining` characters.
+struct Limited<'a, 'b: 'a> {
+    f: &'a mut fmt::Formatter<'b>,
+    remaining: usize,
+    truncated: bool,
+}
+
+impl<'a, 'b> fmt::Write for Limited<'a, 'b> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match s.char_indices().nth(self.remaining) {
+            Some((end, _)) => {
+                self.f.write_str(&s[..end])?;
+                self.remaining = 0;
+                self.truncated = true;
+                // Stop formatting the rest of the value.
+                Err(fmt::Error)
+            }
+            None => {
+                self.remaining -= s.chars().count();
+                self.f.write_str(s)
+            }
+        }
+    }
+}
+
+/// The different kinds of `PollEvent`s.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EventKind<'a> {
+    /// A future is about to be polled.
+    Polling,
+    /// A future was polled.
+    Polled(Outcome<'a>),
+    /// A future is polled from a different thread than before,
+    /// described by its name and id.
+    PolledBy(&'a str),
+    /// A future is still `NotReady` after its deadline passed.
+    DeadlineExceeded(Duration),
+    /// The poll of a future panicked with the given message. The
+    /// panic continues after the event.
+    Panicked(&'a str),
+    /// An attempt of a retry loop is polled for the first time,
+    /// the given time after the previous attempt resolved.
+    Retrying(Duration),
+    /// A task was spawned.
+    Spawned,
+    /// A task was polled for the first time, the given time after
+    /// it was spawned.
+    TaskStarted(Duration),
+    /// A task completed, the given time after it was spawned.
+    TaskCompleted(Duration),
+    /// Work was submitted to a thread pool.
+    Submitted,
+    /// Work submitted to a thread pool started, after waiting the
+    /// given time in the queue.
+    WorkStarted(Duration),
+    /// Work on a thread pool finished, after running for the given
+    /// time.
+    WorkFinished(Duration),
+    /// A future converted from futures 0.1 is polled after the
+    /// futures 0.1 side woke it.
+    CompatWoken,
+    /// A future converted from futures 0.1 was woken by the
+    /// futures 0.1 side, but dropped before it was polled again.
+    LostWakeup,
+    /// A future is polled with the waker of the given fingerprint.
+    Waker(u64),
+    /// The waker of a future changed since the last poll, given
+    /// the fingerprints of the previous and the current one.
+    WakerChanged(u64, u64),
+    /// A future was woken through the waker of the given
+    /// fingerprint since the last poll.
+    WokenBy(u64),
+    /// A future was last woken from within the poll of the
+    /// inspection with the given label and instance, e.g. one
+    /// sending on a channel the future waits on.
+    WokenFrom(&'a str, u64),
+    /// A future completed or was dropped while the given number of
+    /// wakers it received were still alive, out of the given
+    /// number of clones it made.
+    WakersLeaked(usize, usize),
+    /// `FusedFuture::is_terminated` was called on a future and
+    /// returned the given value.
+    IsTerminated(bool),
+    /// A future takes up the given number of bytes in memory.
+    Size(usize),
+    /// The thread by the given name starts blocking on a future,
+    /// see `logged_wait()`.
+    Blocking(&'a str),
+    /// A thread starts blocking on a future inside the poll of the
+    /// inspection by the given label.
+    BlockingInPoll(&'a str),
+    /// A thread blocked on a future was unparked to poll it again,
+    /// numbered starting at 1.
+    Unparked(u64),
+    /// A thread stopped blocking on a future that resolved, after
+    /// being parked for the given time over the given number of
+    /// wakeups.
+    Unblocked(Duration, u64),
+    /// A cause of the error reported by the previous event, at the
+    /// given depth in its source chain starting at 1, see
+    /// `LoggingExt::inspect_error_chain`.
+    ErrorSource(usize, &'a str),
+    /// A poll allocated the given number of bytes in the given
+    /// number of allocations, see `Inspect::track_allocations`.
+    Allocated(u64, u64),
+    /// A poll consumed the given CPU time in the given wall time,
+    /// see `Inspect::cpu_time`.
+    CpuTime(Duration, Duration),
+    /// A poll is about to begin after the polling thread slept for
+    /// the given time, see `Inspect::slow_motion`.
+    SlowedDown(Duration),
+    /// A poll was held back, returning `NotReady` and waking the task
+    /// up again the given time later, see `Inspect::slow_motion`.
+    HeldBack(Duration),
+    /// A poll was replaced by an injected `NotReady`, waking the task
+    /// up right away, see `chaos::inject`.
+    InjectedNotReady,
+    /// A poll was replaced by an injected `NotReady`, waking the task
+    /// up only the given time later, see `chaos::inject`.
+    InjectedDelay(Duration),
+    /// A poll was replaced by the given injected error, see
+    /// `chaos::inject`.
+    InjectedError(&'a dyn Debug),
+    /// The closure of a future created through `logged_poll_fn()` or
+    /// `logged_lazy()` is called for the first time.
+    ClosureInvoked,
+    /// A stream has the given size hint, after yielding the given
+    /// number of items, see `Inspect::log_size_hint`.
+    SizeHint(u64, usize, Option<usize>),
+    /// An inspected value is polled for the first time, the given
+    /// time after it was set up.
+    FirstPoll(Duration),
+    /// A future is polled from a different thread than its first
+    /// poll and the poll before, given by the ids of the first and
+    /// the current thread.
+    ThreadChanged(&'a str, &'a str),
+    /// A future resolved after the given number of futures in a
+    /// row resolved within the same poll of the task, more than
+    /// its budget allows.
+    ReadyStreak(u32),
+    /// A future was polled while its poll was already running on
+    /// the same thread.
+    Reentered,
+    /// An inspected value was left idle for the given time between
+    /// two polls, longer than the given maximum.
+    PollGap(Duration, Duration),
+    /// A choice between futures took the given branch, `left` or
+    /// `right`, see `SelectLoggingExt` and `EitherLoggingExt`.
+    Branch(&'a str),
+    /// An inspected value was polled the given number of times in a
+    /// row without being ready, more than `Inspect::warn_poll_storm`
+    /// allows, the given time after it was set up, if measured.
+    PollStorm(u64, Option<Duration>),
+    /// An inspected value returned `NotReady` from the given number
+    /// of polls not logged since the last one that was, see
+    /// `Inspect::coalesce_not_ready`.
+    StillNotReady(u64),
+    /// An inspection emitted the given maximum number of events,
+    /// and only writes its final result and warnings from now on.
+    Suppressed(u64),
+    /// An inspection in the given group reached the rate limit of
+    /// the group of the given number of events per second, see
+    /// `set_group_rate_limit()`. Further events of the group are
+    /// dropped until the second is over.
+    GroupLimited(&'a str, u64),
+    /// A stream is about to be polled.
+    StreamPolling,
+    /// A stream was polled and returned `NotReady` or an error.
+    StreamPolled(Outcome<'a>),
+    /// A stream yielded an item, numbered starting at 1.
+    StreamItem(u64, &'a dyn Debug),
+    /// A stream ended, given the number of items it yielded and
+    /// the number of polls.
+    StreamEnded(u64, u64),
+    /// A stream was dropped before it ended, given the number of
+    /// items it yielded and the number of polls.
+    StreamDropped(u64, u64),
+    /// A stream of batches yielded a batch, numbered starting at 1,
+    /// of the given number of items, the given time after the first
+    /// poll for it, if measured.
+    StreamBatch(u64, usize, Option<Duration>),
+    /// A stream of timer ticks yielded the tick with the given
+    /// number, starting at 1, the given time after it was due.
+    StreamTick(u64, Duration),
+    /// A tick of a stream of timer ticks came the given time after
+    /// it was due, later than the given maximum.
+    TickDrift(u64, Duration, Duration),
+    /// A stream yielded the given number of items, and of bytes if
+    /// measured, within the given window before the current poll.
+    StreamThroughput(u64, Option<u64>, Duration),
+    /// A stream logging only changes yielded the previous item
+    /// again the given number of times.
+    StreamRepeated(u64),
+    /// An item is about to be sent into a sink.
+    StartSend(&'a dyn Debug),
+    /// A sink accepted an item.
+    StartSendAccepted,
+    /// A sink was not ready and handed the item back.
+    StartSendNotReady,
+    /// A sink failed to accept an item.
+    StartSendFailed(&'a dyn Debug),
+    /// A sink is about to be polled for completion.
+    PollComplete,
+    /// A sink was polled for completion.
+    PollCompleted(Outcome<'a>),
+    /// A sink is about to be closed.
+    Close,
+    /// A sink was polled for closing.
+    Closed(Outcome<'a>),
+    /// The sender of a channel sent the given item.
+    Sent(&'a dyn Debug),
+    /// The sender of a channel found the receiver dropped.
+    ReceiverGone,
+    /// The sender of a channel was dropped after sending the
+    /// given number of items.
+    SenderDropped(u64),
+    /// A lock was requested.
+    LockRequested,
+    /// A lock was acquired, the given time after it was requested.
+    LockAcquired(Option<Duration>),
+    /// A lock was released after being held for the given time.
+    LockReleased(Option<Duration>),
+    /// A pipeline of a stream forwarded into a sink passes the
+    /// item from its stream with the given number, starting at 1.
+    PipelineItem(u64, &'a dyn Debug),
+    /// A pipeline flushed its sink while waiting on its stream,
+    /// completely or not.
+    PipelineFlushed(bool),
+    /// A poll of a pipeline returned `NotReady` because of the
+    /// given half of it, `stream` or `sink`.
+    PipelineStalled(&'a str),
+    /// A pipeline closed its sink after the given number of items.
+    PipelineDone(u64),
+    /// A future with summaries enabled was dropped.
+    Dropped(Summary),
+}
+
+/// The summary of a future's inspection, reported when
+/// it is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Summary {
+    /// The number of polls.
+    pub polls: u64,
+    /// The time from the first poll until the future resolved
+    /// or was dropped. Only measured with the `std` feature.
+    pub pending: Option<Duration>,
+    /// How the future ended.
+    pub completion: Completion,
+    /// The time spent logging the future, if measured through
+    /// `Inspect::measure_overhead`.
+    pub overhead: Option<Duration>,
+}
+
+/// The measurements of a future's polls, handed out along with its
+/// item by `LoggingExt::inspect_timed`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PollStats {
+    /// The number of polls, including the one that resolved the
+    /// future.
+    pub polls: u64,
+    /// The time spent inside the polls, summed up. Only measured
+    /// with the `std` feature.
+    pub poll_time: Option<Duration>,
+    /// The time from the setup of the inspection until its first
+    /// poll. Only measured with the `std` feature.
+    pub first_poll: Option<Duration>,
+    /// The time from the setup of the inspection until the future
+    /// resolved. Only measured with the `std` feature.
+    pub total: Option<Duration>,
+}
+
+/// How an inspected future ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Completion {
+    /// The future resolved to an item.
+    Completed,
+    /// The future resolved to an error.
+    Errored,
+    /// The future was dropped before it resolved.
+    Cancelled,
+}
+
+/// The result of a poll, with the item or error
+/// available for printing.
+///
+/// Its `Debug` output matches the one of `Poll`.
+#[derive(Clone, Copy)]
+pub enum Outcome<'a> {
+    /// The poll returned `NotReady`.
+    NotReady,
+    /// The poll returned `Ready`.
+    Ready(&'a dyn Debug),
+    /// The poll returned an error.
+    Err(&'a dyn Debug),
+}
+
+impl<'a> Outcome<'a> {
+    /// Builds the outcome describing `poll`.
+    pub fn from_poll<T: Debug, E: Debug>(poll: &'a Poll<T, E>) -> Outcome<'a> {
+        match *poll {
+            Ok(Async::NotReady) => Outcome::NotReady,
+            Ok(Async::Ready(ref item)) => Outcome::Ready(item),
+            Err(ref error) => Outcome::Err(error),
+        }
+    }
+}
+
+impl<'a> Debug for Outcome<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Outcome::NotReady => f.debug_tuple("Ok").field(&Async::<()>::NotReady).finish(),
+            Outcome::Ready(item) => f.debug_tuple("Ok").field(&Async::Ready(item)).finish(),
+            Outcome::Err(error) => f.debug_tuple("Err").field(&error).finish(),
+        }
+    }
+}
+
+/// The default backend, writing all events through the
+/// `log` crate with the target `futures_log`.
+///
+/// The `log` 0.3 API has no structured key-values, so everything
+/// is written into the message. With the `log-kv` feature, events
+/// go through `log` 0.4 instead, passing the label, instance, poll
+/// number, outcome, error category and duration as key-values of
+/// the record besides the message.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogLogger;
+
+#[cfg(all(not(silenced), not(feature="log-kv")))]
+impl PollLogger for LogLogger {
+    fn on_event(&self, event: &PollEvent) {
+        log!(target: event.target, log_level(event.level), "{}", event);
+    }
+
+    fn enabled(&self, level: Level, target: &str) -> bool {
+        log_enabled!(target: target, log_level(level))
+    }
+}
+
+#[cfg(all(not(silenced), not(feature="log-kv")))]
+fn log_level(level: Level) -> ::log::LogLevel {
+    match level {
+        Level::Error => ::log::LogLevel::Error,
+        Level::Warn => ::log::LogLevel::Warn,
+        Level::Info => ::log::LogLevel::Info,
+        Level::Debug => ::log::LogLevel::Debug,
+        Level::Trace => ::log::LogLevel::Trace,
+    }
+}
+
+#[cfg(all(not(silenced), feature="log-kv"))]
+impl PollLogger for LogLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let outcome = event.outcome().map(|outcome| alloc::format!("{:?}", outcome));
+        let duration_us = event.duration.map(|duration| duration.as_micros() as u64);
+        log04::log!(target: event.target,
+                    log04_level(event.level),
+                    label = event.label,
+                    instance = event.instance,
+                    poll = event.poll,
+                    outcome = outcome,
+                    category = event.category,
+                    duration_us = duration_us;
+                    "{}",
+                    event);
+    }
+
+    fn enabled(&self, level: Level, target: &str) -> bool {
+        log04::log_enabled!(target: target, log04_level(level))
+    }
+}
+
+#[cfg(all(not(silenced), feature="log-kv"))]
+fn log04_level(level: Level) -> log04::Level {
+    match level {
+        Level::Error => log04::Level::Error,
+        Level::Warn => log04::Level::Warn,
+        Level::Info => log04::Level::Info,
+        Level::Debug => log04::Level::Debug,
+        Level::Trace => log04::Level::Trace,
+    }
+}
+
+#[cfg(silenced)]
+impl PollLogger for LogLogger {
+    fn on_event(&self, _: &PollEvent) {}
+}
+
+/// set_poll_logger() installs `logger` as the backend for all
+/// inspections that were not given their own.
+///
+/// Returns the previously installed backend, if any.
+pub fn set_poll_logger(logger: Arc<dyn PollLogger>) -> Option<Arc<dyn PollLogger>> {
+    global::replace(logger)
+}
+
+#[cfg(feature="std")]
+mod global {
+    use std::sync::{Arc, RwLock};
+
+    use super::PollLogger;
+
+    static GLOBAL_LOGGER: RwLock<Option<Arc<dyn PollLogger>>> = RwLock::new(None);
+
+    pub(crate) fn replace(logger: Arc<dyn PollLogger>) -> Option<Arc<dyn PollLogger>> {
+        let mut global = GLOBAL_LOGGER.write().unwrap_or_else(|e| e.into_inner());
+        global.replace(logger)
+    }
+
+    pub(crate) fn with<R, F: FnOnce(Option<&dyn PollLogger>) -> R>(f: F) -> R {
+        let global = GLOBAL_LOGGER.read().unwrap_or_else(|e| e.into_inner());
+        f(global.as_ref().map(|logger| &**logger))
+    }
+}
+
+/// Without `std`, there is no lock to guard the global backend.
+/// Installed backends are leaked instead, so a reader can never
+/// observe one being freed.
+#[cfg(not(feature="std"))]
+mod global {
+    use alloc::boxed::Box;
+    use alloc::sync::Arc;
+    use core::ptr;
+    use core::sync::atomic::{AtomicPtr, Ordering};
+
+    use super::PollLogger;
+
+    static GLOBAL_LOGGER: AtomicPtr<Arc<dyn PollLogger>> = AtomicPtr::new(ptr::null_mut());
+
+    pub(crate) fn replace(logger: Arc<dyn PollLogger>) -> Option<Arc<dyn PollLogger>> {
+        let new = Box::into_raw(Box::new(logger));
+        let old = GLOBAL_LOGGER.swap(new, Ordering::AcqRel);
+        // Safety: pointers stored in GLOBAL_LOGGER come from `Box::into_raw`
+        // and are never freed.
+        unsafe { old.as_ref().cloned() }
+    }
+
+    pub(crate) fn with<R, F: FnOnce(Option<&dyn PollLogger>) -> R>(f: F) -> R {
+        let current = GLOBAL_LOGGER.load(Ordering::Acquire);
+        // Safety: see `replace`.
+        f(unsafe { current.as_ref() }.map(|logger| &**logger))
+    }
+}
+
+/// The backend of a single inspection, falling back to the
+/// global one.
+#[derive(Clone, Default)]
+pub(crate) struct Backend(Option<Arc<dyn PollLogger>>);
+
+#[cfg_attr(silenced, allow(dead_code))]
+impl Backend {
+    pub(crate) fn new(logger: Option<Arc<dyn PollLogger>>) -> Backend {
+        Backend(logger)
+    }
+
+    pub(crate) fn emit(&self, event: &PollEvent) {
+        if let Some(ref logger) = self.0 {
+            return logger.on_event(event);
+        }
+        global::with(|global| match global {
+                         Some(logger) => logger.on_event(event),
+                         None => LogLogger.on_event(event),
+                     })
+    }
+
+    pub(crate) fn enabled(&self, level: Level, target: &str) -> bool {
+        if let Some(ref logger) = self.0 {
+            return logger.enabled(level, target);
+        }
+        global::with(|global| match global {
+                         Some(logger) => logger.enabled(level, target),
+                         None => LogLogger.enabled(level, target),
+                     })
+    }
+}
+
+impl Debug for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(_) => f.write_str("Backend(custom)"),
+            None => f.write_str("Backend(global)"),
+        }
+    }
+}
+
+#[cfg(all(test, feature="log-kv", not(silenced)))]
+mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+    use futures::Future;
+    use log04::kv::{Error, Key, Value, VisitSource};
+    use log04::{Log, Metadata, Record};
+    use std::sync::Mutex;
+
+    use LoggingExt;
+    use inspector::tests::Countdown;
+
+    /// Keeps the key-values of the records labeled `log-kv`.
+    struct KeyValues(Mutex<Vec<Vec<(String, String)>>>);
+
+    impl Log for KeyValues {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            let mut visitor = Visitor(Vec::new());
+            record.key_values().visit(&mut visitor).unwrap();
+            if visitor.0.contains(&("label".to_string(), "log-kv".to_string())) {
+                self.0.lock().unwrap().push(visitor.0);
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    struct Visitor(Vec<(String, String)>);
+
+    impl<'kvs> VisitSource<'kvs> for Visitor {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+            self.0.push((key.to_string(), value.to_string()));
+            Ok(())
+        }
+    }
+
+    static KEY_VALUES: KeyValues = KeyValues(Mutex::new(Vec::new()));
+
+    #[test]
+    fn records_carry_the_event_as_key_values() {
+        log04::set_logger(&KEY_VALUES).unwrap();
+        log04::set_max_level(log04::LevelFilter::Trace);
+        LoggingExt::inspect(Countdown(0), "log-kv").wait().unwrap();
+        let records = KEY_VALUES.0.lock().unwrap();
+        let ready = records.iter()
+            .find(|pairs| pairs.iter().any(|(key, value)| key == "outcome" && value.contains("Ready")))
+            .expect("no record of the ready poll");
+        let keys: Vec<&str> = ready.iter().map(|(key, _)| &key[..]).collect();
+        assert_eq!(keys, ["label", "instance", "poll", "outcome", "category", "duration_us"]);
+        assert!(ready.contains(&("poll".to_string(), "1".to_string())));
+    }
+}
@@ -0,0 +1,167 @@
+//! Macros wrapping futures in a `LoggedFuture`.
+
+/// Wraps a future like `LoggingExt::inspect`, unless the crate was built
+/// with the `allowlist` feature and `label` does not start with one of the
+/// prefixes listed in the `FUTURES_POLL_LOG_LABELS` environment variable at
+/// build time, separated by commas. Call sites left out evaluate to the
+/// future itself, so they cost nothing at runtime, like with the `silence`
+/// feature.
+///
+/// The label needs to be a constant expression, usually a string literal.
+/// Prefixes installed at runtime through `with_label_prefix()` are not
+/// taken into account.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate futures_poll_log;
+/// extern crate futures;
+///
+/// use futures::{Future, future};
+///
+/// # fn main() {
+/// // Built with `FUTURES_POLL_LOG_LABELS=db::,http`, only the first future
+/// // is logged.
+/// let query = inspect!(future::ok::<i32, ()>(1), "db::query");
+/// let render = inspect!(future::ok::<i32, ()>(2), "template");
+///
+/// assert_eq!(query.join(render).wait(), Ok((1, 2)));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! inspect {
+    ($future:expr, $label:expr $(,)*) => {{
+        const ALLOWED: bool = $crate::__private::allowed($label);
+        $crate::__private::Gate::<ALLOWED>::inspect($future, $label)
+    }};
+}
+
+/// Joins two to five futures like `Future::join` (and `join3` to `join5`),
+/// wrapping every branch in a `LoggedFuture` labeled with the text of its
+/// expression.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate futures_poll_log;
+/// extern crate futures;
+///
+/// use futures::{Future, future};
+///
+/// # fn main() {
+/// let left = future::ok::<i32, ()>(1);
+/// let right = future::ok::<i32, ()>(2);
+///
+/// assert_eq!(logged_join!(left, right).wait(), Ok((1, 2)));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! logged_join {
+    ($a:expr, $b:expr $(,)*) => {
+        $crate::__private::Future::join(
+            $crate::LoggingExt::inspect($a, stringify!($a)),
+            $crate::LoggingExt::inspect($b, stringify!($b)))
+    };
+    ($a:expr, $b:expr, $c:expr $(,)*) => {
+        $crate::__private::Future::join3(
+            $crate::LoggingExt::inspect($a, stringify!($a)),
+            $crate::LoggingExt::inspect($b, stringify!($b)),
+            $crate::LoggingExt::inspect($c, stringify!($c)))
+    };
+    ($a:expr, $b:expr, $c:expr, $d:expr $(,)*) => {
+        $crate::__private::Future::join4(
+            $crate::LoggingExt::inspect($a, stringify!($a)),
+            $crate::LoggingExt::inspect($b, stringify!($b)),
+            $crate::LoggingExt::inspect($c, stringify!($c)),
+            $crate::LoggingExt::inspect($d, stringify!($d)))
+    };
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr $(,)*) => {
+        $crate::__private::Future::join5(
+            $crate::LoggingExt::inspect($a, stringify!($a)),
+            $crate::LoggingExt::inspect($b, stringify!($b)),
+            $crate::LoggingExt::inspect($c, stringify!($c)),
+            $crate::LoggingExt::inspect($d, stringify!($d)),
+            $crate::LoggingExt::inspect($e, stringify!($e)))
+    };
+}
+
+/// Selects between two futures like `Future::select`, wrapping both
+/// branches in a `LoggedFuture` labeled with the text of its expression.
+///
+/// As with `Future::select`, both futures need to have the same `Item` and
+/// `Error` types.
+#[macro_export]
+macro_rules! logged_select {
+    ($a:expr, $b:expr $(,)*) => {
+        $crate::__private::Future::select(
+            $crate::LoggingExt::inspect($a, stringify!($a)),
+            $crate::LoggingExt::inspect($b, stringify!($b)))
+    };
+}
+
+/// Wraps every step of a chain of combinators in a `LoggedFuture`, labeled
+/// with the path of method names leading up to it: the receiver with the
+/// text of its expression, the result of the first combinator with that
+/// text and its name, and so on.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate futures_poll_log;
+/// extern crate futures;
+///
+/// use futures::{Future, future};
+///
+/// # fn main() {
+/// let request = future::ok::<u32, ()>(1);
+/// // Labeled `request`, `request.map`, `request.map.and_then` and
+/// // `request.map.and_then.then`.
+/// let response = inspect_chain! {
+///     request.map(|x| x + 1)
+///            .and_then(|x| future::ok(x * 2))
+///            .then(|result| result.map(|x| x + 1))
+/// };
+///
+/// assert_eq!(response.wait(), Ok(5));
+/// # }
+/// ```
+///
+/// Everything up to the first `.` is taken as the receiver, and every
+/// method call after it as a combinator returning a future. A receiver
+/// that is itself built through method calls, like `client.get(url)`,
+/// needs to be put in parentheses. Calls with turbofish generics are not
+/// recognized.
+#[macro_export]
+macro_rules! inspect_chain {
+    // Inspects the receiver once the first method call is reached.
+    (@receiver [$($receiver:tt)+] . $($calls:tt)+) => {
+        $crate::inspect_chain!(@calls [$($receiver)+] []
+            $crate::LoggingExt::inspect_static(($($receiver)+), stringify!($($receiver)+));
+            . $($calls)+)
+    };
+    (@receiver [$($receiver:tt)+]) => {
+        $crate::LoggingExt::inspect_static(($($receiver)+), stringify!($($receiver)+))
+    };
+    (@receiver [$($receiver:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::inspect_chain!(@receiver [$($receiver)* $next] $($rest)*)
+    };
+    // Inspects the result of every call, labeled with the names of the
+    // calls so far.
+    (@calls [$($receiver:tt)+] [$($method:ident)*] $future:expr;
+     . $next:ident ($($args:tt)*) $($rest:tt)*) => {
+        $crate::inspect_chain!(@calls [$($receiver)+] [$($method)* $next]
+            $crate::LoggingExt::inspect_static(
+                $future.$next($($args)*),
+                concat!(stringify!($($receiver)+) $(, ".", stringify!($method))*, ".", stringify!($next)));
+            $($rest)*)
+    };
+    (@calls [$($receiver:tt)+] [$($method:ident)*] $future:expr;) => {
+        $future
+    };
+    ($($chain:tt)+) => {
+        $crate::inspect_chain!(@receiver [] $($chain)+)
+    };
+}
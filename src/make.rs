@@ -0,0 +1,105 @@
+//! A factory of inspections sharing their options.
+
+use core::fmt::{Debug, Display};
+#[cfg(not(silenced))]
+use core::panic::Location;
+use futures::Future;
+
+#[cfg(not(silenced))]
+use future::LoggedFuture;
+#[cfg(not(silenced))]
+use label::Label;
+use options::Inspect;
+
+/// MakeLogged sets up inspections from the options it was created
+/// with, e.g. to be kept by a connection handler wrapping the future
+/// of every request the same way:
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_poll_log;
+/// # use futures::{Future, future};
+/// use futures_poll_log::{Inspect, Level, MakeLogged};
+///
+/// # fn main() {
+/// let make = MakeLogged::new(Inspect::new("request").level(Level::Info).timed());
+/// let _ = make.wrap(future::ok::<u32, ()>(1)).wait();
+/// // Logged as `request 7':
+/// let _ = make.wrap_with_suffix(future::ok::<u32, ()>(2), 7).wait();
+/// # }
+/// ```
+///
+/// The options, including the correlation id and the prefixes of the
+/// label, are taken as they were when the factory was created.
+/// Every future wrapped is a separate inspection with an instance
+/// number of its own.
+#[derive(Debug, Clone)]
+pub struct MakeLogged {
+    options: Inspect,
+}
+
+impl MakeLogged {
+    /// Creates a factory of inspections set up with `options`.
+    pub fn new(options: Inspect) -> MakeLogged {
+        MakeLogged { options }
+    }
+
+    /// Creates a factory of inspections labeled `label`, with the
+    /// defaults installed through `init()`.
+    #[track_caller]
+    pub fn with_label(label: &str) -> MakeLogged {
+        MakeLogged::new(Inspect::new(label))
+    }
+
+    /// The options every inspection is set up with.
+    pub fn options(&self) -> &Inspect {
+        &self.options
+    }
+
+    /// wrap() sets up the logging of `future` with the options of
+    /// the factory.
+    #[cfg(not(silenced))]
+    #[track_caller]
+    pub fn wrap<T, E, F>(&self, future: F) -> LoggedFuture<T, E, F>
+        where T: Debug,
+              E: Debug,
+              F: Future<Item = T, Error = E>
+    {
+        LoggedFuture::new(future, self.options.clone().located(Location::caller()))
+    }
+    /// wrap() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    pub fn wrap<T, E, F>(&self, future: F) -> F
+        where T: Debug,
+              E: Debug,
+              F: Future<Item = T, Error = E>
+    {
+        future
+    }
+
+    /// wrap_with_suffix() sets up the logging like `wrap()`, with
+    /// `suffix` appended to the label after a space, e.g. the id of
+    /// a connection.
+    #[cfg(not(silenced))]
+    #[track_caller]
+    pub fn wrap_with_suffix<T, E, F, S>(&self, future: F, suffix: S) -> LoggedFuture<T, E, F>
+        where T: Debug,
+              E: Debug,
+              F: Future<Item = T, Error = E>,
+              S: Display
+    {
+        let mut options = self.options.clone().located(Location::caller());
+        options.label = Label::from(alloc::format!("{} {}", options.label, suffix));
+        LoggedFuture::new(future, options)
+    }
+    /// wrap_with_suffix() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    pub fn wrap_with_suffix<T, E, F, S>(&self, future: F, _: S) -> F
+        where T: Debug,
+              E: Debug,
+              F: Future<Item = T, Error = E>,
+              S: Display
+    {
+        future
+    }
+}
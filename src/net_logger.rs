@@ -0,0 +1,209 @@
+//! A backend shipping poll events to a remote collector.
+
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread;
+use std::time::Duration;
+
+use json::write_record;
+use logger::{PollEvent, PollLogger};
+use record::EventRecord;
+
+/// How events get to the collector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    /// One datagram per batch.
+    Udp,
+    /// A stream of lines over a connection, reopened after
+    /// failures.
+    Tcp,
+}
+
+/// A PollLogger that sends every event as a line of JSON to a
+/// remote collector, over UDP or TCP, e.g. to follow a headless
+/// device whose local logs are out of reach:
+///
+/// ```rust,no_run
+/// # extern crate futures_poll_log;
+/// use std::sync::Arc;
+/// use futures_poll_log::NetworkLogger;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let logger = NetworkLogger::udp("192.168.1.10:9999")?.batch_size(32);
+/// futures_poll_log::set_poll_logger(Arc::new(logger));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// `nc -klu 9999` on the collecting machine prints the events.
+///
+/// Events are queued and sent in batches from a thread of the
+/// backend, started with the first event, so polls never wait for
+/// the network. When the queue is full, further events are dropped
+/// and counted, see `dropped()`. The thread sends what is left and
+/// exits once the backend is dropped.
+///
+/// This backend is available with the `net` feature.
+#[derive(Debug)]
+pub struct NetworkLogger {
+    transport: Transport,
+    addr: SocketAddr,
+    batch_size: usize,
+    capacity: usize,
+    flush_interval: Duration,
+    sender: OnceLock<SyncSender<EventRecord>>,
+    dropped: AtomicU64,
+}
+
+impl NetworkLogger {
+    /// Creates a backend sending datagrams to `addr`. A batch that
+    /// does not fit into a single datagram is lost.
+    pub fn udp<A: ToSocketAddrs>(addr: A) -> io::Result<NetworkLogger> {
+        NetworkLogger::new(Transport::Udp, addr)
+    }
+
+    /// Creates a backend streaming to `addr`. The connection is
+    /// opened with the first batch, and opened again for the next
+    /// one if sending fails.
+    pub fn tcp<A: ToSocketAddrs>(addr: A) -> io::Result<NetworkLogger> {
+        NetworkLogger::new(Transport::Tcp, addr)
+    }
+
+    fn new<A: ToSocketAddrs>(transport: Transport, addr: A) -> io::Result<NetworkLogger> {
+        let addr = addr.to_socket_addrs()?
+                       .next()
+                       .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to send events to"))?;
+        Ok(NetworkLogger {
+            transport,
+            addr,
+            batch_size: 64,
+            capacity: 1024,
+            flush_interval: Duration::from_millis(100),
+            sender: OnceLock::new(),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Sends at most `size` events at once, 64 by default.
+    pub fn batch_size(mut self, size: usize) -> NetworkLogger {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// Queues at most `capacity` events waiting to be sent, 1024
+    /// by default.
+    pub fn capacity(mut self, capacity: usize) -> NetworkLogger {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sends an incomplete batch once its first event waited for
+    /// `interval`, 100ms by default.
+    pub fn flush_interval(mut self, interval: Duration) -> NetworkLogger {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// The number of events dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn start(&self) -> SyncSender<EventRecord> {
+        let (sender, receiver) = mpsc::sync_channel(self.capacity);
+        let mut connection = Connection {
+            transport: self.transport,
+            addr: self.addr,
+            udp: None,
+            tcp: None,
+        };
+        let (batch_size, flush_interval) = (self.batch_size, self.flush_interval);
+        // If the thread cannot be spawned, the queue is disconnected
+        // and all events are counted as dropped.
+        let _ = thread::Builder::new().name("futures-poll-log-net".into()).spawn(move || {
+            let mut batch = String::new();
+            let mut count = 0;
+            loop {
+                let received = if count == 0 {
+                    receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                } else {
+                    receiver.recv_timeout(flush_interval)
+                };
+                match received {
+                    Ok(record) => {
+                        write_record(&mut batch, &record);
+                        count += 1;
+                        if count < batch_size {
+                            continue;
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        if count > 0 {
+                            connection.send(batch.as_bytes());
+                        }
+                        return;
+                    }
+                }
+                connection.send(batch.as_bytes());
+                batch.clear();
+                count = 0;
+            }
+        });
+        sender
+    }
+}
+
+impl PollLogger for NetworkLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let sender = self.sender.get_or_init(|| self.start());
+        match sender.try_send(event.to_record()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// The socket of the sending thread, opened on demand.
+struct Connection {
+    transport: Transport,
+    addr: SocketAddr,
+    udp: Option<UdpSocket>,
+    tcp: Option<TcpStream>,
+}
+
+impl Connection {
+    /// Sends `batch`, losing it if that fails: there is nowhere
+    /// to report the error.
+    fn send(&mut self, batch: &[u8]) {
+        let sent = match self.transport {
+            Transport::Udp => self.send_udp(batch),
+            Transport::Tcp => self.send_tcp(batch),
+        };
+        if sent.is_err() {
+            self.tcp = None;
+        }
+    }
+
+    fn send_udp(&mut self, batch: &[u8]) -> io::Result<()> {
+        if self.udp.is_none() {
+            let local: SocketAddr = if self.addr.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+            self.udp = Some(UdpSocket::bind(local)?);
+        }
+        let socket = self.udp.as_ref().expect("bound above");
+        socket.send_to(batch, self.addr).map(|_| ())
+    }
+
+    fn send_tcp(&mut self, batch: &[u8]) -> io::Result<()> {
+        if self.tcp.is_none() {
+            self.tcp = Some(TcpStream::connect(self.addr)?);
+        }
+        let stream = self.tcp.as_mut().expect("connected above");
+        stream.write_all(batch)
+    }
+}
@@ -0,0 +1,974 @@
+//! The options of a single inspection.
+
+use alloc::borrow::Cow;
+#[cfg(not(silenced))]
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+use core::panic::Location;
+use core::time::Duration;
+
+use config::{self, Timestamps};
+use context::{self, Field};
+#[cfg(feature="std")]
+use handle::InspectHandle;
+use hook::{Hooks, PollHook};
+use label::{Label, TypedLabel};
+#[cfg(all(feature="std", not(silenced)))]
+use prefix;
+use logger::{Backend, Outcome, PollLogger};
+#[cfg(feature="std")]
+use timing::Clock;
+
+/// The level at which events are written.
+///
+/// Events warning about a problem (e.g. a missed deadline) are
+/// written at `Warn` unless the configured level is `Error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// The `error` level.
+    Error,
+    /// The `warn` level.
+    Warn,
+    /// The `info` level.
+    Info,
+    /// The `debug` level, used by default.
+    Debug,
+    /// The `trace` level.
+    Trace,
+}
+
+/// What a `LoggedFuture` directly wrapping another one logs, see
+/// `Inspect::nesting`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nesting {
+    /// Both log every poll, as if they wrapped different futures.
+    Log,
+    /// Only the inner one logs, the default.
+    Collapse,
+    /// Only the inner one logs, with both labels joined like
+    /// `outer/inner`. Without `std`, like `Collapse`.
+    Merge,
+}
+
+/// The levels of events by the result they report, each
+/// overriding the level of the inspection if set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct OutcomeLevels {
+    pub(crate) not_ready: Option<Level>,
+    pub(crate) ready: Option<Level>,
+    pub(crate) err: Option<Level>,
+}
+
+impl OutcomeLevels {
+    pub(crate) const fn new() -> OutcomeLevels {
+        OutcomeLevels {
+            not_ready: None,
+            ready: None,
+            err: None,
+        }
+    }
+
+    /// The level for events reporting `outcome`, if set.
+    #[cfg_attr(silenced, allow(dead_code))]
+    pub(crate) fn get(&self, outcome: &Outcome) -> Option<Level> {
+        match *outcome {
+            Outcome::NotReady => self.not_ready,
+            Outcome::Ready(_) => self.ready,
+            Outcome::Err(_) => self.err,
+        }
+    }
+}
+
+/// Inspect collects the options for an inspection started
+/// through `inspect_with_opts` and its stream and sink
+/// counterparts.
+///
+/// # Examples
+///
+/// ```rust
+/// extern crate futures;
+/// extern crate futures_poll_log;
+///
+/// use futures::{Future, future};
+/// use futures_poll_log::{Inspect, Level, LoggingExt};
+///
+/// # fn main() {
+/// let _ = future::ok::<i32, ()>(3)
+///     .inspect_with_opts(Inspect::new("label").level(Level::Trace).timed().sample(10))
+///     .wait();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct Inspect {
+    pub(crate) label: Label,
+    pub(crate) correlation: Option<Arc<str>>,
+    pub(crate) context: Option<Arc<[Field]>>,
+    pub(crate) level: Level,
+    pub(crate) outcome_levels: OutcomeLevels,
+    pub(crate) target: Cow<'static, str>,
+    pub(crate) timed: bool,
+    pub(crate) timestamps: Timestamps,
+    pub(crate) relative_timestamps: bool,
+    pub(crate) poll_gaps: bool,
+    pub(crate) max_poll_gap: Option<Duration>,
+    pub(crate) max_polls: Option<u64>,
+    pub(crate) truncate: Option<usize>,
+    pub(crate) pretty: bool,
+    pub(crate) single_line: bool,
+    pub(crate) format: Option<Arc<str>>,
+    pub(crate) max_depth: Option<usize>,
+    /// Where the inspection was set up, as seen by the
+    /// `#[track_caller]` constructors.
+    pub(crate) location: &'static Location<'static>,
+    pub(crate) show_location: bool,
+    pub(crate) sample: u64,
+    pub(crate) max_events: Option<u64>,
+    pub(crate) coalesce: Option<u64>,
+    pub(crate) redact: bool,
+    pub(crate) summary: bool,
+    pub(crate) summary_only: bool,
+    pub(crate) log_size: bool,
+    pub(crate) log_first_poll: bool,
+    pub(crate) log_is_terminated: bool,
+    pub(crate) log_size_hint: bool,
+    pub(crate) track_wakers: bool,
+    pub(crate) log_wakers: bool,
+    pub(crate) backend: Backend,
+    pub(crate) log: bool,
+    pub(crate) nesting: Nesting,
+    /// Whether the value inspected is another inspection that
+    /// logs in place of this one.
+    pub(crate) nested: bool,
+    pub(crate) hooks: Hooks,
+    #[cfg(feature="std")]
+    pub(crate) deadline: Option<Duration>,
+    #[cfg(feature="std")]
+    pub(crate) executor: bool,
+    #[cfg(feature="std")]
+    pub(crate) catch_panics: bool,
+    #[cfg(feature="std")]
+    pub(crate) warn_on_thread_change: bool,
+    #[cfg(feature="std")]
+    pub(crate) ready_budget: Option<u32>,
+    #[cfg(feature="std")]
+    pub(crate) measure_overhead: bool,
+    #[cfg(feature="std")]
+    pub(crate) clock: Option<&'static dyn Clock>,
+    #[cfg(feature="std")]
+    pub(crate) metrics: bool,
+    #[cfg(feature="std")]
+    pub(crate) handle: Option<InspectHandle>,
+    #[cfg(feature="std")]
+    pub(crate) group: Option<Arc<str>>,
+    #[cfg(feature="allocations")]
+    pub(crate) track_allocations: bool,
+    #[cfg(feature="std")]
+    pub(crate) cpu_time: bool,
+    #[cfg(feature="std")]
+    pub(crate) slow_motion: Option<SlowMotion>,
+}
+
+/// SlowMotion holds an inspection back before every poll, to watch
+/// its polls unfold one at a time, see `Inspect::slow_motion`.
+///
+/// This enum is only available with the `std` feature.
+#[cfg(feature="std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlowMotion {
+    /// Puts the polling thread to sleep for the given time before
+    /// every poll. The sleep is left out of the poll durations.
+    Sleep(Duration),
+    /// Returns `NotReady` without polling before every poll, and
+    /// wakes the task up again the given time later on the clock of
+    /// the inspection, from a timer thread shared by all inspections.
+    /// The executor stays free to run other tasks in the meantime.
+    NotReady(Duration),
+}
+
+/// A Stage handles one concern of an inspection set up through
+/// `Inspect::pipeline`.
+#[derive(Clone)]
+pub enum Stage {
+    /// Measures the duration of every poll, like `Inspect::timed`.
+    Timing,
+    /// Writes the events to the backend.
+    Logging,
+    /// Adds the inspection to the statistics of its label, even if
+    /// `stats::enable()` was not called. Does nothing without the
+    /// `std` feature.
+    Metrics,
+    /// Runs a `PollHook` around every poll.
+    Hook(Arc<dyn PollHook>),
+}
+
+impl fmt::Debug for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Stage::Timing => f.write_str("Timing"),
+            Stage::Logging => f.write_str("Logging"),
+            Stage::Metrics => f.write_str("Metrics"),
+            Stage::Hook(_) => f.write_str("Hook(..)"),
+        }
+    }
+}
+
+impl Inspect {
+    /// Starts a set of options for an inspection identified
+    /// by `label`, with the defaults installed through `init()`.
+    #[track_caller]
+    pub fn new(label: &str) -> Inspect {
+        Inspect::labeled(Inspect::copied(label))
+    }
+
+    /// Starts a set of options like `new()`, keeping a reference to
+    /// `label` instead of copying it. Unless prefixes apply, setting
+    /// up the inspection allocates nothing for the label.
+    #[track_caller]
+    pub fn new_static(label: &'static str) -> Inspect {
+        Inspect::labeled(Label::Static(label))
+    }
+
+    /// Starts a set of options like `new()`, with a label written by
+    /// `label` the first time it is needed, e.g. by an event that is
+    /// logged. Labels carrying runtime data cost nothing this way
+    /// while their inspection stays quiet:
+    ///
+    /// ```rust
+    /// use futures_poll_log::Inspect;
+    ///
+    /// let (id, host) = (42, "example.com");
+    /// let options = Inspect::new_fmt(move |f| write!(f, "request {} to {}", id, host));
+    /// # drop(options);
+    /// ```
+    ///
+    /// Without `std`, the label is written right away.
+    #[track_caller]
+    pub fn new_fmt<L>(label: L) -> Inspect
+        where L: Fn(&mut fmt::Formatter) -> fmt::Result + Send + Sync + 'static
+    {
+        Inspect::labeled(Inspect::formatted(label))
+    }
+
+    /// Starts a set of options like `new()`, with a label written from
+    /// `label` through `Display` the first time it is needed. `label`
+    /// itself is kept, so symbols or enums of labels need no
+    /// conversion, and backends get it back through
+    /// `PollEvent::label_as`. The wrapper set up from these options
+    /// is labeled by a `String` in its type, so its `typed_label()` is
+    /// `None`; `LoggingExt::inspect_typed` keeps the type:
+    ///
+    /// ```rust
+    /// use std::fmt;
+    /// use futures_poll_log::Inspect;
+    ///
+    /// #[derive(Debug, Clone, Copy, PartialEq)]
+    /// enum Query {
+    ///     Users,
+    ///     Orders,
+    /// }
+    ///
+    /// impl fmt::Display for Query {
+    ///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    ///         fmt::Debug::fmt(self, f)
+    ///     }
+    /// }
+    ///
+    /// let options = Inspect::new_typed(Query::Users);
+    /// # drop(options);
+    /// ```
+    ///
+    /// Without `std`, the label is written right away and not kept.
+    #[track_caller]
+    pub fn new_typed<L>(label: L) -> Inspect
+        where L: fmt::Display + Send + Sync + 'static
+    {
+        Inspect::new_typed_label(&Arc::new(TypedLabel::new(label)))
+    }
+
+    /// Starts a set of options like `new_typed()`, sharing `typed`
+    /// with the wrapper.
+    #[track_caller]
+    pub(crate) fn new_typed_label<L>(typed: &Arc<TypedLabel<L>>) -> Inspect
+        where L: fmt::Display + Send + Sync + 'static
+    {
+        Inspect::labeled(Inspect::typed(typed))
+    }
+
+    #[track_caller]
+    fn labeled(label: Label) -> Inspect {
+        let defaults = config::current();
+        Inspect {
+            label: Inspect::prefixed(label),
+            correlation: context::correlation(),
+            context: None,
+            level: defaults.level,
+            outcome_levels: defaults.outcome_levels,
+            target: defaults.target,
+            timed: false,
+            timestamps: defaults.timestamps,
+            relative_timestamps: defaults.relative_timestamps,
+            poll_gaps: false,
+            max_poll_gap: None,
+            max_polls: None,
+            truncate: defaults.truncate,
+            pretty: false,
+            single_line: defaults.single_line,
+            format: defaults.format,
+            max_depth: defaults.max_depth,
+            location: Location::caller(),
+            show_location: defaults.caller_locations,
+            sample: 1,
+            max_events: None,
+            coalesce: None,
+            redact: false,
+            summary: false,
+            summary_only: false,
+            log_size: false,
+            log_first_poll: false,
+            log_is_terminated: false,
+            log_size_hint: false,
+            track_wakers: false,
+            log_wakers: false,
+            backend: Backend::default(),
+            log: true,
+            nesting: Nesting::Collapse,
+            nested: false,
+            hooks: Hooks::default(),
+            #[cfg(feature="std")]
+            deadline: None,
+            #[cfg(feature="std")]
+            executor: false,
+            #[cfg(feature="std")]
+            catch_panics: false,
+            #[cfg(feature="std")]
+            warn_on_thread_change: false,
+            #[cfg(feature="std")]
+            ready_budget: None,
+            #[cfg(feature="std")]
+            measure_overhead: false,
+            #[cfg(feature="std")]
+            clock: None,
+            #[cfg(feature="std")]
+            metrics: false,
+            #[cfg(feature="std")]
+            handle: None,
+            #[cfg(feature="std")]
+            group: None,
+            #[cfg(feature="allocations")]
+            track_allocations: false,
+            #[cfg(feature="std")]
+            cpu_time: false,
+            #[cfg(feature="std")]
+            slow_motion: None,
+        }
+    }
+
+    // Nothing is ever logged when silenced, so skip copying the label.
+    #[cfg(silenced)]
+    fn copied(_: &str) -> Label {
+        Label::Static("")
+    }
+    #[cfg(not(silenced))]
+    fn copied(label: &str) -> Label {
+        Label::copied(label)
+    }
+
+    #[cfg(silenced)]
+    fn formatted<L>(_: L) -> Label {
+        Label::Static("")
+    }
+    #[cfg(not(silenced))]
+    fn formatted<L>(label: L) -> Label
+        where L: Fn(&mut fmt::Formatter) -> fmt::Result + Send + Sync + 'static
+    {
+        Label::lazy(Box::new(label))
+    }
+
+    #[cfg(silenced)]
+    fn typed<L>(_: &Arc<TypedLabel<L>>) -> Label {
+        Label::Static("")
+    }
+    #[cfg(not(silenced))]
+    fn typed<L>(typed: &Arc<TypedLabel<L>>) -> Label
+        where L: fmt::Display + Send + Sync + 'static
+    {
+        Label::typed(typed)
+    }
+
+    #[cfg(all(feature="std", not(silenced)))]
+    fn prefixed(label: Label) -> Label {
+        prefix::apply(label)
+    }
+    #[cfg(any(not(feature="std"), silenced))]
+    fn prefixed(label: Label) -> Label {
+        label
+    }
+
+    /// Prepends `prefix` to the label, in front of the prefixes
+    /// of `with_label_prefix()`, e.g. for a subsystem setting up
+    /// its inspections in one place.
+    pub fn label_prefix(mut self, prefix: &str) -> Inspect {
+        if !cfg!(silenced) {
+            let label = mem::replace(&mut self.label, Label::Static(""));
+            self.label = Label::prefixed(prefix, label);
+        }
+        self
+    }
+
+    /// Tags all events with the correlation id `id`, e.g. the id
+    /// of a request. Inspections started while the inspected value
+    /// is being polled inherit the id, so it follows a request into
+    /// all futures created on its behalf.
+    ///
+    /// Without `std`, the id is not inherited.
+    pub fn correlation(mut self, id: &str) -> Inspect {
+        self.correlation = Some(Arc::from(id));
+        self
+    }
+
+    /// Puts the inspection into `group`, e.g. `db` for all queries,
+    /// whatever their labels. Statistics are aggregated per group as
+    /// well, see `stats::group_snapshot()`, and levels and rate limits
+    /// can be set for the whole group, see `set_group_level()` and
+    /// `set_group_rate_limit()`.
+    ///
+    /// This method is only available with the `std` feature.
+    #[cfg(feature="std")]
+    pub fn group(mut self, group: &str) -> Inspect {
+        self.group = Some(Arc::from(group));
+        self
+    }
+
+    /// Adds `key` and `value` to the diagnostic context of all
+    /// events of this inspection, and of the inspections it polls
+    /// while being polled. A key given again replaces the value,
+    /// see `push_context()`.
+    pub fn context(mut self, key: &str, value: &str) -> Inspect {
+        let mut fields = self.context.as_deref().map_or_else(Vec::new, <[Field]>::to_vec);
+        match fields.iter_mut().find(|field| &*field.0 == key) {
+            Some(field) => field.1 = Arc::from(value),
+            None => fields.push((Arc::from(key), Arc::from(value))),
+        }
+        self.context = Some(Arc::from(fields));
+        self
+    }
+
+    /// Writes events at `level`.
+    pub fn level(mut self, level: Level) -> Inspect {
+        self.level = level;
+        self
+    }
+
+    /// Writes events reporting a `NotReady` poll at `level`,
+    /// instead of the level of the inspection.
+    pub fn not_ready_level(mut self, level: Level) -> Inspect {
+        self.outcome_levels.not_ready = Some(level);
+        self
+    }
+
+    /// Writes events reporting a `Ready` poll, e.g. a resolved
+    /// Future or a stream item, at `level`, instead of the level
+    /// of the inspection.
+    pub fn ready_level(mut self, level: Level) -> Inspect {
+        self.outcome_levels.ready = Some(level);
+        self
+    }
+
+    /// Writes events reporting an error at `level`, instead of
+    /// the level of the inspection.
+    pub fn error_level(mut self, level: Level) -> Inspect {
+        self.outcome_levels.err = Some(level);
+        self
+    }
+
+    /// Writes events to the log target `target`. Only backends
+    /// with a notion of targets, like `LogLogger`, make use of it.
+    pub fn target(mut self, target: &str) -> Inspect {
+        self.target = Cow::Owned(target.into());
+        self
+    }
+
+    /// Measures the time spent in every poll and includes it
+    /// in the events reporting the poll's result.
+    ///
+    /// Durations are only measured with the `std` feature.
+    pub fn timed(mut self) -> Inspect {
+        self.timed = true;
+        self
+    }
+
+    /// Stamps events as given by `timestamps`.
+    ///
+    /// Timestamps are only available with the `std` feature.
+    pub fn timestamps(mut self, timestamps: Timestamps) -> Inspect {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Stamps events with the time since the inspection was set
+    /// up, like `[t+14.2ms]`, after the timestamp configured
+    /// through `timestamps()` if any. This reads more easily than
+    /// absolute times when following a single future.
+    ///
+    /// The time is only measured with the `std` feature.
+    pub fn relative_timestamps(mut self) -> Inspect {
+        self.relative_timestamps = true;
+        self
+    }
+
+    /// Stamps the events of every poll but the first with the time
+    /// since the previous poll ended, like `[idle 2.1ms]`. A future
+    /// starved by its executor looks healthy otherwise, with every
+    /// single poll being quick.
+    ///
+    /// The time is only measured with the `std` feature.
+    pub fn poll_gaps(mut self) -> Inspect {
+        self.poll_gaps = true;
+        self
+    }
+
+    /// Warns when the inspected value was left idle for longer than
+    /// `max` between two polls, see `poll_gaps()`.
+    ///
+    /// The time is only measured with the `std` feature.
+    pub fn warn_poll_gap(mut self, max: Duration) -> Inspect {
+        self.max_poll_gap = Some(max);
+        self
+    }
+
+    /// Warns when the inspected value was polled more than `n` times
+    /// in a row without being ready, naming its instance and age. A
+    /// task that keeps waking itself up shows up this way instead of
+    /// just eating CPU.
+    ///
+    /// Streams and sinks start counting again after each item they
+    /// are ready for.
+    pub fn warn_poll_storm(mut self, n: u64) -> Inspect {
+        self.max_polls = Some(n);
+        self
+    }
+
+    /// Cuts items and errors printed in messages to at most
+    /// `len` characters.
+    pub fn truncate(mut self, len: usize) -> Inspect {
+        self.truncate = Some(len);
+        self
+    }
+
+    /// Prints items and errors with `{:#?}` instead of `{:?}`, over
+    /// several indented lines, which keeps deeply nested values
+    /// readable.
+    pub fn pretty_values(mut self) -> Inspect {
+        self.pretty = true;
+        self
+    }
+
+    /// Keeps every message on a single line, for shippers reading
+    /// logs line by line: line breaks in items, errors, labels and
+    /// panic messages are collapsed into a space, together with
+    /// the whitespace around them. Values printed through
+    /// `pretty_values()` are collapsed as well.
+    pub fn single_line(mut self) -> Inspect {
+        self.single_line = true;
+        self
+    }
+
+    /// Formats messages by `template` instead of the built-in
+    /// sentences. The template is copied as is, except for these
+    /// placeholders:
+    ///
+    /// * `{label}`, `{instance}` and `{n}` (or `{poll}`): the label,
+    ///   instance number and poll number.
+    /// * `{seq}`: the number of the event within the process, see
+    ///   `PollEvent::sequence`.
+    /// * `{kind}`: the name of the event, see `EventKind::name`.
+    /// * `{outcome}`: the result reported, e.g. `Ok(NotReady)`, or
+    ///   the name of the event if it reports none.
+    /// * `{elapsed}` and `{timestamp}`: the duration of the poll and
+    ///   the timestamp in seconds, or nothing if not measured.
+    /// * `{age}`: the time since the inspection was set up, like
+    ///   `t+14.2ms`, with `relative_timestamps()`.
+    /// * `{gap}`: the time since the previous poll ended, with
+    ///   `poll_gaps()`.
+    /// * `{level}`, `{parent}` and `{correlation}`: the level, the
+    ///   label of the polling inspection and the correlation id.
+    /// * `{spawned_by}`: the label and instance of the inspection
+    ///   that was polled when this one was set up, like `server (#2)`.
+    /// * `{context}`: the diagnostic context as `key=value` pairs.
+    /// * `{category}`: the category of the error reported, see
+    ///   `LoggingExt::inspect_classified`.
+    /// * `{location}`: the file and line the inspection was set up
+    ///   at, like `src/main.rs:12`, with `caller_location()`.
+    /// * `{message}`: the built-in sentence.
+    ///
+    /// `{{` and `}}` stand for literal braces.
+    ///
+    /// ```rust
+    /// use futures_poll_log::Inspect;
+    ///
+    /// let options = Inspect::new("request").timed()
+    ///     .format("{label} [{instance}] poll {n}: {outcome} ({elapsed})");
+    /// ```
+    pub fn format(mut self, template: &str) -> Inspect {
+        self.format = Some(Arc::from(template));
+        self
+    }
+
+    /// Only writes events while this inspection is polled from
+    /// within fewer than `depth` other inspections, keeping the
+    /// output of deeply nested chains to their outermost layers.
+    /// Warnings are always written.
+    ///
+    /// Nesting is only tracked with the `std` feature.
+    pub fn max_depth(mut self, depth: usize) -> Inspect {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Writes the file and line the inspection was set up at into
+    /// its events, like `` Polling future `x' (at src/main.rs:12)``.
+    /// The `inspect*` methods and the other constructors of logged
+    /// values are `#[track_caller]`, so this is where they are
+    /// called, or where these options were created for
+    /// `inspect_with_opts`. `Config::caller_locations` enables it
+    /// for all inspections.
+    pub fn caller_location(mut self) -> Inspect {
+        self.show_location = true;
+        self
+    }
+
+    /// Moves the location of the inspection to `location`, for
+    /// inspections set up later on behalf of a caller.
+    #[cfg_attr(silenced, allow(dead_code))]
+    pub(crate) fn located(mut self, location: &'static Location<'static>) -> Inspect {
+        self.location = location;
+        self
+    }
+
+    /// Only logs every `n`th poll. Results ending the inspected
+    /// value and warnings are always logged.
+    pub fn sample(mut self, n: u64) -> Inspect {
+        self.sample = if n == 0 { 1 } else { n };
+        self
+    }
+
+    /// Writes at most `n` events of this inspection, then a notice
+    /// that further ones are suppressed. Its final result, like the
+    /// `Ready` of a future or the end of a stream, and warnings are
+    /// still written, so a future polled over and over cannot flood
+    /// the log.
+    pub fn max_events(mut self, n: u64) -> Inspect {
+        self.max_events = Some(n);
+        self
+    }
+
+    /// Writes only the first of consecutive polls returning
+    /// `NotReady`, and for the others a line like ``
+    /// `x' still NotReady (polled 37 more times)`` after every `n`
+    /// of them and before the poll that ends the streak.
+    ///
+    /// Applies to futures and streams.
+    pub fn coalesce_not_ready(mut self, n: u64) -> Inspect {
+        self.coalesce = Some(n.max(1));
+        self
+    }
+
+    /// Replaces all items and errors with `<redacted>` in the
+    /// events, for values that must not end up in logs. The `redact`
+    /// feature does so for all inspections.
+    pub fn redact(mut self) -> Inspect {
+        self.redact = true;
+        self
+    }
+
+    /// Reports a summary of the Future once it is dropped: its
+    /// instance number, polls, the time it was pending and whether
+    /// it completed, errored or was cancelled.
+    ///
+    /// Only applies to futures.
+    pub fn summary(mut self) -> Inspect {
+        self.summary = true;
+        self
+    }
+
+    /// Reports the summary like `summary()`, but leaves out
+    /// all other events except for warnings.
+    ///
+    /// Only applies to futures.
+    pub fn summary_only(mut self) -> Inspect {
+        self.summary = true;
+        self.summary_only = true;
+        self
+    }
+
+    /// Logs the size of the wrapped Future in memory on the first
+    /// poll, to spot futures bloated by deeply nested combinators.
+    ///
+    /// Only applies to futures.
+    pub fn log_size(mut self) -> Inspect {
+        self.log_size = true;
+        self
+    }
+
+    /// Logs how long the inspected value waited between being set
+    /// up and its first poll, which points at an overloaded
+    /// executor or a future that was never spawned. The wait is
+    /// also collected in the statistics, with or without this
+    /// option.
+    ///
+    /// The wait is only measured with the `std` feature.
+    pub fn log_first_poll(mut self) -> Inspect {
+        self.log_first_poll = true;
+        self
+    }
+
+    /// Logs every call to `FusedFuture::is_terminated` with its
+    /// result, to see how `select!` treats the Future.
+    ///
+    /// Only applies to futures wrapped through the `futures03`
+    /// module, which is available with the `futures03` feature.
+    pub fn log_is_terminated(mut self) -> Inspect {
+        self.log_is_terminated = true;
+        self
+    }
+
+    /// Logs the `size_hint()` of the Stream when it is wrapped and
+    /// whenever it changes, to compare the advertised size against
+    /// the items it yields.
+    ///
+    /// Only applies to streams wrapped through the `futures03`
+    /// module, as futures 0.1 streams have no size hint.
+    pub fn log_size_hint(mut self) -> Inspect {
+        self.log_size_hint = true;
+        self
+    }
+
+    /// Counts the clones of the wakers the Future receives, and
+    /// warns if any of them are still alive when the Future
+    /// completes or is dropped. Leaked wakers keep the resources of
+    /// their task alive.
+    ///
+    /// Only applies to futures 0.3, see the `futures03` module.
+    pub fn track_wakers(mut self) -> Inspect {
+        self.track_wakers = true;
+        self
+    }
+
+    /// Logs a fingerprint of the waker on every poll, the same for
+    /// all clones of a waker, and notes when it changed since the
+    /// last poll. Wakeups are logged with the fingerprint of the
+    /// waker used, on the poll that follows them. A Future woken
+    /// through an older waker than the one of its last poll may
+    /// not have registered the latest one.
+    ///
+    /// Only applies to futures 0.3, see the `futures03` module.
+    pub fn log_wakers(mut self) -> Inspect {
+        self.log_wakers = true;
+        self
+    }
+
+    /// Sets what this inspection logs if it directly wraps another
+    /// `LoggedFuture`, e.g. one returned by a helper that inspects
+    /// everything it returns. By default, only the inner one logs,
+    /// so wrapping twice does not double the events:
+    ///
+    /// ```rust
+    /// extern crate futures;
+    /// extern crate futures_poll_log;
+    ///
+    /// use futures::{Future, future};
+    /// use futures_poll_log::{Inspect, LoggingExt, Nesting};
+    ///
+    /// # fn main() {
+    /// let fetch = future::ok::<i32, ()>(3).inspect("fetch");
+    /// // Logged as `request/fetch'.
+    /// let _ = fetch.inspect_with_opts(Inspect::new("request").nesting(Nesting::Merge)).wait();
+    /// # }
+    /// ```
+    ///
+    /// Only a `LoggedFuture` wrapping another one without any
+    /// combinator in between is detected.
+    pub fn nesting(mut self, nesting: Nesting) -> Inspect {
+        self.nesting = nesting;
+        self
+    }
+
+    /// Sends the events to `logger` instead of the globally
+    /// installed backend.
+    pub fn logger(mut self, logger: Arc<dyn PollLogger>) -> Inspect {
+        self.backend = Backend::new(Some(logger));
+        self
+    }
+
+    /// Sets up the inspection as a pipeline of `stages`, each
+    /// handling one concern of the same poll: only the listed
+    /// stages are active, so an inspection without `Stage::Logging`
+    /// writes nothing. See `LoggingExt::inspect_pipeline`.
+    pub fn pipeline(mut self, stages: &[Stage]) -> Inspect {
+        self.log = false;
+        for stage in stages {
+            match *stage {
+                Stage::Timing => self.timed = true,
+                Stage::Logging => self.log = true,
+                #[cfg(feature="std")]
+                Stage::Metrics => self.metrics = true,
+                #[cfg(not(feature="std"))]
+                Stage::Metrics => {}
+                Stage::Hook(ref hook) => self.hooks.push(hook.clone()),
+            }
+        }
+        self
+    }
+
+    /// Runs `hook` around every poll of this inspection, before
+    /// the hooks registered through `add_hook()`. Can be given
+    /// several times.
+    pub fn hook(mut self, hook: Arc<dyn PollHook>) -> Inspect {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Logs a warning the first time the Future is still
+    /// `NotReady` after `duration` has passed since the inspection
+    /// started. The Future is not cancelled when the deadline
+    /// passes.
+    ///
+    /// Only applies to futures, and is only available with the
+    /// `std` feature.
+    #[cfg(feature="std")]
+    pub fn deadline(mut self, duration: Duration) -> Inspect {
+        self.deadline = Some(duration);
+        self
+    }
+
+    /// Logs which thread polls the Future, on the first poll and
+    /// whenever it changes.
+    ///
+    /// Only applies to futures, and is only available with the
+    /// `std` feature.
+    #[cfg(feature="std")]
+    pub fn executor(mut self) -> Inspect {
+        self.executor = true;
+        self
+    }
+
+    /// Logs an error naming the Future when its poll panics,
+    /// before the panic continues.
+    ///
+    /// Only applies to futures, and is only available with the
+    /// `std` feature.
+    #[cfg(feature="std")]
+    pub fn catch_panics(mut self) -> Inspect {
+        self.catch_panics = true;
+        self
+    }
+
+    /// Writes the notice about the Future being polled from a
+    /// different thread than before as a warning, for futures
+    /// that are not expected to move between threads.
+    ///
+    /// Only applies to futures, and is only available with the
+    /// `std` feature.
+    #[cfg(feature="std")]
+    pub fn warn_on_thread_change(mut self) -> Inspect {
+        self.warn_on_thread_change = true;
+        self
+    }
+
+    /// Logs a warning when the Future resolves after more than `n`
+    /// inspected futures in a row resolved within the same poll of
+    /// the task, which hints at a missing yield point. Futures
+    /// returning `NotReady` end the streak.
+    ///
+    /// Only applies to futures, and is only available with the
+    /// `std` feature.
+    #[cfg(feature="std")]
+    pub fn ready_budget(mut self, n: u32) -> Inspect {
+        self.ready_budget = Some(n);
+        self
+    }
+
+    /// Logs the bytes and the number of allocations of every poll
+    /// that allocated anything, as counted by the
+    /// `allocations::CountingAllocator` installed as the global
+    /// allocator.
+    ///
+    /// This method is only available with the `allocations` feature.
+    #[cfg(feature="allocations")]
+    pub fn track_allocations(mut self) -> Inspect {
+        self.track_allocations = true;
+        self
+    }
+
+    /// Logs the CPU time every poll consumed next to the wall time
+    /// it took, telling a poll that waited, e.g. on blocking I/O or
+    /// a lock, from one that kept the CPU busy. The CPU time is read
+    /// from the clock of the polling thread with the `cpu-time`
+    /// feature, on Linux, Android, macOS and FreeBSD; nothing is
+    /// logged elsewhere.
+    ///
+    /// This method is only available with the `std` feature.
+    #[cfg(feature="std")]
+    pub fn cpu_time(mut self) -> Inspect {
+        self.cpu_time = true;
+        self
+    }
+
+    /// Slows the inspection down before every poll as set out by
+    /// `slow_motion`, for watching the sequence of polls unfold
+    /// step by step, e.g. when teaching how futures are driven:
+    ///
+    /// ```plain
+    /// DEBUG - `request' held back, returning NotReady and waking up in 500ms
+    /// DEBUG - Polling future `request'
+    /// DEBUG - Future `request' polled: Ok(NotReady)
+    /// ```
+    ///
+    /// Only futures and streams inspected through `LoggingExt` and
+    /// `StreamLoggingExt` are slowed down, and this method is only
+    /// available with the `std` feature.
+    #[cfg(feature="std")]
+    pub fn slow_motion(mut self, slow_motion: SlowMotion) -> Inspect {
+        self.slow_motion = Some(slow_motion);
+        self
+    }
+
+    /// Measures the time spent logging the inspection, apart from
+    /// the inner polls. Poll durations reported by `timed()` leave
+    /// it out, and it is added to the summary of futures and to the
+    /// statistics of the label, to tell how much the inspection
+    /// distorts a benchmark.
+    ///
+    /// Only available with the `std` feature.
+    #[cfg(feature="std")]
+    pub fn measure_overhead(mut self) -> Inspect {
+        self.measure_overhead = true;
+        self
+    }
+
+    /// Measures the durations of this inspection, like poll times,
+    /// deadlines and relative timestamps, on `clock` instead of the
+    /// one installed through `set_clock()`.
+    ///
+    /// Only available with the `std` feature.
+    #[cfg(feature="std")]
+    pub fn clock(mut self, clock: &'static dyn Clock) -> Inspect {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Lets `handle` change the label, the level and whether
+    /// anything is logged for this inspection while it runs, see
+    /// `InspectHandle`.
+    ///
+    /// Only available with the `std` feature.
+    #[cfg(feature="std")]
+    pub fn handle(mut self, handle: &InspectHandle) -> Inspect {
+        self.handle = Some(handle.clone());
+        self
+    }
+}
@@ -0,0 +1,141 @@
+//! Logging work handed to thread pools.
+//!
+//! On a saturated pool, work waits in the queue before a thread picks it
+//! up, which ordinary poll logs do not show. `wrap_for_pool` prepares a
+//! futures 0.1 Future for a pool like `futures_cpupool::CpuPool`, and
+//! `wrap_blocking` a closure for a pool of blocking threads. Both log when
+//! the work was submitted, how long it waited until it started and how
+//! long it ran:
+//!
+//! ```plain
+//! DEBUG - Work `resize' submitted to the pool
+//! DEBUG - Work `resize' started after waiting 4.1ms in the queue
+//! DEBUG - Work `resize' finished after running for 12.7ms
+//! ```
+//!
+//! The submission is logged right away, so the wrapped work should be
+//! handed to the pool immediately. Nothing is logged when the `silence`
+//! feature is activated.
+//!
+//! This module is only available with the `std` feature.
+
+use futures::{Future, Poll};
+#[cfg(not(silenced))]
+use futures::Async;
+
+use inspector::Inspector;
+#[cfg(not(silenced))]
+use logger::{EventKind, Outcome};
+use options::Inspect;
+use timing::Stopwatch;
+
+/// PooledFuture wraps a Future about to be spawned on a pool, see
+/// `wrap_for_pool`.
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct PooledFuture<F> {
+    future: F,
+    inspector: Inspector,
+    submitted: Stopwatch,
+    started: Option<Stopwatch>,
+}
+
+impl<F: Future> PooledFuture<F> {
+    fn new(future: F, options: Inspect) -> PooledFuture<F> {
+        let inspector = Inspector::new(options);
+        let pooled = PooledFuture {
+            future,
+            submitted: inspector.stopwatch(),
+            started: None,
+            inspector,
+        };
+        #[cfg(not(silenced))]
+        pooled.inspector.emit(EventKind::Submitted, None);
+        pooled
+    }
+}
+
+#[cfg(not(silenced))]
+impl<F: Future> Future for PooledFuture<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<F::Item, F::Error> {
+        let _entered = self.inspector.enter();
+        self.inspector.begin();
+        if self.started.is_none() {
+            if let Some(waited) = self.submitted.elapsed() {
+                self.inspector.emit(EventKind::WorkStarted(waited), None);
+            }
+            self.started = Some(self.inspector.stopwatch());
+        }
+        let poll = self.future.poll();
+        self.inspector.end(&match poll {
+            Ok(Async::NotReady) => Outcome::NotReady,
+            Ok(Async::Ready(_)) => Outcome::Ready(Inspector::opaque()),
+            Err(_) => Outcome::Err(Inspector::opaque()),
+        });
+        if let Ok(Async::NotReady) = poll {
+            return poll;
+        }
+        if let Some(ran) = self.started.and_then(|started| started.elapsed()) {
+            self.inspector.emit(EventKind::WorkFinished(ran), None);
+        }
+        poll
+    }
+}
+
+#[cfg(silenced)]
+impl<F: Future> Future for PooledFuture<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<F::Item, F::Error> {
+        self.future.poll()
+    }
+}
+
+/// wrap_for_pool() prepares `future` to be spawned on a thread pool
+/// as work labeled `label`, e.g. through
+/// `pool.spawn(wrap_for_pool("resize", future))`. The time until its
+/// first poll is logged as the time it waited in the queue, and the
+/// time from there until it resolved as the time it ran.
+#[track_caller]
+pub fn wrap_for_pool<F: Future>(label: &str, future: F) -> PooledFuture<F> {
+    PooledFuture::new(future, Inspect::new(label))
+}
+
+/// wrap_blocking() prepares the closure `work` to be run on a pool
+/// of blocking threads as work labeled `label`, e.g. through
+/// `tokio::task::spawn_blocking(wrap_blocking("hash", work))`. The
+/// time until the closure is called is logged as the time it waited
+/// in the queue, and the time until it returned as the time it ran.
+#[cfg(not(silenced))]
+#[track_caller]
+pub fn wrap_blocking<R, W>(label: &str, work: W) -> impl FnOnce() -> R + Send + 'static
+    where W: FnOnce() -> R + Send + 'static
+{
+    let inspector = Inspector::new(Inspect::new(label));
+    let submitted = inspector.stopwatch();
+    inspector.emit(EventKind::Submitted, None);
+    move || {
+        if let Some(waited) = submitted.elapsed() {
+            inspector.emit(EventKind::WorkStarted(waited), None);
+        }
+        let started = inspector.stopwatch();
+        let result = work();
+        if let Some(ran) = started.elapsed() {
+            inspector.emit(EventKind::WorkFinished(ran), None);
+        }
+        result
+    }
+}
+/// wrap_blocking() hands `work` back untouched when the `silence`
+/// feature is activated.
+#[cfg(silenced)]
+pub fn wrap_blocking<R, W>(_: &str, work: W) -> impl FnOnce() -> R + Send + 'static
+    where W: FnOnce() -> R + Send + 'static
+{
+    work
+}
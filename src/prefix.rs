@@ -0,0 +1,75 @@
+//! Prefixes prepended to the labels of inspections.
+
+use alloc::string::String;
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use label::Label;
+
+thread_local! {
+    /// The prefixes of all live guards, concatenated.
+    static PREFIX: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// PrefixGuard prepends a prefix to the labels of inspections
+/// set up on the current thread until it is dropped, see
+/// `with_label_prefix()`.
+#[derive(Debug)]
+#[must_use = "labels are only prefixed while the guard is alive"]
+pub struct PrefixGuard {
+    /// The length of the prefix before this guard.
+    outer: usize,
+    // Prefixes belong to the thread they were set on.
+    _thread: PhantomData<*const ()>,
+}
+
+/// with_label_prefix() prepends `prefix` to the label of every
+/// inspection set up on the current thread until the returned guard
+/// is dropped. Nested guards add up, the outermost prefix first:
+///
+/// ```rust
+/// let _db = futures_poll_log::with_label_prefix("db::");
+/// let _pool = futures_poll_log::with_label_prefix("pool::");
+/// // `inspect("checkout")` is logged as `db::pool::checkout'.
+/// ```
+///
+/// Only the label given to `Inspect::new` and the `inspect`
+/// methods is prefixed, when the inspection is set up, so futures
+/// keep their label when they are polled elsewhere.
+///
+/// This function is only available with the `std` feature.
+pub fn with_label_prefix(prefix: &str) -> PrefixGuard {
+    let outer = PREFIX.with(|current| {
+        let mut current = current.borrow_mut();
+        let outer = current.len();
+        current.push_str(prefix);
+        outer
+    });
+    PrefixGuard {
+        outer,
+        _thread: PhantomData,
+    }
+}
+
+impl Drop for PrefixGuard {
+    fn drop(&mut self) {
+        let _ = PREFIX.try_with(|current| current.borrow_mut().truncate(self.outer));
+    }
+}
+
+/// `label` behind the prefixes of the current thread.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn apply(label: Label) -> Label {
+    let prefixed = PREFIX.try_with(|current| {
+        let current = current.borrow();
+        if current.is_empty() {
+            None
+        } else {
+            Some(Label::prefixed(&current, label.clone()))
+        }
+    });
+    match prefixed {
+        Ok(Some(prefixed)) => prefixed,
+        Ok(None) | Err(_) => label,
+    }
+}
@@ -0,0 +1,87 @@
+//! A backend printing colored poll events to the terminal.
+
+use std::io::{self, IsTerminal, Write};
+use std::sync::Arc;
+
+use logger::{self, Outcome, PollEvent, PollLogger};
+use options::Level;
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const MAGENTA: &str = "\x1b[35m";
+
+/// A PollLogger printing every event to standard error, colored
+/// by its result: green for `Ready`, yellow for `NotReady`, red for
+/// errors and magenta for warnings.
+///
+/// Colors are only used when standard error is a terminal and the
+/// `NO_COLOR` environment variable is not set.
+///
+/// This backend is available with the `pretty` feature.
+#[derive(Debug, Clone, Copy)]
+pub struct PrettyLogger {
+    color: bool,
+}
+
+impl PrettyLogger {
+    /// Creates a backend, detecting whether to use colors.
+    pub fn new() -> PrettyLogger {
+        let color = std::env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal();
+        PrettyLogger { color }
+    }
+
+    /// Uses colors if `color` is true, regardless of the terminal.
+    pub fn with_color(mut self, color: bool) -> PrettyLogger {
+        self.color = color;
+        self
+    }
+
+    /// Installs a new `PrettyLogger` as the global backend.
+    pub fn init() {
+        logger::set_poll_logger(Arc::new(PrettyLogger::new()));
+    }
+
+    fn color(event: &PollEvent) -> Option<&'static str> {
+        if event.is_warning() {
+            return Some(MAGENTA);
+        }
+        match event.outcome() {
+            Some(Outcome::Ready(_)) => Some(GREEN),
+            Some(Outcome::NotReady) => Some(YELLOW),
+            Some(Outcome::Err(_)) => Some(RED),
+            None => None,
+        }
+    }
+}
+
+impl Default for PrettyLogger {
+    fn default() -> PrettyLogger {
+        PrettyLogger::new()
+    }
+}
+
+impl PollLogger for PrettyLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let level = match event.level {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN ",
+            Level::Info => "INFO ",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        // There is nowhere to report failing to write to stderr.
+        let _ = if !self.color {
+            writeln!(stderr, "{} {}", level, event)
+        } else {
+            match PrettyLogger::color(event) {
+                Some(color) => writeln!(stderr, "{}{}{} {}{}{}", DIM, level, RESET, color, event, RESET),
+                None => writeln!(stderr, "{}{} {}{}", DIM, level, event, RESET),
+            }
+        };
+    }
+}
@@ -0,0 +1,176 @@
+//! Owned copies of poll events.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use logger::{EventKind, Outcome, PollEvent};
+
+/// An EventRecord is an owned copy of a `PollEvent`, with
+/// all items and errors already rendered through `Debug`.
+///
+/// Records can be kept around or sent to other threads,
+/// which `PollEvent`s cannot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EventRecord {
+    /// The label given at inspection.
+    pub label: String,
+    /// The number identifying the inspection.
+    pub instance: u64,
+    /// The number of the event within the process.
+    pub sequence: u64,
+    /// The file and line the inspection was set up at, like
+    /// `src/main.rs:12`, if enabled.
+    pub location: Option<String>,
+    /// The label of the inspection polling this one, if any.
+    pub parent: Option<String>,
+    /// The label and instance of the inspection being polled when
+    /// this one was set up, if any.
+    pub spawned_by: Option<(String, u64)>,
+    /// The correlation id of the inspection, if any.
+    pub correlation: Option<String>,
+    /// The diagnostic context of the event, as key/value pairs.
+    pub context: Vec<(String, String)>,
+    /// The category of the reported error, if classified.
+    pub category: Option<String>,
+    /// The number of the poll this event belongs to.
+    pub poll: u64,
+    /// The time spent in the inner poll, if reported.
+    pub duration: Option<Duration>,
+    /// When the event happened, if timestamps are enabled.
+    pub timestamp: Option<Duration>,
+    /// The name of the event kind, see `EventKind::name`.
+    pub kind: &'static str,
+    /// The result reported by the event, if any.
+    pub outcome: Option<RecordedOutcome>,
+    /// The message `LogLogger` writes for this event.
+    pub message: String,
+}
+
+/// The owned counterpart of `Outcome`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedOutcome {
+    /// The poll returned `NotReady`.
+    NotReady,
+    /// The poll returned `Ready`, with the rendered item.
+    Ready(String),
+    /// The poll returned an error, with the rendered error.
+    Err(String),
+}
+
+impl<'a> From<Outcome<'a>> for RecordedOutcome {
+    fn from(outcome: Outcome<'a>) -> RecordedOutcome {
+        match outcome {
+            Outcome::NotReady => RecordedOutcome::NotReady,
+            Outcome::Ready(item) => RecordedOutcome::Ready(alloc::format!("{:?}", item)),
+            Outcome::Err(error) => RecordedOutcome::Err(alloc::format!("{:?}", error)),
+        }
+    }
+}
+
+impl<'a> PollEvent<'a> {
+    /// Copies this event into an `EventRecord`.
+    pub fn to_record(&self) -> EventRecord {
+        EventRecord {
+            label: self.label.to_string(),
+            instance: self.instance,
+            sequence: self.sequence,
+            location: self.location.map(|location| alloc::format!("{}:{}", location.file(), location.line())),
+            parent: self.parent.map(|parent| parent.to_string()),
+            spawned_by: self.spawned_by.map(|(label, instance)| (label.to_string(), instance)),
+            correlation: self.correlation.map(|correlation| correlation.to_string()),
+            context: self.context.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+            category: self.category.map(|category| category.to_string()),
+            poll: self.poll,
+            duration: self.duration,
+            timestamp: self.timestamp,
+            kind: self.kind.name(),
+            outcome: self.outcome().map(RecordedOutcome::from),
+            message: self.to_string(),
+        }
+    }
+}
+
+impl<'a> EventKind<'a> {
+    /// A short, stable name for this kind of event, such as
+    /// `"polling"` or `"stream_item"`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            EventKind::Polling => "polling",
+            EventKind::Polled(_) => "polled",
+            EventKind::PolledBy(_) => "polled_by",
+            EventKind::DeadlineExceeded(_) => "deadline_exceeded",
+            EventKind::Panicked(_) => "panicked",
+            EventKind::Spawned => "spawned",
+            EventKind::TaskStarted(_) => "task_started",
+            EventKind::TaskCompleted(_) => "task_completed",
+            EventKind::Submitted => "submitted",
+            EventKind::WorkStarted(_) => "work_started",
+            EventKind::WorkFinished(_) => "work_finished",
+            EventKind::Retrying(_) => "retrying",
+            EventKind::CompatWoken => "compat_woken",
+            EventKind::LostWakeup => "lost_wakeup",
+            EventKind::Waker(_) => "waker",
+            EventKind::WakerChanged(..) => "waker_changed",
+            EventKind::WokenBy(_) => "woken_by",
+            EventKind::WokenFrom(..) => "woken_from",
+            EventKind::WakersLeaked(..) => "wakers_leaked",
+            EventKind::IsTerminated(_) => "is_terminated",
+            EventKind::SizeHint(..) => "size_hint",
+            EventKind::Blocking(_) => "blocking",
+            EventKind::BlockingInPoll(_) => "blocking_in_poll",
+            EventKind::Unparked(_) => "unparked",
+            EventKind::Unblocked(..) => "unblocked",
+            EventKind::ErrorSource(..) => "error_source",
+            EventKind::Allocated(..) => "allocated",
+            EventKind::CpuTime(..) => "cpu_time",
+            EventKind::SlowedDown(_) => "slowed_down",
+            EventKind::HeldBack(_) => "held_back",
+            EventKind::InjectedNotReady => "injected_not_ready",
+            EventKind::InjectedDelay(_) => "injected_delay",
+            EventKind::InjectedError(_) => "injected_error",
+            EventKind::ClosureInvoked => "closure_invoked",
+            EventKind::Size(_) => "size",
+            EventKind::FirstPoll(_) => "first_poll",
+            EventKind::ThreadChanged(..) => "thread_changed",
+            EventKind::Reentered => "reentered",
+            EventKind::ReadyStreak(_) => "ready_streak",
+            EventKind::PollGap(..) => "poll_gap",
+            EventKind::PollStorm(..) => "poll_storm",
+            EventKind::Branch(_) => "branch",
+            EventKind::StillNotReady(_) => "still_not_ready",
+            EventKind::Suppressed(_) => "suppressed",
+            EventKind::GroupLimited(..) => "group_limited",
+            EventKind::StreamPolling => "stream_polling",
+            EventKind::StreamPolled(_) => "stream_polled",
+            EventKind::StreamItem(..) => "stream_item",
+            EventKind::StreamEnded(..) => "stream_ended",
+            EventKind::StreamDropped(..) => "stream_dropped",
+            EventKind::StreamTick(..) => "stream_tick",
+            EventKind::TickDrift(..) => "tick_drift",
+            EventKind::StreamThroughput(..) => "stream_throughput",
+            EventKind::StreamRepeated(_) => "stream_repeated",
+            EventKind::StreamBatch(..) => "stream_batch",
+            EventKind::StartSend(_) => "start_send",
+            EventKind::StartSendAccepted => "start_send_accepted",
+            EventKind::StartSendNotReady => "start_send_not_ready",
+            EventKind::StartSendFailed(_) => "start_send_failed",
+            EventKind::PollComplete => "poll_complete",
+            EventKind::PollCompleted(_) => "poll_completed",
+            EventKind::Close => "close",
+            EventKind::Closed(_) => "closed",
+            EventKind::Sent(_) => "sent",
+            EventKind::ReceiverGone => "receiver_gone",
+            EventKind::SenderDropped(_) => "sender_dropped",
+            EventKind::LockRequested => "lock_requested",
+            EventKind::LockAcquired(_) => "lock_acquired",
+            EventKind::LockReleased(_) => "lock_released",
+            EventKind::PipelineItem(..) => "pipeline_item",
+            EventKind::PipelineFlushed(_) => "pipeline_flushed",
+            EventKind::PipelineStalled(_) => "pipeline_stalled",
+            EventKind::PipelineDone(_) => "pipeline_done",
+            EventKind::Dropped(_) => "dropped",
+        }
+    }
+}
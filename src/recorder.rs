@@ -0,0 +1,141 @@
+//! A flight recorder keeping the most recent events in memory.
+//!
+//! After `enable()`, the last events of all inspections are kept in a
+//! ring buffer, whether they are logged or not. They can be retrieved
+//! through `events()`, or printed when the process panics, through
+//! `install_panic_hook()`:
+//!
+//! ```rust
+//! use futures_poll_log::recorder;
+//!
+//! recorder::enable(256);
+//! recorder::install_panic_hook();
+//! ```
+//!
+//! Recording never blocks a poll. An event arriving while its slot is
+//! being read or written by another thread is dropped instead.
+//!
+//! This module is only available with the `std` feature.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::io::{self, Write};
+use std::panic;
+use std::sync::OnceLock;
+
+use logger::PollEvent;
+use record::EventRecord;
+
+static RING: OnceLock<Ring> = OnceLock::new();
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+struct Slot {
+    busy: AtomicBool,
+    /// The position of the event in the ring, to bring the events
+    /// back into order.
+    record: UnsafeCell<Option<(usize, EventRecord)>>,
+}
+
+// Safety: `record` is only accessed by the thread that set `busy`.
+unsafe impl Sync for Slot {}
+
+impl Slot {
+    fn with<R, F: FnOnce(&mut Option<(usize, EventRecord)>) -> R>(&self, f: F) -> Option<R> {
+        if self.busy.swap(true, Ordering::Acquire) {
+            return None;
+        }
+        // Safety: `busy` was acquired above.
+        let result = f(unsafe { &mut *self.record.get() });
+        self.busy.store(false, Ordering::Release);
+        Some(result)
+    }
+}
+
+struct Ring {
+    slots: Box<[Slot]>,
+    next: AtomicUsize,
+}
+
+/// enable() starts recording the last `capacity` events. The
+/// capacity of the first call is kept by all later ones.
+pub fn enable(capacity: usize) {
+    RING.get_or_init(|| {
+        Ring {
+            slots: (0..capacity.max(1))
+                .map(|_| {
+                    Slot {
+                        busy: AtomicBool::new(false),
+                        record: UnsafeCell::new(None),
+                    }
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    });
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// disable() stops recording. Recorded events are kept.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Release);
+}
+
+/// Whether events are recorded.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// events() copies the recorded events, from the oldest to the
+/// most recent one.
+pub fn events() -> Vec<EventRecord> {
+    let ring = match RING.get() {
+        Some(ring) => ring,
+        None => return Vec::new(),
+    };
+    let mut records: Vec<(usize, EventRecord)> = ring.slots
+        .iter()
+        .filter_map(|slot| slot.with(|record| record.clone()).and_then(|record| record))
+        .collect();
+    records.sort_by_key(|&(position, _)| position);
+    records.into_iter().map(|(_, record)| record).collect()
+}
+
+/// write_events() writes the messages of the recorded events to
+/// `writer`, one per line, from the oldest to the most recent one.
+pub fn write_events<W: Write>(mut writer: W) -> io::Result<()> {
+    for record in events() {
+        writeln!(writer, "{}", record.message)?;
+    }
+    Ok(())
+}
+
+/// install_panic_hook() installs a panic hook printing the recorded
+/// events to standard error, before handing over to the previously
+/// installed hook.
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        let _ = writeln!(stderr, "Last poll events before the panic:");
+        let _ = write_events(&mut stderr);
+        drop(stderr);
+        previous(info);
+    }));
+}
+
+/// Records `event`, if enabled.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn record(event: &PollEvent) {
+    if !is_enabled() {
+        return;
+    }
+    if let Some(ring) = RING.get() {
+        let position = ring.next.fetch_add(1, Ordering::Relaxed);
+        let slot = &ring.slots[position % ring.slots.len()];
+        let record = event.to_record();
+        slot.with(|slot| *slot = Some((position, record)));
+    }
+}
@@ -0,0 +1,271 @@
+//! The inspections alive right now.
+//!
+//! After `enable()`, every inspection set up registers itself until it
+//! is dropped. `snapshot()` lists them, e.g. to see what a hanging
+//! process is still waiting for:
+//!
+//! ```rust
+//! extern crate futures;
+//! extern crate futures_poll_log;
+//!
+//! use futures::future;
+//! use futures_poll_log::{LoggingExt, registry};
+//!
+//! # fn main() {
+//! registry::enable();
+//! let request = future::empty::<i32, ()>().inspect("request");
+//! for inspection in registry::snapshot() {
+//!     println!("{} (#{}): {:?} after {} polls",
+//!              inspection.label,
+//!              inspection.instance,
+//!              inspection.status,
+//!              inspection.polls);
+//! }
+//! # drop(request);
+//! # }
+//! ```
+//!
+//! Inspections set up before `enable()` are not listed.
+//!
+//! This module is only available with the `std` feature.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(any(unix, windows))]
+use core::ffi::c_int;
+use core::fmt::Write;
+use core::time::Duration;
+#[cfg(any(unix, windows))]
+use std::io::{self, Write as IoWrite};
+use std::sync::Mutex;
+
+use label::Label;
+use timing::Stopwatch;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static LIVE: Mutex<BTreeMap<u64, Entry>> = Mutex::new(BTreeMap::new());
+
+struct Entry {
+    label: Label,
+    correlation: Option<Arc<str>>,
+    spawned_by: Option<(Label, u64)>,
+    created: Stopwatch,
+    polls: u64,
+    status: Status,
+    last_polled: Option<Stopwatch>,
+    finished: bool,
+}
+
+/// Where a live inspection stands, as of its last poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// It was not polled yet.
+    Unpolled,
+    /// Its last poll returned `NotReady`.
+    NotReady,
+    /// Its last poll returned `Ready`: a future resolved but is
+    /// still alive, or a stream yielded an item.
+    Ready,
+    /// Its last poll returned an error.
+    Failed,
+}
+
+/// A live inspection, as listed by `snapshot()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Inspection {
+    /// The label given at inspection.
+    pub label: String,
+    /// The number identifying the inspection.
+    pub instance: u64,
+    /// The correlation id of the inspection, if any.
+    pub correlation: Option<String>,
+    /// The label and instance of the inspection being polled when
+    /// this one was set up, e.g. the task that spawned it, if any.
+    pub spawned_by: Option<(String, u64)>,
+    /// The time since the inspection was set up.
+    pub age: Duration,
+    /// The number of polls so far.
+    pub polls: u64,
+    /// Where the inspection stands.
+    pub status: Status,
+    /// The time since its last poll ended, if it was polled.
+    pub idle: Option<Duration>,
+    /// Whether it is done: a future resolved or failed, or a stream
+    /// or sink ended.
+    pub finished: bool,
+}
+
+/// enable() starts registering the inspections set up from now on.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// disable() stops registering new inspections. The registered ones
+/// stay listed until they are dropped.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Release);
+}
+
+/// Whether new inspections are registered.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// snapshot() lists the registered inspections alive right now,
+/// oldest first.
+pub fn snapshot() -> Vec<Inspection> {
+    let live = LIVE.lock().unwrap_or_else(|e| e.into_inner());
+    live.iter()
+        .map(|(&instance, entry)| {
+            Inspection {
+                label: entry.label.to_string(),
+                instance,
+                correlation: entry.correlation.as_ref().map(|correlation| correlation.to_string()),
+                spawned_by: entry.spawned_by.as_ref().map(|&(ref label, instance)| (label.to_string(), instance)),
+                age: entry.created.elapsed().unwrap_or_default(),
+                polls: entry.polls,
+                status: entry.status,
+                idle: entry.last_polled.as_ref().and_then(Stopwatch::elapsed),
+                finished: entry.finished,
+            }
+        })
+        .collect()
+}
+
+/// pending() lists the registered inspections alive right now that
+/// are not finished, oldest first.
+pub fn pending() -> Vec<Inspection> {
+    snapshot().into_iter().filter(|inspection| !inspection.finished).collect()
+}
+
+/// report_pending() renders the inspections listed by `pending()` as
+/// a table, one row per inspection with its label, instance, age,
+/// polls, the result of its last poll and the time since. Called
+/// when the process shuts down, it names the futures that never
+/// finished:
+///
+/// ```plain
+/// label  instance           age   polls  status       last poll
+/// flush         7        30.01s       1  NotReady        30.01s
+/// ```
+///
+/// An empty string is returned when nothing is pending.
+pub fn report_pending() -> String {
+    let pending = pending();
+    if pending.is_empty() {
+        return String::new();
+    }
+    let width = pending.iter().map(|inspection| inspection.label.chars().count()).max().unwrap_or(0).max(5);
+    let mut report = String::new();
+    let _ = writeln!(report,
+                     "{:<width$}  {:>8}  {:>12}  {:>6}  {:<8}  {:>12}",
+                     "label",
+                     "instance",
+                     "age",
+                     "polls",
+                     "status",
+                     "last poll",
+                     width = width);
+    for inspection in &pending {
+        let _ = writeln!(report,
+                         "{:<width$}  {:>8}  {:>12}  {:>6}  {:<8}  {:>12}",
+                         inspection.label,
+                         inspection.instance,
+                         format!("{:.2?}", inspection.age),
+                         inspection.polls,
+                         format!("{:?}", inspection.status),
+                         inspection.idle.map_or_else(|| "-".to_string(), |idle| format!("{:.2?}", idle)),
+                         width = width);
+    }
+    report
+}
+
+/// report_pending_at_exit() writes `report_pending()` to stderr when
+/// the process exits by returning from `main` or through
+/// `std::process::exit`, unless nothing is pending by then. It also
+/// enables the registry, so call it early. Calling it again has no
+/// effect.
+///
+/// This function is only available on Unix and Windows.
+#[cfg(any(unix, windows))]
+pub fn report_pending_at_exit() {
+    use std::sync::Once;
+
+    extern "C" {
+        fn atexit(callback: extern "C" fn()) -> c_int;
+    }
+
+    extern "C" fn report() {
+        let report = report_pending();
+        if !report.is_empty() {
+            let mut stderr = io::stderr();
+            let _ = write!(stderr, "futures still pending at exit:\n{}", report);
+        }
+    }
+
+    static REGISTERED: Once = Once::new();
+    enable();
+    REGISTERED.call_once(|| {
+        // Safe as `report` stays valid until the process is gone.
+        unsafe {
+            atexit(report);
+        }
+    });
+}
+
+/// Registers the inspection numbered `instance`.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn register(instance: u64,
+                       label: Label,
+                       correlation: Option<Arc<str>>,
+                       spawned_by: Option<(Label, u64)>,
+                       created: Stopwatch) {
+    let entry = Entry {
+        label,
+        correlation,
+        spawned_by,
+        created,
+        polls: 0,
+        status: Status::Unpolled,
+        last_polled: None,
+        finished: false,
+    };
+    LIVE.lock().unwrap_or_else(|e| e.into_inner()).insert(instance, entry);
+}
+
+/// Updates the registered inspection `instance` after its `polls`th
+/// poll, which ended when `ended` was started.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn polled(instance: u64, polls: u64, status: Status, ended: Stopwatch) {
+    if let Some(entry) = LIVE.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&instance) {
+        entry.polls = polls;
+        entry.status = status;
+        entry.last_polled = Some(ended);
+    }
+}
+
+/// Marks the registered inspection `instance` as done.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn finished(instance: u64) {
+    if let Some(entry) = LIVE.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&instance) {
+        entry.finished = true;
+    }
+}
+
+/// Changes the label of the registered inspection `instance`.
+pub(crate) fn relabeled(instance: u64, label: Label) {
+    if let Some(entry) = LIVE.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&instance) {
+        entry.label = label;
+    }
+}
+
+/// Removes the registered inspection `instance`.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn unregister(instance: u64) {
+    LIVE.lock().unwrap_or_else(|e| e.into_inner()).remove(&instance);
+}
@@ -0,0 +1,306 @@
+//! Renderers turning recorded events into diagrams.
+//!
+//! Events are recorded as `EventRecord`s, e.g. through a
+//! `ChannelLogger`:
+//!
+//! ```rust
+//! extern crate futures;
+//! extern crate futures_poll_log;
+//!
+//! use std::sync::Arc;
+//! use futures::{Future, Stream, future};
+//! use futures_poll_log::{ChannelLogger, Inspect, LoggingExt, PollLogger, render};
+//!
+//! # fn main() {
+//! let (logger, records) = ChannelLogger::new();
+//! let logger: Arc<dyn PollLogger> = Arc::new(logger);
+//! let _ = future::ok::<i32, ()>(3)
+//!     .inspect_with_opts(Inspect::new("inner").logger(logger.clone()))
+//!     .map(|x| x * 2)
+//!     .inspect_with_opts(Inspect::new("outer").logger(logger))
+//!     .wait();
+//! let records = records.collect().wait().unwrap();
+//! print!("{}", render::mermaid(&records));
+//! # }
+//! ```
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::time::Duration;
+
+use record::{EventRecord, RecordedOutcome};
+
+/// The participant standing in for whatever polls the outermost
+/// inspections.
+const EXECUTOR: &str = "executor";
+
+/// mermaid() renders `records` as a Mermaid sequence diagram, with
+/// an arrow for every poll call from the executor or the polling
+/// inspection, and one back for its result, in the order they were
+/// recorded. All other events become notes.
+///
+/// Polls from within other polls can only be attributed with the
+/// `std` feature, see `PollEvent::parent`.
+pub fn mermaid(records: &[EventRecord]) -> String {
+    let mut participants: Vec<&str> = Vec::new();
+    participants.push(EXECUTOR);
+    for record in records {
+        for label in record.parent.as_deref().into_iter().chain(Some(&*record.label)) {
+            if !participants.contains(&label) {
+                participants.push(label);
+            }
+        }
+    }
+    let id = |label: &str| participants.iter().position(|&p| p == label).unwrap_or(0);
+
+    let mut diagram = String::from("sequenceDiagram\n");
+    for (index, label) in participants.iter().enumerate() {
+        let _ = writeln!(diagram, "    participant p{} as {}", index, mermaid_text(label));
+    }
+    for record in records {
+        let this = id(&record.label);
+        let caller = id(record.parent.as_deref().unwrap_or(EXECUTOR));
+        match record.kind {
+            "polling" | "stream_polling" => {
+                let _ = writeln!(diagram, "    p{}->>p{}: poll #{}", caller, this, record.poll);
+            }
+            "poll_complete" | "close" => {
+                let _ = writeln!(diagram, "    p{}->>p{}: {} #{}", caller, this, record.kind, record.poll);
+            }
+            "start_send" => {
+                let _ = writeln!(diagram, "    p{}->>p{}: start_send #{}", caller, this, record.poll);
+            }
+            "polled" | "stream_polled" | "stream_item" | "poll_completed" | "closed" |
+            "start_send_failed" => {
+                let result = record.outcome.as_ref().map_or_else(String::new, outcome_text);
+                let _ = writeln!(diagram, "    p{}-->>p{}: {}", this, caller, mermaid_text(&result));
+            }
+            "stream_ended" => {
+                let _ = writeln!(diagram, "    p{}-->>p{}: Ready(None)", this, caller);
+            }
+            "start_send_accepted" => {
+                let _ = writeln!(diagram, "    p{}-->>p{}: accepted", this, caller);
+            }
+            "start_send_not_ready" => {
+                let _ = writeln!(diagram, "    p{}-->>p{}: NotReady", this, caller);
+            }
+            _ => {
+                let _ = writeln!(diagram, "    Note over p{}: {}", this, mermaid_text(&record.message));
+            }
+        }
+    }
+    diagram
+}
+
+fn outcome_text(outcome: &RecordedOutcome) -> String {
+    match *outcome {
+        RecordedOutcome::NotReady => String::from("NotReady"),
+        RecordedOutcome::Ready(ref item) => alloc::format!("Ready({})", item),
+        RecordedOutcome::Err(ref error) => alloc::format!("Err({})", error),
+    }
+}
+
+/// Escapes the characters Mermaid treats specially in names
+/// and messages.
+fn mermaid_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            ';' => escaped.push_str("#59;"),
+            '#' => escaped.push_str("#35;"),
+            '\n' | '\r' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Timeline renders recorded events as a timeline for the terminal,
+/// with one row per label and time running from left to right.
+///
+/// With timestamps enabled (see `Timestamps`), columns are spread
+/// evenly over the recorded time. Otherwise, every event gets a
+/// column of its own, scaled down to the width if needed.
+///
+/// Rows show `○` for polls returning `NotReady`, `•` for stream items
+/// and accepted sink items, `✓` when the inspected value completed,
+/// `✗` for errors and `!` for warnings, with `─` in between. The
+/// futures 0.1 task system offers no hook for wakeups, so they are
+/// not shown.
+///
+/// ```rust
+/// use futures_poll_log::render::Timeline;
+///
+/// # let records = Vec::new();
+/// print!("{}", Timeline::new().width(60).ascii().render(&records));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    width: usize,
+    ascii: bool,
+}
+
+/// The markers of a timeline, from the least to the most important.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Marker {
+    Empty,
+    Alive,
+    Poll,
+    NotReady,
+    Item,
+    Completed,
+    Failed,
+    Warning,
+}
+
+impl Marker {
+    fn of(record: &EventRecord) -> Marker {
+        match (record.kind, record.outcome.as_ref()) {
+            ("polling", _) | ("stream_polling", _) | ("start_send", _) | ("poll_complete", _) |
+            ("close", _) => Marker::Poll,
+            ("deadline_exceeded", _) => Marker::Warning,
+            ("start_send_failed", _) |
+            (_, Some(&RecordedOutcome::Err(_))) => Marker::Failed,
+            ("polled", Some(&RecordedOutcome::Ready(_))) |
+            ("closed", Some(&RecordedOutcome::Ready(_))) |
+            ("stream_ended", _) => Marker::Completed,
+            ("stream_item", _) | ("start_send_accepted", _) |
+            ("poll_completed", Some(&RecordedOutcome::Ready(_))) => Marker::Item,
+            (_, Some(&RecordedOutcome::NotReady)) | ("start_send_not_ready", _) => Marker::NotReady,
+            _ => Marker::Alive,
+        }
+    }
+
+    fn symbol(self, ascii: bool) -> char {
+        match (self, ascii) {
+            (Marker::Empty, _) => ' ',
+            (Marker::Alive, false) | (Marker::Poll, false) => '─',
+            (Marker::Alive, true) | (Marker::Poll, true) => '-',
+            (Marker::NotReady, false) => '○',
+            (Marker::NotReady, true) => 'o',
+            (Marker::Item, false) => '•',
+            (Marker::Item, true) => '*',
+            (Marker::Completed, false) => '✓',
+            (Marker::Completed, true) => '#',
+            (Marker::Failed, false) => '✗',
+            (Marker::Failed, true) => 'x',
+            (Marker::Warning, _) => '!',
+        }
+    }
+}
+
+impl Timeline {
+    /// A timeline 72 columns wide, drawn with Unicode characters.
+    pub fn new() -> Timeline {
+        Timeline {
+            width: 72,
+            ascii: false,
+        }
+    }
+
+    /// Uses `width` columns for the time axis.
+    pub fn width(mut self, width: usize) -> Timeline {
+        self.width = width.max(2);
+        self
+    }
+
+    /// Draws with ASCII characters only.
+    pub fn ascii(mut self) -> Timeline {
+        self.ascii = true;
+        self
+    }
+
+    /// Renders `records` as a timeline.
+    pub fn render(&self, records: &[EventRecord]) -> String {
+        let mut labels: Vec<&str> = Vec::new();
+        for record in records {
+            if !labels.contains(&&*record.label) {
+                labels.push(&record.label);
+            }
+        }
+        let columns = self.columns(records);
+        let mut rows: Vec<Vec<Marker>> = labels.iter().map(|_| vec![Marker::Empty; self.width]).collect();
+        for (row, label) in rows.iter_mut().zip(&labels) {
+            let mut cells = records
+                .iter()
+                .zip(&columns)
+                .filter(|&(record, _)| record.label == *label);
+            let first = cells.clone().next().map_or(0, |(_, &column)| column);
+            let last = cells.clone().next_back().map_or(0, |(_, &column)| column);
+            for cell in &mut row[first..=last] {
+                *cell = Marker::Alive;
+            }
+            for (record, &column) in &mut cells {
+                row[column] = row[column].max(Marker::of(record));
+            }
+        }
+
+        let width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+        let mut timeline = String::new();
+        for (row, label) in rows.iter().zip(&labels) {
+            let _ = write!(timeline, "{:<width$} ", label, width = width);
+            let line: String = row.iter().map(|marker| marker.symbol(self.ascii)).collect();
+            timeline.push_str(line.trim_end());
+            timeline.push('\n');
+        }
+        if let Some(span) = self.span(records) {
+            let _ = writeln!(timeline,
+                             "{:<width$} 0{:>axis$?}",
+                             "",
+                             span,
+                             width = width,
+                             axis = self.width - 1);
+        }
+        timeline
+    }
+
+    /// The time covered by `records`, if all of them carry a timestamp.
+    fn span(&self, records: &[EventRecord]) -> Option<Duration> {
+        let (first, last) = self.bounds(records)?;
+        Some(last - first)
+    }
+
+    fn bounds(&self, records: &[EventRecord]) -> Option<(Duration, Duration)> {
+        let mut timestamps = records.iter().map(|record| record.timestamp);
+        let first = timestamps.next()??;
+        timestamps.try_fold((first, first), |(min, max), timestamp| {
+            timestamp.map(|timestamp| (min.min(timestamp), max.max(timestamp)))
+        })
+    }
+
+    /// The column of every record.
+    fn columns(&self, records: &[EventRecord]) -> Vec<usize> {
+        let last_column = self.width - 1;
+        match self.bounds(records) {
+            Some((first, last)) if last > first => {
+                let span = (last - first).as_nanos();
+                records
+                    .iter()
+                    .map(|record| {
+                        let offset = record.timestamp.map_or(0, |timestamp| (timestamp - first).as_nanos());
+                        (offset * last_column as u128 / span) as usize
+                    })
+                    .collect()
+            }
+            _ => {
+                let count = records.len().max(1);
+                (0..records.len())
+                    .map(|index| if count <= self.width { index } else { index * last_column / (count - 1) })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Timeline {
+        Timeline::new()
+    }
+}
+
+/// timeline() renders `records` with the default `Timeline`.
+pub fn timeline(records: &[EventRecord]) -> String {
+    Timeline::new().render(records)
+}
@@ -0,0 +1,156 @@
+//! Logging support for retry loops.
+
+use futures::Future;
+#[cfg(not(silenced))]
+use futures::Poll;
+#[cfg(all(feature="std", not(silenced)))]
+use futures::Async;
+use core::fmt::Debug;
+use core::panic::Location;
+#[cfg(feature="std")]
+use alloc::sync::Arc;
+#[cfg(all(feature="std", not(silenced)))]
+use core::time::Duration;
+#[cfg(feature="std")]
+use std::sync::Mutex;
+
+#[cfg(not(silenced))]
+use future::LoggedFuture;
+#[cfg(not(silenced))]
+use options::Inspect;
+#[cfg(all(feature="std", not(silenced)))]
+use logger::EventKind;
+#[cfg(feature="std")]
+use timing::Stopwatch;
+
+/// Retries creates the attempts of a retry loop, see
+/// `inspect_retries()`.
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct Retries<M> {
+    label: alloc::string::String,
+    make_future: M,
+    attempts: u32,
+    /// Where the retry loop was set up, reported for all attempts.
+    #[cfg_attr(silenced, allow(dead_code))]
+    location: &'static Location<'static>,
+    /// When the last attempt resolved.
+    #[cfg(feature="std")]
+    resolved: Arc<Mutex<Option<Stopwatch>>>,
+}
+
+/// inspect_retries() sets up the logging of a retry loop. Every
+/// call of `Retries::attempt()` creates a new future through
+/// `make_future` and labels it with its attempt number, like
+/// `fetch (attempt 2)`. Apart from the first attempt, the time
+/// since the previous attempt resolved is logged on its first poll,
+/// which shows the backoff between the attempts:
+///
+/// ```rust
+/// # extern crate futures;
+/// # extern crate futures_poll_log;
+/// # use futures::{Future, future};
+/// # use futures::future::Loop;
+/// # fn main() {
+/// let mut retries = futures_poll_log::inspect_retries("fetch", || future::err::<(), u32>(503));
+/// let _ = future::loop_fn(1, move |attempt| {
+///     retries.attempt().then(move |result| match result {
+///         Err(_) if attempt < 3 => Ok(Loop::Continue(attempt + 1)),
+///         result => result.map(Loop::Break),
+///     })
+/// }).wait();
+/// # }
+/// ```
+///
+/// The backoff is only measured with the `std` feature.
+#[track_caller]
+pub fn inspect_retries<M, F>(label: &str, make_future: M) -> Retries<M>
+    where M: FnMut() -> F,
+          F: Future,
+          F::Item: Debug,
+          F::Error: Debug
+{
+    Retries {
+        label: label.into(),
+        make_future,
+        attempts: 0,
+        location: Location::caller(),
+        #[cfg(feature="std")]
+        resolved: Arc::new(Mutex::new(None)),
+    }
+}
+
+impl<M, F> Retries<M>
+    where M: FnMut() -> F,
+          F: Future,
+          F::Item: Debug,
+          F::Error: Debug
+{
+    /// attempt() creates the future of the next attempt.
+    ///
+    /// This method returns the future unchanged when the `silence`
+    /// feature is activated.
+    #[cfg(not(silenced))]
+    pub fn attempt(&mut self) -> Attempt<F::Item, F::Error, F> {
+        self.attempts += 1;
+        let label = alloc::format!("{} (attempt {})", self.label, self.attempts);
+        #[cfg(feature="std")]
+        let resolved = self.resolved.lock().unwrap_or_else(|e| e.into_inner()).take();
+        Attempt {
+            future: LoggedFuture::new((self.make_future)(), Inspect::new(&label).located(self.location)),
+            #[cfg(feature="std")]
+            backoff: resolved.and_then(|resolved| resolved.elapsed()),
+            #[cfg(feature="std")]
+            resolved: self.resolved.clone(),
+        }
+    }
+    /// attempt() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    pub fn attempt(&mut self) -> F {
+        self.attempts += 1;
+        (self.make_future)()
+    }
+
+    /// The number of attempts created so far.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
+/// Attempt wraps the future of one attempt of a retry loop like
+/// `LoggedFuture`, see `inspect_retries()`.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct Attempt<T, E, F: Future<Item = T, Error = E>> {
+    future: LoggedFuture<T, E, F>,
+    /// The time between the previous attempt resolving and this
+    /// one being created, until it is logged.
+    #[cfg(feature="std")]
+    backoff: Option<Duration>,
+    #[cfg(feature="std")]
+    resolved: Arc<Mutex<Option<Stopwatch>>>,
+}
+
+#[cfg(not(silenced))]
+impl<T, E, F> Future for Attempt<T, E, F>
+    where T: Debug,
+          E: Debug,
+          F: Future<Item = T, Error = E>
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        #[cfg(feature="std")]
+        if let Some(backoff) = self.backoff.take() {
+            self.future.inspector().emit(EventKind::Retrying(backoff), None);
+        }
+        let poll = self.future.poll();
+        #[cfg(feature="std")]
+        if !matches!(poll, Ok(Async::NotReady)) {
+            *self.resolved.lock().unwrap_or_else(|e| e.into_inner()) = Some(self.future.inspector().stopwatch());
+        }
+        poll
+    }
+}
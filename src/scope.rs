@@ -0,0 +1,53 @@
+//! Logging limited to the lifetime of a guard.
+
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by the first call of `enabled_scope()`.
+static SCOPED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static SCOPES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// ScopeGuard keeps logging enabled on the current thread until
+/// it is dropped, see `enabled_scope()`.
+#[derive(Debug)]
+#[must_use = "logging is only enabled while the guard is alive"]
+pub struct ScopeGuard {
+    // Scopes belong to the thread they were entered on.
+    _thread: PhantomData<*const ()>,
+}
+
+/// enabled_scope() enables logging on the current thread until the
+/// returned guard is dropped.
+///
+/// Once enabled_scope() was called, events are only written for
+/// polls happening on a thread with a live guard, and logging is off
+/// everywhere else. Guards can be nested.
+///
+/// ```rust
+/// let _guard = futures_poll_log::enabled_scope();
+/// // Polls on this thread are logged until `_guard` is dropped.
+/// ```
+///
+/// This function is only available with the `std` feature.
+pub fn enabled_scope() -> ScopeGuard {
+    SCOPED.store(true, Ordering::Release);
+    SCOPES.with(|scopes| scopes.set(scopes.get() + 1));
+    ScopeGuard { _thread: PhantomData }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let _ = SCOPES.try_with(|scopes| scopes.set(scopes.get() - 1));
+    }
+}
+
+/// Whether logging is enabled on the current thread as far as
+/// scopes are concerned.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn is_enabled() -> bool {
+    !SCOPED.load(Ordering::Acquire) || SCOPES.try_with(|scopes| scopes.get() > 0).unwrap_or(false)
+}
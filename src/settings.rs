@@ -0,0 +1,503 @@
+//! Options for labels matching a pattern, loaded from a file.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use config::matches_pattern;
+use options::{Inspect, Level};
+
+static RULES: RwLock<Vec<Rule>> = RwLock::new(Vec::new());
+static PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// Spares new inspections the lock while no settings are loaded.
+static HAS_RULES: AtomicBool = AtomicBool::new(false);
+
+/// The settings of a section, applied to the labels matching its
+/// pattern.
+#[derive(Debug)]
+struct Rule {
+    pattern: String,
+    settings: Vec<Setting>,
+}
+
+#[derive(Debug)]
+enum Setting {
+    Level(Level),
+    NotReadyLevel(Level),
+    ReadyLevel(Level),
+    ErrorLevel(Level),
+    Target(String),
+    Timed(bool),
+    Sample(u64),
+    MaxEvents(u64),
+    CoalesceNotReady(u64),
+    Redact(bool),
+    Summary(bool),
+    Truncate(usize),
+    WarnPollGap(Duration),
+    WarnPollStorm(u64),
+    Deadline(Duration),
+}
+
+/// SettingsError is returned when settings cannot be read or are
+/// not valid.
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The file could not be read, or no file was loaded yet.
+    Io(io::Error),
+    /// The given line, starting at 1, is not valid.
+    Invalid {
+        /// The line the problem is on.
+        line: usize,
+        /// What is wrong with it.
+        message: String,
+    },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SettingsError::Io(ref error) => write!(f, "cannot read the settings: {}", error),
+            SettingsError::Invalid { line, ref message } => write!(f, "invalid settings on line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for SettingsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            SettingsError::Io(ref error) => Some(error),
+            SettingsError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for SettingsError {
+    fn from(error: io::Error) -> SettingsError {
+        SettingsError::Io(error)
+    }
+}
+
+/// load_settings() reads the options of labels from the file at
+/// `path` and applies them to all inspections set up from then on.
+/// The file is written in a subset of TOML, with a section for every
+/// label pattern, where `*` stands for any sequence of characters.
+/// Patterns are quoted like all TOML keys except bare ones, which
+/// only consist of ASCII letters, digits, `_` and `-`:
+///
+/// ```toml
+/// ["db*"]
+/// level = "trace"
+/// sample = 10
+/// warn_poll_gap = "250ms"
+///
+/// ["handler"]
+/// redact = true
+/// deadline = "2s"
+/// ```
+///
+/// The settings are `level`, `not_ready_level`, `ready_level`,
+/// `error_level`, `target`, `timed`, `sample`, `max_events`,
+/// `coalesce_not_ready`, `redact`, `summary`, `truncate`,
+/// `warn_poll_gap`, `warn_poll_storm` and `deadline`, each working
+/// like the method of `Inspect` of the same name. Durations are
+/// written with a unit of `ns`, `us`, `ms` or `s`. They take
+/// precedence over the options given in the code, and the sections
+/// are applied in order, so a later one overrides an earlier one.
+///
+/// Strings are basic strings in `"`, with the escapes of TOML, or
+/// literal strings in `'`. Comments start with `#`. What is not
+/// covered, like arrays, dotted keys or multi-line strings, is
+/// rejected, as are sections and settings defined twice.
+///
+/// Nothing changes if the file cannot be read or is not valid.
+///
+/// This function is only available with the `std` feature.
+pub fn load_settings<P: AsRef<Path>>(path: P) -> Result<(), SettingsError> {
+    let path = path.as_ref();
+    set_settings(&fs::read_to_string(path)?)?;
+    *PATH.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.to_path_buf());
+    Ok(())
+}
+
+/// reload_settings() reads the file last given to `load_settings()`
+/// again, e.g. after a signal or from an admin command, so changes
+/// apply without restarting the process. Inspections already running
+/// keep the options they were set up with.
+///
+/// The settings loaded before stay in place if the file cannot be
+/// read or is not valid any more.
+///
+/// This function is only available with the `std` feature.
+pub fn reload_settings() -> Result<(), SettingsError> {
+    let path = PATH.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    match path {
+        Some(path) => load_settings(path),
+        None => Err(SettingsError::Io(io::Error::new(io::ErrorKind::NotFound, "no settings file was loaded"))),
+    }
+}
+
+/// set_settings() applies settings written like the file of
+/// `load_settings()` to all inspections set up from then on,
+/// replacing the settings loaded before.
+///
+/// This function is only available with the `std` feature.
+pub fn set_settings(text: &str) -> Result<(), SettingsError> {
+    let rules = parse(text)?;
+    let mut installed = RULES.write().unwrap_or_else(|e| e.into_inner());
+    HAS_RULES.store(!rules.is_empty(), Ordering::Release);
+    *installed = rules;
+    Ok(())
+}
+
+/// clear_settings() removes the settings loaded through
+/// `load_settings()` or `set_settings()`.
+///
+/// This function is only available with the `std` feature.
+pub fn clear_settings() {
+    RULES.write().unwrap_or_else(|e| e.into_inner()).clear();
+    HAS_RULES.store(false, Ordering::Release);
+}
+
+/// Applies the settings of all sections matching the label of
+/// `options`.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn apply(mut options: Inspect) -> Inspect {
+    if !HAS_RULES.load(Ordering::Acquire) {
+        return options;
+    }
+    let label = options.label.clone();
+    let rules = RULES.read().unwrap_or_else(|e| e.into_inner());
+    for rule in rules.iter().filter(|rule| matches_pattern(&rule.pattern, &label)) {
+        for setting in &rule.settings {
+            options = match *setting {
+                Setting::Level(level) => options.level(level),
+                Setting::NotReadyLevel(level) => options.not_ready_level(level),
+                Setting::ReadyLevel(level) => options.ready_level(level),
+                Setting::ErrorLevel(level) => options.error_level(level),
+                Setting::Target(ref target) => options.target(target),
+                Setting::Timed(timed) => {
+                    options.timed = timed;
+                    options
+                }
+                Setting::Sample(n) => options.sample(n),
+                Setting::MaxEvents(n) => options.max_events(n),
+                Setting::CoalesceNotReady(n) => options.coalesce_not_ready(n),
+                Setting::Redact(redact) => {
+                    options.redact = redact;
+                    options
+                }
+                Setting::Summary(summary) => {
+                    options.summary = summary;
+                    options
+                }
+                Setting::Truncate(len) => options.truncate(len),
+                Setting::WarnPollGap(max) => options.warn_poll_gap(max),
+                Setting::WarnPollStorm(n) => options.warn_poll_storm(n),
+                Setting::Deadline(duration) => options.deadline(duration),
+            };
+        }
+    }
+    options
+}
+
+/// A value on the right of a `=`.
+enum Value {
+    String(String),
+    Integer(u64),
+    Boolean(bool),
+}
+
+fn parse(text: &str) -> Result<Vec<Rule>, SettingsError> {
+    let mut rules: Vec<Rule> = Vec::new();
+    let mut keys: Vec<String> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let invalid = |message: &str| SettingsError::Invalid {
+            line: index + 1,
+            message: message.to_owned(),
+        };
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let pattern = header.strip_suffix(']').ok_or_else(|| invalid("missing `]'"))?;
+            let pattern = parse_key(pattern.trim()).ok_or_else(|| invalid("expected a label pattern, quoted unless it is a bare key"))?;
+            if rules.iter().any(|rule| rule.pattern == pattern) {
+                return Err(invalid(&format!("section `{}' is defined twice", pattern)));
+            }
+            rules.push(Rule {
+                pattern,
+                settings: Vec::new(),
+            });
+            keys.clear();
+            continue;
+        }
+        let (key, value) = split_key_value(line).ok_or_else(|| invalid("expected `key = value'"))?;
+        let key = parse_key(key.trim()).ok_or_else(|| invalid("expected a key before `='"))?;
+        let value = parse_value(value.trim()).ok_or_else(|| invalid("expected a string, an integer or a boolean"))?;
+        let setting = setting(&key, value).map_err(|message| invalid(&message))?;
+        let rule = rules.last_mut().ok_or_else(|| invalid("setting outside of a label section"))?;
+        if keys.contains(&key) {
+            return Err(invalid(&format!("`{}' is set twice in this section", key)));
+        }
+        keys.push(key);
+        rule.settings.push(setting);
+    }
+    Ok(rules)
+}
+
+fn setting(key: &str, value: Value) -> Result<Setting, String> {
+    let setting = match (key, value) {
+        ("level", Value::String(level)) => Setting::Level(parse_level(&level)?),
+        ("not_ready_level", Value::String(level)) => Setting::NotReadyLevel(parse_level(&level)?),
+        ("ready_level", Value::String(level)) => Setting::ReadyLevel(parse_level(&level)?),
+        ("error_level", Value::String(level)) => Setting::ErrorLevel(parse_level(&level)?),
+        ("target", Value::String(target)) => Setting::Target(target),
+        ("timed", Value::Boolean(timed)) => Setting::Timed(timed),
+        ("sample", Value::Integer(n)) => Setting::Sample(n),
+        ("max_events", Value::Integer(n)) => Setting::MaxEvents(n),
+        ("coalesce_not_ready", Value::Integer(n)) => Setting::CoalesceNotReady(n),
+        ("redact", Value::Boolean(redact)) => Setting::Redact(redact),
+        ("summary", Value::Boolean(summary)) => Setting::Summary(summary),
+        ("truncate", Value::Integer(len)) => Setting::Truncate(len as usize),
+        ("warn_poll_gap", Value::String(max)) => Setting::WarnPollGap(parse_duration(&max)?),
+        ("warn_poll_storm", Value::Integer(n)) => Setting::WarnPollStorm(n),
+        ("deadline", Value::String(duration)) => Setting::Deadline(parse_duration(&duration)?),
+        ("level", _) | ("not_ready_level", _) | ("ready_level", _) | ("error_level", _) | ("target", _) |
+        ("warn_poll_gap", _) | ("deadline", _) => return Err(format!("`{}' takes a string", key)),
+        ("timed", _) | ("redact", _) | ("summary", _) => return Err(format!("`{}' takes a boolean", key)),
+        ("sample", _) | ("max_events", _) | ("coalesce_not_ready", _) | ("truncate", _) | ("warn_poll_storm", _) => {
+            return Err(format!("`{}' takes an integer", key))
+        }
+        _ => return Err(format!("unknown setting `{}'", key)),
+    };
+    Ok(setting)
+}
+
+/// Cuts off a `#` comment outside of a string.
+fn strip_comment(line: &str) -> &str {
+    match quoted_at(line, '#') {
+        Some(index) => &line[..index],
+        None => line,
+    }
+}
+
+/// Splits a line at the first `=` outside of a string.
+fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    quoted_at(line, '=').map(|index| (&line[..index], &line[index + 1..]))
+}
+
+/// The index of the first `wanted` outside of a basic `"` or literal
+/// `'` string.
+fn quoted_at(line: &str, wanted: char) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match (c, quote) {
+            _ if escaped => escaped = false,
+            ('\\', Some('"')) => escaped = true,
+            ('"', None) | ('\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (c, None) if c == wanted => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses a bare key of ASCII letters, digits, `_` and `-`, or a
+/// quoted one.
+fn parse_key(key: &str) -> Option<String> {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Some(key.to_owned());
+    }
+    match parse_value(key)? {
+        Value::String(key) => Some(key),
+        _ => None,
+    }
+}
+
+fn parse_value(value: &str) -> Option<Value> {
+    match value {
+        "true" => return Some(Value::Boolean(true)),
+        "false" => return Some(Value::Boolean(false)),
+        _ => {}
+    }
+    if let Some(literal) = value.strip_prefix('\'') {
+        let literal = literal.strip_suffix('\'')?;
+        return if literal.contains('\'') { None } else { Some(Value::String(literal.to_owned())) };
+    }
+    if let Some(quoted) = value.strip_prefix('"') {
+        let quoted = quoted.strip_suffix('"')?;
+        let mut string = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next()? {
+                    'b' => string.push('\u{8}'),
+                    't' => string.push('\t'),
+                    'n' => string.push('\n'),
+                    'f' => string.push('\u{c}'),
+                    'r' => string.push('\r'),
+                    c @ ('"' | '\\') => string.push(c),
+                    'u' => string.push(parse_unicode(&mut chars, 4)?),
+                    'U' => string.push(parse_unicode(&mut chars, 8)?),
+                    _ => return None,
+                },
+                '"' => return None,
+                c => string.push(c),
+            }
+        }
+        return Some(Value::String(string));
+    }
+    parse_integer(value).map(Value::Integer)
+}
+
+/// Parses the `digits` hexadecimal digits of a `\u` or `\U` escape.
+fn parse_unicode(chars: &mut core::str::Chars, digits: usize) -> Option<char> {
+    let hex: String = chars.by_ref().take(digits).collect();
+    if hex.len() != digits {
+        return None;
+    }
+    u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+}
+
+/// Parses a decimal integer, with `_` only between digits and no
+/// leading zeros, like TOML.
+fn parse_integer(value: &str) -> Option<u64> {
+    let digits = value.strip_prefix('+').unwrap_or(value);
+    let valid = digits.split('_').all(|group| !group.is_empty() && group.chars().all(|c| c.is_ascii_digit()));
+    if !valid || (digits.len() > 1 && digits.starts_with('0')) {
+        return None;
+    }
+    digits.replace('_', "").parse().ok()
+}
+
+fn parse_level(level: &str) -> Result<Level, String> {
+    match &*level.to_ascii_lowercase() {
+        "error" => Ok(Level::Error),
+        "warn" => Ok(Level::Warn),
+        "info" => Ok(Level::Info),
+        "debug" => Ok(Level::Debug),
+        "trace" => Ok(Level::Trace),
+        _ => Err(format!("unknown level `{}'", level)),
+    }
+}
+
+/// Parses a duration like `250ms` or `1.5s`.
+fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let invalid = || format!("expected a duration like \"250ms\", got `{}'", duration);
+    let split = duration.find(|c: char| !c.is_ascii_digit() && c != '.').ok_or_else(invalid)?;
+    let (number, unit) = duration.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let seconds = match unit.trim() {
+        "ns" => number / 1e9,
+        "us" | "µs" => number / 1e6,
+        "ms" => number / 1e3,
+        "s" => number,
+        _ => return Err(invalid()),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use options::Level;
+    use super::{Setting, SettingsError, parse, parse_duration};
+
+    fn line_of_error(text: &str) -> usize {
+        match parse(text) {
+            Err(SettingsError::Invalid { line, .. }) => line,
+            other => panic!("expected an invalid line, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sections() {
+        let rules = parse("[\"db*\"]\nlevel = \"trace\"\nsample = 10\n\n[handler]\nredact = true\n").unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].pattern, "db*");
+        assert!(matches!(rules[0].settings[..], [Setting::Level(Level::Trace), Setting::Sample(10)]));
+        assert_eq!(rules[1].pattern, "handler");
+        assert!(matches!(rules[1].settings[..], [Setting::Redact(true)]));
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn quoting() {
+        let rules = parse("[ 'db.*' ]\n\"target\" = \"a\\\"b\\\\c\\td\\u00e9\\U0001F600\"\n['x\\y']\ntarget = 'c:\\logs'\n").unwrap();
+        assert_eq!(rules[0].pattern, "db.*");
+        assert!(matches!(rules[0].settings[0], Setting::Target(ref target) if target == "a\"b\\c\td\u{e9}\u{1F600}"));
+        assert_eq!(rules[1].pattern, "x\\y");
+        assert!(matches!(rules[1].settings[0], Setting::Target(ref target) if target == "c:\\logs"));
+        assert_eq!(line_of_error("[db*]\n"), 1);
+        assert_eq!(line_of_error("[db.query]\n"), 1);
+        assert_eq!(line_of_error("[\"db\"]\ntarget = db\n"), 2);
+        assert_eq!(line_of_error("[\"db\"]\ntarget = \"\\q\"\n"), 2);
+        assert_eq!(line_of_error("[\"db\"]\ntarget = \"\\u00\"\n"), 2);
+        assert_eq!(line_of_error("[\"db\"]\ntarget = 'it's'\n"), 2);
+    }
+
+    #[test]
+    fn comments() {
+        let rules = parse("# settings\n[\"db#1\"] # the first\ntarget = \"a # b\" # not part of it\n  # indented\n").unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].pattern, "db#1");
+        assert!(matches!(rules[0].settings[..], [Setting::Target(ref target)] if target == "a # b"));
+    }
+
+    #[test]
+    fn errors_tell_the_line() {
+        assert_eq!(line_of_error("level = \"info\"\n"), 1);
+        assert_eq!(line_of_error("[\"db\"]\n\nlevel\n"), 3);
+        assert_eq!(line_of_error("[\"db\"\n"), 1);
+        assert_eq!(line_of_error("[\"db\"]\nlevel = \"loud\"\n"), 2);
+        assert_eq!(line_of_error("[\"db\"]\nsample = \"10\"\n"), 2);
+        assert_eq!(line_of_error("[\"db\"]\nsample = 01\n"), 2);
+        assert_eq!(line_of_error("[\"db\"]\nsample = 1__0\n"), 2);
+        assert_eq!(line_of_error("[\"db\"]\ncolour = true\n"), 2);
+        assert_eq!(line_of_error("[\"db\"]\ntimed = true\ntimed = false\n"), 3);
+        assert_eq!(line_of_error("[\"db\"]\n[\"x\"]\n[\"db\"]\n"), 3);
+        match parse("[\"db\"]\nlevel = \"loud\"\n") {
+            Err(error) => assert_eq!(error.to_string(), "invalid settings on line 2: unknown level `loud'"),
+            Ok(_) => panic!("accepted an unknown level"),
+        }
+    }
+
+    #[test]
+    fn integers() {
+        let rules = parse("[\"db\"]\nsample = 1_000\nmax_events = +5\ntruncate = 0\n").unwrap();
+        assert!(matches!(rules[0].settings[..],
+                         [Setting::Sample(1000), Setting::MaxEvents(5), Setting::Truncate(0)]));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("250ns"), Ok(Duration::from_nanos(250)));
+        assert_eq!(parse_duration("15us"), Ok(Duration::from_micros(15)));
+        assert_eq!(parse_duration("15µs"), Ok(Duration::from_micros(15)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("2s"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("0s"), Ok(Duration::from_secs(0)));
+        for invalid in &["", "s", "10", "10m", "-1s", "1.2.3s", "ms10"] {
+            assert!(parse_duration(invalid).is_err(), "accepted `{}'", invalid);
+        }
+        let rules = parse("[\"db\"]\nwarn_poll_gap = \"250ms\"\ndeadline = \"2s\"\n").unwrap();
+        assert!(matches!(rules[0].settings[..], [Setting::WarnPollGap(gap), Setting::Deadline(deadline)]
+                         if gap == Duration::from_millis(250) && deadline == Duration::from_secs(2)));
+        assert_eq!(line_of_error("[\"db\"]\ndeadline = 2\n"), 2);
+        assert_eq!(line_of_error("[\"db\"]\ndeadline = \"2 days\"\n"), 2);
+    }
+}
@@ -0,0 +1,75 @@
+//! Logging support for `Shared` futures.
+
+use futures::{Future, Poll};
+use futures::future::{Shared, SharedError, SharedItem};
+use alloc::sync::Arc;
+use core::fmt::Debug;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use future::{LoggedFuture, LoggingExt};
+
+/// The future shared by all waiters, logged under the plain label.
+type Source<F> = Shared<LoggedFuture<<F as Future>::Item, <F as Future>::Error, F>>;
+
+/// LoggedShared wraps a `Shared` future and logs the polls of
+/// every clone separately, see `LoggingExt::inspect_shared`.
+#[derive(Debug)]
+pub struct LoggedShared<F: Future>
+    where F::Item: Debug,
+          F::Error: Debug
+{
+    future: LoggedFuture<SharedItem<F::Item>, SharedError<F::Error>, Source<F>>,
+    waiter: usize,
+    label: Arc<str>,
+    waiters: Arc<AtomicUsize>,
+}
+
+impl<F: Future> LoggedShared<F>
+    where F::Item: Debug,
+          F::Error: Debug
+{
+    #[track_caller]
+    pub(crate) fn new(future: F, label: &str) -> LoggedShared<F> {
+        LoggedShared::waiting(LoggingExt::inspect(future, label).shared(), label.into(), Arc::new(AtomicUsize::new(1)))
+    }
+
+    #[track_caller]
+    fn waiting(source: Source<F>, label: Arc<str>, waiters: Arc<AtomicUsize>) -> LoggedShared<F> {
+        let waiter = waiters.fetch_add(1, Ordering::Relaxed);
+        LoggedShared {
+            future: LoggingExt::inspect(source, &alloc::format!("{} (waiter {})", label, waiter)),
+            waiter,
+            label,
+            waiters,
+        }
+    }
+
+    /// The number of this waiter, counting from 1 for the
+    /// future `inspect_shared()` returned.
+    pub fn waiter(&self) -> usize {
+        self.waiter
+    }
+}
+
+impl<F: Future> Clone for LoggedShared<F>
+    where F::Item: Debug,
+          F::Error: Debug
+{
+    #[track_caller]
+    fn clone(&self) -> LoggedShared<F> {
+        LoggedShared::waiting(self.future.get_ref().clone(), self.label.clone(), self.waiters.clone())
+    }
+}
+
+impl<F: Future> Future for LoggedShared<F>
+    where F::Item: Debug,
+          F::Error: Debug
+{
+    type Item = SharedItem<F::Item>;
+    type Error = SharedError<F::Error>;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        self.future.poll()
+    }
+}
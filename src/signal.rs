@@ -0,0 +1,189 @@
+//! Dumping the live state to the log on a signal.
+
+#[cfg(not(silenced))]
+use core::ffi::c_int;
+#[cfg(not(silenced))]
+use core::mem;
+#[cfg(not(silenced))]
+use core::sync::atomic::{AtomicI32, Ordering};
+use std::io;
+#[cfg(not(silenced))]
+use std::io::Read;
+#[cfg(not(silenced))]
+use std::os::unix::io::AsRawFd;
+#[cfg(not(silenced))]
+use std::os::unix::net::UnixStream;
+#[cfg(not(silenced))]
+use std::sync::Mutex;
+#[cfg(not(silenced))]
+use std::thread;
+
+#[cfg(not(silenced))]
+use recorder;
+#[cfg(not(silenced))]
+use registry;
+
+/// The end of the pipe written to by the signal handler, or -1.
+#[cfg(not(silenced))]
+static WAKE_FD: AtomicI32 = AtomicI32::new(-1);
+/// Whether the thread dumping the state runs.
+#[cfg(not(silenced))]
+static DUMPER: Mutex<bool> = Mutex::new(false);
+
+/// The signal `dump_on_signal()` dumps the state on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DumpSignal {
+    /// `SIGUSR1`.
+    Usr1,
+    /// `SIGUSR2`.
+    Usr2,
+}
+
+#[cfg(not(silenced))]
+impl DumpSignal {
+    fn number(self) -> c_int {
+        match self {
+            DumpSignal::Usr1 => libc::SIGUSR1,
+            DumpSignal::Usr2 => libc::SIGUSR2,
+        }
+    }
+}
+
+/// dump_on_signal() dumps the state to the log through `dump_state()`
+/// every time the process receives `signal`, so a hanging service
+/// tells what it waits for after a `kill -USR1 <pid>`:
+///
+/// ```rust,no_run
+/// # extern crate futures_poll_log;
+/// use futures_poll_log::{DumpSignal, recorder};
+///
+/// # fn main() -> std::io::Result<()> {
+/// recorder::enable(256);
+/// futures_poll_log::dump_on_signal(DumpSignal::Usr1)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// It enables the registry, so only inspections set up afterwards are
+/// listed, and the recent events are only there once the flight
+/// recorder is enabled. The handler itself only wakes a thread of
+/// its own, named `futures-poll-log-dump`, which writes the dump.
+/// Whatever handled `signal` before is replaced.
+///
+/// This function does nothing when the `silence` feature is
+/// activated, and is only available with the `signal` feature, on
+/// Unix.
+#[cfg(not(silenced))]
+pub fn dump_on_signal(signal: DumpSignal) -> io::Result<()> {
+    registry::enable();
+    start_dumper()?;
+    // Safe as the handler only does what is allowed in a signal
+    // handler, and `action` is fully initialized before it is used.
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = on_signal as extern "C" fn(c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(signal.number(), &action, core::ptr::null_mut()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+/// dump_on_signal() is a no-op when the `silence` feature is activated.
+#[cfg(silenced)]
+pub fn dump_on_signal(_: DumpSignal) -> io::Result<()> {
+    Ok(())
+}
+
+/// Starts the thread writing a dump for every byte the signal
+/// handler sends, unless it is running already.
+#[cfg(not(silenced))]
+fn start_dumper() -> io::Result<()> {
+    let mut running = DUMPER.lock().unwrap_or_else(|e| e.into_inner());
+    if *running {
+        return Ok(());
+    }
+    let (wake, mut woken) = UnixStream::pair()?;
+    // A full pipe means a dump is due anyway, so the handler never
+    // has to wait.
+    wake.set_nonblocking(true)?;
+    thread::Builder::new().name("futures-poll-log-dump".into()).spawn(move || {
+        let mut byte = [0u8];
+        while let Ok(1) = woken.read(&mut byte) {
+            dump_state();
+        }
+    })?;
+    WAKE_FD.store(wake.as_raw_fd(), Ordering::Release);
+    // Kept open for as long as the process runs.
+    mem::forget(wake);
+    *running = true;
+    Ok(())
+}
+
+#[cfg(not(silenced))]
+extern "C" fn on_signal(_: c_int) {
+    let fd = WAKE_FD.load(Ordering::Acquire);
+    if fd >= 0 {
+        let byte = 1u8;
+        // Safe as `write` may be called in a signal handler, and
+        // `byte` outlives the call.
+        unsafe {
+            libc::write(fd, &byte as *const u8 as *const libc::c_void, 1);
+        }
+    }
+}
+
+/// dump_state() writes the state of the inspections to the log, at
+/// the `warn` level with the target `futures_log`: a line for every
+/// inspection alive in the registry, followed by the events in the
+/// flight recorder, oldest first:
+///
+/// ```plain
+/// WARN - State dump: 2 live inspections, 3 recorded events
+/// WARN - Live: `connection' (#12): NotReady after 4 polls, 31.02s old, last polled 30.99s ago
+/// WARN - Live: `flush' (#14): Unpolled after 0 polls, 30.99s old
+/// WARN - Recorded: Polling future `connection'
+/// ...
+/// ```
+///
+/// This function is a no-op when the `silence` feature is activated,
+/// and is only available with the `signal` feature, on Unix.
+#[cfg(not(silenced))]
+pub fn dump_state() {
+    let live = registry::snapshot();
+    let recorded = recorder::events();
+    warn!(target: "futures_log",
+          "State dump: {} live inspections, {} recorded events",
+          live.len(),
+          recorded.len());
+    for inspection in &live {
+        match inspection.idle {
+            Some(idle) => {
+                warn!(target: "futures_log",
+                      "Live: `{}' (#{}): {:?} after {} polls, {:.2?} old, last polled {:.2?} ago",
+                      inspection.label,
+                      inspection.instance,
+                      inspection.status,
+                      inspection.polls,
+                      inspection.age,
+                      idle)
+            }
+            None => {
+                warn!(target: "futures_log",
+                      "Live: `{}' (#{}): {:?} after {} polls, {:.2?} old",
+                      inspection.label,
+                      inspection.instance,
+                      inspection.status,
+                      inspection.polls,
+                      inspection.age)
+            }
+        }
+    }
+    for record in &recorded {
+        warn!(target: "futures_log", "Recorded: {}", record.message);
+    }
+}
+/// dump_state() is a no-op when the `silence` feature is activated.
+#[cfg(silenced)]
+pub fn dump_state() {}
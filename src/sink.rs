@@ -0,0 +1,193 @@
+//! Logging support for `Sink`s.
+
+use futures::{Poll, Sink, StartSend};
+#[cfg(not(silenced))]
+use futures::AsyncSink;
+use alloc::sync::Arc;
+use core::fmt::Debug;
+
+use inspector::Inspector;
+use logger::PollLogger;
+#[cfg(not(silenced))]
+use logger::{EventKind, Outcome};
+use options::Inspect;
+
+/// The LoggedSink struct wraps another Sink and
+/// will log all calls to `start_send`, `poll_complete`
+/// and `close`, each under its own name.
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct LoggedSink<T, E, S: Sink<SinkItem = T, SinkError = E>> {
+    sink: S,
+    inspector: Inspector,
+}
+
+#[cfg(not(silenced))]
+impl<T, E, S> LoggedSink<T, E, S>
+    where S: Sink<SinkItem = T, SinkError = E>
+{
+    fn new(sink: S, options: Inspect) -> LoggedSink<T, E, S> {
+        LoggedSink {
+            sink,
+            inspector: Inspector::new(options),
+        }
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T, E, S> Sink for LoggedSink<T, E, S>
+    where T: Debug,
+          E: Debug,
+          S: Sink<SinkItem = T, SinkError = E>
+{
+    type SinkItem = S::SinkItem;
+    type SinkError = S::SinkError;
+
+    #[inline]
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::StartSend(self.inspector.value(&item)), None);
+        let result = self.sink.start_send(item);
+        self.inspector.end(&match result {
+            Ok(AsyncSink::Ready) => Outcome::Ready(&()),
+            Ok(AsyncSink::NotReady(_)) => Outcome::NotReady,
+            Err(ref error) => Outcome::Err(self.inspector.value(error)),
+        });
+        let kind = match result {
+            Ok(AsyncSink::Ready) => EventKind::StartSendAccepted,
+            Ok(AsyncSink::NotReady(_)) => EventKind::StartSendNotReady,
+            Err(ref error) => EventKind::StartSendFailed(self.inspector.value(error)),
+        };
+        self.inspector.emit(kind, stopwatch);
+        result
+    }
+
+    #[inline]
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::PollComplete, None);
+        let poll = self.sink.poll_complete();
+        let outcome = self.inspector.outcome(&poll);
+        self.inspector.end(&outcome);
+        self.inspector.emit(EventKind::PollCompleted(outcome), stopwatch);
+        poll
+    }
+
+    #[inline]
+    fn close(&mut self) -> Poll<(), Self::SinkError> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::Close, None);
+        let poll = self.sink.close();
+        let outcome = self.inspector.outcome(&poll);
+        self.inspector.end(&outcome);
+        self.inspector.emit(EventKind::Closed(outcome), stopwatch);
+        poll
+    }
+}
+
+#[cfg(silenced)]
+impl<T, E, S> Sink for LoggedSink<T, E, S>
+    where T: Debug,
+          E: Debug,
+          S: Sink<SinkItem = T, SinkError = E>
+{
+    type SinkItem = S::SinkItem;
+    type SinkError = S::SinkError;
+
+    #[inline]
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        self.sink.start_send(item)
+    }
+
+    #[inline]
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        self.sink.poll_complete()
+    }
+
+    #[inline]
+    fn close(&mut self) -> Poll<(), Self::SinkError> {
+        self.sink.close()
+    }
+}
+
+/// SinkLoggingExt introduces the logging capabilities
+/// to any Sink, as long as all its SinkItem and SinkError
+/// can be printed.
+pub trait SinkLoggingExt<T, E>
+    where T: Debug,
+          E: Debug,
+          Self: Sink<SinkItem = T, SinkError = E> + Sized
+{
+    /// inspect_sink() sets up the logging. The `label` will
+    /// be used to identify the Sink in the log messages
+    /// used.
+    ///
+    /// This method returns `Self` instead of a `LoggedSink`
+    /// when the `silence` feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect_sink(self, label: &str) -> LoggedSink<T, E, Self>;
+    /// inspect_sink() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_sink(self, label: &str) -> Self;
+
+    /// inspect_sink_with_opts() sets up the logging according
+    /// to `options`, see `Inspect`.
+    #[cfg(not(silenced))]
+    fn inspect_sink_with_opts(self, options: Inspect) -> LoggedSink<T, E, Self>;
+    /// inspect_sink_with_opts() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_sink_with_opts(self, options: Inspect) -> Self;
+
+    /// inspect_sink_with_logger() sets up the logging like
+    /// `inspect_sink()`, but sends the events of this Sink
+    /// to `logger` instead of the globally installed backend.
+    #[cfg(not(silenced))]
+    fn inspect_sink_with_logger(self,
+                                label: &str,
+                                logger: Arc<dyn PollLogger>)
+                                -> LoggedSink<T, E, Self>;
+    /// inspect_sink_with_logger() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_sink_with_logger(self, label: &str, logger: Arc<dyn PollLogger>) -> Self;
+}
+
+impl<T, E, S> SinkLoggingExt<T, E> for S
+    where T: Debug,
+          E: Debug,
+          Self: Sink<SinkItem = T, SinkError = E>
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_sink(self, label: &str) -> LoggedSink<T, E, Self> {
+        LoggedSink::new(self, Inspect::new(label))
+    }
+    #[cfg(silenced)]
+    fn inspect_sink(self, _: &str) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    fn inspect_sink_with_opts(self, options: Inspect) -> LoggedSink<T, E, Self> {
+        LoggedSink::new(self, options)
+    }
+    #[cfg(silenced)]
+    fn inspect_sink_with_opts(self, _: Inspect) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_sink_with_logger(self,
+                                label: &str,
+                                logger: Arc<dyn PollLogger>)
+                                -> LoggedSink<T, E, Self> {
+        LoggedSink::new(self, Inspect::new(label).logger(logger))
+    }
+    #[cfg(silenced)]
+    fn inspect_sink_with_logger(self, _: &str, _: Arc<dyn PollLogger>) -> Self {
+        self
+    }
+}
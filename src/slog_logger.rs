@@ -0,0 +1,48 @@
+//! A backend emitting poll events through `slog`.
+
+use slog::Logger;
+
+use logger::{PollEvent, PollLogger};
+use options::Level;
+
+/// A PollLogger that emits all events through a `slog::Logger`,
+/// passing the label, instance, poll number, outcome, error category
+/// and duration as key-value pairs.
+///
+/// This backend is available with the `slog` feature.
+#[derive(Debug, Clone)]
+pub struct SlogLogger {
+    logger: Logger,
+}
+
+impl SlogLogger {
+    /// Creates a backend writing to `logger`.
+    pub fn new(logger: Logger) -> SlogLogger {
+        SlogLogger { logger }
+    }
+}
+
+impl PollLogger for SlogLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let outcome = event.outcome().map(|outcome| alloc::format!("{:?}", outcome));
+        let duration_us = event.duration.map(|duration| duration.as_micros() as u64);
+        macro_rules! emit {
+            ($level:expr) => {
+                slog::log!(self.logger, $level, "", "{}", event;
+                           "label" => event.label,
+                           "instance" => event.instance,
+                           "poll" => event.poll,
+                           "outcome" => outcome,
+                           "category" => event.category,
+                           "duration_us" => duration_us)
+            }
+        }
+        match event.level {
+            Level::Error => emit!(slog::Level::Error),
+            Level::Warn => emit!(slog::Level::Warning),
+            Level::Info => emit!(slog::Level::Info),
+            Level::Debug => emit!(slog::Level::Debug),
+            Level::Trace => emit!(slog::Level::Trace),
+        }
+    }
+}
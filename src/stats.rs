@@ -0,0 +1,644 @@
+//! Per-label statistics, aggregated across all inspections.
+//!
+//! Statistics are only collected after `enable()` was called:
+//!
+//! ```rust
+//! extern crate futures;
+//! extern crate futures_poll_log;
+//!
+//! use futures::{Future, future};
+//! use futures_poll_log::{LoggingExt, stats};
+//!
+//! # fn main() {
+//! stats::enable();
+//! for _ in 0..3 {
+//!     let _ = future::ok::<i32, ()>(3).inspect("request").wait();
+//! }
+//! print!("{}", futures_poll_log::report());
+//! stats::write_csv(std::io::stdout()).unwrap();
+//! # }
+//! ```
+//!
+//! This module is only available with the `std` feature.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use std::io;
+use std::sync::Mutex;
+#[cfg(not(silenced))]
+use std::thread;
+
+use logger::{EventKind, Outcome};
+use timing;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STATS: Mutex<BTreeMap<String, LabelStats>> = Mutex::new(BTreeMap::new());
+static GROUP_STATS: Mutex<BTreeMap<String, LabelStats>> = Mutex::new(BTreeMap::new());
+
+/// The statistics collected for one label, or for one group of
+/// inspections, see `Inspect::group`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LabelStats {
+    /// The label, or the name of the group.
+    pub label: String,
+    /// The number of inspections polled at least once.
+    pub count: u64,
+    /// The number of inspections that ended successfully.
+    pub completions: u64,
+    /// The number of inspections that ended with an error.
+    pub errors: u64,
+    /// The errors counted by category, for inspections classifying
+    /// them through `LoggingExt::inspect_classified`.
+    pub error_categories: BTreeMap<String, u64>,
+    /// The fewest polls an ended inspection took.
+    pub min_polls: Option<u64>,
+    /// The most polls an ended inspection took.
+    pub max_polls: Option<u64>,
+    /// The polls of all ended inspections, summed up.
+    pub total_polls: u64,
+    /// The time spent in all polls, summed up.
+    pub poll_time: Duration,
+    /// The distribution of the time spent in single polls.
+    pub latency: Histogram,
+    /// The distribution of the time between setting up inspections
+    /// and their first polls.
+    pub first_poll: Histogram,
+    /// The time spent logging inspections measuring it through
+    /// `Inspect::measure_overhead`, summed up.
+    pub overhead: Duration,
+    /// The polls of the last seconds.
+    pub rate: PollRate,
+}
+
+impl LabelStats {
+    fn new(label: &str) -> LabelStats {
+        LabelStats {
+            label: label.into(),
+            count: 0,
+            completions: 0,
+            errors: 0,
+            error_categories: BTreeMap::new(),
+            min_polls: None,
+            max_polls: None,
+            total_polls: 0,
+            poll_time: Duration::from_secs(0),
+            latency: Histogram::new(),
+            first_poll: Histogram::new(),
+            overhead: Duration::from_secs(0),
+            rate: PollRate::new(),
+        }
+    }
+
+    /// The average number of polls of the ended inspections.
+    pub fn avg_polls(&self) -> Option<f64> {
+        match self.completions + self.errors {
+            0 => None,
+            ended => Some(self.total_polls as f64 / ended as f64),
+        }
+    }
+
+    fn ended(&mut self, polls: u64) {
+        self.total_polls += polls;
+        self.min_polls = Some(self.min_polls.map_or(polls, |min| min.min(polls)));
+        self.max_polls = Some(self.max_polls.map_or(polls, |max| max.max(polls)));
+    }
+}
+
+/// The number of bits of a value kept exactly by a `Histogram`.
+const SIGNIFICANT_BITS: u32 = 5;
+const SUB_BUCKETS: u64 = 1 << SIGNIFICANT_BITS;
+const HALF_SUB_BUCKETS: u64 = SUB_BUCKETS / 2;
+
+/// A Histogram counts durations in buckets whose width grows
+/// with the magnitude of the values, like an HDR histogram.
+///
+/// Durations are kept with a relative error of at most about 3%,
+/// however large they are, so percentiles in the tail stay
+/// meaningful.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    max: u64,
+}
+
+impl Histogram {
+    /// Creates an empty histogram.
+    pub fn new() -> Histogram {
+        Histogram::default()
+    }
+
+    /// Counts `duration`.
+    pub fn record(&mut self, duration: Duration) {
+        let nanos = duration.as_nanos().min(u64::MAX as u128) as u64;
+        let index = Histogram::index(nanos);
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.total += 1;
+        self.max = self.max.max(nanos);
+    }
+
+    /// The number of durations counted.
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// The duration below or at which `percentile` percent of
+    /// the counted durations lie, or `None` if none were counted.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.total == 0 {
+            return None;
+        }
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * self.total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let highest = Histogram::highest_value(index).min(self.max);
+                return Some(Duration::from_nanos(highest));
+            }
+        }
+        Some(Duration::from_nanos(self.max))
+    }
+
+    /// The longest duration counted, exactly.
+    pub fn max(&self) -> Option<Duration> {
+        match self.total {
+            0 => None,
+            _ => Some(Duration::from_nanos(self.max)),
+        }
+    }
+
+    /// The median duration.
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50.0)
+    }
+
+    /// The 95th percentile.
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(95.0)
+    }
+
+    /// The 99th percentile.
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+
+    /// Values below `SUB_BUCKETS` get a bucket each. Above, every
+    /// power of two is split into `HALF_SUB_BUCKETS` buckets.
+    fn index(value: u64) -> usize {
+        if value < SUB_BUCKETS {
+            return value as usize;
+        }
+        let shift = 64 - value.leading_zeros() - SIGNIFICANT_BITS;
+        let sub = value >> shift;
+        (SUB_BUCKETS + (shift as u64 - 1) * HALF_SUB_BUCKETS + (sub - HALF_SUB_BUCKETS)) as usize
+    }
+
+    /// The highest value counted in bucket `index`.
+    fn highest_value(index: usize) -> u64 {
+        let index = index as u64;
+        if index < SUB_BUCKETS {
+            return index;
+        }
+        let shift = (index - SUB_BUCKETS) / HALF_SUB_BUCKETS + 1;
+        let sub = (index - SUB_BUCKETS) % HALF_SUB_BUCKETS + HALF_SUB_BUCKETS;
+        (sub << shift) + ((1 << shift) - 1)
+    }
+}
+
+/// The number of whole seconds a `PollRate` averages over.
+pub const RATE_WINDOW: usize = 10;
+
+/// A PollRate counts polls per second over the last `RATE_WINDOW`
+/// seconds, as a gauge for labels that are suddenly polled far more
+/// often than usual, or not at all any more.
+///
+/// Seconds are those of the clock installed through `set_clock()`.
+/// The second still running is counted apart, and left out of the
+/// averages until it has passed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PollRate {
+    /// The polls of the current second, followed by those of the
+    /// seconds before it, wrapping around at `second`.
+    counts: [u64; RATE_WINDOW + 1],
+    /// The current second.
+    second: u64,
+}
+
+impl PollRate {
+    /// Creates a rate without any polls.
+    pub fn new() -> PollRate {
+        PollRate::default()
+    }
+
+    /// Counts a poll during `second`.
+    fn count(&mut self, second: u64) {
+        self.advance(second);
+        self.counts[self.slot(second)] += 1;
+    }
+
+    /// Moves on to `second`, dropping the counts that fall out of
+    /// the window.
+    fn advance(&mut self, second: u64) {
+        if second <= self.second {
+            return;
+        }
+        let passed = (second - self.second).min(self.counts.len() as u64);
+        for skipped in 1..=passed {
+            let slot = self.slot(self.second + skipped);
+            self.counts[slot] = 0;
+        }
+        self.second = second;
+    }
+
+    fn slot(&self, second: u64) -> usize {
+        (second % self.counts.len() as u64) as usize
+    }
+
+    /// The polls of the last `RATE_WINDOW` seconds that have passed,
+    /// oldest first.
+    pub fn seconds(&self) -> Vec<u64> {
+        (1..=RATE_WINDOW as u64).rev()
+                                .map(|ago| match self.second.checked_sub(ago) {
+                                    Some(second) => self.counts[self.slot(second)],
+                                    None => 0,
+                                })
+                                .collect()
+    }
+
+    /// The polls of the second that passed last.
+    pub fn last_second(&self) -> u64 {
+        self.seconds().last().cloned().unwrap_or(0)
+    }
+
+    /// The polls so far of the second still running.
+    pub fn current_second(&self) -> u64 {
+        self.counts[self.slot(self.second)]
+    }
+
+    /// The average polls per second over the last `RATE_WINDOW`
+    /// seconds that have passed.
+    pub fn per_second(&self) -> f64 {
+        self.seconds().iter().sum::<u64>() as f64 / RATE_WINDOW as f64
+    }
+}
+
+/// enable() starts collecting statistics for all inspections.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// disable() stops collecting statistics. The collected ones
+/// are kept.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Release);
+}
+
+/// Whether statistics are collected.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// reset() discards all collected statistics.
+pub fn reset() {
+    with_stats(&STATS, |stats| stats.clear());
+    with_stats(&GROUP_STATS, |stats| stats.clear());
+}
+
+/// snapshot() copies the statistics collected so far, ordered
+/// by label.
+pub fn snapshot() -> Vec<LabelStats> {
+    with_stats(&STATS, copy)
+}
+
+/// group_snapshot() copies the statistics collected so far for the
+/// groups set through `Inspect::group`, ordered by group. Each
+/// inspection in a group counts for its label and for its group.
+pub fn group_snapshot() -> Vec<LabelStats> {
+    with_stats(&GROUP_STATS, copy)
+}
+
+/// Copies `stats`, with their poll rates brought up to the current
+/// second, so seconds without polls count as such.
+fn copy(stats: &mut BTreeMap<String, LabelStats>) -> Vec<LabelStats> {
+    let second = current_second();
+    stats.values_mut()
+         .map(|stats| {
+             stats.rate.advance(second);
+             stats.clone()
+         })
+         .collect()
+}
+
+/// The current second of the clock installed through `set_clock()`.
+fn current_second() -> u64 {
+    timing::clock().now().as_secs()
+}
+
+/// report_rates() renders the poll rates of all labels as a table:
+/// the average polls per second over the last `RATE_WINDOW` seconds,
+/// and the polls of the second that passed last.
+///
+/// ```plain
+/// label              polls/s  last second
+/// db                    12.4           40
+/// request.handler        3.0            0
+/// ```
+pub fn report_rates() -> String {
+    let stats = snapshot();
+    let width = stats.iter().map(|stats| stats.label.chars().count()).max().unwrap_or(0).max("label".len());
+    let mut report = String::new();
+    let _ = writeln!(report, "{:<width$}  {:>7}  {:>11}", "label", "polls/s", "last second", width = width);
+    for stats in &stats {
+        let _ = writeln!(report,
+                         "{:<width$}  {:>7.1}  {:>11}",
+                         stats.label,
+                         stats.rate.per_second(),
+                         stats.rate.last_second(),
+                         width = width);
+    }
+    report
+}
+
+/// log_rates() writes the poll rate of every label to the log, at
+/// the `info` level with the target `futures_log`:
+///
+/// ```plain
+/// INFO - Poll rate of `db': 12.4 polls/s, 40 in the last second
+/// ```
+///
+/// This function is a no-op when the `silence` feature is activated.
+#[cfg(not(silenced))]
+pub fn log_rates() {
+    for stats in snapshot() {
+        info!(target: "futures_log",
+              "Poll rate of `{}': {:.1} polls/s, {} in the last second",
+              stats.label,
+              stats.rate.per_second(),
+              stats.rate.last_second());
+    }
+}
+/// log_rates() is a no-op when the `silence` feature is activated.
+#[cfg(silenced)]
+pub fn log_rates() {}
+
+/// log_rates_every() calls `log_rates()` every `interval` on a
+/// thread of its own, named `futures-poll-log-rates`, for as long as
+/// the process runs.
+///
+/// This function starts no thread when the `silence` feature is
+/// activated.
+#[cfg(not(silenced))]
+pub fn log_rates_every(interval: Duration) -> io::Result<()> {
+    thread::Builder::new().name("futures-poll-log-rates".into()).spawn(move || {
+        loop {
+            thread::sleep(interval);
+            log_rates();
+        }
+    })?;
+    Ok(())
+}
+/// log_rates_every() is a no-op when the `silence` feature is activated.
+#[cfg(silenced)]
+pub fn log_rates_every(_: Duration) -> io::Result<()> {
+    Ok(())
+}
+
+/// report() renders the statistics collected so far as a table,
+/// one row per label, followed by a line with the error categories
+/// of each label that has some. The first poll column is the
+/// median wait for the first poll.
+pub fn report() -> String {
+    table("label", &snapshot())
+}
+
+/// report_groups() renders the statistics collected so far for the
+/// groups set through `Inspect::group` like `report()`, one row per
+/// group.
+pub fn report_groups() -> String {
+    table("group", &group_snapshot())
+}
+
+/// Renders `stats` as a table, with their labels in a column named
+/// `name`.
+fn table(name: &str, stats: &[LabelStats]) -> String {
+    let width = stats.iter().map(|stats| stats.label.chars().count()).max().unwrap_or(0).max(name.len());
+    let mut report = String::new();
+    let _ = writeln!(report,
+                     "{:<width$}  {:>7}  {:>9}  {:>6}  {:>9}  {:>9}  {:>9}  {:>12}  {:>10}  {:>10}  {:>10}  {:>10}  {:>12}",
+                     name,
+                     "count",
+                     "completed",
+                     "errors",
+                     "min polls",
+                     "avg polls",
+                     "max polls",
+                     "poll time",
+                     "p50",
+                     "p95",
+                     "p99",
+                     "first poll",
+                     "overhead",
+                     width = width);
+    for stats in stats {
+        let _ = writeln!(report,
+                         "{:<width$}  {:>7}  {:>9}  {:>6}  {:>9}  {:>9}  {:>9}  {:>12}  {:>10}  {:>10}  {:>10}  {:>10}  {:>12}",
+                         stats.label,
+                         stats.count,
+                         stats.completions,
+                         stats.errors,
+                         optional(stats.min_polls),
+                         optional(stats.avg_polls().map(|avg| alloc::format!("{:.1}", avg))),
+                         optional(stats.max_polls),
+                         alloc::format!("{:?}", stats.poll_time),
+                         optional(stats.latency.p50().map(|p| alloc::format!("{:?}", p))),
+                         optional(stats.latency.p95().map(|p| alloc::format!("{:?}", p))),
+                         optional(stats.latency.p99().map(|p| alloc::format!("{:?}", p))),
+                         optional(stats.first_poll.p50().map(|p| alloc::format!("{:?}", p))),
+                         alloc::format!("{:?}", stats.overhead),
+                         width = width);
+    }
+    for stats in stats.iter().filter(|stats| !stats.error_categories.is_empty()) {
+        let _ = write!(report, "{:<width$}  errors by category:", stats.label, width = width);
+        for (category, count) in &stats.error_categories {
+            let _ = write!(report, " {} {}", category, count);
+        }
+        report.push('\n');
+    }
+    report
+}
+
+/// report_top() renders the `n` labels that stand out most in the
+/// statistics collected so far, as the first ones to look at after
+/// inspecting broadly: those with the highest poll time summed up,
+/// with the worst single poll and with the most polls, counting the
+/// polls of inspections still running.
+///
+/// ```plain
+/// highest poll time:
+///   1. db               1.283s
+///   2. request.handler  3.773µs
+/// worst single poll:
+///   1. db               412.7ms
+///   2. request.handler  1.6µs
+/// most polls:
+///   1. request.handler  5
+///   2. db               3
+/// ```
+pub fn report_top(n: usize) -> String {
+    let stats = snapshot();
+    let mut report = String::new();
+    top(&mut report, "highest poll time", &stats, n, |stats| Some(stats.poll_time), |time| alloc::format!("{:?}", time));
+    top(&mut report, "worst single poll", &stats, n, |stats| stats.latency.max(), |time| alloc::format!("{:?}", time));
+    top(&mut report, "most polls", &stats, n, |stats| Some(stats.latency.count()), |polls| polls.to_string());
+    report
+}
+
+/// Writes the `n` labels in `stats` with the highest `key` below
+/// `title`, leaving out those with none.
+fn top<K, Key, Show>(report: &mut String, title: &str, stats: &[LabelStats], n: usize, key: Key, show: Show)
+    where K: Ord + Copy + Default,
+          Key: Fn(&LabelStats) -> Option<K>,
+          Show: Fn(K) -> String
+{
+    let mut ranked: Vec<(&str, K)> = stats.iter()
+        .filter_map(|stats| key(stats).filter(|key| *key > K::default()).map(|key| (&*stats.label, key)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    ranked.truncate(n);
+    let width = ranked.iter().map(|&(label, _)| label.chars().count()).max().unwrap_or(0);
+    let _ = writeln!(report, "{}:", title);
+    for (rank, &(label, key)) in ranked.iter().enumerate() {
+        let _ = writeln!(report, "  {}. {:<width$}  {}", rank + 1, label, show(key), width = width);
+    }
+}
+
+/// write_csv() writes the statistics collected so far to `writer`
+/// as CSV, with a header and one row per label. Durations are
+/// given in nanoseconds, and missing values are left empty.
+pub fn write_csv<W: io::Write>(mut writer: W) -> io::Result<()> {
+    writeln!(writer,
+             "label,count,completions,errors,min_polls,avg_polls,max_polls,\
+              poll_time_ns,p50_ns,p95_ns,p99_ns,first_poll_p50_ns,overhead_ns")?;
+    for stats in snapshot() {
+        let nanos = |duration: Option<Duration>| duration.map(|duration| duration.as_nanos());
+        writeln!(writer,
+                 "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                 csv_field(&stats.label),
+                 stats.count,
+                 stats.completions,
+                 stats.errors,
+                 csv_optional(stats.min_polls),
+                 csv_optional(stats.avg_polls()),
+                 csv_optional(stats.max_polls),
+                 stats.poll_time.as_nanos(),
+                 csv_optional(nanos(stats.latency.p50())),
+                 csv_optional(nanos(stats.latency.p95())),
+                 csv_optional(nanos(stats.latency.p99())),
+                 csv_optional(nanos(stats.first_poll.p50())),
+                 stats.overhead.as_nanos())?;
+    }
+    writer.flush()
+}
+
+/// Quotes `field` if it contains characters special to CSV.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        alloc::format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.into()
+    }
+}
+
+fn csv_optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(String::new, |value| value.to_string())
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".into(), |value| value.to_string())
+}
+
+fn with_stats<R, F>(stats: &Mutex<BTreeMap<String, LabelStats>>, f: F) -> R
+    where F: FnOnce(&mut BTreeMap<String, LabelStats>) -> R
+{
+    let mut stats = stats.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut stats)
+}
+
+/// Runs `f` on the statistics of `label`, and on those of `group`.
+fn with_label<F: FnMut(&mut LabelStats)>(label: &str, group: Option<&str>, mut f: F) {
+    let mut update = |all: &Mutex<BTreeMap<String, LabelStats>>, key: &str| {
+        with_stats(all, |stats| {
+            if let Some(stats) = stats.get_mut(key) {
+                return f(stats);
+            }
+            f(stats.entry(key.into()).or_insert_with(|| LabelStats::new(key)))
+        })
+    };
+    update(&STATS, label);
+    if let Some(group) = group {
+        update(&GROUP_STATS, group);
+    }
+}
+
+/// Counts an inspection polled for the first time, `waited`
+/// after it was set up.
+///
+/// Like the other recording functions, this is only called for
+/// inspections collecting statistics.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn started(label: &str, group: Option<&str>, waited: Option<Duration>) {
+    with_label(label, group, |stats| {
+        stats.count += 1;
+        if let Some(waited) = waited {
+            stats.first_poll.record(waited);
+        }
+    });
+}
+
+/// Accounts for `kind`, reported by the `poll`th poll that took
+/// `duration`, with the `category` of the error it reports.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn record(label: &str, group: Option<&str>, poll: u64, kind: &EventKind, duration: Option<Duration>, category: Option<&str>) {
+    let completed = matches!(*kind,
+                             EventKind::Polled(Outcome::Ready(_)) |
+                             EventKind::StreamEnded(..) |
+                             EventKind::Closed(Outcome::Ready(_)));
+    let errored = kind.is_error();
+    if duration.is_none() && !completed && !errored {
+        return;
+    }
+    let second = duration.map(|_| current_second());
+    with_label(label, group, |stats| {
+        if let Some(second) = second {
+            stats.rate.count(second);
+        }
+        if let Some(duration) = duration {
+            stats.poll_time += duration;
+            stats.latency.record(duration);
+        }
+        if completed {
+            stats.completions += 1;
+            stats.ended(poll);
+        } else if errored {
+            stats.errors += 1;
+            if let Some(category) = category {
+                *stats.error_categories.entry(category.into()).or_insert(0) += 1;
+            }
+            stats.ended(poll);
+        }
+    })
+}
+
+/// Accounts for `overhead` spent logging an inspection.
+pub(crate) fn record_overhead(label: &str, group: Option<&str>, overhead: Duration) {
+    with_label(label, group, |stats| stats.overhead += overhead);
+}
@@ -0,0 +1,57 @@
+//! A minimal `log` logger for examples and tests.
+//!
+//! ```rust
+//! extern crate futures;
+//! extern crate futures_poll_log;
+//!
+//! use futures::{Future, future};
+//! use futures_poll_log::LoggingExt;
+//!
+//! # fn main() {
+//! futures_poll_log::stdout_logger::init();
+//!
+//! let _ = future::ok::<i32, ()>(3).inspect("printed future").wait();
+//! # }
+//! ```
+//!
+//! This module is only available with the `std` feature.
+
+#[cfg(not(silenced))]
+use log::{LogLevel, LogLevelFilter, LogMetadata, LogRecord};
+
+/// The target printed by the logger.
+#[cfg(not(silenced))]
+const TARGET: &str = "futures_log";
+
+#[cfg(not(silenced))]
+struct StdoutLogger;
+
+#[cfg(not(silenced))]
+impl ::log::Log for StdoutLogger {
+    fn enabled(&self, metadata: &LogMetadata) -> bool {
+        metadata.target() == TARGET && metadata.level() <= LogLevel::Debug
+    }
+
+    fn log(&self, record: &LogRecord) {
+        if self.enabled(record.metadata()) {
+            println!("{} - {}", record.level(), record.args());
+        }
+    }
+}
+
+/// init() installs a logger printing all messages with the target
+/// `futures_log` at the `debug` level or above to standard output.
+///
+/// Nothing happens if a logger is installed already, so it is safe
+/// to call init() at the start of every test.
+#[cfg(not(silenced))]
+pub fn init() {
+    let _ = ::log::set_logger(|max_log_level| {
+                                  max_log_level.set(LogLevelFilter::Debug);
+                                  Box::new(StdoutLogger)
+                              });
+}
+
+/// init() is a no-op when the `silence` feature is activated.
+#[cfg(silenced)]
+pub fn init() {}
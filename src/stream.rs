@@ -0,0 +1,935 @@
+//! Logging support for `Stream`s.
+
+use futures::{Poll, Stream};
+#[cfg(not(silenced))]
+use futures::Async;
+#[cfg(not(silenced))]
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+#[cfg(not(silenced))]
+use core::fmt;
+use core::time::Duration;
+
+use inspector::Inspector;
+use logger::PollLogger;
+#[cfg(not(silenced))]
+use logger::{EventKind, Outcome};
+use options::Inspect;
+#[cfg(not(silenced))]
+use timing::Stopwatch;
+
+/// The LoggedStream struct wraps another Stream and
+/// will log all poll calls, numbering every item it yields.
+/// Dropping it before the stream ended is logged too.
+#[derive(Debug)]
+#[cfg_attr(silenced, allow(dead_code))]
+pub struct LoggedStream<T, E, S: Stream<Item = T, Error = E>> {
+    stream: S,
+    inspector: Inspector,
+    items: u64,
+    ended: bool,
+}
+
+#[cfg(not(silenced))]
+impl<T, E, S> LoggedStream<T, E, S>
+    where S: Stream<Item = T, Error = E>
+{
+    fn new(stream: S, options: Inspect) -> LoggedStream<T, E, S> {
+        LoggedStream {
+            stream,
+            inspector: Inspector::new(options),
+            items: 0,
+            ended: false,
+        }
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T, E, S> Stream for LoggedStream<T, E, S>
+    where T: Debug,
+          E: Debug,
+          S: Stream<Item = T, Error = E>
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        #[cfg(feature="std")]
+        if self.inspector.slow_motion() {
+            return Ok(Async::NotReady);
+        }
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::StreamPolling, None);
+        #[cfg(feature="chaos")]
+        let poll = match self.inspector.inject_fault(false) {
+            Some(_) => Ok(Async::NotReady),
+            None => self.stream.poll(),
+        };
+        #[cfg(not(feature="chaos"))]
+        let poll = self.stream.poll();
+        self.inspector.end(&self.inspector.outcome(&poll));
+        let kind = match poll {
+            Ok(Async::Ready(Some(ref item))) => {
+                self.items += 1;
+                EventKind::StreamItem(self.items, self.inspector.value(item))
+            }
+            Ok(Async::Ready(None)) => {
+                self.ended = true;
+                EventKind::StreamEnded(self.items, self.inspector.polls())
+            }
+            Ok(Async::NotReady) => EventKind::StreamPolled(Outcome::NotReady),
+            Err(ref error) => EventKind::StreamPolled(Outcome::Err(self.inspector.value(error))),
+        };
+        self.inspector.emit(kind, stopwatch);
+        poll
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T, E, S> Drop for LoggedStream<T, E, S>
+    where S: Stream<Item = T, Error = E>
+{
+    fn drop(&mut self) {
+        if !self.ended {
+            self.inspector.emit(EventKind::StreamDropped(self.items, self.inspector.polls()), None);
+        }
+    }
+}
+
+#[cfg(silenced)]
+impl<T, E, S> Stream for LoggedStream<T, E, S>
+    where T: Debug,
+          E: Debug,
+          S: Stream<Item = T, Error = E>
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        self.stream.poll()
+    }
+}
+
+/// StreamLoggingExt introduces the logging capabilities
+/// to any Stream, as long as all its Item and Error
+/// can be printed.
+pub trait StreamLoggingExt<T, E>
+    where T: Debug,
+          E: Debug,
+          Self: Stream<Item = T, Error = E> + Sized
+{
+    /// inspect_stream() sets up the logging. The `label` will
+    /// be used to identify the Stream in the log messages
+    /// used.
+    ///
+    /// This method returns `Self` instead of a `LoggedStream`
+    /// when the `silence` feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect_stream(self, label: &str) -> LoggedStream<T, E, Self>;
+    /// inspect_stream() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_stream(self, label: &str) -> Self;
+
+    /// inspect_stream_with_opts() sets up the logging according
+    /// to `options`, see `Inspect`.
+    #[cfg(not(silenced))]
+    fn inspect_stream_with_opts(self, options: Inspect) -> LoggedStream<T, E, Self>;
+    /// inspect_stream_with_opts() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_stream_with_opts(self, options: Inspect) -> Self;
+
+    /// inspect_stream_with_logger() sets up the logging like
+    /// `inspect_stream()`, but sends the events of this Stream
+    /// to `logger` instead of the globally installed backend.
+    #[cfg(not(silenced))]
+    fn inspect_stream_with_logger(self,
+                                  label: &str,
+                                  logger: Arc<dyn PollLogger>)
+                                  -> LoggedStream<T, E, Self>;
+    /// inspect_stream_with_logger() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_stream_with_logger(self, label: &str, logger: Arc<dyn PollLogger>) -> Self;
+}
+
+impl<T, E, S> StreamLoggingExt<T, E> for S
+    where T: Debug,
+          E: Debug,
+          Self: Stream<Item = T, Error = E>
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_stream(self, label: &str) -> LoggedStream<T, E, Self> {
+        LoggedStream::new(self, Inspect::new(label))
+    }
+    #[cfg(silenced)]
+    fn inspect_stream(self, _: &str) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    fn inspect_stream_with_opts(self, options: Inspect) -> LoggedStream<T, E, Self> {
+        LoggedStream::new(self, options)
+    }
+    #[cfg(silenced)]
+    fn inspect_stream_with_opts(self, _: Inspect) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_stream_with_logger(self,
+                                  label: &str,
+                                  logger: Arc<dyn PollLogger>)
+                                  -> LoggedStream<T, E, Self> {
+        LoggedStream::new(self, Inspect::new(label).logger(logger))
+    }
+    #[cfg(silenced)]
+    fn inspect_stream_with_logger(self, _: &str, _: Arc<dyn PollLogger>) -> Self {
+        self
+    }
+}
+
+/// ChangesLoggedStream wraps a Stream like `LoggedStream`, but
+/// logs an item only if it differs from the previous one, see
+/// `ChangesStreamLoggingExt`.
+#[cfg(not(silenced))]
+pub struct ChangesLoggedStream<S: Stream> {
+    stream: S,
+    inspector: Inspector,
+    items: u64,
+    previous: Option<S::Item>,
+    repeated: u64,
+    ended: bool,
+}
+
+#[cfg(not(silenced))]
+impl<S: Stream + Debug> Debug for ChangesLoggedStream<S>
+    where S::Item: Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChangesLoggedStream")
+         .field("stream", &self.stream)
+         .field("inspector", &self.inspector)
+         .field("items", &self.items)
+         .field("previous", &self.previous)
+         .field("repeated", &self.repeated)
+         .field("ended", &self.ended)
+         .finish()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S: Stream> ChangesLoggedStream<S> {
+    /// Reports the repetitions of the previous item counted so far.
+    fn flush(&mut self) {
+        if self.repeated > 0 {
+            self.inspector.emit(EventKind::StreamRepeated(self.repeated), None);
+            self.repeated = 0;
+        }
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S> Stream for ChangesLoggedStream<S>
+    where S: Stream,
+          S::Item: Debug + PartialEq + Clone,
+          S::Error: Debug
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        let poll = self.stream.poll();
+        self.inspector.end(&self.inspector.outcome(&poll));
+        match poll {
+            Ok(Async::Ready(Some(ref item))) => {
+                self.items += 1;
+                if self.previous.as_ref() == Some(item) {
+                    self.repeated += 1;
+                } else {
+                    self.flush();
+                    self.inspector.emit(EventKind::StreamItem(self.items, self.inspector.value(item)),
+                                        stopwatch);
+                    self.previous = Some(item.clone());
+                }
+            }
+            Ok(Async::Ready(None)) => {
+                self.flush();
+                self.ended = true;
+                self.inspector.emit(EventKind::StreamEnded(self.items, self.inspector.polls()), stopwatch);
+            }
+            Ok(Async::NotReady) => {
+                self.inspector.emit(EventKind::StreamPolled(Outcome::NotReady), stopwatch);
+            }
+            Err(ref error) => {
+                self.flush();
+                let outcome = Outcome::Err(self.inspector.value(error));
+                self.inspector.emit(EventKind::StreamPolled(outcome), stopwatch);
+            }
+        }
+        poll
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S: Stream> Drop for ChangesLoggedStream<S> {
+    fn drop(&mut self) {
+        self.flush();
+        if !self.ended {
+            self.inspector.emit(EventKind::StreamDropped(self.items, self.inspector.polls()), None);
+        }
+    }
+}
+
+/// ChangesStreamLoggingExt introduces logging of changes to any
+/// Stream whose items can be printed and compared.
+pub trait ChangesStreamLoggingExt: Stream + Sized
+    where Self::Item: Debug + PartialEq + Clone,
+          Self::Error: Debug
+{
+    /// inspect_stream_changes() sets up the logging like
+    /// `inspect_stream()`, but leaves out items equal to the one
+    /// before, counting them instead:
+    ///
+    /// ```plain
+    /// DEBUG - Stream `status' yielded item #1: Healthy
+    /// DEBUG - Stream `status' repeated the previous item 41 times
+    /// DEBUG - Stream `status' yielded item #43: Degraded
+    /// ```
+    ///
+    /// The "polling" message before each poll is left out as well.
+    #[cfg(not(silenced))]
+    fn inspect_stream_changes(self, label: &str) -> ChangesLoggedStream<Self>;
+    /// inspect_stream_changes() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_stream_changes(self, label: &str) -> Self;
+}
+
+impl<S> ChangesStreamLoggingExt for S
+    where S: Stream,
+          S::Item: Debug + PartialEq + Clone,
+          S::Error: Debug
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_stream_changes(self, label: &str) -> ChangesLoggedStream<Self> {
+        ChangesLoggedStream {
+            stream: self,
+            inspector: Inspector::new(Inspect::new(label)),
+            items: 0,
+            previous: None,
+            repeated: 0,
+            ended: false,
+        }
+    }
+    #[cfg(silenced)]
+    fn inspect_stream_changes(self, _: &str) -> Self {
+        self
+    }
+}
+
+/// BatchLoggedStream wraps a Stream of batches, like the one
+/// returned by `Stream::chunks`, and logs the size of every batch
+/// and the time it took to fill, see `BatchStreamLoggingExt`.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct BatchLoggedStream<S> {
+    stream: S,
+    inspector: Inspector,
+    batches: u64,
+    /// The items in all batches so far.
+    items: u64,
+    /// Started by the first poll for the next batch.
+    filling: Option<Stopwatch>,
+    ended: bool,
+}
+
+#[cfg(not(silenced))]
+impl<S, T> Stream for BatchLoggedStream<S>
+    where S: Stream<Item = Vec<T>>,
+          T: Debug,
+          S::Error: Debug
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        let inspector = &self.inspector;
+        let filling = *self.filling.get_or_insert_with(|| inspector.stopwatch());
+        let poll = self.stream.poll();
+        self.inspector.end(&self.inspector.outcome(&poll));
+        let kind = match poll {
+            Ok(Async::Ready(Some(ref batch))) => {
+                self.batches += 1;
+                self.items += batch.len() as u64;
+                self.filling = None;
+                EventKind::StreamBatch(self.batches, batch.len(), filling.elapsed())
+            }
+            Ok(Async::Ready(None)) => {
+                self.ended = true;
+                EventKind::StreamEnded(self.items, self.inspector.polls())
+            }
+            Ok(Async::NotReady) => EventKind::StreamPolled(Outcome::NotReady),
+            Err(ref error) => EventKind::StreamPolled(Outcome::Err(self.inspector.value(error))),
+        };
+        self.inspector.emit(kind, stopwatch);
+        poll
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S> Drop for BatchLoggedStream<S> {
+    fn drop(&mut self) {
+        if !self.ended {
+            self.inspector.emit(EventKind::StreamDropped(self.items, self.inspector.polls()), None);
+        }
+    }
+}
+
+/// BatchStreamLoggingExt introduces the logging of batch sizes to
+/// any Stream yielding `Vec`s, such as the chunks of another Stream.
+pub trait BatchStreamLoggingExt<T>: Stream<Item = Vec<T>> + Sized
+    where T: Debug,
+          Self::Error: Debug
+{
+    /// inspect_batches() sets up the logging like
+    /// `inspect_stream()`, but logs the number of items in every
+    /// batch instead of the items themselves, with the time from the
+    /// first poll for the batch until it was yielded:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Stream, stream};
+    /// # use futures_poll_log::BatchStreamLoggingExt;
+    /// # fn main() {
+    /// let rows = (0..10).map(Ok::<u32, ()>);
+    /// let batches = stream::iter(rows).chunks(4).inspect_batches("rows");
+    /// assert_eq!(batches.wait().count(), 3);
+    /// // Stream `rows' yielded batch #1 of 4 items, filled in 1.2µs
+    /// # }
+    /// ```
+    ///
+    /// Short batches show a size limit that is never reached, long
+    /// fill times one that is reached too slowly. The "polling"
+    /// message before each poll is left out.
+    #[cfg(not(silenced))]
+    fn inspect_batches(self, label: &str) -> BatchLoggedStream<Self>;
+    /// inspect_batches() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_batches(self, label: &str) -> Self;
+}
+
+impl<S, T> BatchStreamLoggingExt<T> for S
+    where S: Stream<Item = Vec<T>>,
+          T: Debug,
+          S::Error: Debug
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_batches(self, label: &str) -> BatchLoggedStream<Self> {
+        BatchLoggedStream {
+            stream: self,
+            inspector: Inspector::new(Inspect::new(label)),
+            batches: 0,
+            items: 0,
+            filling: None,
+            ended: false,
+        }
+    }
+    #[cfg(silenced)]
+    fn inspect_batches(self, _: &str) -> Self {
+        self
+    }
+}
+
+/// TickLoggedStream wraps a Stream of timer ticks, like an
+/// interval, and logs how late every tick came compared to its
+/// schedule, see `TickStreamLoggingExt`.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct TickLoggedStream<S> {
+    stream: S,
+    inspector: Inspector,
+    period: Duration,
+    max_drift: Duration,
+    /// Started by the first tick, which the schedule counts from.
+    first: Option<Stopwatch>,
+    ticks: u64,
+    ended: bool,
+}
+
+#[cfg(not(silenced))]
+impl<S> Stream for TickLoggedStream<S>
+    where S: Stream,
+          S::Error: Debug
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        let _entered = self.inspector.enter();
+        self.inspector.begin();
+        let poll = self.stream.poll();
+        self.inspector.end(&match poll {
+            Ok(Async::Ready(_)) => Outcome::Ready(&self.ticks),
+            Ok(Async::NotReady) => Outcome::NotReady,
+            Err(ref error) => Outcome::Err(self.inspector.value(error)),
+        });
+        match poll {
+            Ok(Async::Ready(Some(_))) => {
+                self.ticks += 1;
+                let late = match self.first {
+                    Some(first) => first.elapsed().map(|elapsed| {
+                        let due = self.period.as_nanos().saturating_mul(u128::from(self.ticks - 1));
+                        Duration::from_nanos(elapsed.as_nanos().saturating_sub(due) as u64)
+                    }),
+                    None => {
+                        let first = self.inspector.stopwatch();
+                        self.first = Some(first);
+                        // The first tick is on time by definition, but
+                        // still nothing is measured without `std`.
+                        first.elapsed().map(|_| Duration::ZERO)
+                    }
+                };
+                match late {
+                    Some(late) if late > self.max_drift => {
+                        self.inspector.emit(EventKind::TickDrift(self.ticks, late, self.max_drift), None)
+                    }
+                    Some(late) => self.inspector.emit(EventKind::StreamTick(self.ticks, late), None),
+                    None => {}
+                }
+            }
+            Ok(Async::Ready(None)) => {
+                self.ended = true;
+                self.inspector.emit(EventKind::StreamEnded(self.ticks, self.inspector.polls()), None);
+            }
+            Ok(Async::NotReady) => {}
+            Err(ref error) => {
+                self.inspector.emit(EventKind::StreamPolled(Outcome::Err(self.inspector.value(error))), None)
+            }
+        }
+        poll
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S> Drop for TickLoggedStream<S> {
+    fn drop(&mut self) {
+        if !self.ended {
+            self.inspector.emit(EventKind::StreamDropped(self.ticks, self.inspector.polls()), None);
+        }
+    }
+}
+
+/// TickStreamLoggingExt introduces the logging of timer drift to
+/// any Stream of ticks, such as an interval.
+pub trait TickStreamLoggingExt: Stream + Sized
+    where Self::Error: Debug
+{
+    /// inspect_ticks() logs every tick of a Stream expected to
+    /// yield once every `period`, with how late it came, and warns
+    /// when a tick is later than `max_drift`:
+    ///
+    /// ```plain
+    /// DEBUG - Stream `heartbeat' tick #3 came 412µs late
+    /// WARN - Stream `heartbeat' tick #4 came 31.6ms late, more than 10ms
+    /// ```
+    ///
+    /// The schedule counts from the first tick, so tick `n` is due
+    /// `n - 1` periods after it. A tick arriving early counts as on
+    /// time. Timer coalescing and a stalled executor show up as
+    /// drift before anything else looks wrong. Ticks are not
+    /// measured without `std`.
+    #[cfg(not(silenced))]
+    fn inspect_ticks(self, label: &str, period: Duration, max_drift: Duration) -> TickLoggedStream<Self>;
+    /// inspect_ticks() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_ticks(self, label: &str, period: Duration, max_drift: Duration) -> Self;
+}
+
+impl<S> TickStreamLoggingExt for S
+    where S: Stream,
+          S::Error: Debug
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_ticks(self, label: &str, period: Duration, max_drift: Duration) -> TickLoggedStream<Self> {
+        TickLoggedStream {
+            stream: self,
+            inspector: Inspector::new(Inspect::new(label)),
+            period,
+            max_drift,
+            first: None,
+            ticks: 0,
+            ended: false,
+        }
+    }
+    #[cfg(silenced)]
+    fn inspect_ticks(self, _: &str, _: Duration, _: Duration) -> Self {
+        self
+    }
+}
+
+/// ThroughputLoggedStream wraps a Stream and logs how many items,
+/// and optionally bytes, it yielded per second over a sliding
+/// window, see `ThroughputStreamLoggingExt`.
+#[cfg(not(silenced))]
+pub struct ThroughputLoggedStream<S, L> {
+    stream: S,
+    inspector: Inspector,
+    window: Duration,
+    length: Option<L>,
+    /// Started when the stream was set up, timing the items.
+    started: Stopwatch,
+    /// When the items of the current window came, with their lengths.
+    recent: VecDeque<(Duration, u64)>,
+    reported: Duration,
+    items: u64,
+    ended: bool,
+}
+
+#[cfg(not(silenced))]
+impl<S: Debug, L> Debug for ThroughputLoggedStream<S, L> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ThroughputLoggedStream")
+         .field("stream", &self.stream)
+         .field("inspector", &self.inspector)
+         .field("window", &self.window)
+         .field("bytes", &self.length.is_some())
+         .field("items", &self.items)
+         .field("ended", &self.ended)
+         .finish()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S, L> ThroughputLoggedStream<S, L> {
+    /// Reports the throughput of the window ending `now`, once a
+    /// whole window passed since the last report.
+    fn report(&mut self, now: Duration) {
+        if now < self.reported + self.window {
+            return;
+        }
+        while self.recent.front().is_some_and(|&(at, _)| at + self.window <= now) {
+            self.recent.pop_front();
+        }
+        let bytes = self.length.as_ref().map(|_| self.recent.iter().map(|&(_, length)| length).sum());
+        self.inspector.emit(EventKind::StreamThroughput(self.recent.len() as u64, bytes, self.window), None);
+        self.reported = now;
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S, L> Stream for ThroughputLoggedStream<S, L>
+    where S: Stream,
+          S::Error: Debug,
+          L: FnMut(&S::Item) -> usize
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        let _entered = self.inspector.enter();
+        self.inspector.begin();
+        let poll = self.stream.poll();
+        self.inspector.end(&match poll {
+            Ok(Async::Ready(_)) => Outcome::Ready(&self.items),
+            Ok(Async::NotReady) => Outcome::NotReady,
+            Err(ref error) => Outcome::Err(self.inspector.value(error)),
+        });
+        let now = self.started.elapsed();
+        match poll {
+            Ok(Async::Ready(Some(ref item))) => {
+                self.items += 1;
+                if let Some(now) = now {
+                    let length = self.length.as_mut().map_or(0, |length| length(item) as u64);
+                    self.recent.push_back((now, length));
+                }
+            }
+            Ok(Async::Ready(None)) => {
+                self.ended = true;
+                self.inspector.emit(EventKind::StreamEnded(self.items, self.inspector.polls()), None);
+            }
+            Ok(Async::NotReady) => {}
+            Err(ref error) => {
+                self.inspector.emit(EventKind::StreamPolled(Outcome::Err(self.inspector.value(error))), None)
+            }
+        }
+        if let Some(now) = now {
+            self.report(now);
+        }
+        poll
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S, L> Drop for ThroughputLoggedStream<S, L> {
+    fn drop(&mut self) {
+        if !self.ended {
+            self.inspector.emit(EventKind::StreamDropped(self.items, self.inspector.polls()), None);
+        }
+    }
+}
+
+/// ThroughputStreamLoggingExt introduces the logging of throughput
+/// to any Stream.
+pub trait ThroughputStreamLoggingExt: Stream + Sized
+    where Self::Error: Debug
+{
+    /// inspect_throughput() logs how many items the Stream yielded
+    /// per second over the last `window`, once every `window`:
+    ///
+    /// ```plain
+    /// DEBUG - Stream `messages' yielded 1204 items in the last 1s: 1204.0 items/s
+    /// ```
+    ///
+    /// Items are not logged one by one. The throughput is measured
+    /// when the Stream is polled, so a stalled Stream reports again
+    /// once it is polled the next time. Nothing is measured without
+    /// `std`.
+    #[cfg(not(silenced))]
+    fn inspect_throughput(self, label: &str, window: Duration) -> ThroughputLoggedStream<Self, fn(&Self::Item) -> usize>;
+    /// inspect_throughput() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_throughput(self, label: &str, window: Duration) -> Self;
+
+    /// inspect_throughput_bytes() logs the throughput like
+    /// `inspect_throughput()`, and adds up the bytes of the items as
+    /// measured by `length`:
+    ///
+    /// ```rust
+    /// # extern crate futures;
+    /// # extern crate futures_poll_log;
+    /// # use futures::{Future, Stream, stream};
+    /// # use futures_poll_log::ThroughputStreamLoggingExt;
+    /// # use std::time::Duration;
+    /// # fn main() {
+    /// let chunks = stream::iter(vec![vec![0u8; 512], vec![0u8; 1024]].into_iter().map(Ok::<_, ()>));
+    /// let _ = chunks.inspect_throughput_bytes("upload", Duration::from_secs(1), |chunk| chunk.len())
+    ///               .collect().wait();
+    /// // Once a second, while the upload lasts:
+    /// // Stream `upload' yielded 96 items, 98304 bytes in the last 1s: 96.0 items/s, 98304.0 bytes/s
+    /// # }
+    /// ```
+    #[cfg(not(silenced))]
+    fn inspect_throughput_bytes<L>(self, label: &str, window: Duration, length: L) -> ThroughputLoggedStream<Self, L>
+        where L: FnMut(&Self::Item) -> usize;
+    /// inspect_throughput_bytes() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_throughput_bytes<L>(self, label: &str, window: Duration, length: L) -> Self
+        where L: FnMut(&Self::Item) -> usize;
+}
+
+impl<S> ThroughputStreamLoggingExt for S
+    where S: Stream,
+          S::Error: Debug
+{
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_throughput(self, label: &str, window: Duration) -> ThroughputLoggedStream<Self, fn(&Self::Item) -> usize> {
+        throughput(self, Inspect::new(label), window, None)
+    }
+    #[cfg(silenced)]
+    fn inspect_throughput(self, _: &str, _: Duration) -> Self {
+        self
+    }
+
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_throughput_bytes<L>(self, label: &str, window: Duration, length: L) -> ThroughputLoggedStream<Self, L>
+        where L: FnMut(&Self::Item) -> usize
+    {
+        throughput(self, Inspect::new(label), window, Some(length))
+    }
+    #[cfg(silenced)]
+    fn inspect_throughput_bytes<L>(self, _: &str, _: Duration, _: L) -> Self
+        where L: FnMut(&Self::Item) -> usize
+    {
+        self
+    }
+}
+
+#[cfg(not(silenced))]
+fn throughput<S, L>(stream: S, options: Inspect, window: Duration, length: Option<L>) -> ThroughputLoggedStream<S, L> {
+    let inspector = Inspector::new(options);
+    ThroughputLoggedStream {
+        stream,
+        started: inspector.stopwatch(),
+        inspector,
+        window,
+        length,
+        recent: VecDeque::new(),
+        reported: Duration::ZERO,
+        items: 0,
+        ended: false,
+    }
+}
+
+/// SimpleLoggedStream wraps a Stream like `LoggedStream`, but
+/// logs only whether it yielded an item, ended, failed or was not
+/// ready, without printing items or errors.
+#[cfg(not(silenced))]
+pub struct SimpleLoggedStream<S: Stream> {
+    inner: LoggedStream<Hidden<S::Item>, Hidden<S::Error>, Conceal<S>>,
+}
+
+#[cfg(not(silenced))]
+impl<S: Stream + Debug> Debug for SimpleLoggedStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SimpleLoggedStream").field("stream", &self.inner.stream.0).finish()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S: Stream> Stream for SimpleLoggedStream<S> {
+    type Item = S::Item;
+    type Error = S::Error;
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        match self.inner.poll() {
+            Ok(Async::Ready(item)) => Ok(Async::Ready(item.map(|item| item.0))),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(error) => Err(error.0),
+        }
+    }
+}
+
+/// Wraps the items and errors of a Stream in `Hidden`.
+#[cfg(not(silenced))]
+struct Conceal<S>(S);
+
+#[cfg(not(silenced))]
+impl<S: Stream> Stream for Conceal<S> {
+    type Item = Hidden<S::Item>;
+    type Error = Hidden<S::Error>;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        match self.0.poll() {
+            Ok(Async::Ready(item)) => Ok(Async::Ready(item.map(Hidden))),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(error) => Err(Hidden(error)),
+        }
+    }
+}
+
+/// An item or error printed as `_`.
+#[cfg(not(silenced))]
+struct Hidden<T>(T);
+
+#[cfg(not(silenced))]
+impl<T> Debug for Hidden<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("_")
+    }
+}
+
+/// SimpleStreamLoggingExt introduces the logging capabilities
+/// to any Stream, without requiring its Item and Error to be
+/// printable.
+pub trait SimpleStreamLoggingExt: Stream + Sized {
+    /// inspect_stream_simple() sets up the logging like
+    /// `inspect_stream()`, but prints items and errors as `_`:
+    ///
+    /// ```plain
+    /// DEBUG - Stream `frames' yielded item #1: _
+    /// ```
+    ///
+    /// This method returns `Self` instead of a `SimpleLoggedStream`
+    /// when the `silence` feature is activated.
+    #[cfg(not(silenced))]
+    fn inspect_stream_simple(self, label: &str) -> SimpleLoggedStream<Self>;
+    /// inspect_stream_simple() is a no-op when the `silence` feature is activated.
+    #[cfg(silenced)]
+    fn inspect_stream_simple(self, label: &str) -> Self;
+}
+
+impl<S: Stream> SimpleStreamLoggingExt for S {
+    #[cfg(not(silenced))]
+    #[track_caller]
+    fn inspect_stream_simple(self, label: &str) -> SimpleLoggedStream<Self> {
+        SimpleLoggedStream { inner: LoggedStream::new(Conceal(self), Inspect::new(label)) }
+    }
+    #[cfg(silenced)]
+    fn inspect_stream_simple(self, _: &str) -> Self {
+        self
+    }
+}
+
+#[cfg(all(test, feature="std", not(silenced)))]
+mod tests {
+    use alloc::collections::VecDeque;
+    use alloc::string::String;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::time::Duration;
+    use futures::{Async, Future, Poll, Stream};
+
+    use inspector::tests::Written;
+    use options::Inspect;
+    use timing::TestClock;
+    use super::throughput;
+
+    /// Moves its clock on by the given time before each item, ending
+    /// with `None`.
+    struct Script(&'static TestClock, VecDeque<(Duration, Option<Vec<u8>>)>);
+
+    impl Stream for Script {
+        type Item = Vec<u8>;
+        type Error = ();
+
+        fn poll(&mut self) -> Poll<Option<Vec<u8>>, ()> {
+            let (advance, item) = self.1.pop_front().unwrap_or((Duration::ZERO, None));
+            self.0.advance(advance);
+            Ok(Async::Ready(item))
+        }
+    }
+
+    /// Runs a stream yielding `items` of two bytes 100ms apart and
+    /// ending `end` after the last one, returning the throughput
+    /// reports over windows of a second.
+    fn reports<L>(clock: &'static TestClock, items: usize, end: Duration, length: Option<L>) -> Vec<String>
+        where L: FnMut(&Vec<u8>) -> usize
+    {
+        let mut script: VecDeque<_> = (0..items).map(|_| (Duration::from_millis(100), Some(vec![0u8; 2]))).collect();
+        script.push_back((end, None));
+        let written = Arc::new(Written::default());
+        let options = Inspect::new("throughput").clock(clock).logger(written.clone());
+        throughput(Script(clock, script), options, Duration::from_secs(1), length).collect().wait().unwrap();
+        written.take().into_iter().filter(|message| message.contains(" in the last ")).collect()
+    }
+
+    #[test]
+    fn reports_throughput_once_per_window() {
+        static CLOCK: TestClock = TestClock::new();
+        let length = |item: &Vec<u8>| item.len();
+        assert_eq!(reports(&CLOCK, 25, Duration::from_secs(5), Some(length)), [
+            "Stream `throughput' yielded 10 items, 20 bytes in the last 1s: 10.0 items/s, 20.0 bytes/s",
+            "Stream `throughput' yielded 10 items, 20 bytes in the last 1s: 10.0 items/s, 20.0 bytes/s",
+            "Stream `throughput' yielded 0 items, 0 bytes in the last 1s: 0.0 items/s, 0.0 bytes/s",
+        ]);
+    }
+
+    #[test]
+    fn forgets_items_older_than_the_window() {
+        static CLOCK: TestClock = TestClock::new();
+        assert_eq!(reports::<fn(&Vec<u8>) -> usize>(&CLOCK, 15, Duration::from_millis(550), None), [
+            "Stream `throughput' yielded 10 items in the last 1s: 10.0 items/s",
+            "Stream `throughput' yielded 5 items in the last 1s: 5.0 items/s",
+        ]);
+    }
+}
@@ -0,0 +1,116 @@
+//! Subscribers receiving the events of all inspections.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+use logger::PollEvent;
+
+/// An EventSubscriber receives every event of every inspection, for
+/// tooling built on the events themselves, like exporters, UIs or
+/// analyzers, rather than on the messages written by a backend.
+///
+/// Unlike a `PollLogger`, which writes the events an inspection
+/// shows, a subscriber also receives the ones filtered out by the
+/// level of the backend, sampling or a depth limit, with `PollEvent`
+/// telling their level. Fields and kinds of events are only ever
+/// added, see `EVENT_MODEL_VERSION`.
+pub trait EventSubscriber: Send + Sync {
+    /// on_event() is called for every event, synchronously from
+    /// within the poll call it belongs to.
+    fn on_event(&self, event: &PollEvent);
+
+    /// on_retired() is called once `instance` is dropped, whether or
+    /// not its last event told so, since futures are only summarized
+    /// on drop when asked to. No more events of it follow.
+    fn on_retired(&self, instance: u64) {
+        let _ = instance;
+    }
+}
+
+/// The version of the event model handed to subscribers and
+/// backends: the fields of `PollEvent` and `EventRecord` and the
+/// names returned by `EventKind::name()`. New fields and kinds do not
+/// change it, a change of the meaning of existing ones does.
+pub const EVENT_MODEL_VERSION: u32 = 1;
+
+/// A Subscription identifies a subscriber registered through
+/// `subscribe()`, to remove it through `unsubscribe()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Subscription(u64);
+
+static SUBSCRIBERS: RwLock<Vec<(Subscription, Box<dyn EventSubscriber>)>> = RwLock::new(Vec::new());
+/// The number of subscribers, checked before an event is built.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+static NEXT_SUBSCRIPTION: AtomicU64 = AtomicU64::new(1);
+
+/// subscribe() registers `subscriber` to receive the events of all
+/// inspections from now on, including running ones. Subscribers
+/// must not subscribe or unsubscribe while being called.
+///
+/// ```rust
+/// use futures_poll_log::{EventSubscriber, Outcome, PollEvent};
+///
+/// struct Errors;
+///
+/// impl EventSubscriber for Errors {
+///     fn on_event(&self, event: &PollEvent) {
+///         if let Some(Outcome::Err(error)) = event.outcome() {
+///             eprintln!("`{}' failed: {:?}", event.label, error);
+///         }
+///     }
+/// }
+///
+/// let subscription = futures_poll_log::subscribe(Box::new(Errors));
+/// # futures_poll_log::unsubscribe(subscription);
+/// ```
+///
+/// This function is only available with the `std` feature.
+pub fn subscribe(subscriber: Box<dyn EventSubscriber>) -> Subscription {
+    let subscription = Subscription(NEXT_SUBSCRIPTION.fetch_add(1, Ordering::Relaxed));
+    let mut subscribers = SUBSCRIBERS.write().unwrap_or_else(|e| e.into_inner());
+    subscribers.push((subscription, subscriber));
+    ACTIVE.store(subscribers.len(), Ordering::Relaxed);
+    subscription
+}
+
+/// unsubscribe() removes the subscriber registered as `subscription`
+/// and returns it, if it is still registered.
+///
+/// This function is only available with the `std` feature.
+pub fn unsubscribe(subscription: Subscription) -> Option<Box<dyn EventSubscriber>> {
+    let mut subscribers = SUBSCRIBERS.write().unwrap_or_else(|e| e.into_inner());
+    let index = subscribers.iter().position(|&(registered, _)| registered == subscription)?;
+    let (_, subscriber) = subscribers.remove(index);
+    ACTIVE.store(subscribers.len(), Ordering::Relaxed);
+    Some(subscriber)
+}
+
+/// Whether any subscriber is registered.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed) > 0
+}
+
+/// Hands `event` to all subscribers.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn notify(event: &PollEvent) {
+    if !is_active() {
+        return;
+    }
+    for (_, subscriber) in SUBSCRIBERS.read().unwrap_or_else(|e| e.into_inner()).iter() {
+        subscriber.on_event(event);
+    }
+}
+
+/// Tells all subscribers that `instance` was dropped.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn retire(instance: u64) {
+    if !is_active() {
+        return;
+    }
+    for (_, subscriber) in SUBSCRIBERS.read().unwrap_or_else(|e| e.into_inner()).iter() {
+        subscriber.on_retired(instance);
+    }
+}
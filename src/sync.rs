@@ -0,0 +1,383 @@
+//! Labeled endpoints of the channels and locks in `futures::sync`.
+//!
+//! `oneshot()`, `channel()` and `unbounded()` create channels
+//! whose both ends log under the same label. Senders log every
+//! item sent, backpressure and the receiver going away, and tell
+//! how many items they sent when they are dropped. Receivers are
+//! inspected as usual, and report being dropped before the
+//! channel ended.
+//!
+//! ```rust
+//! # extern crate futures;
+//! # extern crate futures_poll_log;
+//! # use futures::Future;
+//! # fn main() {
+//! let (sender, receiver) = futures_poll_log::sync::oneshot::<u32>("reply");
+//! drop(sender);
+//! assert!(receiver.wait().is_err());
+//! # }
+//! ```
+//!
+//! ```plain
+//! DEBUG - Sender of `reply' dropped after sending 0 items
+//! DEBUG - Polling future `reply'
+//! DEBUG - Future `reply' polled: Err(Canceled)
+//! ```
+//!
+//! `lock()` acquires a `BiLock`, logging its contention and how
+//! long it was held.
+//!
+//! This module is only available with the `std` feature.
+
+use futures::sync::{BiLock, mpsc, oneshot as sync_oneshot};
+#[cfg(silenced)]
+use futures::sync::BiLockAcquire;
+#[cfg(not(silenced))]
+use futures::sync::{BiLockAcquire, BiLockAcquired};
+#[cfg(not(silenced))]
+use futures::{Async, AsyncSink, Future, Poll, Sink, StartSend};
+#[cfg(not(silenced))]
+use alloc::string::String;
+use core::fmt::Debug;
+#[cfg(not(silenced))]
+use core::ops::{Deref, DerefMut};
+
+#[cfg(not(silenced))]
+use future::{LoggedFuture, LoggingExt};
+#[cfg(not(silenced))]
+use inspector::Inspector;
+#[cfg(not(silenced))]
+use logger::{EventKind, Outcome};
+#[cfg(not(silenced))]
+use options::Inspect;
+#[cfg(not(silenced))]
+use stream::{LoggedStream, StreamLoggingExt};
+#[cfg(not(silenced))]
+use timing::Stopwatch;
+
+/// The receiving end of a channel created by `channel()`.
+#[cfg(not(silenced))]
+pub type Receiver<T> = LoggedStream<T, (), mpsc::Receiver<T>>;
+
+/// The receiving end of a channel created by `unbounded()`.
+#[cfg(not(silenced))]
+pub type UnboundedReceiver<T> = LoggedStream<T, (), mpsc::UnboundedReceiver<T>>;
+
+/// The receiving end of a channel created by `oneshot()`.
+#[cfg(not(silenced))]
+pub type OneshotReceiver<T> = LoggedFuture<T, sync_oneshot::Canceled, sync_oneshot::Receiver<T>>;
+
+/// oneshot() creates a `futures::sync::oneshot` channel logged
+/// under `label`. The receiver logs a summary when it is dropped,
+/// see `Inspect::summary`.
+#[cfg(not(silenced))]
+#[track_caller]
+pub fn oneshot<T: Debug>(label: &str) -> (OneshotSender<T>, OneshotReceiver<T>) {
+    let (sender, receiver) = sync_oneshot::channel();
+    let sender = OneshotSender {
+        sender: Some(sender),
+        inspector: Inspector::new(Inspect::new(label)),
+    };
+    (sender, receiver.inspect_with_opts(Inspect::new(label).summary()))
+}
+
+/// oneshot() is a no-op when the `silence` feature is activated,
+/// apart from creating the channel.
+#[cfg(silenced)]
+pub fn oneshot<T: Debug>(_: &str) -> (sync_oneshot::Sender<T>, sync_oneshot::Receiver<T>) {
+    sync_oneshot::channel()
+}
+
+/// channel() creates a bounded `futures::sync::mpsc` channel
+/// logged under `label`.
+#[cfg(not(silenced))]
+pub fn channel<T: Debug>(label: &str, buffer: usize) -> (Sender<mpsc::Sender<T>>, Receiver<T>) {
+    let (sender, receiver) = mpsc::channel(buffer);
+    (Sender::new(sender, label), receiver.inspect_stream(label))
+}
+
+/// channel() is a no-op when the `silence` feature is activated,
+/// apart from creating the channel.
+#[cfg(silenced)]
+pub fn channel<T: Debug>(_: &str, buffer: usize) -> (mpsc::Sender<T>, mpsc::Receiver<T>) {
+    mpsc::channel(buffer)
+}
+
+/// unbounded() creates an unbounded `futures::sync::mpsc` channel
+/// logged under `label`.
+#[cfg(not(silenced))]
+pub fn unbounded<T: Debug>(label: &str) -> (Sender<mpsc::UnboundedSender<T>>, UnboundedReceiver<T>) {
+    let (sender, receiver) = mpsc::unbounded();
+    (Sender::new(sender, label), receiver.inspect_stream(label))
+}
+
+/// unbounded() is a no-op when the `silence` feature is activated,
+/// apart from creating the channel.
+#[cfg(silenced)]
+pub fn unbounded<T: Debug>(_: &str) -> (mpsc::UnboundedSender<T>, mpsc::UnboundedReceiver<T>) {
+    mpsc::unbounded()
+}
+
+/// The sending end of a channel created by `oneshot()`.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct OneshotSender<T> {
+    // Taken by `send()`, which consumes the sender.
+    sender: Option<sync_oneshot::Sender<T>>,
+    inspector: Inspector,
+}
+
+#[cfg(not(silenced))]
+impl<T: Debug> OneshotSender<T> {
+    /// send() sends `t` like `oneshot::Sender::send`, logging the
+    /// item, or that the receiver is gone if it is handed back.
+    pub fn send(mut self, t: T) -> Result<(), T> {
+        let sender = self.sender.take().expect("the sender is only taken by send()");
+        self.inspector.emit(EventKind::Sent(self.inspector.value(&t)), None);
+        let result = sender.send(t);
+        if result.is_err() {
+            self.inspector.emit(EventKind::ReceiverGone, None);
+        }
+        self.inspector.emit(EventKind::SenderDropped(if result.is_ok() { 1 } else { 0 }), None);
+        result
+    }
+
+    /// poll_cancel() polls like `oneshot::Sender::poll_cancel`, and
+    /// logs once it finds the receiver gone.
+    #[allow(clippy::result_unit_err)]
+    pub fn poll_cancel(&mut self) -> Poll<(), ()> {
+        let _entered = self.inspector.enter();
+        self.inspector.begin();
+        let poll = self.sender.as_mut().map_or(Ok(Async::Ready(())), |sender| sender.poll_cancel());
+        self.inspector.end(&self.inspector.outcome(&poll));
+        if let Ok(Async::Ready(())) = poll {
+            self.inspector.emit(EventKind::ReceiverGone, None);
+        }
+        poll
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T> Drop for OneshotSender<T> {
+    fn drop(&mut self) {
+        if self.sender.is_some() {
+            self.inspector.emit(EventKind::SenderDropped(0), None);
+        }
+    }
+}
+
+/// The sending end of a channel created by `channel()` or
+/// `unbounded()`, logged like a `LoggedSink`.
+///
+/// Clones log under the same label.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct Sender<S> {
+    sink: S,
+    inspector: Inspector,
+    label: String,
+    sent: u64,
+}
+
+#[cfg(not(silenced))]
+impl<S> Sender<S> {
+    #[track_caller]
+    fn new(sink: S, label: &str) -> Sender<S> {
+        Sender {
+            sink,
+            inspector: Inspector::new(Inspect::new(label)),
+            label: label.into(),
+            sent: 0,
+        }
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S: Clone> Clone for Sender<S> {
+    fn clone(&self) -> Sender<S> {
+        Sender::new(self.sink.clone(), &self.label)
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S> Sink for Sender<S>
+    where S: Sink,
+          S::SinkItem: Debug,
+          S::SinkError: Debug
+{
+    type SinkItem = S::SinkItem;
+    type SinkError = S::SinkError;
+
+    #[inline]
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::StartSend(self.inspector.value(&item)), None);
+        let result = self.sink.start_send(item);
+        self.inspector.end(&match result {
+            Ok(AsyncSink::Ready) => Outcome::Ready(&()),
+            Ok(AsyncSink::NotReady(_)) => Outcome::NotReady,
+            Err(ref error) => Outcome::Err(self.inspector.value(error)),
+        });
+        let kind = match result {
+            Ok(AsyncSink::Ready) => {
+                self.sent += 1;
+                EventKind::StartSendAccepted
+            }
+            Ok(AsyncSink::NotReady(_)) => EventKind::StartSendNotReady,
+            Err(_) => EventKind::ReceiverGone,
+        };
+        self.inspector.emit(kind, stopwatch);
+        result
+    }
+
+    #[inline]
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::PollComplete, None);
+        let poll = self.sink.poll_complete();
+        let outcome = self.inspector.outcome(&poll);
+        self.inspector.end(&outcome);
+        self.inspector.emit(EventKind::PollCompleted(outcome), stopwatch);
+        poll
+    }
+
+    #[inline]
+    fn close(&mut self) -> Poll<(), Self::SinkError> {
+        let _entered = self.inspector.enter();
+        let stopwatch = self.inspector.begin();
+        self.inspector.emit(EventKind::Close, None);
+        let poll = self.sink.close();
+        let outcome = self.inspector.outcome(&poll);
+        self.inspector.end(&outcome);
+        self.inspector.emit(EventKind::Closed(outcome), stopwatch);
+        poll
+    }
+}
+
+#[cfg(not(silenced))]
+impl<S> Drop for Sender<S> {
+    fn drop(&mut self) {
+        self.inspector.emit(EventKind::SenderDropped(self.sent), None);
+    }
+}
+
+/// lock() acquires `lock` like `BiLock::lock`, logging when the
+/// lock is requested, how long the acquisition was pending, and
+/// how long the lock was held once it is released:
+///
+/// ```plain
+/// DEBUG - Lock `socket' requested
+/// DEBUG - Future `socket' polled: Ok(NotReady)
+/// DEBUG - Lock `socket' acquired after 2 polls, pending for 1.2ms
+/// DEBUG - Lock `socket' released, held for 310µs
+/// ```
+#[cfg(not(silenced))]
+#[track_caller]
+pub fn lock<T>(lock: BiLock<T>, label: &str) -> LockAcquire<T> {
+    let inspector = Inspector::new(Inspect::new(label));
+    inspector.emit(EventKind::LockRequested, None);
+    LockAcquire {
+        acquire: lock.lock(),
+        requested: inspector.stopwatch(),
+        inspector: Some(inspector),
+    }
+}
+
+/// lock() is a no-op when the `silence` feature is activated,
+/// apart from acquiring the lock.
+#[cfg(silenced)]
+pub fn lock<T>(lock: BiLock<T>, _: &str) -> BiLockAcquire<T> {
+    lock.lock()
+}
+
+/// LockAcquire is the future returned by `lock()`, resolving
+/// to a `LockAcquired` once the lock is acquired.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct LockAcquire<T> {
+    acquire: BiLockAcquire<T>,
+    // Handed to the `LockAcquired`.
+    inspector: Option<Inspector>,
+    requested: Stopwatch,
+}
+
+#[cfg(not(silenced))]
+impl<T> Future for LockAcquire<T> {
+    type Item = LockAcquired<T>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<LockAcquired<T>, ()> {
+        let mut inspector = self.inspector.take().expect("cannot poll LockAcquire twice");
+        let acquired = {
+            let _entered = inspector.enter();
+            inspector.begin();
+            let poll = self.acquire.poll();
+            inspector.end(&match poll {
+                Ok(Async::Ready(_)) => Outcome::Ready(Inspector::opaque()),
+                Ok(Async::NotReady) | Err(()) => Outcome::NotReady,
+            });
+            match poll {
+                Ok(Async::Ready(acquired)) => {
+                    inspector.emit(EventKind::LockAcquired(self.requested.elapsed()), None);
+                    acquired
+                }
+                Ok(Async::NotReady) | Err(()) => {
+                    inspector.emit(EventKind::Polled(Outcome::NotReady), None);
+                    self.inspector = Some(inspector);
+                    return Ok(Async::NotReady);
+                }
+            }
+        };
+        Ok(Async::Ready(LockAcquired {
+            acquired: Some(acquired),
+            held: inspector.stopwatch(),
+            inspector,
+        }))
+    }
+}
+
+/// LockAcquired gives access to the value behind an acquired
+/// lock like `BiLockAcquired`, and logs when it is released.
+#[cfg(not(silenced))]
+#[derive(Debug)]
+pub struct LockAcquired<T> {
+    // Taken by `unlock()`, which consumes the guard.
+    acquired: Option<BiLockAcquired<T>>,
+    inspector: Inspector,
+    held: Stopwatch,
+}
+
+#[cfg(not(silenced))]
+impl<T> LockAcquired<T> {
+    /// unlock() releases the lock like `BiLockAcquired::unlock`.
+    pub fn unlock(mut self) -> BiLock<T> {
+        self.acquired.take().expect("the lock is only taken by unlock()").unlock()
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T> Deref for LockAcquired<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.acquired.as_ref().expect("the lock is only taken by unlock()")
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T> DerefMut for LockAcquired<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.acquired.as_mut().expect("the lock is only taken by unlock()")
+    }
+}
+
+#[cfg(not(silenced))]
+impl<T> Drop for LockAcquired<T> {
+    fn drop(&mut self) {
+        // Releases the lock before telling about it.
+        drop(self.acquired.take());
+        self.inspector.emit(EventKind::LockReleased(self.held.elapsed()), None);
+    }
+}
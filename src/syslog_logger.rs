@@ -0,0 +1,195 @@
+//! A backend sending poll events to syslog.
+
+use std::fmt::Write as _;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::Path;
+use std::process;
+
+use config::Timestamps;
+use logger::{PollEvent, PollLogger, Stamp};
+use options::Level;
+use timing;
+
+/// The socket messages are sent through.
+#[derive(Debug)]
+enum Socket {
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+    Udp(UdpSocket, SocketAddr),
+}
+
+/// A PollLogger that sends every event to syslog as an RFC 5424
+/// message, for deployments where syslog is the only sanctioned
+/// way out:
+///
+/// ```rust,no_run
+/// # extern crate futures_poll_log;
+/// use std::sync::Arc;
+/// use futures_poll_log::SyslogLogger;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let logger = SyslogLogger::local()?.app_name("gateway").facility(16);
+/// futures_poll_log::set_poll_logger(Arc::new(logger));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The event is named in the MSGID, like `polled`, and the label,
+/// instance and poll number are passed as structured data, which
+/// many collectors index:
+///
+/// ```plain
+/// <135>1 2024-05-01T12:30:00.000123Z - gateway 4242 polled [poll@32473 label="request" instance="7" poll="2"] Future `request' polled: Ok(NotReady)
+/// ```
+///
+/// Messages are sent as they are emitted. A message that cannot be
+/// sent is lost.
+///
+/// This backend is available with the `syslog` feature.
+#[derive(Debug)]
+pub struct SyslogLogger {
+    socket: Socket,
+    facility: u8,
+    hostname: Option<String>,
+    app_name: Option<String>,
+}
+
+impl SyslogLogger {
+    /// Creates a backend sending to the local syslog daemon through
+    /// `/dev/log`.
+    #[cfg(unix)]
+    pub fn local() -> io::Result<SyslogLogger> {
+        SyslogLogger::unix("/dev/log")
+    }
+
+    /// Creates a backend sending to the Unix datagram socket at
+    /// `path`.
+    #[cfg(unix)]
+    pub fn unix<P: AsRef<Path>>(path: P) -> io::Result<SyslogLogger> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(SyslogLogger::new(Socket::Unix(socket)))
+    }
+
+    /// Creates a backend sending datagrams to the syslog server at
+    /// `addr`.
+    pub fn udp<A: ToSocketAddrs>(addr: A) -> io::Result<SyslogLogger> {
+        let addr = addr.to_socket_addrs()?
+                       .next()
+                       .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to send events to"))?;
+        let local: SocketAddr = if addr.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+        Ok(SyslogLogger::new(Socket::Udp(UdpSocket::bind(local)?, addr)))
+    }
+
+    fn new(socket: Socket) -> SyslogLogger {
+        SyslogLogger {
+            socket,
+            facility: 1,
+            hostname: None,
+            app_name: std::env::current_exe()
+                .ok()
+                .and_then(|path| path.file_stem().map(|name| name.to_string_lossy().into_owned())),
+        }
+    }
+
+    /// Sends the messages with the facility numbered `facility`,
+    /// 1 (user-level messages) by default. The local use facilities
+    /// are numbered from 16 (local0) to 23 (local7).
+    pub fn facility(mut self, facility: u8) -> SyslogLogger {
+        self.facility = facility.min(23);
+        self
+    }
+
+    /// Names the host in the messages, left out by default so that
+    /// the syslog daemon fills it in.
+    pub fn hostname(mut self, hostname: &str) -> SyslogLogger {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Names the application in the messages, by default the name
+    /// of the executable.
+    pub fn app_name(mut self, app_name: &str) -> SyslogLogger {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// Formats `event` as an RFC 5424 message.
+    fn message(&self, event: &PollEvent) -> String {
+        let severity = match event.level {
+            Level::Error => 3,
+            Level::Warn => 4,
+            Level::Info => 6,
+            Level::Debug | Level::Trace => 7,
+        };
+        let mut message = String::new();
+        let _ = write!(message, "<{}>1 ", u32::from(self.facility) * 8 + severity);
+        match timing::since_unix_epoch() {
+            Some(now) => {
+                let _ = write!(message, "{} ", Stamp(now, Timestamps::Rfc3339));
+            }
+            None => message.push_str("- "),
+        }
+        let _ = write!(message,
+                       "{} {} {} {} ",
+                       header_field(self.hostname.as_deref(), 255),
+                       header_field(self.app_name.as_deref(), 48),
+                       process::id(),
+                       event.kind.name());
+        message.push_str("[poll@32473 label=\"");
+        param_value(&mut message, event.label);
+        let _ = write!(message, "\" instance=\"{}\" poll=\"{}\"", event.instance, event.poll);
+        if let Some(correlation) = event.correlation {
+            message.push_str(" correlation=\"");
+            param_value(&mut message, correlation);
+            message.push('"');
+        }
+        if let Some(category) = event.category {
+            message.push_str(" category=\"");
+            param_value(&mut message, category);
+            message.push('"');
+        }
+        if let Some(duration) = event.duration {
+            let _ = write!(message, " duration_us=\"{}\"", duration.as_micros());
+        }
+        let _ = write!(message, "] {}", event);
+        message
+    }
+}
+
+impl PollLogger for SyslogLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let message = self.message(event);
+        let _ = match self.socket {
+            #[cfg(unix)]
+            Socket::Unix(ref socket) => socket.send(message.as_bytes()),
+            Socket::Udp(ref socket, addr) => socket.send_to(message.as_bytes(), addr),
+        };
+    }
+}
+
+/// A header field of at most `max` printable characters without
+/// spaces, or `-` if there is none.
+fn header_field(field: Option<&str>, max: usize) -> String {
+    let field: String = field.unwrap_or("")
+                             .chars()
+                             .filter(|c| c.is_ascii_graphic())
+                             .take(max)
+                             .collect();
+    if field.is_empty() { "-".into() } else { field }
+}
+
+/// Appends `value` to `out` as the value of a structured data
+/// parameter.
+fn param_value(out: &mut String, value: &str) {
+    for c in value.chars() {
+        if let '"' | '\\' | ']' = c {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
@@ -0,0 +1,194 @@
+//! The thread waking tasks up once their delay passed, shared by all
+//! inspections holding polls back.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use futures::task::{self, Task};
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::thread;
+
+use timing::Clock;
+
+/// The wake-ups not due yet.
+static WAKES: Mutex<Vec<Wake>> = Mutex::new(Vec::new());
+/// Signalled when a wake-up is added, or a clock moved by hand.
+static CHANGED: Condvar = Condvar::new();
+/// Whether the thread started.
+static STARTED: OnceLock<bool> = OnceLock::new();
+
+/// A wake-up at `at` on `clock`. Inspections may measure time on
+/// clocks of their own, so wake-ups are not ordered across them.
+struct Wake {
+    clock: &'static dyn Clock,
+    at: Duration,
+    task: Task,
+    released: Option<Arc<AtomicBool>>,
+}
+
+/// Wakes the current task up again once `clock` moved on by
+/// `delay`, setting `released` first. Returns whether the thread
+/// waking it up runs, logging why if it could not be started.
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn wake_up_in(clock: &'static dyn Clock, delay: Duration, released: Option<Arc<AtomicBool>>) -> bool {
+    if !*STARTED.get_or_init(start) {
+        return false;
+    }
+    let task = task::park();
+    // Not woken up within the life of the process anyway.
+    let at = match clock.now().checked_add(delay) {
+        Some(at) => at,
+        None => return true,
+    };
+    lock().push(Wake { clock, at, task, released });
+    CHANGED.notify_one();
+    true
+}
+
+/// Has the thread look at the wake-ups again, as a clock that
+/// does not follow the time of the system moved on.
+pub(crate) fn clock_moved() {
+    if STARTED.get() == Some(&true) {
+        let _wakes = lock();
+        CHANGED.notify_one();
+    }
+}
+
+fn start() -> bool {
+    match thread::Builder::new().name("futures-poll-log-timer".into()).spawn(run) {
+        Ok(_) => true,
+        Err(_error) => {
+            #[cfg(not(silenced))]
+            warn!(target: "futures_log",
+                  "Cannot start the timer thread, waking held back tasks up right away: {}",
+                  _error);
+            false
+        }
+    }
+}
+
+fn run() {
+    let mut wakes = lock();
+    loop {
+        // The wake-up most overdue, and the time until the next one.
+        let mut due: Option<(usize, Duration)> = None;
+        let mut next: Option<Duration> = None;
+        for (index, wake) in wakes.iter().enumerate() {
+            let now = wake.clock.now();
+            match wake.at.checked_sub(now) {
+                Some(left) if left > Duration::ZERO => next = Some(next.map_or(left, |next| next.min(left))),
+                _ => {
+                    let overdue = now - wake.at;
+                    if due.is_none_or(|(_, most)| overdue > most) {
+                        due = Some((index, overdue));
+                    }
+                }
+            }
+        }
+        if let Some((index, _)) = due {
+            let wake = wakes.swap_remove(index);
+            // The task may be polled right away, so it is not woken
+            // up under the lock.
+            drop(wakes);
+            if let Some(released) = wake.released {
+                released.store(true, Ordering::Release);
+            }
+            wake.task.unpark();
+            wakes = lock();
+            continue;
+        }
+        wakes = match next {
+            Some(left) => CHANGED.wait_timeout(wakes, left).unwrap_or_else(|e| e.into_inner()).0,
+            None => CHANGED.wait(wakes).unwrap_or_else(|e| e.into_inner()),
+        };
+    }
+}
+
+fn lock() -> MutexGuard<'static, Vec<Wake>> {
+    WAKES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use core::time::Duration;
+    use futures::{Async, Future, Poll, future};
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::Instant;
+
+    use timing::TestClock;
+
+    use super::wake_up_in;
+
+    static CLOCK: TestClock = TestClock::new();
+
+    /// Waits for a wake-up `delay` on `CLOCK` from its first poll,
+    /// counted in `waiting`, then adds `id` to `woken`.
+    struct Delayed {
+        id: u32,
+        delay: Duration,
+        released: Option<Arc<AtomicBool>>,
+        waiting: Arc<AtomicUsize>,
+        woken: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl Future for Delayed {
+        type Item = ();
+        type Error = ();
+
+        fn poll(&mut self) -> Poll<(), ()> {
+            match self.released {
+                None => {
+                    let released = Arc::new(AtomicBool::new(false));
+                    assert!(wake_up_in(&CLOCK, self.delay, Some(released.clone())));
+                    self.released = Some(released);
+                    self.waiting.fetch_add(1, Ordering::SeqCst);
+                    Ok(Async::NotReady)
+                }
+                Some(ref released) if released.load(Ordering::Acquire) => {
+                    self.woken.lock().unwrap().push(self.id);
+                    Ok(Async::Ready(()))
+                }
+                Some(_) => Ok(Async::NotReady),
+            }
+        }
+    }
+
+    /// Waits for `done` to hold, for a few seconds at most.
+    fn eventually<F: Fn() -> bool>(done: F) {
+        let started = Instant::now();
+        while !done() {
+            assert!(started.elapsed() < Duration::from_secs(5), "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn wakes_tasks_up_by_their_deadlines_on_their_clock() {
+        let waiting = Arc::new(AtomicUsize::new(0));
+        let woken = Arc::new(Mutex::new(Vec::new()));
+        let delayed = |id, secs| Delayed {
+            id,
+            delay: Duration::from_secs(secs),
+            released: None,
+            waiting: waiting.clone(),
+            woken: woken.clone(),
+        };
+        let all = future::join_all(vec![delayed(1, 60), delayed(2, 20), delayed(3, 40)]);
+        let joined = thread::spawn(move || all.wait());
+        eventually(|| waiting.load(Ordering::SeqCst) == 3);
+        thread::sleep(Duration::from_millis(20));
+        assert!(woken.lock().unwrap().is_empty());
+        CLOCK.advance(Duration::from_secs(20));
+        eventually(|| *woken.lock().unwrap() == [2]);
+        CLOCK.advance(Duration::from_secs(20));
+        eventually(|| *woken.lock().unwrap() == [2, 3]);
+        CLOCK.advance(Duration::from_secs(20));
+        eventually(|| *woken.lock().unwrap() == [2, 3, 1]);
+        joined.join().unwrap().unwrap();
+    }
+}
@@ -0,0 +1,231 @@
+//! Time measurement, which is only available with the `std` feature.
+
+use core::time::Duration;
+#[cfg(feature="std")]
+use core::fmt::Debug;
+#[cfg(feature="std")]
+use core::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature="std")]
+use std::sync::RwLock;
+#[cfg(feature="std")]
+use timer;
+#[cfg(all(feature="std", not(all(target_arch="wasm32", target_os="unknown"))))]
+use std::time::Instant;
+
+/// A Clock is the source of all durations measured by the
+/// inspections, like poll times, deadlines and timestamps since
+/// `init()`.
+///
+/// The `SystemClock` is used unless another one is installed
+/// through `set_clock()` or `Inspect::clock`. `TestClock` makes the
+/// measurements deterministic in tests.
+///
+/// This trait is only available with the `std` feature.
+#[cfg(feature="std")]
+pub trait Clock: Debug + Sync {
+    /// The time passed since a fixed point in time, which must
+    /// never decrease.
+    fn now(&self) -> Duration;
+}
+
+/// SystemClock measures time through `std::time::Instant`.
+///
+/// On `wasm32-unknown-unknown`, which has no clock without
+/// JavaScript, it stands still at zero. Install a `Clock` reading
+/// `performance.now()` there instead.
+#[cfg(feature="std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(all(feature="std", not(all(target_arch="wasm32", target_os="unknown"))))]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        use std::sync::OnceLock;
+
+        static ORIGIN: OnceLock<Instant> = OnceLock::new();
+        ORIGIN.get_or_init(Instant::now).elapsed()
+    }
+}
+
+// `Instant::now` panics on this target.
+#[cfg(all(feature="std", target_arch="wasm32", target_os="unknown"))]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+}
+
+/// TestClock stands still until it is moved on by hand, for tests
+/// asserting on slow polls or deadlines. Polls held back by
+/// `Inspect::slow_motion` on it are released as it is moved on:
+///
+/// ```rust
+/// use std::time::Duration;
+/// use futures_poll_log::{Clock, Inspect, TestClock};
+///
+/// static CLOCK: TestClock = TestClock::new();
+///
+/// let options = Inspect::new("request").clock(&CLOCK).deadline(Duration::from_secs(1));
+/// CLOCK.advance(Duration::from_secs(2));
+/// assert_eq!(CLOCK.now(), Duration::from_secs(2));
+/// ```
+#[cfg(feature="std")]
+#[derive(Debug, Default)]
+pub struct TestClock {
+    nanos: AtomicU64,
+}
+
+#[cfg(feature="std")]
+impl TestClock {
+    /// Creates a clock standing at zero.
+    pub const fn new() -> TestClock {
+        TestClock { nanos: AtomicU64::new(0) }
+    }
+
+    /// Moves the clock on by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.nanos.fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+        timer::clock_moved();
+    }
+
+    /// Moves the clock to `now`, which should not be earlier than
+    /// the time it shows.
+    pub fn set(&self, now: Duration) {
+        self.nanos.store(now.as_nanos() as u64, Ordering::SeqCst);
+        timer::clock_moved();
+    }
+}
+
+#[cfg(feature="std")]
+impl Clock for TestClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(feature="std")]
+static CLOCK: RwLock<&'static dyn Clock> = RwLock::new(&SystemClock);
+
+/// set_clock() installs `clock` for all inspections that were not
+/// given their own through `Inspect::clock`.
+///
+/// This function is only available with the `std` feature.
+#[cfg(feature="std")]
+pub fn set_clock(clock: &'static dyn Clock) {
+    *CLOCK.write().unwrap_or_else(|e| e.into_inner()) = clock;
+}
+
+/// The clock installed through `set_clock()`.
+#[cfg(feature="std")]
+pub(crate) fn clock() -> &'static dyn Clock {
+    *CLOCK.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Measures the time spent since it was started, or
+/// does nothing without `std`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stopwatch {
+    #[cfg(feature="std")]
+    start: Duration,
+    #[cfg(feature="std")]
+    clock: &'static dyn Clock,
+}
+
+#[cfg_attr(silenced, allow(dead_code))]
+impl Stopwatch {
+    #[cfg(not(feature="std"))]
+    pub(crate) fn start() -> Stopwatch {
+        Stopwatch {}
+    }
+
+    /// Starts measuring on the clock installed through
+    /// `set_clock()`.
+    #[cfg(feature="std")]
+    #[cfg_attr(not(silenced), allow(dead_code))]
+    pub(crate) fn start() -> Stopwatch {
+        Stopwatch::start_on(clock())
+    }
+
+    #[cfg(feature="std")]
+    pub(crate) fn start_on(clock: &'static dyn Clock) -> Stopwatch {
+        Stopwatch {
+            start: clock.now(),
+            clock,
+        }
+    }
+
+    #[cfg(feature="std")]
+    pub(crate) fn elapsed(&self) -> Option<Duration> {
+        Some(self.clock.now().saturating_sub(self.start))
+    }
+
+    #[cfg(not(feature="std"))]
+    pub(crate) fn elapsed(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// The time passed since the first call, which `init()` makes
+/// unless an event asked for it before.
+#[cfg(feature="std")]
+pub(crate) fn since_start() -> Option<Duration> {
+    use std::sync::OnceLock;
+
+    static START: OnceLock<Duration> = OnceLock::new();
+    let start = *START.get_or_init(|| clock().now());
+    Some(clock().now().saturating_sub(start))
+}
+
+#[cfg(not(feature="std"))]
+pub(crate) fn since_start() -> Option<Duration> {
+    None
+}
+
+/// The time passed since the Unix epoch.
+#[cfg(all(feature="std", not(all(target_arch="wasm32", target_os="unknown"))))]
+pub(crate) fn since_unix_epoch() -> Option<Duration> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now().duration_since(UNIX_EPOCH).ok()
+}
+
+// `SystemTime::now` panics on `wasm32-unknown-unknown`.
+#[cfg(any(not(feature="std"), all(target_arch="wasm32", target_os="unknown")))]
+pub(crate) fn since_unix_epoch() -> Option<Duration> {
+    None
+}
+
+/// The CPU time the current thread has consumed, on the platforms
+/// with a clock for it.
+#[cfg(all(feature="cpu-time", any(target_os="linux", target_os="android", target_os="macos", target_os="freebsd")))]
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn thread_cpu_time() -> Option<Duration> {
+    // Safe as all zeroes are a valid timespec, which `time` then is
+    // to write to.
+    let mut time: libc::timespec = unsafe { core::mem::zeroed() };
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) } != 0 {
+        return None;
+    }
+    Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+}
+
+#[cfg(all(feature="std", not(all(feature="cpu-time", any(target_os="linux", target_os="android", target_os="macos", target_os="freebsd")))))]
+#[cfg_attr(silenced, allow(dead_code))]
+pub(crate) fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+#[cfg(all(test, feature="cpu-time", target_os="linux"))]
+mod tests {
+    use super::thread_cpu_time;
+
+    #[test]
+    fn thread_cpu_time_moves_on_with_work() {
+        let before = thread_cpu_time().unwrap();
+        let mut x = 0u64;
+        while thread_cpu_time().unwrap() == before {
+            x = x.wrapping_add(1);
+        }
+        assert!(thread_cpu_time().unwrap() > before, "{}", x);
+    }
+}
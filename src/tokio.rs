@@ -0,0 +1,53 @@
+//! Spawning logged tasks on tokio.
+//!
+//! ```rust,edition2018
+//! # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! # runtime.block_on(async {
+//! let handle = futures_poll_log::tokio::spawn_logged("request", async { 3 });
+//! # assert_eq!(handle.await.unwrap(), 3);
+//! # });
+//! ```
+//!
+//! This module is only available with the `tokio` feature.
+
+use core::future::Future;
+use tokio_crate::task::{self, JoinHandle};
+
+use futures03;
+use pool;
+
+/// spawn_logged() spawns `future` as a tokio task labeled `label`,
+/// logging it like `futures03::wrap_for_spawn`.
+///
+/// Like `tokio::spawn`, it must be called within a runtime. The task
+/// is spawned without logging when the `silence` feature is activated.
+#[track_caller]
+pub fn spawn_logged<F>(label: &str, future: F) -> JoinHandle<F::Output>
+    where F: Future + Send + 'static,
+          F::Output: Send + 'static
+{
+    task::spawn(futures03::wrap_for_spawn(label, future))
+}
+
+/// spawn_blocking_logged() runs `work` on tokio's pool of blocking
+/// threads as work labeled `label`, logging like
+/// `pool::wrap_blocking` how long it waited for a thread and how long
+/// it ran:
+///
+/// ```rust,edition2018
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// # runtime.block_on(async {
+/// let digest = futures_poll_log::tokio::spawn_blocking_logged("hash", || 7 * 6);
+/// # assert_eq!(digest.await.unwrap(), 42);
+/// # });
+/// ```
+///
+/// Like `tokio::task::spawn_blocking`, it must be called within a
+/// runtime.
+#[track_caller]
+pub fn spawn_blocking_logged<R, W>(label: &str, work: W) -> JoinHandle<R>
+    where W: FnOnce() -> R + Send + 'static,
+          R: Send + 'static
+{
+    task::spawn_blocking(pool::wrap_blocking(label, work))
+}
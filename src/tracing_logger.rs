@@ -0,0 +1,43 @@
+//! A backend emitting poll events through `tracing`.
+
+use logger::{PollEvent, PollLogger};
+use options::Level;
+
+/// A PollLogger that emits all events through `tracing` with the
+/// target `futures_log`, passing the label, instance, poll number,
+/// outcome, error category and duration as fields.
+///
+/// `tracing` requires targets to be known at compile time, so
+/// the target configured through `Inspect::target` is ignored.
+///
+/// This backend is available with the `tracing` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingLogger;
+
+impl PollLogger for TracingLogger {
+    fn on_event(&self, event: &PollEvent) {
+        let outcome = event.outcome().map(|outcome| alloc::format!("{:?}", outcome));
+        let duration_us = event.duration.map(|duration| duration.as_micros() as u64);
+        macro_rules! emit {
+            ($level:expr) => {
+                tracing::event!(target: "futures_log",
+                                $level,
+                                label = event.label,
+                                instance = event.instance,
+                                poll = event.poll,
+                                outcome = outcome,
+                                category = event.category,
+                                duration_us = duration_us,
+                                "{}",
+                                event)
+            }
+        }
+        match event.level {
+            Level::Error => emit!(tracing::Level::ERROR),
+            Level::Warn => emit!(tracing::Level::WARN),
+            Level::Info => emit!(tracing::Level::INFO),
+            Level::Debug => emit!(tracing::Level::DEBUG),
+            Level::Trace => emit!(tracing::Level::TRACE),
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! A live view of all inspections in the terminal.
+//!
+//! `run()` subscribes to the events of all inspections and redraws a
+//! table of them once a second, until `q` is entered:
+//!
+//! ```plain
+//! futures-poll-log: 3 inspections, filter `*db*'
+//! instance  label       state     polls  last poll        age    poll time
+//!        7  db.query    NotReady      4      1.02s      5.11s      1.204ms
+//!        9  db.pool     polling       1          -      4.98s            -
+//!       12  db.query    failed        2     30.1ms     31.9ms        873µs
+//!
+//! /pattern filters, #instance shows its events, a shows finished ones, q quits
+//! ```
+//!
+//! The poll time is only measured for timed inspections, see
+//! `Inspect::timed`. Commands are entered as lines, so the viewer works
+//! in any terminal without switching it to raw mode. The table and the
+//! events of an inspection are also available through `Viewer`, for
+//! embedding them in a user interface of its own.
+//!
+//! This module is only available with the `tui` feature.
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Write as FmtWrite;
+use core::time::Duration;
+use std::io::{self, BufRead, Write};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+
+use config::matches_pattern;
+use logger::{EventKind, Outcome, PollEvent};
+use registry::Status;
+use subscriber::{self, EventSubscriber};
+use timing;
+
+/// How many events are kept per inspection.
+const HISTORY: usize = 50;
+/// How many finished inspections are kept.
+const FINISHED: usize = 1000;
+/// How often `run()` redraws the screen.
+const REFRESH: Duration = Duration::from_secs(1);
+
+/// A Viewer collects the events of all inspections as a subscriber
+/// and renders them as a table, see `run()`.
+///
+/// Clones share their state, so one clone can be subscribed and
+/// another one rendered.
+#[derive(Debug, Clone, Default)]
+pub struct Viewer {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    rows: BTreeMap<u64, Row>,
+    finished: VecDeque<u64>,
+    filter: Option<String>,
+    selected: Option<u64>,
+    show_finished: bool,
+}
+
+#[derive(Debug)]
+struct Row {
+    label: String,
+    status: Status,
+    polls: u64,
+    /// When it was first seen, by the installed clock.
+    seen: Duration,
+    /// Whether a poll began and did not report its result yet.
+    polling: bool,
+    last_poll: Option<Duration>,
+    /// The time spent polling, if timed.
+    poll_time: Option<Duration>,
+    /// When it finished, and how.
+    finished: Option<(Duration, &'static str)>,
+    history: VecDeque<String>,
+}
+
+impl Viewer {
+    /// Creates a viewer that has not seen any events yet.
+    pub fn new() -> Viewer {
+        Viewer::default()
+    }
+
+    /// Shows only the inspections whose label matches `pattern`,
+    /// where `*` matches any sequence of characters. A pattern
+    /// without `*` matches labels containing it.
+    pub fn set_filter(&self, pattern: Option<&str>) {
+        self.with_state(|state| {
+            state.filter = pattern.map(|pattern| {
+                if pattern.contains('*') { pattern.into() } else { alloc::format!("*{}*", pattern) }
+            })
+        })
+    }
+
+    /// Shows the recent events of `instance` instead of the table,
+    /// or the table again for `None`.
+    pub fn select(&self, instance: Option<u64>) {
+        self.with_state(|state| state.selected = instance)
+    }
+
+    /// Lists the inspections that finished too, not only the live
+    /// ones.
+    pub fn show_finished(&self, show: bool) {
+        self.with_state(|state| state.show_finished = show)
+    }
+
+    /// Applies a command entered in `run()`: `/pattern` sets the
+    /// filter and `/` clears it, `#instance` selects an inspection
+    /// and `#` the table again, `a` toggles the finished inspections.
+    /// Returns `false` for `q`, which quits.
+    pub fn command(&self, command: &str) -> bool {
+        let command = command.trim();
+        if command == "q" {
+            return false;
+        }
+        if let Some(pattern) = command.strip_prefix('/') {
+            self.set_filter(if pattern.is_empty() { None } else { Some(pattern) });
+        } else if let Some(instance) = command.strip_prefix('#') {
+            self.select(instance.parse().ok());
+        } else if command == "a" {
+            self.with_state(|state| state.show_finished = !state.show_finished);
+        }
+        true
+    }
+
+    /// Renders the table of inspections, or the events of the
+    /// selected one.
+    pub fn render(&self) -> String {
+        let now = timing::clock().now();
+        self.with_state(|state| match state.selected {
+            Some(instance) => state.history(instance),
+            None => state.table(now),
+        })
+    }
+
+    fn with_state<R, F: FnOnce(&mut State) -> R>(&self, f: F) -> R {
+        f(&mut self.state.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl EventSubscriber for Viewer {
+    fn on_event(&self, event: &PollEvent) {
+        let now = timing::clock().now();
+        self.with_state(|state| state.record(event, now))
+    }
+
+    fn on_retired(&self, instance: u64) {
+        let now = timing::clock().now();
+        self.with_state(|state| state.finish(instance, now, "dropped"))
+    }
+}
+
+impl State {
+    fn record(&mut self, event: &PollEvent, now: Duration) {
+        let row = self.rows.entry(event.instance).or_insert_with(|| {
+            Row {
+                label: event.label.into(),
+                status: Status::Unpolled,
+                polls: 0,
+                seen: now,
+                polling: false,
+                last_poll: None,
+                poll_time: None,
+                finished: None,
+                history: VecDeque::new(),
+            }
+        });
+        if row.label != event.label {
+            row.label = event.label.into();
+        }
+        row.polls = row.polls.max(event.poll);
+        if let Some(duration) = event.duration {
+            row.poll_time = Some(row.poll_time.unwrap_or_default() + duration);
+        }
+        if matches!(event.kind, EventKind::Polling | EventKind::StreamPolling) {
+            row.polling = true;
+        }
+        if let Some(outcome) = event.kind.outcome() {
+            row.polling = false;
+            row.last_poll = Some(now);
+            row.status = match outcome {
+                Outcome::NotReady => Status::NotReady,
+                Outcome::Ready(_) => Status::Ready,
+                Outcome::Err(_) => Status::Failed,
+            };
+        }
+        let finished = match event.kind {
+            EventKind::Dropped(_) | EventKind::StreamDropped(..) => Some("dropped"),
+            _ if event.kind.is_error() && event.kind.is_terminal() => Some("failed"),
+            _ if event.kind.is_terminal() => Some("done"),
+            _ => None,
+        };
+        if row.history.len() == HISTORY {
+            row.history.pop_front();
+        }
+        row.history.push_back(event.to_string());
+        if let Some(how) = finished {
+            self.finish(event.instance, now, how);
+        }
+    }
+
+    /// Marks `instance` finished, unless it already is, and forgets the
+    /// oldest finished inspections beyond `FINISHED`.
+    fn finish(&mut self, instance: u64, now: Duration, how: &'static str) {
+        match self.rows.get_mut(&instance) {
+            Some(row) if row.finished.is_none() => row.finished = Some((now, how)),
+            _ => return,
+        }
+        self.finished.push_back(instance);
+        while self.finished.len() > FINISHED {
+            if let Some(oldest) = self.finished.pop_front() {
+                self.rows.remove(&oldest);
+            }
+        }
+    }
+
+    fn shows(&self, row: &Row) -> bool {
+        (self.show_finished || row.finished.is_none()) &&
+        self.filter.as_ref().is_none_or(|filter| matches_pattern(filter, &row.label))
+    }
+
+    fn table(&self, now: Duration) -> String {
+        let rows: Vec<(&u64, &Row)> = self.rows.iter().filter(|&(_, row)| self.shows(row)).collect();
+        let width = rows.iter().map(|(_, row)| row.label.chars().count()).max().unwrap_or(0).max("label".len());
+        let mut table = String::new();
+        let _ = write!(table, "futures-poll-log: {} inspections", rows.len());
+        if let Some(ref filter) = self.filter {
+            let _ = write!(table, ", filter `{}'", filter);
+        }
+        table.push('\n');
+        let _ = writeln!(table,
+                         "instance  {:<width$}  {:<8}  {:>5}  {:>9}  {:>9}  {:>11}",
+                         "label",
+                         "state",
+                         "polls",
+                         "last poll",
+                         "age",
+                         "poll time",
+                         width = width);
+        for (instance, row) in rows {
+            let state = match row.finished {
+                Some((_, how)) => how.to_string(),
+                None if row.polling => "polling".into(),
+                None => alloc::format!("{:?}", row.status),
+            };
+            let end = row.finished.map_or(now, |(finished, _)| finished);
+            let last_poll = match row.last_poll {
+                Some(last_poll) => alloc::format!("{:.2?}", now.saturating_sub(last_poll)),
+                None => "-".into(),
+            };
+            let _ = writeln!(table,
+                             "{:>8}  {:<width$}  {:<8}  {:>5}  {:>9}  {:>9}  {:>11}",
+                             instance,
+                             row.label,
+                             state,
+                             row.polls,
+                             last_poll,
+                             alloc::format!("{:.2?}", end.saturating_sub(row.seen)),
+                             row.poll_time.map_or_else(|| "-".into(), |poll_time| alloc::format!("{:?}", poll_time)),
+                             width = width);
+        }
+        table
+    }
+
+    fn history(&self, instance: u64) -> String {
+        let mut history = String::new();
+        match self.rows.get(&instance) {
+            Some(row) => {
+                let _ = writeln!(history, "futures-poll-log: last events of `{}' (#{})", row.label, instance);
+                for event in &row.history {
+                    let _ = writeln!(history, "{}", event);
+                }
+            }
+            None => {
+                let _ = writeln!(history, "futures-poll-log: no events of #{}", instance);
+            }
+        }
+        history
+    }
+}
+
+/// run() shows the inspections in the terminal, redrawing their
+/// table once a second until `q` is entered. Commands are entered as
+/// lines, see `Viewer::command`.
+///
+/// ```rust,no_run
+/// # extern crate futures_poll_log;
+/// # fn main() -> std::io::Result<()> {
+/// // The application runs on other threads.
+/// futures_poll_log::tui::run()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// It draws on the alternate screen of the terminal, so log output
+/// on the same terminal should be turned off or written elsewhere.
+/// Only events that happen after it started are shown.
+pub fn run() -> io::Result<()> {
+    let viewer = Viewer::new();
+    let (commands, entered) = mpsc::channel();
+    thread::Builder::new().name("futures-poll-log-tui".into()).spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let sent = line.map(|line| commands.send(line).is_ok());
+            if !sent.unwrap_or(false) {
+                break;
+            }
+        }
+    })?;
+    let _screen = Screen::enter(&viewer)?;
+    draw(&viewer, &entered, &mut io::stdout())
+}
+
+/// Holds the alternate screen and the subscription of `run()`, giving
+/// both up when dropped, however `run()` returns.
+struct Screen {
+    subscription: subscriber::Subscription,
+}
+
+impl Screen {
+    fn enter(viewer: &Viewer) -> io::Result<Screen> {
+        let screen = Screen { subscription: subscriber::subscribe(Box::new(viewer.clone())) };
+        write!(io::stdout(), "\x1b[?1049h")?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = write!(stdout, "\x1b[?1049l");
+        let _ = stdout.flush();
+        subscriber::unsubscribe(self.subscription);
+    }
+}
+
+/// Redraws the screen until `q` is entered.
+fn draw<W: Write>(viewer: &Viewer, entered: &mpsc::Receiver<String>, out: &mut W) -> io::Result<()> {
+    loop {
+        write!(out, "\x1b[2J\x1b[H{}\n/pattern filters, #instance shows its events, a shows finished ones, q quits\n> ",
+               viewer.render())?;
+        out.flush()?;
+        match entered.recv_timeout(REFRESH) {
+            Ok(command) if !viewer.command(&command) => return Ok(()),
+            Ok(_) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+#[cfg(all(test, not(silenced)))]
+mod tests {
+    use alloc::sync::Arc;
+    use futures::executor::{self, Unpark};
+    use futures::future;
+
+    use LoggingExt;
+    use subscriber;
+    use super::Viewer;
+
+    struct Ignore;
+
+    impl Unpark for Ignore {
+        fn unpark(&self) {}
+    }
+
+    #[test]
+    fn retires_futures_dropped_without_summary() {
+        let viewer = Viewer::new();
+        let subscription = subscriber::subscribe(Box::new(viewer.clone()));
+        let mut pending = executor::spawn(future::empty::<(), ()>().inspect("tui.retired"));
+        assert!(pending.poll_future(Arc::new(Ignore)).unwrap().is_not_ready());
+        viewer.set_filter(Some("tui.retired"));
+        assert!(viewer.render().contains("NotReady"));
+        drop(pending);
+        subscriber::unsubscribe(subscription);
+        assert!(viewer.render().starts_with("futures-poll-log: 0 inspections"));
+        viewer.show_finished(true);
+        assert!(viewer.render().contains("dropped"));
+    }
+}